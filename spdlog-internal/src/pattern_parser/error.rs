@@ -92,6 +92,16 @@ pub enum TemplateError {
         placeholder: String,
     },
     MultipleStyleRange,
+    UnexpectedPatternArgument {
+        placeholder: String,
+    },
+    InvalidPatternArgument {
+        placeholder: String,
+        arg: String,
+    },
+    UnknownCondition {
+        condition: String,
+    },
 }
 
 impl Display for TemplateError {
@@ -127,7 +137,22 @@ impl Display for TemplateError {
                 }
             }
             TemplateError::MultipleStyleRange => {
-                write!(f, "multiple style ranges are not currently supported")
+                write!(
+                    f,
+                    "multiple level style ranges are not supported, use `{{^<style>}}...{{$}}` with explicit styles instead"
+                )
+            }
+            TemplateError::UnexpectedPatternArgument { placeholder } => {
+                write!(f, "pattern '{placeholder}' does not take an argument")
+            }
+            TemplateError::InvalidPatternArgument { placeholder, arg } => {
+                write!(f, "invalid argument '{arg}' for pattern '{placeholder}'")
+            }
+            TemplateError::UnknownCondition { condition } => {
+                write!(
+                    f,
+                    "unknown condition '{condition}', expected one of 'logger', 'source' or 'kv'"
+                )
             }
         }
     }
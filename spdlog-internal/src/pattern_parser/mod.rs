@@ -4,7 +4,9 @@ pub mod error;
 mod helper;
 pub mod parse;
 mod registry;
+pub mod style;
 
+use error::TemplateError;
 pub use error::{Error, Result};
 pub use registry::{check_custom_pattern_names, PatternRegistry};
 
@@ -54,7 +56,13 @@ macro_rules! for_builtin_formatters {
             Payload => "payload",
             KV => "kv",
             ProcessId => "pid",
+            ProcessName => "process_name",
             ThreadId => "tid",
+            ThreadName => "thread_name",
+            Hostname => "hostname",
+            Elapsed => "elapsed",
+            ElapsedDelta => "elapsed_delta",
+            Sequence => "seq",
             Eol => "eol",
         }
     };
@@ -89,6 +97,60 @@ for_builtin_formatters! {
     };
 }
 
+impl BuiltInFormatter {
+    /// Returns the struct name of the pattern that takes the given argument.
+    pub fn struct_name_with_arg(&self, arg: &str) -> Result<&'static str> {
+        match self {
+            Self::Time => {
+                let invalid = chrono::format::StrftimeItems::new(arg)
+                    .any(|item| matches!(item, chrono::format::Item::Error));
+                if invalid {
+                    Err(Error::Template(TemplateError::InvalidPatternArgument {
+                        placeholder: self.placeholder().into(),
+                        arg: arg.into(),
+                    }))
+                } else {
+                    Ok("FormattedTime")
+                }
+            }
+            _ => Err(Error::Template(TemplateError::UnexpectedPatternArgument {
+                placeholder: self.placeholder().into(),
+            })),
+        }
+    }
+}
+
+/// A condition of conditional sections in templates, e.g. `logger` in
+/// `{?logger:[{logger}] }`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Condition {
+    LoggerName,
+    SourceLocation,
+    KV,
+}
+
+impl Condition {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "logger" => Ok(Self::LoggerName),
+            "source" => Ok(Self::SourceLocation),
+            "kv" => Ok(Self::KV),
+            _ => Err(Error::Template(TemplateError::UnknownCondition {
+                condition: name.into(),
+            })),
+        }
+    }
+
+    #[must_use]
+    pub fn variant_name(&self) -> &'static str {
+        match self {
+            Self::LoggerName => "LoggerName",
+            Self::SourceLocation => "SourceLocation",
+            Self::KV => "KV",
+        }
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum PatternKind<F> {
     BuiltIn(BuiltInFormatter),
@@ -106,6 +168,20 @@ impl<F> PatternKind<F> {
         }
     }
 
+    pub fn check_arg(&self, arg: Option<&str>) -> Result<()> {
+        match (self, arg) {
+            (_, None) => Ok(()),
+            (PatternKind::BuiltIn(builtin), Some(arg)) => {
+                builtin.struct_name_with_arg(arg).map(|_| ())
+            }
+            (PatternKind::Custom { placeholder, .. }, Some(_)) => {
+                Err(Error::Template(TemplateError::UnexpectedPatternArgument {
+                    placeholder: placeholder.to_string(),
+                }))
+            }
+        }
+    }
+
     pub(crate) fn to_factory_erased(&self) -> PatternKind<()> {
         match self {
             PatternKind::BuiltIn(b) => PatternKind::BuiltIn(*b),
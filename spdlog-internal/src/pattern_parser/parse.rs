@@ -1,6 +1,6 @@
 use nom::{error::Error as NomError, Parser};
 
-use super::{helper, Error, Result};
+use super::{helper, style::StyleAttr, Error, Result};
 
 #[cfg_attr(test, derive(Debug, Eq, PartialEq))]
 pub struct Template<'a> {
@@ -39,6 +39,14 @@ impl<'a> Template<'a> {
         nom::combinator::complete(nom::multi::many0(token_parser).and(nom::combinator::eof))
             .map(|(tokens, _)| Self { tokens })
     }
+
+    #[must_use]
+    fn parser_without_conditional(
+    ) -> impl Parser<&'a str, Output = Template<'a>, Error = NomError<&'a str>> {
+        let token_parser = TemplateToken::parser_without_conditional();
+        nom::combinator::complete(nom::multi::many0(token_parser).and(nom::combinator::eof))
+            .map(|(tokens, _)| Self { tokens })
+    }
 }
 
 #[cfg_attr(test, derive(Debug, Eq, PartialEq))]
@@ -46,11 +54,21 @@ pub enum TemplateToken<'a> {
     Literal(TemplateLiteral),
     Formatter(TemplateFormatterToken<'a>),
     StyleRange(TemplateStyleRange<'a>),
+    Conditional(TemplateConditional<'a>),
 }
 
 impl<'a> TemplateToken<'a> {
     #[must_use]
     fn parser() -> impl Parser<&'a str, Output = TemplateToken<'a>, Error = NomError<&'a str>> {
+        let conditional_parser = TemplateConditional::parser();
+        let other_parser = Self::parser_without_conditional();
+
+        nom::combinator::map(conditional_parser, Self::Conditional).or(other_parser)
+    }
+
+    #[must_use]
+    fn parser_without_conditional(
+    ) -> impl Parser<&'a str, Output = TemplateToken<'a>, Error = NomError<&'a str>> {
         let style_range_parser = TemplateStyleRange::parser();
         let other_parser = Self::parser_without_style_range();
 
@@ -89,6 +107,7 @@ impl TemplateLiteral {
 pub struct TemplateFormatterToken<'a> {
     pub has_custom_prefix: bool,
     pub placeholder: &'a str,
+    pub arg: Option<&'a str>,
 }
 
 impl<'a> TemplateFormatterToken<'a> {
@@ -110,15 +129,27 @@ impl<'a> TemplateFormatterToken<'a> {
             ))),
         ));
 
-        nom::sequence::delimited(open_paren, formatter_placeholder, close_paren).map(
-            move |placeholder: &str| match placeholder.strip_prefix('$') {
+        let formatter_arg = nom::combinator::opt(nom::sequence::preceded(
+            nom::character::complete::char(':'),
+            helper::take_until_unbalanced('{', '}'),
+        ));
+
+        nom::sequence::delimited(
+            open_paren,
+            (formatter_placeholder, formatter_arg),
+            close_paren,
+        )
+        .map(
+            move |(placeholder, arg): (&str, _)| match placeholder.strip_prefix('$') {
                 Some(placeholder) => Self {
                     has_custom_prefix: true,
                     placeholder,
+                    arg,
                 },
                 None => Self {
                     has_custom_prefix: false,
                     placeholder,
+                    arg,
                 },
             },
         )
@@ -127,6 +158,8 @@ impl<'a> TemplateFormatterToken<'a> {
 
 #[cfg_attr(test, derive(Debug, Eq, PartialEq))]
 pub struct TemplateStyleRange<'a> {
+    /// `None` for the style corresponding to the log level.
+    pub style: Option<Vec<StyleAttr>>,
     pub body: Template<'a>,
 }
 
@@ -134,12 +167,89 @@ impl<'a> TemplateStyleRange<'a> {
     #[must_use]
     fn parser() -> impl Parser<&'a str, Output = TemplateStyleRange<'a>, Error = NomError<&'a str>>
     {
+        Self::explicit_style_parser().or(Self::level_style_parser())
+    }
+
+    // `{^bold cyan}...{$}`
+    #[must_use]
+    fn explicit_style_parser(
+    ) -> impl Parser<&'a str, Output = TemplateStyleRange<'a>, Error = NomError<&'a str>> {
+        let style_parser = nom::combinator::map_opt(
+            nom::bytes::complete::take_while1(|c: char| {
+                c.is_ascii_alphanumeric() || c == '_' || c == ' '
+            }),
+            |spec: &str| {
+                let style = spec
+                    .split_whitespace()
+                    .map(StyleAttr::parse)
+                    .collect::<Option<Vec<_>>>()?;
+                (!style.is_empty()).then_some(style)
+            },
+        );
+
+        nom::sequence::delimited(
+            nom::bytes::complete::tag("{^"),
+            style_parser,
+            nom::bytes::complete::tag("}"),
+        )
+        .and(nom::multi::many0(
+            TemplateToken::parser_without_style_range(),
+        ))
+        .and(nom::bytes::complete::tag("{$}"))
+        .map(|((style, tokens), _)| Self {
+            style: Some(style),
+            body: Template { tokens },
+        })
+    }
+
+    // `{^...}`
+    #[must_use]
+    fn level_style_parser(
+    ) -> impl Parser<&'a str, Output = TemplateStyleRange<'a>, Error = NomError<&'a str>> {
         nom::bytes::complete::tag("{^")
             .and(helper::take_until_unbalanced('{', '}'))
             .and(nom::bytes::complete::tag("}"))
             .map(|((_, body), _)| body)
             .and_then(Template::parser_without_style_range())
-            .map(|body| Self { body })
+            .map(|body| Self { style: None, body })
+    }
+}
+
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
+pub struct TemplateConditional<'a> {
+    pub condition: &'a str,
+    pub body: Template<'a>,
+}
+
+impl<'a> TemplateConditional<'a> {
+    // `{?logger:[{logger}] }`
+    #[must_use]
+    fn parser() -> impl Parser<&'a str, Output = TemplateConditional<'a>, Error = NomError<&'a str>>
+    {
+        let condition_parser = nom::combinator::recognize((
+            nom::branch::alt((
+                nom::character::complete::alpha1,
+                nom::bytes::complete::tag("_"),
+            )),
+            nom::multi::many0_count(nom::branch::alt((
+                nom::character::complete::alphanumeric1,
+                nom::bytes::complete::tag("_"),
+            ))),
+        ));
+
+        nom::sequence::delimited(
+            nom::bytes::complete::tag("{?"),
+            (
+                condition_parser,
+                nom::sequence::preceded(
+                    nom::character::complete::char(':'),
+                    helper::take_until_unbalanced('{', '}')
+                        .and_then(Template::parser_without_conditional()),
+                ),
+            ),
+            nom::bytes::complete::tag("}"),
+        )
+        .map(|(condition, body)| Self { condition, body })
     }
 }
 
@@ -220,7 +330,8 @@ mod tests {
                             }),
                             TemplateToken::Formatter(TemplateFormatterToken {
                                 has_custom_prefix: false,
-                                placeholder: "full"
+                                placeholder: "full",
+                                arg: None,
                             }),
                             TemplateToken::Literal(TemplateLiteral {
                                 literal: String::from("!"),
@@ -228,6 +339,7 @@ mod tests {
                             TemplateToken::Formatter(TemplateFormatterToken {
                                 has_custom_prefix: true,
                                 placeholder: "custom",
+                                arg: None,
                             }),
                         ],
                     }
@@ -246,6 +358,7 @@ mod tests {
                             TemplateToken::Formatter(TemplateFormatterToken {
                                 has_custom_prefix: false,
                                 placeholder: "not_exists",
+                                arg: None,
                             }),
                             TemplateToken::Literal(TemplateLiteral {
                                 literal: String::from("!"),
@@ -253,6 +366,7 @@ mod tests {
                             TemplateToken::Formatter(TemplateFormatterToken {
                                 has_custom_prefix: true,
                                 placeholder: "custom",
+                                arg: None,
                             }),
                         ],
                     }
@@ -260,6 +374,43 @@ mod tests {
             );
         }
 
+        #[test]
+        fn test_parse_formatter_with_arg() {
+            assert_eq!(
+                parse_template_str(r#"[{time:%H:%M %z}] {$custom:{a}b}{level:}"#),
+                Ok((
+                    "",
+                    Template {
+                        tokens: vec![
+                            TemplateToken::Literal(TemplateLiteral {
+                                literal: String::from("["),
+                            }),
+                            TemplateToken::Formatter(TemplateFormatterToken {
+                                has_custom_prefix: false,
+                                placeholder: "time",
+                                arg: Some("%H:%M %z"),
+                            }),
+                            TemplateToken::Literal(TemplateLiteral {
+                                literal: String::from("] "),
+                            }),
+                            TemplateToken::Formatter(TemplateFormatterToken {
+                                has_custom_prefix: true,
+                                placeholder: "custom",
+                                arg: Some("{a}b"),
+                            }),
+                            TemplateToken::Formatter(TemplateFormatterToken {
+                                has_custom_prefix: false,
+                                placeholder: "level",
+                                arg: Some(""),
+                            }),
+                        ],
+                    }
+                ))
+            );
+
+            assert!(parse_template_str(r#"{time:%H{}"#).is_err());
+        }
+
         #[test]
         fn test_parse_literal_single_close_paren() {
             assert_eq!(
@@ -299,6 +450,7 @@ mod tests {
                             TemplateToken::Formatter(TemplateFormatterToken {
                                 has_custom_prefix: false,
                                 placeholder: "time",
+                                arg: None,
                             }),
                             TemplateToken::Literal(TemplateLiteral {
                                 literal: String::from("}"),
@@ -321,6 +473,7 @@ mod tests {
                                 literal: String::from("hello "),
                             }),
                             TemplateToken::StyleRange(TemplateStyleRange {
+                                style: None,
                                 body: Template {
                                     tokens: vec![TemplateToken::Literal(TemplateLiteral {
                                         literal: String::from("world"),
@@ -342,6 +495,7 @@ mod tests {
                                 literal: String::from("hello "),
                             }),
                             TemplateToken::StyleRange(TemplateStyleRange {
+                                style: None,
                                 body: Template {
                                     tokens: vec![
                                         TemplateToken::Literal(TemplateLiteral {
@@ -350,6 +504,7 @@ mod tests {
                                         TemplateToken::Formatter(TemplateFormatterToken {
                                             has_custom_prefix: false,
                                             placeholder: "level",
+                                            arg: None,
                                         }),
                                         TemplateToken::Literal(TemplateLiteral {
                                             literal: String::from(" "),
@@ -357,6 +512,7 @@ mod tests {
                                         TemplateToken::Formatter(TemplateFormatterToken {
                                             has_custom_prefix: true,
                                             placeholder: "c_pat",
+                                            arg: None,
                                         }),
                                         TemplateToken::Literal(TemplateLiteral {
                                             literal: String::from(" {escape}"),
@@ -370,6 +526,114 @@ mod tests {
             );
         }
 
+        #[test]
+        fn test_parse_style_range_explicit() {
+            use crate::pattern_parser::style::StyleColor;
+
+            assert_eq!(
+                parse_template_str(r#"{^dim}[{time}]{$} {^bold cyan}{$}{^red}"#),
+                Ok((
+                    "",
+                    Template {
+                        tokens: vec![
+                            TemplateToken::StyleRange(TemplateStyleRange {
+                                style: Some(vec![StyleAttr::Faint]),
+                                body: Template {
+                                    tokens: vec![
+                                        TemplateToken::Literal(TemplateLiteral {
+                                            literal: String::from("["),
+                                        }),
+                                        TemplateToken::Formatter(TemplateFormatterToken {
+                                            has_custom_prefix: false,
+                                            placeholder: "time",
+                                            arg: None,
+                                        }),
+                                        TemplateToken::Literal(TemplateLiteral {
+                                            literal: String::from("]"),
+                                        }),
+                                    ],
+                                },
+                            }),
+                            TemplateToken::Literal(TemplateLiteral {
+                                literal: String::from(" "),
+                            }),
+                            TemplateToken::StyleRange(TemplateStyleRange {
+                                style: Some(vec![
+                                    StyleAttr::Bold,
+                                    StyleAttr::Color(StyleColor::Cyan)
+                                ]),
+                                body: Template { tokens: vec![] },
+                            }),
+                            // Falls back to a level style range if there is no `{$}`
+                            TemplateToken::StyleRange(TemplateStyleRange {
+                                style: None,
+                                body: Template {
+                                    tokens: vec![TemplateToken::Literal(TemplateLiteral {
+                                        literal: String::from("red"),
+                                    }),],
+                                },
+                            }),
+                        ],
+                    }
+                ))
+            );
+
+            assert!(parse_template_str(r#"{^unknown}text{$}"#).is_err());
+            assert!(parse_template_str(r#"{^dim}{^{level}}{$}"#).is_err());
+            assert!(parse_template_str(r#"text{$}"#).is_err());
+        }
+
+        #[test]
+        fn test_parse_conditional() {
+            assert_eq!(
+                parse_template_str(r#"{?logger:[{^cyan}{logger}{$}] }{payload}"#),
+                Ok((
+                    "",
+                    Template {
+                        tokens: vec![
+                            TemplateToken::Conditional(TemplateConditional {
+                                condition: "logger",
+                                body: Template {
+                                    tokens: vec![
+                                        TemplateToken::Literal(TemplateLiteral {
+                                            literal: String::from("["),
+                                        }),
+                                        TemplateToken::StyleRange(TemplateStyleRange {
+                                            style: Some(vec![StyleAttr::Color(
+                                                crate::pattern_parser::style::StyleColor::Cyan
+                                            )]),
+                                            body: Template {
+                                                tokens: vec![TemplateToken::Formatter(
+                                                    TemplateFormatterToken {
+                                                        has_custom_prefix: false,
+                                                        placeholder: "logger",
+                                                        arg: None,
+                                                    }
+                                                )],
+                                            },
+                                        }),
+                                        TemplateToken::Literal(TemplateLiteral {
+                                            literal: String::from("] "),
+                                        }),
+                                    ],
+                                },
+                            }),
+                            TemplateToken::Formatter(TemplateFormatterToken {
+                                has_custom_prefix: false,
+                                placeholder: "payload",
+                                arg: None,
+                            }),
+                        ],
+                    }
+                ))
+            );
+
+            assert!(parse_template_str(r#"{?logger:{?kv:text}}"#).is_err());
+            assert!(parse_template_str(r#"{^{?logger:text}}"#).is_err());
+            assert!(parse_template_str(r#"{?logger}"#).is_err());
+            assert!(parse_template_str(r#"{?:text}"#).is_err());
+        }
+
         #[test]
         fn test_parse_style_range_nested() {
             assert!(parse_template_str(r#"hello {^ hello {^ world } }"#).is_err());
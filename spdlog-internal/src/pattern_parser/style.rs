@@ -0,0 +1,126 @@
+macro_rules! define_style_colors {
+    ( $( $variant:ident => $name:literal ),+ $(,)? ) => {
+        #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+        pub enum StyleColor {
+            $( $variant ),+
+        }
+
+        impl StyleColor {
+            #[must_use]
+            fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $( $name => Some(Self::$variant), )+
+                    _ => None,
+                }
+            }
+
+            #[must_use]
+            pub fn variant_name(&self) -> &'static str {
+                match self {
+                    $( Self::$variant => stringify!($variant), )+
+                }
+            }
+        }
+    };
+}
+
+define_style_colors! {
+    Black => "black",
+    Red => "red",
+    Green => "green",
+    Yellow => "yellow",
+    Blue => "blue",
+    Magenta => "magenta",
+    Cyan => "cyan",
+    White => "white",
+}
+
+/// An attribute of an explicit style specified in a template, e.g. `bold` or
+/// `cyan` in `{^bold cyan}...{$}`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StyleAttr {
+    Color(StyleColor),
+    BgColor(StyleColor),
+    Bold,
+    Faint,
+    Italic,
+    Underline,
+    SlowBlink,
+    RapidBlink,
+    Invert,
+    Conceal,
+    Strikethrough,
+}
+
+impl StyleAttr {
+    #[must_use]
+    pub fn parse(word: &str) -> Option<Self> {
+        if let Some(color) = word.strip_prefix("bg_") {
+            return StyleColor::from_name(color).map(Self::BgColor);
+        }
+        if let Some(color) = StyleColor::from_name(word) {
+            return Some(Self::Color(color));
+        }
+        let attr = match word {
+            "bold" => Self::Bold,
+            "faint" | "dim" => Self::Faint,
+            "italic" => Self::Italic,
+            "underline" => Self::Underline,
+            "slow_blink" | "blink" => Self::SlowBlink,
+            "rapid_blink" => Self::RapidBlink,
+            "invert" => Self::Invert,
+            "conceal" => Self::Conceal,
+            "strikethrough" => Self::Strikethrough,
+            _ => return None,
+        };
+        Some(attr)
+    }
+
+    /// Returns the name of the corresponding setter of `StyleBuilder`.
+    #[must_use]
+    pub fn builder_setter(&self) -> &'static str {
+        match self {
+            Self::Color(_) => "color",
+            Self::BgColor(_) => "bg_color",
+            Self::Bold => "bold",
+            Self::Faint => "faint",
+            Self::Italic => "italic",
+            Self::Underline => "underline",
+            Self::SlowBlink => "slow_blink",
+            Self::RapidBlink => "rapid_blink",
+            Self::Invert => "invert",
+            Self::Conceal => "conceal",
+            Self::Strikethrough => "strikethrough",
+        }
+    }
+
+    #[must_use]
+    pub fn color(&self) -> Option<StyleColor> {
+        match self {
+            Self::Color(color) | Self::BgColor(color) => Some(*color),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            StyleAttr::parse("cyan"),
+            Some(StyleAttr::Color(StyleColor::Cyan))
+        );
+        assert_eq!(
+            StyleAttr::parse("bg_red"),
+            Some(StyleAttr::BgColor(StyleColor::Red))
+        );
+        assert_eq!(StyleAttr::parse("dim"), Some(StyleAttr::Faint));
+        assert_eq!(StyleAttr::parse("faint"), Some(StyleAttr::Faint));
+        assert_eq!(StyleAttr::parse("bg_bold"), None);
+        assert_eq!(StyleAttr::parse("Cyan"), None);
+        assert_eq!(StyleAttr::parse(""), None);
+    }
+}
@@ -0,0 +1,39 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{Ident, ItemFn, LitStr};
+
+pub fn instrument(args: TokenStream, item: ItemFn) -> syn::Result<TokenStream> {
+    if let Some(asyncness) = item.sig.asyncness {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            "`instrument` does not support async functions yet",
+        ));
+    }
+
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = item;
+    let name = LitStr::new(&sig.ident.to_string(), sig.ident.span());
+    let guard = Ident::new("__spdlog_span", Span::mixed_site());
+
+    let emitted = quote! {
+        #(#attrs)*
+        #vis #sig {
+            let #guard = ::spdlog::__normalize_forward!(
+                __instrument_impl => default[
+                    logger: ::spdlog::span::__DefaultLogger,
+                    kv: {},
+                    log_enter: true,
+                    level: ::spdlog::Level::Info,
+                    name: #name
+                ],
+                #args
+            );
+            #block
+        }
+    };
+    Ok(emitted)
+}
@@ -5,6 +5,7 @@
 //!
 //! [`spdlog-rs`]: https://crates.io/crates/spdlog-rs
 
+mod instrument;
 mod normalize_forward;
 mod pattern;
 
@@ -27,6 +28,12 @@ pub fn runtime_pattern(input: TokenStream) -> TokenStream {
     into_or_error(pattern::runtime_pattern_impl(runtime_pattern).map_err(Error::PatternParser))
 }
 
+#[proc_macro_attribute]
+pub fn instrument(args: TokenStream, item: TokenStream) -> TokenStream {
+    let item = syn::parse_macro_input!(item);
+    into_or_error(instrument::instrument(args.into(), item).map_err(Error::Instrument))
+}
+
 // Example:
 //
 // ```rust
@@ -43,6 +50,7 @@ pub fn normalize_forward(input: TokenStream) -> TokenStream {
 enum Error {
     PatternParser(spdlog_internal::pattern_parser::Error),
     NormalizeForward(syn::Error),
+    Instrument(syn::Error),
 }
 
 impl Error {
@@ -52,7 +60,7 @@ impl Error {
                 let error = err.to_string();
                 quote!(compile_error!(#error))
             }
-            Error::NormalizeForward(err) => err.to_compile_error(),
+            Error::NormalizeForward(err) | Error::Instrument(err) => err.to_compile_error(),
         }
     }
 }
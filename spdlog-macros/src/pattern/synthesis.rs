@@ -5,9 +5,11 @@ use quote::ToTokens as _;
 use spdlog_internal::pattern_parser::{
     error::TemplateError,
     parse::{Template, TemplateFormatterToken, TemplateLiteral, TemplateToken},
-    Error, PatternKind as GenericPatternKind, PatternRegistry as GenericPatternRegistry, Result,
+    style::StyleAttr,
+    Condition, Error, PatternKind as GenericPatternKind, PatternRegistry as GenericPatternRegistry,
+    Result,
 };
-use syn::{Expr, ExprLit, Lit, LitStr, Path};
+use syn::{Expr, ExprLit, Ident, Lit, LitStr, Path};
 
 type PatternRegistry = GenericPatternRegistry<Path>;
 type PatternKind = GenericPatternKind<Path>;
@@ -22,11 +24,11 @@ impl Synthesiser {
     }
 
     pub fn synthesize(&self, template: &Template) -> Result<TokenStream> {
-        let expr = self.build_expr(template, false)?;
+        let expr = self.build_expr(template, &mut false)?;
         Ok(expr.into_token_stream())
     }
 
-    fn build_expr(&self, template: &Template, mut style_range_seen: bool) -> Result<Expr> {
+    fn build_expr(&self, template: &Template, style_range_seen: &mut bool) -> Result<Expr> {
         let mut tuple_elems = Vec::with_capacity(template.tokens.len());
 
         for token in &template.tokens {
@@ -35,13 +37,27 @@ impl Synthesiser {
                 TemplateToken::Formatter(formatter_token) => {
                     self.build_formatter_creation(formatter_token)?
                 }
-                TemplateToken::StyleRange(style_range_token) => {
-                    if style_range_seen {
-                        return Err(Error::Template(TemplateError::MultipleStyleRange));
+                TemplateToken::StyleRange(style_range_token) => match &style_range_token.style {
+                    None => {
+                        if *style_range_seen {
+                            return Err(Error::Template(TemplateError::MultipleStyleRange));
+                        }
+                        *style_range_seen = true;
+                        let nested_pattern =
+                            self.build_expr(&style_range_token.body, style_range_seen)?;
+                        self.build_style_range_creation(nested_pattern)?
                     }
-                    style_range_seen = true;
-                    let nested_pattern = self.build_expr(&style_range_token.body, true)?;
-                    self.build_style_range_creation(nested_pattern)?
+                    Some(style) => {
+                        let nested_pattern =
+                            self.build_expr(&style_range_token.body, style_range_seen)?;
+                        self.build_styled_range_creation(nested_pattern, style)?
+                    }
+                },
+                TemplateToken::Conditional(conditional_token) => {
+                    let condition = Condition::parse(conditional_token.condition)?;
+                    let nested_pattern =
+                        self.build_expr(&conditional_token.body, style_range_seen)?;
+                    self.build_conditional_creation(nested_pattern, condition)?
                 }
             };
             tuple_elems.push(token_template_expr);
@@ -67,10 +83,24 @@ impl Synthesiser {
             formatter_token.placeholder,
         )?;
 
-        let factory = factory_of_pattern(pattern);
-        let stream = quote::quote!( #factory() );
-        let factory_call = syn::parse2(stream).unwrap();
-        Ok(Expr::Call(factory_call))
+        let stream = match (pattern, formatter_token.arg) {
+            (PatternKind::BuiltIn(builtin), Some(arg)) => {
+                let struct_path = syn::parse_str::<Path>(&format!(
+                    "::spdlog::formatter::__pattern::{}::new",
+                    builtin.struct_name_with_arg(arg)?
+                ))
+                .unwrap();
+                let arg = LitStr::new(arg, Span::mixed_site());
+                // The argument has been validated above, so it never fails
+                quote::quote!( #struct_path(#arg).unwrap() )
+            }
+            _ => {
+                pattern.check_arg(formatter_token.arg)?;
+                let factory = factory_of_pattern(pattern);
+                quote::quote!( #factory() )
+            }
+        };
+        Ok(syn::parse2(stream).unwrap())
     }
 
     fn build_style_range_creation(&self, body: Expr) -> Result<Expr> {
@@ -80,6 +110,46 @@ impl Synthesiser {
         let expr = syn::parse2(stream).unwrap();
         Ok(Expr::Call(expr))
     }
+
+    fn build_conditional_creation(&self, body: Expr, condition: Condition) -> Result<Expr> {
+        let conditional_pattern_new_path: Path =
+            syn::parse_str("::spdlog::formatter::__pattern::Conditional::new").unwrap();
+        let condition: Path = syn::parse_str(&format!(
+            "::spdlog::formatter::__pattern::Condition::{}",
+            condition.variant_name()
+        ))
+        .unwrap();
+        let stream = quote::quote!( #conditional_pattern_new_path (#condition, #body) );
+        let expr = syn::parse2(stream).unwrap();
+        Ok(Expr::Call(expr))
+    }
+
+    fn build_styled_range_creation(&self, body: Expr, style: &[StyleAttr]) -> Result<Expr> {
+        let style_range_pattern_with_style_path: Path =
+            syn::parse_str("::spdlog::formatter::__pattern::StyleRange::with_style").unwrap();
+        let setters = style.iter().map(|attr| {
+            let setter = Ident::new(attr.builder_setter(), Span::mixed_site());
+            match attr.color() {
+                Some(color) => {
+                    let color: Path = syn::parse_str(&format!(
+                        "::spdlog::terminal_style::Color::{}",
+                        color.variant_name()
+                    ))
+                    .unwrap();
+                    quote::quote!( .#setter(#color) )
+                }
+                None => quote::quote!( .#setter() ),
+            }
+        });
+        let stream = quote::quote!(
+            #style_range_pattern_with_style_path(
+                #body,
+                ::spdlog::terminal_style::Style::builder() #(#setters)* .build()
+            )
+        );
+        let expr = syn::parse2(stream).unwrap();
+        Ok(Expr::Call(expr))
+    }
 }
 
 pub(crate) fn factory_of_pattern(pattern: &PatternKind) -> Cow<'_, Path> {
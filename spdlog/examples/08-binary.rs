@@ -0,0 +1,47 @@
+// Usage:
+//
+// - `cargo run --example 08-binary` writes some logs to the binary file
+//   `binary.bin` next to the executable.
+// - `cargo run --example 08-binary -- decode <FILE>` decodes a binary log file
+//   and prints the records to stdout.
+
+use std::{env, io, path::Path};
+
+use spdlog::{
+    binary::{self, BinaryDecoder},
+    formatter::FullFormatter,
+    log_deferred,
+    prelude::*,
+    sink::BinaryFileSink,
+};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    match args.as_slice() {
+        [] => write_binary_file(&env::current_exe()?.with_file_name("binary.bin")),
+        [command, path] if command == "decode" => decode_binary_file(Path::new(path)),
+        _ => Err("usage: 08-binary [decode <FILE>]".into()),
+    }
+}
+
+fn write_binary_file(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let sink = BinaryFileSink::builder().path(path).build_arc()?;
+    let logger = Logger::builder().sink(sink).name("binary").build()?;
+
+    for id in 0..3 {
+        // The arguments are written as raw values, the payload is never formatted here
+        log_deferred!(logger: logger, Level::Info, "request {} done in {}ms", id, 1.5 * id as f64);
+    }
+    log_deferred!(logger: logger, Level::Warn, "unexpected address {}", binary::display(&"127.0.0.1"));
+
+    // Records logged by other macros are written with their formatted payload
+    info!(logger: logger, "bye");
+
+    println!("logs are written to `{}`", path.display());
+    Ok(())
+}
+
+fn decode_binary_file(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    BinaryDecoder::open(path)?.write_formatted(&FullFormatter::new(), io::stdout().lock())?;
+    Ok(())
+}
@@ -0,0 +1,427 @@
+//! Encodes and decodes the binary log format.
+//!
+//! A file starts with `MAGIC` (including the format version), followed by
+//! frames. Each frame starts with a tag byte:
+//!
+//! - `FRAME_CALLSITE`: callsite id, format string, source location.
+//! - `FRAME_DEFERRED`: callsite id, record header, arguments.
+//! - `FRAME_TEXT`: record header, source location, payload.
+//!
+//! The record header consists of the level, the time, the thread ID, the
+//! logger name and the key-values. Key-values are written as their count,
+//! followed by each key and its value, which is encoded like an argument.
+//! Integers are LEB128 varints (signed ones are zigzag-encoded),
+//! strings are prefixed by their length in bytes, and optional values are
+//! prefixed by a presence byte.
+
+use std::{
+    borrow::Cow,
+    io::{self, Read},
+    time::{Duration, SystemTime},
+};
+
+use crate::{binary::Arg, kv::Value, Error, Level, Record, Result, SourceLocation};
+
+pub(crate) const MAGIC: &[u8; 8] = b"SPDLOGB\x01";
+
+pub(crate) const FRAME_CALLSITE: u8 = 1;
+pub(crate) const FRAME_DEFERRED: u8 = 2;
+pub(crate) const FRAME_TEXT: u8 = 3;
+
+const ARG_BOOL: u8 = 0;
+const ARG_CHAR: u8 = 1;
+const ARG_I64: u8 = 2;
+const ARG_U64: u8 = 3;
+const ARG_I128: u8 = 4;
+const ARG_U128: u8 = 5;
+const ARG_F32: u8 = 6;
+const ARG_F64: u8 = 7;
+const ARG_STR: u8 = 8;
+const ARG_FORMATTED: u8 = 9;
+
+// Encoding
+//
+
+pub(crate) fn put_callsite(
+    buf: &mut Vec<u8>,
+    id: u32,
+    format: &str,
+    srcloc: Option<&SourceLocation>,
+) {
+    buf.push(FRAME_CALLSITE);
+    put_varint(buf, id.into());
+    put_str(buf, format);
+    put_srcloc(buf, srcloc);
+}
+
+pub(crate) fn put_deferred_record(buf: &mut Vec<u8>, id: u32, record: &Record, args: &[Arg]) {
+    buf.push(FRAME_DEFERRED);
+    put_varint(buf, id.into());
+    put_record_header(buf, record);
+    put_varint(buf, args.len() as u64);
+    for arg in args {
+        put_arg(buf, arg);
+    }
+}
+
+pub(crate) fn put_text_record(buf: &mut Vec<u8>, record: &Record) {
+    buf.push(FRAME_TEXT);
+    put_record_header(buf, record);
+    put_srcloc(buf, record.source_location());
+    put_str(buf, record.payload());
+}
+
+fn put_record_header(buf: &mut Vec<u8>, record: &Record) {
+    buf.push(record.level() as u8);
+    let since_epoch = record
+        .time()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    put_varint(buf, since_epoch.as_secs());
+    put_varint(buf, since_epoch.subsec_nanos().into());
+    put_varint(buf, record.tid());
+    put_opt_str(buf, record.logger_name());
+
+    let kvs = record.key_values();
+    put_varint(buf, kvs.len() as u64);
+    for (key, value) in kvs {
+        put_str(buf, key.as_str());
+        put_arg(buf, &value_to_arg(&value));
+    }
+}
+
+// Values that are neither primitives nor strings are formatted.
+#[must_use]
+fn value_to_arg<'a>(value: &'a Value<'_>) -> Arg<'a> {
+    if let Some(value) = value.to_bool() {
+        Arg::Bool(value)
+    } else if let Some(value) = value.to_char() {
+        Arg::Char(value)
+    } else if let Some(value) = value.to_i64() {
+        Arg::I64(value)
+    } else if let Some(value) = value.to_u64() {
+        Arg::U64(value)
+    } else if let Some(value) = value.to_i128() {
+        Arg::I128(value)
+    } else if let Some(value) = value.to_u128() {
+        Arg::U128(value)
+    } else if let Some(value) = value.to_f64() {
+        Arg::F64(value)
+    } else if let Some(value) = value.to_borrowed_str() {
+        Arg::Str(Cow::Borrowed(value))
+    } else {
+        Arg::Formatted(Cow::Owned(value.to_string()))
+    }
+}
+
+#[must_use]
+pub(crate) fn arg_to_value<'a>(arg: &'a Arg<'_>) -> Value<'a> {
+    match arg {
+        Arg::Bool(value) => Value::from(*value),
+        Arg::Char(value) => Value::from(*value),
+        Arg::I64(value) => Value::from(*value),
+        Arg::U64(value) => Value::from(*value),
+        Arg::I128(value) => Value::from(*value),
+        Arg::U128(value) => Value::from(*value),
+        Arg::F32(value) => Value::from(*value),
+        Arg::F64(value) => Value::from(*value),
+        Arg::Str(value) | Arg::Formatted(value) => Value::from(value.as_ref()),
+    }
+}
+
+fn put_srcloc(buf: &mut Vec<u8>, srcloc: Option<&SourceLocation>) {
+    match srcloc {
+        Some(srcloc) => {
+            buf.push(1);
+            put_str(buf, srcloc.module_path());
+            put_str(buf, srcloc.file());
+            put_varint(buf, srcloc.line().into());
+            put_varint(buf, srcloc.column().into());
+        }
+        None => buf.push(0),
+    }
+}
+
+fn put_arg(buf: &mut Vec<u8>, arg: &Arg) {
+    match arg {
+        Arg::Bool(value) => {
+            buf.push(ARG_BOOL);
+            buf.push(u8::from(*value));
+        }
+        Arg::Char(value) => {
+            buf.push(ARG_CHAR);
+            put_varint(buf, u32::from(*value).into());
+        }
+        &Arg::I64(value) => {
+            buf.push(ARG_I64);
+            put_varint(buf, ((value << 1) ^ (value >> 63)) as u64);
+        }
+        Arg::U64(value) => {
+            buf.push(ARG_U64);
+            put_varint(buf, *value);
+        }
+        Arg::I128(value) => {
+            buf.push(ARG_I128);
+            buf.extend_from_slice(&value.to_le_bytes());
+        }
+        Arg::U128(value) => {
+            buf.push(ARG_U128);
+            buf.extend_from_slice(&value.to_le_bytes());
+        }
+        Arg::F32(value) => {
+            buf.push(ARG_F32);
+            buf.extend_from_slice(&value.to_le_bytes());
+        }
+        Arg::F64(value) => {
+            buf.push(ARG_F64);
+            buf.extend_from_slice(&value.to_le_bytes());
+        }
+        Arg::Str(value) => {
+            buf.push(ARG_STR);
+            put_str(buf, value);
+        }
+        Arg::Formatted(value) => {
+            buf.push(ARG_FORMATTED);
+            put_str(buf, value);
+        }
+    }
+}
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn put_str(buf: &mut Vec<u8>, value: &str) {
+    put_varint(buf, value.len() as u64);
+    buf.extend_from_slice(value.as_bytes());
+}
+
+fn put_opt_str(buf: &mut Vec<u8>, value: Option<&str>) {
+    match value {
+        Some(value) => {
+            buf.push(1);
+            put_str(buf, value);
+        }
+        None => buf.push(0),
+    }
+}
+
+// Decoding
+//
+
+pub(crate) struct RecordHeader {
+    pub(crate) level: Level,
+    pub(crate) time: SystemTime,
+    pub(crate) tid: u64,
+    pub(crate) logger_name: Option<String>,
+    pub(crate) kvs: Vec<(String, Arg<'static>)>,
+}
+
+pub(crate) struct DecodedSrcloc {
+    pub(crate) module_path: String,
+    pub(crate) file: String,
+    pub(crate) line: u32,
+    pub(crate) column: u32,
+}
+
+#[must_use]
+pub(crate) fn invalid(msg: impl Into<String>) -> Error {
+    Error::DecodeBinaryLog(msg.into())
+}
+
+pub(crate) struct Reader<R> {
+    inner: R,
+}
+
+impl<R: Read> Reader<R> {
+    #[must_use]
+    pub(crate) fn new(inner: R) -> Self {
+        Self { inner }
+    }
+
+    pub(crate) fn read_magic(&mut self) -> Result<()> {
+        let mut magic = [0; MAGIC.len()];
+        self.read_exact(&mut magic)?;
+        if magic[..MAGIC.len() - 1] != MAGIC[..MAGIC.len() - 1] {
+            return Err(invalid("not a binary log file"));
+        }
+        if magic[MAGIC.len() - 1] != MAGIC[MAGIC.len() - 1] {
+            return Err(invalid(format!(
+                "unsupported format version {}",
+                magic[MAGIC.len() - 1]
+            )));
+        }
+        Ok(())
+    }
+
+    // Returns `None` at the end of the input.
+    pub(crate) fn read_frame_tag(&mut self) -> Result<Option<u8>> {
+        let mut tag = [0];
+        loop {
+            match self.inner.read(&mut tag) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(tag[0])),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(Error::ReadFile(err)),
+            }
+        }
+    }
+
+    pub(crate) fn read_callsite(&mut self) -> Result<(u32, String, Option<DecodedSrcloc>)> {
+        let id = self.read_u32()?;
+        let format = self.read_str()?;
+        let srcloc = self.read_srcloc()?;
+        Ok((id, format, srcloc))
+    }
+
+    pub(crate) fn read_callsite_id(&mut self) -> Result<u32> {
+        self.read_u32()
+    }
+
+    pub(crate) fn read_record_header(&mut self) -> Result<RecordHeader> {
+        let level = self.read_u8()?;
+        let level = Level::from_usize(level.into())
+            .ok_or_else(|| invalid(format!("invalid level {level}")))?;
+        let secs = self.read_varint()?;
+        let nanos = self.read_u32()?;
+        if nanos >= 1_000_000_000 {
+            return Err(invalid("time out of range"));
+        }
+        let time = SystemTime::UNIX_EPOCH
+            .checked_add(Duration::new(secs, nanos))
+            .ok_or_else(|| invalid("time out of range"))?;
+        let tid = self.read_varint()?;
+        let logger_name = self.read_opt_str()?;
+        let kvs_len = self.read_varint()?;
+        let mut kvs = Vec::new();
+        for _ in 0..kvs_len {
+            kvs.push((self.read_str()?, self.read_arg()?));
+        }
+        Ok(RecordHeader {
+            level,
+            time,
+            tid,
+            logger_name,
+            kvs,
+        })
+    }
+
+    pub(crate) fn read_args(&mut self) -> Result<Vec<Arg<'static>>> {
+        let len = self.read_varint()?;
+        let mut args = Vec::new();
+        for _ in 0..len {
+            args.push(self.read_arg()?);
+        }
+        Ok(args)
+    }
+
+    pub(crate) fn read_srcloc(&mut self) -> Result<Option<DecodedSrcloc>> {
+        if !self.read_bool()? {
+            return Ok(None);
+        }
+        Ok(Some(DecodedSrcloc {
+            module_path: self.read_str()?,
+            file: self.read_str()?,
+            line: self.read_u32()?,
+            column: self.read_u32()?,
+        }))
+    }
+
+    pub(crate) fn read_str(&mut self) -> Result<String> {
+        let len = self.read_varint()?;
+        let mut bytes = Vec::new();
+        (&mut self.inner)
+            .take(len)
+            .read_to_end(&mut bytes)
+            .map_err(Error::ReadFile)?;
+        if bytes.len() as u64 != len {
+            return Err(truncated());
+        }
+        String::from_utf8(bytes).map_err(|_| invalid("invalid UTF-8 string"))
+    }
+
+    fn read_arg(&mut self) -> Result<Arg<'static>> {
+        let arg = match self.read_u8()? {
+            ARG_BOOL => Arg::Bool(self.read_bool()?),
+            ARG_CHAR => {
+                Arg::Char(char::from_u32(self.read_u32()?).ok_or_else(|| invalid("invalid char"))?)
+            }
+            ARG_I64 => {
+                let value = self.read_varint()?;
+                Arg::I64((value >> 1) as i64 ^ -((value & 1) as i64))
+            }
+            ARG_U64 => Arg::U64(self.read_varint()?),
+            ARG_I128 => Arg::I128(i128::from_le_bytes(self.read_array()?)),
+            ARG_U128 => Arg::U128(u128::from_le_bytes(self.read_array()?)),
+            ARG_F32 => Arg::F32(f32::from_le_bytes(self.read_array()?)),
+            ARG_F64 => Arg::F64(f64::from_le_bytes(self.read_array()?)),
+            ARG_STR => Arg::Str(Cow::Owned(self.read_str()?)),
+            ARG_FORMATTED => Arg::Formatted(Cow::Owned(self.read_str()?)),
+            tag => return Err(invalid(format!("invalid argument tag {tag}"))),
+        };
+        Ok(arg)
+    }
+
+    fn read_opt_str(&mut self) -> Result<Option<String>> {
+        if self.read_bool()? {
+            self.read_str().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn read_bool(&mut self) -> Result<bool> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(invalid(format!("invalid bool {value}"))),
+        }
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        self.read_varint()?
+            .try_into()
+            .map_err(|_| invalid("integer out of range"))
+    }
+
+    fn read_varint(&mut self) -> Result<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_u8()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("varint too long"))
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        self.read_array().map(|[byte]| byte)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut bytes = [0; N];
+        self.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.inner.read_exact(buf).map_err(|err| {
+            if err.kind() == io::ErrorKind::UnexpectedEof {
+                truncated()
+            } else {
+                Error::ReadFile(err)
+            }
+        })
+    }
+}
+
+#[must_use]
+fn truncated() -> Error {
+    invalid("unexpected end of input")
+}
@@ -0,0 +1,200 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{BufReader, Read, Write},
+    path::Path,
+};
+
+use crate::{
+    binary::{
+        codec::{self, DecodedSrcloc, Reader},
+        format_payload,
+    },
+    formatter::{Formatter, FormatterContext},
+    Error, RecordOwned, Result, SourceLocation, StringBuf,
+};
+
+/// Decodes binary log files written by [`BinaryFileSink`].
+///
+/// It is an iterator of decoded records. Records logged by [`log_deferred!`]
+/// have their payloads formatted when they are decoded.
+///
+/// Decoded records have the key-values of the logged records as their own,
+/// regardless of whether they were given at the callsite, by the [scoped
+/// context] or bound to the logger. Decoded records do not have thread names,
+/// or elapsed time.
+///
+/// # Error
+///
+/// If the input is not a valid binary log file, or is truncated (e.g. the
+/// process crashed while writing), [`Error::DecodeBinaryLog`] will be returned,
+/// and the iteration ends. If an error occurs reading the input,
+/// [`Error::ReadFile`] will be returned.
+///
+/// # Examples
+///
+/// See [module level documentation](crate::binary).
+///
+/// [`BinaryFileSink`]: crate::sink::BinaryFileSink
+/// [`log_deferred!`]: crate::log_deferred
+/// [scoped context]: crate::context
+pub struct BinaryDecoder<R> {
+    reader: Reader<R>,
+    callsites: HashMap<u32, DecodedCallsite>,
+    // Strings of source locations, leaked to be `&'static str`
+    static_strs: HashSet<&'static str>,
+    finished: bool,
+}
+
+struct DecodedCallsite {
+    format: String,
+    srcloc: Option<SourceLocation>,
+}
+
+impl BinaryDecoder<BufReader<File>> {
+    /// Opens a binary log file to decode.
+    ///
+    /// # Error
+    ///
+    /// If an error occurs opening the file, [`Error::OpenFile`] will be
+    /// returned. If an error occurs reading the header of the file,
+    /// [`Error::ReadFile`] or [`Error::DecodeBinaryLog`] will be returned.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path).map_err(Error::OpenFile)?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read> BinaryDecoder<R> {
+    /// Constructs a `BinaryDecoder` reading from the reader.
+    ///
+    /// The reader should be buffered, as the decoder performs many small reads.
+    ///
+    /// # Error
+    ///
+    /// If an error occurs reading the header of the input, [`Error::ReadFile`]
+    /// or [`Error::DecodeBinaryLog`] will be returned.
+    pub fn new(reader: R) -> Result<Self> {
+        let mut reader = Reader::new(reader);
+        reader.read_magic()?;
+        Ok(Self {
+            reader,
+            callsites: HashMap::new(),
+            static_strs: HashSet::new(),
+            finished: false,
+        })
+    }
+
+    /// Decodes the remaining records, formats them with the formatter and
+    /// writes them to the writer.
+    ///
+    /// # Error
+    ///
+    /// Errors of decoding and formatting records are returned, as well as
+    /// [`Error::WriteRecord`] and [`Error::FlushBuffer`] if an error occurs
+    /// writing to the writer.
+    pub fn write_formatted<W: Write>(
+        &mut self,
+        formatter: &dyn Formatter,
+        mut writer: W,
+    ) -> Result<()> {
+        let mut string_buf = StringBuf::new();
+        for record in self.by_ref() {
+            let record = record?;
+            string_buf.clear();
+            let mut ctx = FormatterContext::new();
+            formatter.format(&record.as_ref(), &mut string_buf, &mut ctx)?;
+            writer
+                .write_all(string_buf.as_bytes())
+                .map_err(Error::WriteRecord)?;
+        }
+        writer.flush().map_err(Error::FlushBuffer)
+    }
+
+    fn decode_next(&mut self) -> Result<Option<RecordOwned>> {
+        loop {
+            let Some(tag) = self.reader.read_frame_tag()? else {
+                return Ok(None);
+            };
+            match tag {
+                codec::FRAME_CALLSITE => {
+                    // Callsite ids are only unique in a process, if the file is appended by
+                    // another process, the latest definition takes effect.
+                    let (id, format, srcloc) = self.reader.read_callsite()?;
+                    let srcloc = srcloc.map(|srcloc| self.intern_srcloc(srcloc));
+                    self.callsites
+                        .insert(id, DecodedCallsite { format, srcloc });
+                }
+                codec::FRAME_DEFERRED => {
+                    let id = self.reader.read_callsite_id()?;
+                    let header = self.reader.read_record_header()?;
+                    let args = self.reader.read_args()?;
+                    let callsite = self
+                        .callsites
+                        .get(&id)
+                        .ok_or_else(|| codec::invalid(format!("undefined callsite {id}")))?;
+
+                    let mut payload = String::new();
+                    format_payload(&mut payload, &callsite.format, &args);
+                    return Ok(Some(RecordOwned::new_decoded(
+                        header.level,
+                        payload,
+                        callsite.srcloc.clone(),
+                        header.logger_name,
+                        header.time,
+                        header.tid,
+                        &header.kvs,
+                    )));
+                }
+                codec::FRAME_TEXT => {
+                    let header = self.reader.read_record_header()?;
+                    let srcloc = self.reader.read_srcloc()?;
+                    let srcloc = srcloc.map(|srcloc| self.intern_srcloc(srcloc));
+                    let payload = self.reader.read_str()?;
+                    return Ok(Some(RecordOwned::new_decoded(
+                        header.level,
+                        payload,
+                        srcloc,
+                        header.logger_name,
+                        header.time,
+                        header.tid,
+                        &header.kvs,
+                    )));
+                }
+                tag => return Err(codec::invalid(format!("invalid frame tag {tag}"))),
+            }
+        }
+    }
+
+    // `SourceLocation` only holds `&'static str`, so the strings are leaked.
+    // Each distinct string is leaked once per decoder, which is bounded by the
+    // number of callsites in the program that wrote the file.
+    fn intern_srcloc(&mut self, srcloc: DecodedSrcloc) -> SourceLocation {
+        let mut to_static = |s: String| -> &'static str {
+            if let Some(&interned) = self.static_strs.get(s.as_str()) {
+                return interned;
+            }
+            let s = Box::leak(s.into_boxed_str());
+            self.static_strs.insert(s);
+            s
+        };
+        let module_path = to_static(srcloc.module_path);
+        let file = to_static(srcloc.file);
+        SourceLocation::__new(module_path, file, srcloc.line, srcloc.column)
+    }
+}
+
+impl<R: Read> Iterator for BinaryDecoder<R> {
+    type Item = Result<RecordOwned>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let result = self.decode_next().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.finished = true;
+        }
+        result
+    }
+}
@@ -0,0 +1,566 @@
+//! Provides deferred formatting and a compact binary log format.
+//!
+//! Formatting the payload is often the most expensive part of logging a record
+//! on the caller thread. Macro [`log_deferred!`] captures the raw argument
+//! values instead of formatting them, and the payload is only formatted when a
+//! sink or a formatter asks for it via [`Record::payload`].
+//!
+//! [`BinaryFileSink`] never formats payloads. It writes records in a compact
+//! binary encoding: the format string and the source location of a callsite are
+//! written once, and each record only contains the callsite id, the level, the
+//! time, the thread ID, the logger name, the key-values and the raw argument
+//! values. Records logged by other macros are written with their formatted
+//! payload. Key-values that are neither primitives nor strings are written
+//! formatted.
+//!
+//! [`BinaryDecoder`] reads a binary log file back into [`RecordOwned`]s, which
+//! can be formatted by any [`Formatter`] (see [`BinaryDecoder::write_formatted`]).
+//! The example `08-binary` is a small tool converting binary log files to text.
+//!
+//! # Arguments
+//!
+//! The arguments must implement [`DeferredArg`], which is implemented for
+//! integers, floating-point numbers, `bool`, `char`, strings and references to
+//! them. Values of other types can be wrapped by [`display`] or [`debug`], they
+//! are formatted on the caller thread.
+//!
+//! # Format strings
+//!
+//! Only the placeholders `{}`, `{:?}`, `{N}` and `{N:?}` are supported, as well
+//! as the escapes `{{` and `}}`. Format specs such as width and precision,
+//! named arguments and captured identifiers are rejected at compile time.
+//!
+//! # Examples
+//!
+//! ```
+//! use spdlog::{binary, log_deferred, sink::BinaryFileSink, Level, Logger};
+//!
+//! # let path = std::env::temp_dir().join("spdlog-rs-doctest-binary.bin");
+//! let sink = BinaryFileSink::builder().path(&path).truncate(true).build_arc()?;
+//! let logger = Logger::builder().sink(sink).build()?;
+//!
+//! # struct Peer;
+//! # impl std::fmt::Display for Peer {
+//! #     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result { f.write_str("peer") }
+//! # }
+//! # let peer = Peer;
+//! let (port, elapsed) = (8080, 0.25);
+//! log_deferred!(logger: logger, Level::Info, "listening on port {} in {}s", port, elapsed);
+//! log_deferred!(logger: logger, Level::Info, "connected to {}", binary::display(&peer));
+//! # drop(logger);
+//!
+//! let records = binary::BinaryDecoder::open(&path)?.collect::<spdlog::Result<Vec<_>>>()?;
+//! assert_eq!(records[0].payload(), "listening on port 8080 in 0.25s");
+//! assert_eq!(records[1].payload(), "connected to peer");
+//! # Ok::<(), spdlog::Error>(())
+//! ```
+//!
+//! [`log_deferred!`]: crate::log_deferred
+//! [`Record::payload`]: crate::Record::payload
+//! [`BinaryFileSink`]: crate::sink::BinaryFileSink
+//! [`RecordOwned`]: crate::RecordOwned
+//! [`Formatter`]: crate::formatter::Formatter
+
+pub(crate) mod codec;
+mod decoder;
+
+use std::{
+    borrow::Cow,
+    fmt::{self, Write as _},
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+pub use decoder::*;
+
+use crate::{sync::*, Level, Logger, Record, SourceLocation};
+
+static NEXT_CALLSITE_ID: AtomicU32 = AtomicU32::new(1);
+
+/// A callsite of [`log_deferred!`].
+///
+/// It is created by the macro as a `static`, and holds the format string that
+/// is checked at compile time.
+///
+/// [`log_deferred!`]: crate::log_deferred
+#[derive(Debug)]
+pub struct Callsite {
+    format: &'static str,
+    id: AtomicU32,
+}
+
+impl Callsite {
+    // Used at macro `log_deferred!`
+    #[doc(hidden)]
+    #[must_use]
+    pub const fn __new(format: &'static str, args: usize) -> Self {
+        check_format(format, args);
+        Self {
+            format,
+            id: AtomicU32::new(0),
+        }
+    }
+
+    /// Gets the format string.
+    #[must_use]
+    pub fn format(&self) -> &'static str {
+        self.format
+    }
+
+    // Gets the id of the callsite, which is unique in the process and assigned
+    // on the first call.
+    #[must_use]
+    pub(crate) fn id(&self) -> u32 {
+        let id = self.id.load(Ordering::Relaxed);
+        if id != 0 {
+            return id;
+        }
+        let new_id = NEXT_CALLSITE_ID.fetch_add(1, Ordering::Relaxed);
+        match self
+            .id
+            .compare_exchange(0, new_id, Ordering::Relaxed, Ordering::Relaxed)
+        {
+            Ok(_) => new_id,
+            Err(id) => id,
+        }
+    }
+}
+
+// Panics at compile time if the format string contains unsupported
+// placeholders, or references arguments that do not exist.
+const fn check_format(format: &str, args: usize) {
+    let bytes = format.as_bytes();
+    let mut i = 0;
+    let mut next_arg = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'{' {
+            if i + 1 < bytes.len() && bytes[i + 1] == b'{' {
+                i += 2;
+                continue;
+            }
+            i += 1;
+            let mut index = 0;
+            let mut has_index = false;
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                index = index * 10 + (bytes[i] - b'0') as usize;
+                has_index = true;
+                i += 1;
+            }
+            if !has_index {
+                index = next_arg;
+                next_arg += 1;
+            }
+            if i + 1 < bytes.len() && bytes[i] == b':' && bytes[i + 1] == b'?' {
+                i += 2;
+            }
+            if i >= bytes.len() || bytes[i] != b'}' {
+                panic!("`log_deferred!` only supports placeholders `{{}}`, `{{:?}}`, `{{N}}` and `{{N:?}}`");
+            }
+            if index >= args {
+                panic!("`log_deferred!` format string references an argument that does not exist");
+            }
+        } else if bytes[i] == b'}' {
+            if i + 1 >= bytes.len() || bytes[i + 1] != b'}' {
+                panic!("`log_deferred!` format string contains an unmatched `}}`");
+            }
+            i += 1;
+        }
+        i += 1;
+    }
+}
+
+// Formats the arguments with the format string.
+//
+// Format strings of callsites have been checked at compile time, but decoded
+// ones may be malformed, in which case the invalid parts are written verbatim.
+pub(crate) fn format_payload(dest: &mut String, format: &str, args: &[Arg<'_>]) {
+    let bytes = format.as_bytes();
+    let (mut i, mut start, mut next_arg) = (0, 0, 0);
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' | b'}' if bytes.get(i + 1) == Some(&bytes[i]) => {
+                dest.push_str(&format[start..=i]);
+                i += 2;
+                start = i;
+            }
+            b'{' => {
+                let Some(len) = format[i..].find('}') else {
+                    break;
+                };
+                let end = i + len;
+                let spec = &format[i + 1..end];
+                let (index, debug) = match spec.strip_suffix(":?") {
+                    Some(index) => (index, true),
+                    None => (spec, false),
+                };
+                let index = if index.is_empty() {
+                    next_arg += 1;
+                    Some(next_arg - 1)
+                } else {
+                    index.parse().ok()
+                };
+
+                dest.push_str(&format[start..i]);
+                match index.and_then(|index| args.get(index)) {
+                    Some(arg) if debug => _ = write!(dest, "{arg:?}"),
+                    Some(arg) => _ = write!(dest, "{arg}"),
+                    None => dest.push_str(&format[i..=end]),
+                }
+                i = end + 1;
+                start = i;
+            }
+            _ => i += 1,
+        }
+    }
+    dest.push_str(&format[start..]);
+}
+
+// Used at macro `log_deferred!`
+//
+// The `Debug` implementation formats values with `{:?}`, arguments wrapped by
+// `display` or `debug` are written verbatim in both cases.
+#[doc(hidden)]
+#[derive(Clone, PartialEq)]
+pub enum Arg<'a> {
+    Bool(bool),
+    Char(char),
+    I64(i64),
+    U64(u64),
+    I128(i128),
+    U128(u128),
+    F32(f32),
+    F64(f64),
+    Str(Cow<'a, str>),
+    Formatted(Cow<'a, str>),
+}
+
+impl fmt::Display for Arg<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bool(value) => fmt::Display::fmt(value, f),
+            Self::Char(value) => fmt::Display::fmt(value, f),
+            Self::I64(value) => fmt::Display::fmt(value, f),
+            Self::U64(value) => fmt::Display::fmt(value, f),
+            Self::I128(value) => fmt::Display::fmt(value, f),
+            Self::U128(value) => fmt::Display::fmt(value, f),
+            Self::F32(value) => fmt::Display::fmt(value, f),
+            Self::F64(value) => fmt::Display::fmt(value, f),
+            Self::Str(value) | Self::Formatted(value) => f.write_str(value),
+        }
+    }
+}
+
+impl fmt::Debug for Arg<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bool(value) => fmt::Debug::fmt(value, f),
+            Self::Char(value) => fmt::Debug::fmt(value, f),
+            Self::I64(value) => fmt::Debug::fmt(value, f),
+            Self::U64(value) => fmt::Debug::fmt(value, f),
+            Self::I128(value) => fmt::Debug::fmt(value, f),
+            Self::U128(value) => fmt::Debug::fmt(value, f),
+            Self::F32(value) => fmt::Debug::fmt(value, f),
+            Self::F64(value) => fmt::Debug::fmt(value, f),
+            Self::Str(value) => fmt::Debug::fmt(value, f),
+            Self::Formatted(value) => f.write_str(value),
+        }
+    }
+}
+
+/// Represents types that can be arguments of [`log_deferred!`].
+///
+/// Values are captured without being formatted. For types not implementing
+/// this trait, use [`display`] or [`debug`].
+///
+/// [`log_deferred!`]: crate::log_deferred
+pub trait DeferredArg {
+    #[doc(hidden)]
+    #[must_use]
+    fn __to_arg(&self) -> Arg<'_>;
+}
+
+macro_rules! impl_deferred_arg {
+    ( $($ty:ty => $variant:ident as $as:ty),+ $(,)? ) => {
+        $(impl DeferredArg for $ty {
+            fn __to_arg(&self) -> Arg<'_> {
+                Arg::$variant(*self as $as)
+            }
+        })+
+    };
+}
+
+impl_deferred_arg! {
+    bool => Bool as bool,
+    char => Char as char,
+    i8 => I64 as i64,
+    i16 => I64 as i64,
+    i32 => I64 as i64,
+    i64 => I64 as i64,
+    isize => I64 as i64,
+    u8 => U64 as u64,
+    u16 => U64 as u64,
+    u32 => U64 as u64,
+    u64 => U64 as u64,
+    usize => U64 as u64,
+    i128 => I128 as i128,
+    u128 => U128 as u128,
+    f32 => F32 as f32,
+    f64 => F64 as f64,
+}
+
+impl DeferredArg for str {
+    fn __to_arg(&self) -> Arg<'_> {
+        Arg::Str(Cow::Borrowed(self))
+    }
+}
+
+impl DeferredArg for String {
+    fn __to_arg(&self) -> Arg<'_> {
+        Arg::Str(Cow::Borrowed(self))
+    }
+}
+
+impl DeferredArg for Cow<'_, str> {
+    fn __to_arg(&self) -> Arg<'_> {
+        Arg::Str(Cow::Borrowed(self))
+    }
+}
+
+impl<T: DeferredArg + ?Sized> DeferredArg for &T {
+    fn __to_arg(&self) -> Arg<'_> {
+        (**self).__to_arg()
+    }
+}
+
+/// Wraps a value implementing [`Display`] as an argument of [`log_deferred!`].
+///
+/// The value is formatted on the caller thread, and written as a string.
+///
+/// [`Display`]: std::fmt::Display
+/// [`log_deferred!`]: crate::log_deferred
+#[must_use]
+pub fn display<T: fmt::Display + ?Sized>(value: &T) -> DisplayArg<'_, T> {
+    DisplayArg(value)
+}
+
+/// Wraps a value implementing [`Debug`] as an argument of [`log_deferred!`].
+///
+/// The value is formatted on the caller thread, and written as a string.
+///
+/// [`Debug`]: std::fmt::Debug
+/// [`log_deferred!`]: crate::log_deferred
+#[must_use]
+pub fn debug<T: fmt::Debug + ?Sized>(value: &T) -> DebugArg<'_, T> {
+    DebugArg(value)
+}
+
+/// An argument of [`log_deferred!`] created by [`display`].
+///
+/// It is formatted by [`Display`] regardless of the placeholder.
+///
+/// [`log_deferred!`]: crate::log_deferred
+/// [`Display`]: std::fmt::Display
+pub struct DisplayArg<'a, T: ?Sized>(&'a T);
+
+/// An argument of [`log_deferred!`] created by [`debug`].
+///
+/// It is formatted by [`Debug`] regardless of the placeholder.
+///
+/// [`log_deferred!`]: crate::log_deferred
+/// [`Debug`]: std::fmt::Debug
+pub struct DebugArg<'a, T: ?Sized>(&'a T);
+
+impl<T: fmt::Display + ?Sized> fmt::Display for DisplayArg<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: fmt::Display + ?Sized> fmt::Debug for DisplayArg<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: fmt::Display + ?Sized> DeferredArg for DisplayArg<'_, T> {
+    fn __to_arg(&self) -> Arg<'_> {
+        Arg::Formatted(Cow::Owned(self.0.to_string()))
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Display for DebugArg<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for DebugArg<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> DeferredArg for DebugArg<'_, T> {
+    fn __to_arg(&self) -> Arg<'_> {
+        Arg::Formatted(Cow::Owned(format!("{:?}", self.0)))
+    }
+}
+
+impl Arg<'_> {
+    #[must_use]
+    fn to_static(&self) -> Arg<'static> {
+        match self {
+            Self::Bool(value) => Arg::Bool(*value),
+            Self::Char(value) => Arg::Char(*value),
+            Self::I64(value) => Arg::I64(*value),
+            Self::U64(value) => Arg::U64(*value),
+            Self::I128(value) => Arg::I128(*value),
+            Self::U128(value) => Arg::U128(*value),
+            Self::F32(value) => Arg::F32(*value),
+            Self::F64(value) => Arg::F64(*value),
+            Self::Str(value) => Arg::Str(Cow::Owned(value.clone().into_owned())),
+            Self::Formatted(value) => Arg::Formatted(Cow::Owned(value.clone().into_owned())),
+        }
+    }
+}
+
+// The callsite and the unformatted arguments of a record logged by
+// `log_deferred!`.
+#[derive(Clone, Debug)]
+pub(crate) struct Deferred<'a> {
+    callsite: &'static Callsite,
+    args: Args<'a>,
+    payload: OnceCell<String>,
+}
+
+// Not `Cow<'a, [Arg<'a>]>`, which would make `Deferred` invariant over `'a`.
+#[derive(Clone, Debug)]
+enum Args<'a> {
+    Borrowed(&'a [Arg<'a>]),
+    Owned(Vec<Arg<'a>>),
+}
+
+impl<'a> Args<'a> {
+    #[must_use]
+    fn as_slice(&self) -> &[Arg<'a>] {
+        match self {
+            Self::Borrowed(args) => args,
+            Self::Owned(args) => args,
+        }
+    }
+}
+
+impl<'a> Deferred<'a> {
+    #[must_use]
+    pub(crate) fn callsite(&self) -> &'static Callsite {
+        self.callsite
+    }
+
+    #[must_use]
+    pub(crate) fn args(&self) -> &[Arg<'a>] {
+        self.args.as_slice()
+    }
+
+    // Copies the arguments without formatting them, so that the payload can be
+    // formatted later on another thread.
+    #[must_use]
+    pub(crate) fn to_static(&self) -> Deferred<'static> {
+        Deferred {
+            callsite: self.callsite,
+            args: Args::Owned(self.args().iter().map(Arg::to_static).collect()),
+            payload: self.payload.clone(),
+        }
+    }
+
+    // Formats the payload on the first call.
+    #[must_use]
+    pub(crate) fn payload(&self) -> &str {
+        self.payload.get_or_init(|| {
+            let mut payload = String::new();
+            format_payload(&mut payload, self.callsite.format, self.args());
+            payload
+        })
+    }
+
+    #[cfg(test)]
+    #[must_use]
+    pub(crate) fn is_formatted(&self) -> bool {
+        self.payload.get().is_some()
+    }
+}
+
+// Used at macro `log_deferred!`
+#[doc(hidden)]
+pub fn __log(
+    logger: &Logger,
+    level: Level,
+    srcloc: Option<SourceLocation>,
+    callsite: &'static Callsite,
+    args: &[Arg],
+) {
+    let deferred = Deferred {
+        callsite,
+        args: Args::Borrowed(args),
+        payload: OnceCell::new(),
+    };
+    let record = Record::new_deferred(level, &deferred, srcloc, logger.name());
+    logger.log(&record);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{log_deferred, test_utils::*};
+
+    #[test]
+    fn format() {
+        let sink = Arc::new(TestSink::new());
+        let logger = build_test_logger(|b| b.sink(sink.clone()));
+
+        let (int, float, string) = (-42, 1.5, String::from("str"));
+        log_deferred!(logger: logger, Level::Info, "no args {{}}");
+        log_deferred!(logger: logger, Level::Info, "{} {} {:?} {}", int, float, string, 'c');
+        log_deferred!(logger: logger, Level::Info, "{1} {0:?} {1:?} {{{}}}", "a\n", u128::MAX);
+        log_deferred!(
+            logger: logger,
+            Level::Info,
+            "{} {:?} {}",
+            display(&Level::Warn),
+            debug(&Some(1)),
+            debug("x")
+        );
+
+        assert_eq!(
+            sink.payloads(),
+            [
+                "no args {}",
+                "-42 1.5 \"str\" c",
+                "340282366920938463463374607431768211455 \"a\\n\" 340282366920938463463374607431768211455 {a\n}",
+                "warn Some(1) \"x\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn format_malformed() {
+        let format = |format: &str| {
+            let mut dest = String::new();
+            format_payload(&mut dest, format, &[Arg::U64(1)]);
+            dest
+        };
+        assert_eq!(format("{} {}"), "1 {}");
+        assert_eq!(format("{x} {:>5}"), "{x} {:>5}");
+        assert_eq!(format("{0} {"), "1 {");
+        assert_eq!(format("}"), "}");
+    }
+
+    #[test]
+    fn callsite_id() {
+        static A: Callsite = Callsite::__new("a", 0);
+        static B: Callsite = Callsite::__new("b {} {:?}", 2);
+
+        let id = A.id();
+        assert_ne!(id, 0);
+        assert_eq!(A.id(), id);
+        assert_ne!(B.id(), id);
+    }
+}
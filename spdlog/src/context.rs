@@ -0,0 +1,301 @@
+//! Provides scoped contextual key-values.
+//!
+//! Key-values pushed into the context are attached to every [`Record`] created
+//! in the scope, in addition to the key-values given at the logging callsite.
+//! This is also known as MDC (Mapped Diagnostic Context) in other logging
+//! libraries.
+//!
+//! The context is thread-local. Use [`push`] to attach key-values until the
+//! returned guard is dropped:
+//!
+//! ```
+//! use spdlog::{context, info};
+//! # use spdlog::formatter::{pattern, PatternFormatter};
+#![doc = include_str!(concat!(env!("OUT_DIR"), "/test_utils/common_for_doc_test.rs"))]
+//!
+//! # let (doctest, sink) = test_utils::echo_logger_from_pattern(pattern!("{payload} {kv}{eol}"), None);
+//! # let handle_request = |request_id: u64| {
+//! let _guard = context::push(spdlog::kv!{ request_id });
+//! info!(logger: doctest, "request accepted", kv: { method = "GET" });
+//! // ...
+//! info!(logger: doctest, "request finished");
+//! # };
+//! # handle_request(42);
+//! # assert_eq!(
+//! #     sink.clone_string().replace("\r", ""),
+//! /* Output */ "request accepted method=GET request_id=42\nrequest finished request_id=42\n"
+//! # );
+//! ```
+//!
+//! Since the thread-local context does not follow a future across threads or
+//! `.await` points, use [`FutureExt::with_context`] or
+//! [`FutureExt::in_current_context`] to make a future carry a context with
+//! it, so that the context is active whenever the future is polled. This takes
+//! the place of a task-local context, it works with any async runtime and does
+//! not require a crate feature.
+//!
+//! # Precedence
+//!
+//! If a key is pushed again in an inner scope, the inner value replaces the
+//! outer one until the inner guard is dropped. If a key from the context also
+//! appears at the logging callsite, only the pair from the callsite is
+//! attached to the record, and the one from the context is shadowed. Key-values
+//! bound to the logger are shadowed by both of them, see [`KeyValues::get`].
+//!
+//! [`Record`]: crate::Record
+//! [`KeyValues::get`]: crate::kv::KeyValues::get
+
+use std::{
+    cell::RefCell,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context as TaskContext, Poll},
+};
+
+use crate::{
+    kv::{self, PairOwned},
+    sync::*,
+};
+
+thread_local! {
+    static CURRENT: RefCell<Option<Arc<[PairOwned]>>> = const { RefCell::new(None) };
+}
+
+/// Pushes key-values into the context of the current thread.
+///
+/// The key-values are attached to records created on the current thread until
+/// the returned guard is dropped. Guards should be dropped in the reverse order
+/// in which they were created, which is naturally the case when they are bound
+/// to scopes.
+///
+/// The [`kv!`] macro is a convenient way to build the key-values, it accepts
+/// the same syntax as the `kv` parameter of logging macros.
+///
+/// [`kv!`]: crate::kv!
+#[must_use = "the key-values are popped immediately if the guard is not held"]
+pub fn push(pairs: &[kv::Pair]) -> ContextGuard {
+    let current = current_pairs();
+    let merged = kv::merge_owned(current.as_deref().unwrap_or_default(), pairs);
+    Context(Some(merged)).enter()
+}
+
+/// A snapshot of the key-values in a context.
+///
+/// It can be captured on one thread with [`Context::current`], and entered on
+/// another thread with [`Context::enter`] or attached to a future with
+/// [`FutureExt::with_context`].
+#[derive(Clone, Debug, Default)]
+pub struct Context(Option<Arc<[PairOwned]>>);
+
+impl Context {
+    /// Captures the context of the current thread.
+    ///
+    /// This is cheap, the key-values are shared rather than copied.
+    #[must_use]
+    pub fn current() -> Self {
+        Self(current_pairs())
+    }
+
+    /// Gets the key-values in this context.
+    #[must_use]
+    pub fn key_values(&self) -> kv::KeyValues<'_> {
+        kv::KeyValues::with_borrowed(&[], self.0.as_deref().unwrap_or_default(), &[])
+    }
+
+    /// Makes this context the context of the current thread, until the returned
+    /// guard is dropped.
+    ///
+    /// The key-values previously in the context of the current thread are not
+    /// inherited.
+    #[must_use = "the context is left immediately if the guard is not held"]
+    pub fn enter(&self) -> ContextGuard {
+        let prev = CURRENT.with(|current| current.replace(self.0.clone()));
+        ContextGuard {
+            prev,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+/// A guard that restores the previous context of the current thread when
+/// dropped.
+///
+/// It is returned by [`push`] and [`Context::enter`].
+#[must_use = "the context is restored immediately if the guard is not held"]
+pub struct ContextGuard {
+    prev: Option<Arc<[PairOwned]>>,
+    // The guard manipulates thread-local state, so it must be dropped on the
+    // thread it was created.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        let prev = self.prev.take();
+        CURRENT.with(|current| *current.borrow_mut() = prev);
+    }
+}
+
+/// Extension methods for futures to carry a [`Context`].
+pub trait FutureExt: Future + Sized {
+    /// Attaches the given context to this future.
+    ///
+    /// The context is entered each time the future is polled, so records
+    /// created inside the future carry its key-values regardless of which
+    /// thread polls it.
+    fn with_context(self, context: Context) -> WithContext<Self> {
+        WithContext {
+            inner: self,
+            context,
+        }
+    }
+
+    /// Attaches the context of the current thread to this future.
+    ///
+    /// This is a shorthand for `self.with_context(Context::current())`.
+    fn in_current_context(self) -> WithContext<Self> {
+        self.with_context(Context::current())
+    }
+}
+
+impl<F: Future> FutureExt for F {}
+
+/// A future with a [`Context`] attached.
+///
+/// It is returned by [`FutureExt::with_context`] and
+/// [`FutureExt::in_current_context`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct WithContext<F> {
+    inner: F,
+    context: Context,
+}
+
+impl<F: Future> Future for WithContext<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        // SAFETY: `inner` is structurally pinned, it is never moved out of
+        // `self`, and `WithContext` does not implement `Drop` or `Unpin`
+        // manually.
+        let this = unsafe { self.get_unchecked_mut() };
+        let _guard = this.context.enter();
+        unsafe { Pin::new_unchecked(&mut this.inner) }.poll(cx)
+    }
+}
+
+#[must_use]
+pub(crate) fn current_pairs() -> Option<Arc<[PairOwned]>> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::{RawWaker, RawWakerVTable, Waker};
+
+    use super::*;
+    use crate::{info, kv::Key, test_utils::*};
+
+    fn current_string() -> String {
+        Context::current()
+            .key_values()
+            .iter()
+            .map(|(k, v)| format!("{}={v}", k.as_str()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn push_and_pop() {
+        assert_eq!(current_string(), "");
+        {
+            let _outer = push(crate::kv!(a = 1, b = "x"));
+            assert_eq!(current_string(), "a=1 b=x");
+            {
+                let _inner = push(crate::kv!(b = "y", c = true));
+                assert_eq!(current_string(), "a=1 b=y c=true");
+            }
+            assert_eq!(current_string(), "a=1 b=x");
+        }
+        assert_eq!(current_string(), "");
+    }
+
+    #[test]
+    fn records_carry_context() {
+        let sink = Arc::new(TestSink::new());
+        let logger = build_test_logger(|b| b.sink(sink.clone()));
+
+        {
+            let _guard = push(crate::kv!(request_id = 42));
+            info!(logger: logger, "with context", kv: { request_id = 1, extra = true });
+        }
+        info!(logger: logger, "without context");
+
+        let records = sink.records();
+        let kvs = |i: usize| {
+            records[i]
+                .key_values()
+                .iter()
+                .map(|(k, v)| format!("{}={v}", k.as_str()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(kvs(0), ["request_id=1", "extra=true"]);
+        assert_eq!(
+            records[0]
+                .key_values()
+                .get(Key::__from_static_str("request_id"))
+                .map(|v| v.to_string()),
+            Some("1".to_string())
+        );
+        assert!(kvs(1).is_empty());
+    }
+
+    #[test]
+    fn enter_on_another_thread() {
+        let _guard = push(crate::kv!(id = 7));
+        let context = Context::current();
+        std::thread::spawn(move || {
+            assert_eq!(current_string(), "");
+            let _guard = context.enter();
+            assert_eq!(current_string(), "id=7");
+            assert_eq!(
+                Context::current()
+                    .key_values()
+                    .get(Key::__from_static_str("id"))
+                    .map(|v| v.to_string()),
+                Some("7".to_string())
+            );
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[test]
+    fn future_with_context() {
+        fn noop_waker() -> Waker {
+            const VTABLE: RawWakerVTable = RawWakerVTable::new(
+                |_| RawWaker::new(std::ptr::null(), &VTABLE),
+                |_| {},
+                |_| {},
+                |_| {},
+            );
+            unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) }
+        }
+
+        let future = {
+            let _guard = push(crate::kv!(task = "a"));
+            std::future::poll_fn(|_| Poll::Ready(current_string())).in_current_context()
+        };
+        assert_eq!(current_string(), "");
+
+        let mut future = std::pin::pin!(future);
+        let waker = noop_waker();
+        let mut cx = TaskContext::from_waker(&waker);
+        assert_eq!(
+            future.as_mut().poll(&mut cx),
+            Poll::Ready("task=a".to_string())
+        );
+        assert_eq!(current_string(), "");
+    }
+}
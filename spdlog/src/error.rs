@@ -15,7 +15,8 @@ use std::{
 
 pub use crate::env_level::EnvLevelError;
 #[cfg(feature = "multi-thread")]
-use crate::{sink::Task, RecordOwned};
+use crate::sink::Task;
+use crate::RecordOwned;
 
 /// Contains most errors of this crate.
 #[derive(Debug)]
@@ -47,6 +48,11 @@ pub enum Error {
     /// [`Sink`]: crate::sink::Sink
     OpenFile(io::Error),
 
+    /// Returned by [`Sink`]s when an error occurs in reading a file.
+    ///
+    /// [`Sink`]: crate::sink::Sink
+    ReadFile(io::Error),
+
     /// Returned by [`Sink`]s when an error occurs in querying the metadata of a
     /// file.
     ///
@@ -63,6 +69,30 @@ pub enum Error {
     /// [`Sink`]: crate::sink::Sink
     RemoveFile(io::Error),
 
+    /// Returned by [`Sink`]s when an error occurs in synchronizing a file or a
+    /// directory to the storage device.
+    ///
+    /// See [`SyncPolicy`].
+    ///
+    /// [`Sink`]: crate::sink::Sink
+    /// [`SyncPolicy`]: crate::sink::SyncPolicy
+    SyncFile(io::Error),
+
+    /// Returned by [`Sink`]s when an error occurs in locking a file shared with
+    /// other processes.
+    ///
+    /// See [`FileSinkBuilder::multi_process`].
+    ///
+    /// [`Sink`]: crate::sink::Sink
+    /// [`FileSinkBuilder::multi_process`]: crate::sink::FileSinkBuilder::multi_process
+    LockFile(io::Error),
+
+    /// Returned by [`BinaryDecoder`] when the input is not a valid binary log
+    /// file.
+    ///
+    /// [`BinaryDecoder`]: crate::binary::BinaryDecoder
+    DecodeBinaryLog(String),
+
     /// Returned by [`from_str`] when the string doesn't match any of the log
     /// levels.
     ///
@@ -75,7 +105,6 @@ pub enum Error {
     /// Returned by [`Sink`]s when an error occurs in sending to the channel.
     ///
     /// [`Sink`]: crate::sink::Sink
-    #[cfg(feature = "multi-thread")]
     SendToChannel(SendToChannelError, SendToChannelErrorDropped),
 
     /// Returned by [`runtime_pattern!`] when the pattern is failed to be built
@@ -91,6 +120,13 @@ pub enum Error {
     #[cfg(feature = "serde")]
     SerializeRecord(io::Error),
 
+    /// Returned by [`reopen_files_on_signal`] when an error occurs in setting
+    /// the signal handler.
+    ///
+    /// [`reopen_files_on_signal`]: crate::sink::reopen_files_on_signal
+    #[cfg(unix)]
+    SetSignalHandler(io::Error),
+
     /// Returned from a downstream implementation of `spdlog-rs`. Its actual
     /// error type may be a downstream struct.
     ///
@@ -117,19 +153,24 @@ impl Display for Error {
             Self::FlushBuffer(err) => write!(f, "flush buffer error: {err}"),
             Self::CreateDirectory(err) => write!(f, "create directory error: {err}"),
             Self::OpenFile(err) => write!(f, "open file error: {err}"),
+            Self::ReadFile(err) => write!(f, "read file error: {err}"),
             Self::QueryFileMetadata(err) => write!(f, "query file metadata error: {err}"),
             Self::RenameFile(err) => write!(f, "rename file error: {err}"),
             Self::RemoveFile(err) => write!(f, "remove file error: {err}"),
+            Self::SyncFile(err) => write!(f, "sync file error: {err}"),
+            Self::LockFile(err) => write!(f, "lock file error: {err}"),
+            Self::DecodeBinaryLog(msg) => write!(f, "decode binary log error: {msg}"),
             Self::ParseLevel(level_str) => {
                 write!(f, "attempted to convert a string that doesn't match an existing log level: {level_str}")
             }
             Self::InvalidArgument(err) => write!(f, "invalid argument {err}"),
-            #[cfg(feature = "multi-thread")]
             Self::SendToChannel(err, _) => write!(f, "failed to send message to channel: {err}"),
             #[cfg(feature = "runtime-pattern")]
             Self::BuildPattern(err) => write!(f, "failed to build pattern at runtime: {err}"),
             #[cfg(feature = "serde")]
             Self::SerializeRecord(err) => write!(f, "failed to serialize log: {err}"),
+            #[cfg(unix)]
+            Self::SetSignalHandler(err) => write!(f, "set signal handler error: {err}"),
             Self::Downstream(err) => write!(f, "{err}"),
             Self::Multiple(errs) => write!(f, "{errs:?}"),
             #[cfg(test)]
@@ -163,12 +204,51 @@ pub enum InvalidArgumentError {
     /// [`RotationPolicy`]: crate::sink::RotationPolicy
     RotationPolicy(String),
 
+    /// Invalid [`SyncPolicy`].
+    ///
+    /// See the documentation of [`SyncPolicy`] for the input requirements.
+    ///
+    /// [`SyncPolicy`]: crate::sink::SyncPolicy
+    SyncPolicy(String),
+
+    /// Invalid [`TimeZone`].
+    ///
+    /// See the documentation of [`TimeZone::fixed_offset`] for the input
+    /// requirements.
+    ///
+    /// [`TimeZone`]: crate::formatter::TimeZone
+    /// [`TimeZone::fixed_offset`]: crate::formatter::TimeZone::fixed_offset
+    TimeZone(String),
+
+    /// Invalid strftime format of [`FormattedTime`].
+    ///
+    /// [`FormattedTime`]: crate::formatter::__pattern::FormattedTime
+    TimeFormat(String),
+
     /// Invalid thread pool capacity.
     #[deprecated(
         since = "0.5.0",
         note = "non-zero thread pool capacity is now guarded by NonZeroUsize type"
     )]
     ThreadPoolCapacity(String),
+
+    /// Invalid OTLP endpoint.
+    ///
+    /// See the documentation of [`OtlpSinkBuilder::endpoint`] for the input
+    /// requirements.
+    ///
+    /// [`OtlpSinkBuilder::endpoint`]: crate::sink::OtlpSinkBuilder::endpoint
+    #[cfg(feature = "otlp")]
+    Endpoint(String),
+
+    /// Invalid HTTP header.
+    ///
+    /// See the documentation of [`OtlpSinkBuilder::header`] for the input
+    /// requirements.
+    ///
+    /// [`OtlpSinkBuilder::header`]: crate::sink::OtlpSinkBuilder::header
+    #[cfg(feature = "otlp")]
+    Header(String),
 }
 
 impl StdError for InvalidArgumentError {}
@@ -178,8 +258,15 @@ impl Display for InvalidArgumentError {
         match self {
             Self::LoggerName(err) => write!(f, "'logger name': {err}"),
             Self::RotationPolicy(value) => write!(f, "'rotation policy': {value}"),
+            Self::SyncPolicy(value) => write!(f, "'sync policy': {value}"),
+            Self::TimeZone(value) => write!(f, "'time zone': {value}"),
+            Self::TimeFormat(value) => write!(f, "'time format': {value}"),
             #[allow(deprecated)]
             Self::ThreadPoolCapacity(value) => write!(f, "'thread pool capacity': {value}"),
+            #[cfg(feature = "otlp")]
+            Self::Endpoint(value) => write!(f, "'endpoint': {value}"),
+            #[cfg(feature = "otlp")]
+            Self::Header(value) => write!(f, "'header': {value}"),
         }
     }
 }
@@ -222,7 +309,6 @@ impl Display for SetLoggerNameError {
 }
 
 /// Indicates that an error occurred while sending to channel.
-#[cfg(feature = "multi-thread")]
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum SendToChannelError {
@@ -237,10 +323,8 @@ pub enum SendToChannelError {
     Disconnected,
 }
 
-#[cfg(feature = "multi-thread")]
 impl StdError for SendToChannelError {}
 
-#[cfg(feature = "multi-thread")]
 impl Display for SendToChannelError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -253,7 +337,6 @@ impl Display for SendToChannelError {
 /// Contains data that is dropped after sending to the channel failed.
 ///
 /// You can handle them manually or just ignore them.
-#[cfg(feature = "multi-thread")]
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum SendToChannelErrorDropped {
@@ -286,23 +369,8 @@ impl Error {
 #[cfg(feature = "multi-thread")]
 impl Error {
     #[must_use]
-    pub(crate) fn from_crossbeam_send(err: crossbeam::channel::SendError<Task>) -> Self {
-        Self::SendToChannel(
-            SendToChannelError::Disconnected,
-            SendToChannelErrorDropped::from_task(err.0),
-        )
-    }
-
-    #[must_use]
-    pub(crate) fn from_crossbeam_try_send(err: crossbeam::channel::TrySendError<Task>) -> Self {
-        use crossbeam::channel::TrySendError;
-
-        let (error, dropped_task) = match err {
-            TrySendError::Full(dropped) => (SendToChannelError::Full, dropped),
-            TrySendError::Disconnected(dropped) => (SendToChannelError::Disconnected, dropped),
-        };
-
-        Self::SendToChannel(error, SendToChannelErrorDropped::from_task(dropped_task))
+    pub(crate) fn from_dropped_task(err: SendToChannelError, task: Task) -> Self {
+        Self::SendToChannel(err, SendToChannelErrorDropped::from_task(task))
     }
 }
 
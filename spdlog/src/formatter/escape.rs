@@ -0,0 +1,137 @@
+use std::fmt::{self, Write};
+
+/// Represents how untrusted text in log records is escaped when formatting.
+///
+/// Payloads and key-value pairs often contain user-controlled strings. Without
+/// escaping, such a string containing a line break can forge fake log lines,
+/// and one containing ANSI escape sequences can manipulate the terminal that
+/// displays the logs.
+///
+/// When escaping is enabled, the affected characters are replaced with their
+/// Rust escape sequences, e.g. a line feed becomes `\n` and an ESC becomes
+/// `\u{1b}`. Tabs are never escaped.
+///
+/// Modes are ordered by strictness, so the stricter one of two modes can be
+/// picked with [`Ord::max`].
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum EscapeMode {
+    /// Do not escape anything.
+    #[default]
+    Disabled,
+    /// Escape characters that could forge log lines or corrupt files, intended
+    /// for file output.
+    ///
+    /// This escapes control characters (including line breaks and ESC) and the
+    /// Unicode line and paragraph separators.
+    File,
+    /// Escape characters that could manipulate or mislead terminals, intended
+    /// for terminal output.
+    ///
+    /// In addition to what [`EscapeMode::File`] escapes, this also escapes
+    /// Unicode bidirectional formatting characters, which can visually reorder
+    /// the displayed text.
+    Terminal,
+}
+
+impl EscapeMode {
+    #[must_use]
+    fn should_escape(self, ch: char) -> bool {
+        match self {
+            Self::Disabled => false,
+            Self::File => is_file_unsafe(ch),
+            Self::Terminal => is_file_unsafe(ch) || is_bidi_control(ch),
+        }
+    }
+}
+
+#[must_use]
+fn is_file_unsafe(ch: char) -> bool {
+    (ch.is_control() && ch != '\t') || ch == '\u{2028}' || ch == '\u{2029}'
+}
+
+#[must_use]
+fn is_bidi_control(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{061c}' | '\u{200e}' | '\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}'
+    )
+}
+
+/// Writes `text` into `dest`, escaping characters according to `mode`.
+pub(crate) fn write_escaped(dest: &mut impl Write, text: &str, mode: EscapeMode) -> fmt::Result {
+    if mode == EscapeMode::Disabled {
+        return dest.write_str(text);
+    }
+
+    let mut begin = 0;
+    for (index, ch) in text.char_indices() {
+        if mode.should_escape(ch) {
+            dest.write_str(&text[begin..index])?;
+            write!(dest, "{}", ch.escape_default())?;
+            begin = index + ch.len_utf8();
+        }
+    }
+    dest.write_str(&text[begin..])
+}
+
+/// A [`Write`] adapter that escapes everything written through it.
+pub(crate) struct EscapeWriter<'a, W> {
+    inner: &'a mut W,
+    mode: EscapeMode,
+}
+
+impl<'a, W: Write> EscapeWriter<'a, W> {
+    #[must_use]
+    pub(crate) fn new(inner: &'a mut W, mode: EscapeMode) -> Self {
+        Self { inner, mode }
+    }
+}
+
+impl<W: Write> Write for EscapeWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        write_escaped(self.inner, s, self.mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn escape(text: &str, mode: EscapeMode) -> String {
+        let mut dest = String::new();
+        write_escaped(&mut dest, text, mode).unwrap();
+        dest
+    }
+
+    #[test]
+    fn modes() {
+        let input = "a\tb\r\nc\x1b[31md\u{2028}e\u{202e}f";
+
+        assert_eq!(escape(input, EscapeMode::Disabled), input);
+        assert_eq!(
+            escape(input, EscapeMode::File),
+            "a\tb\\r\\nc\\u{1b}[31md\\u{2028}e\u{202e}f"
+        );
+        assert_eq!(
+            escape(input, EscapeMode::Terminal),
+            "a\tb\\r\\nc\\u{1b}[31md\\u{2028}e\\u{202e}f"
+        );
+        assert_eq!(escape("plain text", EscapeMode::Terminal), "plain text");
+
+        let mut dest = String::new();
+        let (text, number) = ("x\ny", 1);
+        write!(
+            EscapeWriter::new(&mut dest, EscapeMode::File),
+            "{text}|{number}"
+        )
+        .unwrap();
+        assert_eq!(dest, "x\\ny|1");
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(EscapeMode::Disabled < EscapeMode::File);
+        assert!(EscapeMode::File < EscapeMode::Terminal);
+        assert_eq!(EscapeMode::File.max(EscapeMode::Disabled), EscapeMode::File);
+    }
+}
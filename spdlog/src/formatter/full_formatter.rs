@@ -3,7 +3,9 @@
 use std::fmt::{self, Write as _};
 
 use crate::{
-    formatter::{fmt_with_time, Formatter, FormatterContext, TimeDate},
+    formatter::{
+        fmt_with_time, write_escaped, EscapeMode, Formatter, FormatterContext, TimeDate, TimeZone,
+    },
     Error, Record, StringBuf, __EOL,
 };
 
@@ -94,6 +96,8 @@ impl FullFormatter {
     /// | [source_location] | `true`        |
     /// | [kv]              | `true`        |
     /// | [eol]             | `true`        |
+    /// | [time_zone]       | [local]       |
+    /// | [escape_mode]     | [disabled]    |
     ///
     /// [time]: FullFormatterBuilder::time
     /// [logger_name]: FullFormatterBuilder::logger_name
@@ -101,6 +105,10 @@ impl FullFormatter {
     /// [source_location]: FullFormatterBuilder::source_location
     /// [kv]: FullFormatterBuilder::kv
     /// [eol]: FullFormatterBuilder::eol
+    /// [time_zone]: FullFormatterBuilder::time_zone
+    /// [local]: TimeZone::local
+    /// [escape_mode]: FullFormatterBuilder::escape_mode
+    /// [disabled]: EscapeMode::Disabled
     #[must_use]
     pub fn builder() -> FullFormatterBuilder {
         FullFormatterBuilder(FormattingOptions {
@@ -110,6 +118,8 @@ impl FullFormatter {
             source_location: true,
             kv: true,
             eol: true,
+            time_zone: TimeZone::local(),
+            escape_mode: EscapeMode::Disabled,
         })
     }
 
@@ -128,6 +138,7 @@ impl FullFormatter {
             fmt_with_time(
                 ctx,
                 record,
+                self.options.time_zone,
                 |mut time: TimeDate| -> Result<(), fmt::Error> {
                     dest.write_str("[")?;
                     dest.write_str(time.full_second_str())?;
@@ -172,12 +183,15 @@ impl FullFormatter {
                 dest.write_str("]")
             },
         )?;
-        spacer.write_always(dest, |dest| dest.write_str(record.payload()))?;
+        let escape_mode = self.options.escape_mode.max(ctx.escape_mode());
+        spacer.write_always(dest, |dest| {
+            write_escaped(dest, record.payload(), escape_mode)
+        })?;
 
         let key_values = record.key_values();
         spacer.write_if(self.options.kv && !key_values.is_empty(), dest, |dest| {
             dest.write_str("{ ")?;
-            key_values.write_to(dest, false)?;
+            key_values.write_to(dest, false, escape_mode)?;
             dest.write_str(" }")
         })?;
 
@@ -269,6 +283,24 @@ impl FullFormatterBuilder {
         self
     }
 
+    /// Specify the time zone in which the time field is displayed.
+    #[must_use]
+    pub fn time_zone(&mut self, value: TimeZone) -> &mut Self {
+        self.0.time_zone = value;
+        self
+    }
+
+    /// Specify how the payload and key-values are escaped.
+    ///
+    /// If the sink requests a stricter mode via
+    /// [`FormatterContext::escape_mode`], that mode is used instead. See
+    /// [`EscapeMode`] for details.
+    #[must_use]
+    pub fn escape_mode(&mut self, value: EscapeMode) -> &mut Self {
+        self.0.escape_mode = value;
+        self
+    }
+
     /// Builds a `FullFormatter`.
     #[must_use]
     pub fn build(&mut self) -> FullFormatter {
@@ -286,6 +318,8 @@ struct FormattingOptions {
     source_location: bool,
     kv: bool,
     eol: bool,
+    time_zone: TimeZone,
+    escape_mode: EscapeMode,
 }
 
 struct AutoSpacer(bool);
@@ -349,7 +383,7 @@ impl AutoSpacer {
 
 #[cfg(test)]
 mod tests {
-    use chrono::prelude::*;
+    use chrono::prelude::{DateTime, FixedOffset, Local, Utc};
 
     use super::*;
     use crate::{kv, Level, RecordOwned, __EOL};
@@ -384,6 +418,77 @@ mod tests {
         assert_eq!(Some(36..40), ctx.style_range());
     }
 
+    #[test]
+    fn time_zone() {
+        let record = record();
+        let record = record.as_ref();
+
+        let mut buf = StringBuf::new();
+        let mut ctx = FormatterContext::new();
+        FullFormatter::builder()
+            .time_zone(TimeZone::utc())
+            .build()
+            .format(&record, &mut buf, &mut ctx)
+            .unwrap();
+        let utc_time: DateTime<Utc> = record.time().into();
+        assert_eq!(
+            format!(
+                "[{}] [logger] [warn] test log content {{ k1=114 k2=514 }}{}",
+                utc_time.format("%Y-%m-%d %H:%M:%S.%3f"),
+                __EOL
+            ),
+            buf
+        );
+
+        let mut buf = StringBuf::new();
+        let mut ctx = FormatterContext::new();
+        FullFormatter::builder()
+            .time_zone(TimeZone::fixed_offset(9 * 3600).unwrap())
+            .build()
+            .format(&record, &mut buf, &mut ctx)
+            .unwrap();
+        let fixed_time = utc_time.with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap());
+        assert!(buf.starts_with(&format!("[{}]", fixed_time.format("%Y-%m-%d %H:%M:%S.%3f"))));
+    }
+
+    #[test]
+    fn escape_mode() {
+        let kvs = [(
+            kv::Key::__from_static_str("user"),
+            kv::Value::from("bob\x1b[2J"),
+        )];
+        let record = Record::new(Level::Info, "a\nb", None, None, &kvs);
+
+        let format = |configured, requested| {
+            let mut buf = StringBuf::new();
+            let mut ctx = FormatterContext::new();
+            ctx.set_escape_mode(requested);
+            FullFormatter::builder()
+                .time(false)
+                .level(false)
+                .eol(false)
+                .escape_mode(configured)
+                .build()
+                .format(&record, &mut buf, &mut ctx)
+                .unwrap();
+            buf
+        };
+
+        assert_eq!(
+            format(EscapeMode::Disabled, EscapeMode::Disabled),
+            "a\nb { user=bob\x1b[2J }"
+        );
+        assert_eq!(
+            format(EscapeMode::File, EscapeMode::Disabled),
+            "a\\nb { user=bob\\u{1b}[2J }"
+        );
+        // The stricter mode requested by the sink takes effect
+        assert_eq!(
+            format(EscapeMode::Disabled, EscapeMode::Terminal),
+            "a\\nb { user=bob\\u{1b}[2J }"
+        );
+    }
+
     #[test]
     fn no_time() {
         let record = record();
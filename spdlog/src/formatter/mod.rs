@@ -52,24 +52,28 @@
 //! [`SinkPropAccess::set_formatter`]: crate::sink::SinkPropAccess::set_formatter
 //! [./examples]: https://github.com/SpriteOvO/spdlog-rs/tree/main/spdlog/examples
 
+mod escape;
 mod full_formatter;
 #[cfg(feature = "serde_json")]
 mod json_formatter;
-mod local_time_cacher;
 mod pattern_formatter;
+mod time_cacher;
+mod time_zone;
 mod unreachable_formatter;
 
 use std::ops::Range;
 
 use dyn_clone::*;
+pub use escape::*;
 pub use full_formatter::*;
 #[cfg(feature = "serde_json")]
 pub use json_formatter::*;
-pub(crate) use local_time_cacher::*;
 pub use pattern_formatter::*;
+pub(crate) use time_cacher::*;
+pub use time_zone::*;
 pub(crate) use unreachable_formatter::*;
 
-use crate::{Record, Result, StringBuf};
+use crate::{terminal_style::Style, Record, Result, StringBuf};
 
 /// Represents a formatter that can be used for formatting logs.
 ///
@@ -93,6 +97,8 @@ clone_trait_object!(Formatter);
 #[derive(Debug, Default)]
 pub struct FormatterContext<'a> {
     style_range: Option<Range<usize>>,
+    styled_ranges: Vec<(Range<usize>, Style)>,
+    escape_mode: EscapeMode,
     // Set to `Some` if the cached date time is locked in the upper caller.
     locked_time_date: Option<TimeDateLazyLocked<'a>>,
 }
@@ -103,6 +109,8 @@ impl FormatterContext<'_> {
     pub fn new() -> Self {
         Self {
             style_range: None,
+            styled_ranges: Vec::new(),
+            escape_mode: EscapeMode::Disabled,
             locked_time_date: None,
         }
     }
@@ -126,4 +134,44 @@ impl FormatterContext<'_> {
     pub fn style_range(&self) -> Option<Range<usize>> {
         self.style_range.clone() // This clone is cheap
     }
+
+    /// Adds a range (in bytes) of the formatted text with an explicit style.
+    ///
+    /// Unlike [`FormatterContext::set_style_range`], the text in the range will
+    /// be rendered in the given style regardless of the log message level.
+    /// Multiple ranges can be added, they should not overlap each other or the
+    /// level style range, otherwise the overlapping ones may be ignored by the
+    /// sink.
+    ///
+    /// Users must ensure that indexes are correctly UTF-8 boundary.
+    pub fn add_styled_range(&mut self, range: Range<usize>, style: Style) {
+        self.styled_ranges.push((range, style));
+    }
+
+    /// Ranges (in bytes) of the formatted text with explicit styles, in the
+    /// order they were added.
+    ///
+    /// If style is available in the sink, the text in each range will be
+    /// rendered in the corresponding style, otherwise they will be ignored.
+    #[must_use]
+    pub fn styled_ranges(&self) -> &[(Range<usize>, Style)] {
+        &self.styled_ranges
+    }
+
+    /// Sets the escape mode requested by the caller.
+    ///
+    /// Sinks call this before formatting to request escaping suitable for
+    /// their output. Formatters should apply the stricter one of this mode and
+    /// their own configured mode.
+    pub fn set_escape_mode(&mut self, mode: EscapeMode) {
+        self.escape_mode = mode;
+    }
+
+    /// The escape mode requested by the caller.
+    ///
+    /// The default is [`EscapeMode::Disabled`].
+    #[must_use]
+    pub fn escape_mode(&self) -> EscapeMode {
+        self.escape_mode
+    }
 }
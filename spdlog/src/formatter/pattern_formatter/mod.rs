@@ -12,7 +12,7 @@ use dyn_clone::*;
 pub use runtime::*;
 
 use crate::{
-    formatter::{Formatter, FormatterContext, TimeDate, TimeDateLazyLocked},
+    formatter::{EscapeMode, Formatter, FormatterContext, TimeDate, TimeDateLazyLocked, TimeZone},
     Error, Record, StringBuf,
 };
 
@@ -130,7 +130,73 @@ use crate::{
 /// //            ^^^^^^ <- style range
 /// # );
 /// ```
-/// 
+///
+/// The style range above is rendered in the style corresponding to the log
+/// level, only one such style range is allowed in a template. To render
+/// multiple portions in explicit styles, use `{^<style>}...{$}`, where
+/// `<style>` is a space-separated list of the following attributes:
+///
+/// - Colors: `black`, `red`, `green`, `yellow`, `blue`, `magenta`, `cyan`,
+///   `white`
+/// - Background colors: `bg_black`, `bg_red`, ..., `bg_white`
+/// - Others: `bold`, `dim` (or `faint`), `italic`, `underline`, `blink` (or
+///   `slow_blink`), `rapid_blink`, `invert`, `conceal`, `strikethrough`
+///
+/// ```
+/// # use spdlog::{
+/// #     formatter::{pattern, PatternFormatter},
+/// #     info,
+/// # };
+#[doc = include_str!(concat!(env!("OUT_DIR"), "/test_utils/common_for_doc_test.rs"))]
+/// let formatter = PatternFormatter::new(pattern!(
+///     "{^dim}[{date} {time}]{$} [{^{level}}] {^bold cyan}{logger}{$}: {payload}{eol}"
+/// ));
+/// # let (doctest, sink) = test_utils::echo_logger_from_formatter(formatter, Some("my-logger"));
+///
+/// info!(logger: doctest, "Interesting log message");
+/// # assert!(sink.clone_string().replace("\r", "").ends_with(" [info] my-logger: Interesting log message\n"));
+/// /* Output */ // "[2022-04-01 22:28:02] [info] my-logger: Interesting log message\n"
+/// //              ^^^^^^^^^^^^^^^^^^^^^   ^^^^  ^^^^^^^^^ <- style ranges
+/// ```
+///
+/// # Conditional Sections
+///
+/// Some information of a log record is optional, e.g. a logger may not have a
+/// name. Use `{?<condition>:...}` to render the content only when the
+/// condition is met, so that no empty brackets or extra spaces are left in
+/// the output. The available conditions are:
+///
+/// | Conditions  | Met when                                 |
+/// | ----------- | ---------------------------------------- |
+/// | `logger`    | The logger has a name                    |
+/// | `source`    | The record has a source location [^1]    |
+/// | `kv`        | The record has at least one key-value    |
+///
+/// ```
+/// # use spdlog::{
+/// #     formatter::{pattern, PatternFormatter},
+/// #     info,
+/// # };
+#[doc = include_str!(concat!(env!("OUT_DIR"), "/test_utils/common_for_doc_test.rs"))]
+/// let formatter = PatternFormatter::new(pattern!(
+///     "{?logger:[{logger}] }{payload}{?kv: {{ {kv} }}}{eol}"
+/// ));
+/// # let (doctest, sink) = test_utils::echo_logger_from_formatter(formatter.clone(), None);
+///
+/// info!(logger: doctest, "Interesting log message");
+/// # assert_eq!(
+/// #     sink.clone_string().replace("\r", ""),
+/// /* Output */ "Interesting log message\n"
+/// # );
+/// # let (doctest, sink) = test_utils::echo_logger_from_formatter(formatter, Some("my-logger"));
+///
+/// info!(logger: doctest, kv: { a=1 }, "Interesting log message");
+/// # assert_eq!(
+/// #     sink.clone_string().replace("\r", ""),
+/// /* Output */ "[my-logger] Interesting log message { a=1 }\n"
+/// # );
+/// ```
+///
 /// # Using Your Own Patterns
 ///
 /// Yes, you can refer your own implementation of [`Pattern`] in the pattern
@@ -277,6 +343,39 @@ use crate::{
 /// );
 /// ```
 ///
+/// # Custom Date and Time Format
+///
+/// Placeholder `{time}` optionally takes a [chrono strftime] format string as
+/// its argument, in the form `{time:<format>}`, to format the date and time in
+/// a free-form way. The format string is validated when the pattern is built.
+///
+/// The date and time patterns are displayed in the local time zone by default,
+/// use [`PatternFormatter::with_time_zone`] to select UTC or a fixed offset.
+///
+/// ```
+/// use spdlog::{
+///     formatter::{pattern, PatternFormatter, TimeZone},
+///     info,
+/// };
+#[doc = include_str!(concat!(env!("OUT_DIR"), "/test_utils/common_for_doc_test.rs"))]
+///
+/// let formatter = PatternFormatter::new(pattern!("[{time:%Y-%m-%dT%H:%M:%S%.3f%:z}] {payload}{eol}"))
+///     .with_time_zone(TimeZone::utc());
+/// # let (doctest, sink) = test_utils::echo_logger_from_formatter(formatter, None);
+///
+/// info!(logger: doctest, "Interesting log message");
+/// # let output = sink.clone_string();
+/// # assert!(output.starts_with('['));
+/// # assert!(output.contains("+00:00] Interesting log message"));
+/// /* Output */ // "[2022-04-01T06:28:02.372+00:00] Interesting log message\n"
+/// ```
+///
+/// ```compile_fail
+/// # use spdlog::formatter::pattern;
+/// // Error: invalid strftime format
+/// let pattern = pattern!("{time:%Y-%!}");
+/// ```
+///
 /// # Appendix: Full List of Built-in Patterns
 ///
 /// | Placeholders          | Description                  | Example                                      |
@@ -303,6 +402,7 @@ use crate::{
 /// | `{time_12}`           | Time in 12-hour format       | `02:55:02 PM`                                |
 /// | `{time_short}`        | Short time                   | `22:28`, `09:53`                             |
 /// | `{time}`              | Time                         | `22:28:02`, `09:53:41`                       |
+/// | `{time:<format>}`     | Time in a custom format      | `2022-04-01T22:28:02.372` [^2]               |
 /// | `{tz_offset}`         | Timezone offset              | `+08:00`, `+00:00`, `-06:00`                 |
 /// | `{unix_timestamp}`    | Unix timestamp               | `1528834770`                                 |
 /// | `{full}`              | Full log message             | See [`FullFormatter`]                        |
@@ -318,14 +418,34 @@ use crate::{
 /// | `{payload}`           | Log payload                  | `log message`                                |
 /// | `{kv}`                | Key-values                   | `k1=123 k2=text`                             |
 /// | `{pid}`               | Process ID                   | `3824`                                       |
+/// | `{process_name}`      | Process name                 | `my-app`                                     |
 /// | `{tid}`               | Thread ID                    | `3132`                                       |
+/// | `{thread_name}`       | Thread name                  | `worker-3` [^3]                              |
+/// | `{hostname}`          | Hostname                     | `build-server-01`                            |
+/// | `{elapsed}`           | Time since start in seconds  | `12.034871` [^4]                             |
+/// | `{elapsed_delta}`     | Time since previous record   | `0.000213` [^4]                              |
+/// | `{seq}`               | Sequence number in logger    | `0`, `1`, `42` [^5]                          |
 /// | `{eol}`               | End of line                  | `\n` (on non-Windows) or `\r\n` (on Windows) |
 /// 
 /// [^1]: Patterns related to source location require that feature
 ///       `source-location` is enabled, otherwise the output is empty.
 ///
+/// [^2]: See [Custom Date and Time Format](#custom-date-and-time-format).
+///
+/// [^3]: The output is empty if the thread that created the record is unnamed.
+///
+/// [^4]: `{elapsed}` measures the monotonic time from the first logger in the
+///       process being built to the creation of the record. `{elapsed_delta}`
+///       measures the monotonic time since the previous record formatted by
+///       the same formatter.
+///
+/// [^5]: See [`Record::seq`].
+///
+/// [chrono strftime]: https://docs.rs/chrono/latest/chrono/format/strftime/index.html
+/// [`PatternFormatter::with_time_zone`]: crate::formatter::PatternFormatter::with_time_zone
 /// [`runtime_pattern!`]: crate::formatter::runtime_pattern
 /// [`FullFormatter`]: crate::formatter::FullFormatter
+/// [`Record::seq`]: crate::Record::seq
 pub use ::spdlog_macros::pattern;
 
 // Emit a compile error if the feature is not enabled.
@@ -347,6 +467,8 @@ macro_rules! __runtime_pattern_disabled {
 #[derive(Clone)]
 pub struct PatternFormatter<P> {
     pattern: P,
+    time_zone: TimeZone,
+    escape_mode: EscapeMode,
 }
 
 impl<P> PatternFormatter<P>
@@ -362,7 +484,59 @@ where
     /// - Macro [`runtime_pattern!`] to build a pattern at runtime.
     #[must_use]
     pub fn new(pattern: P) -> Self {
-        Self { pattern }
+        Self {
+            pattern,
+            time_zone: TimeZone::local(),
+            escape_mode: EscapeMode::Disabled,
+        }
+    }
+
+    /// Specifies the time zone in which the date and time patterns are
+    /// displayed.
+    ///
+    /// The default time zone is [`TimeZone::local`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spdlog::formatter::{pattern, PatternFormatter, TimeZone};
+    ///
+    /// let formatter = PatternFormatter::new(pattern!("[{time:%Y-%m-%dT%H:%M:%S%.3fZ}] {payload}{eol}"))
+    ///     .with_time_zone(TimeZone::utc());
+    /// ```
+    #[must_use]
+    pub fn with_time_zone(mut self, time_zone: TimeZone) -> Self {
+        self.time_zone = time_zone;
+        self
+    }
+
+    /// Specifies how the `{payload}` and `{kv}` patterns escape their output.
+    ///
+    /// The default mode is [`EscapeMode::Disabled`]. If the sink requests a
+    /// stricter mode via [`FormatterContext::escape_mode`], that mode is used
+    /// instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spdlog::formatter::{pattern, EscapeMode, PatternFormatter};
+    /// # use spdlog::info;
+    #[doc = include_str!(concat!(env!("OUT_DIR"), "/test_utils/common_for_doc_test.rs"))]
+    ///
+    /// let formatter = PatternFormatter::new(pattern!("[{level}] {payload}{eol}"))
+    ///     .with_escape_mode(EscapeMode::File);
+    /// # let (doctest, sink) = test_utils::echo_logger_from_formatter(formatter, None);
+    /// let user_input = "bob\n[error] forged line";
+    /// info!(logger: doctest, "login failed for {}", user_input);
+    /// # assert_eq!(
+    /// #     sink.clone_string().replace("\r", ""),
+    /// /* Output */ "[info] login failed for bob\\n[error] forged line\n"
+    /// # );
+    /// ```
+    #[must_use]
+    pub fn with_escape_mode(mut self, escape_mode: EscapeMode) -> Self {
+        self.escape_mode = escape_mode;
+        self
     }
 }
 
@@ -379,7 +553,8 @@ where
         #[cfg(not(feature = "flexible-string"))]
         dest.reserve(crate::string_buf::RESERVE_SIZE);
 
-        fmt_ctx.locked_time_date = Some(TimeDateLazyLocked::new(record.time()));
+        fmt_ctx.locked_time_date = Some(TimeDateLazyLocked::new(record.time(), self.time_zone));
+        fmt_ctx.set_escape_mode(self.escape_mode.max(fmt_ctx.escape_mode()));
         {
             let mut pat_ctx = PatternContext { fmt_ctx };
             self.pattern.format(record, dest, &mut pat_ctx)?;
@@ -390,14 +565,22 @@ where
 }
 
 /// Provides context for patterns.
-///
-/// There is nothing to set up here at the moment, reserved for future use.
 #[derive(Debug)]
 pub struct PatternContext<'a, 'b> {
     fmt_ctx: &'a mut FormatterContext<'b>,
 }
 
 impl PatternContext<'_, '_> {
+    /// The escape mode that patterns writing untrusted text, such as payloads
+    /// and key-values, should apply.
+    ///
+    /// It is the stricter one of the mode configured on [`PatternFormatter`]
+    /// and the mode requested by the sink.
+    #[must_use]
+    pub fn escape_mode(&self) -> EscapeMode {
+        self.fmt_ctx.escape_mode()
+    }
+
     #[must_use]
     fn time_date(&mut self) -> TimeDate<'_> {
         self.fmt_ctx.locked_time_date.as_mut().unwrap().get()
@@ -426,8 +609,8 @@ pub trait Pattern: Send + Sync + DynClone {
     /// Format this pattern against the given log record and write the formatted
     /// message into the output buffer.
     ///
-    /// **For implementors:** the `ctx` parameter is reserved for future use.
-    /// For now, please ignore it.
+    /// **For implementors:** patterns that write untrusted text should escape
+    /// it according to [`PatternContext::escape_mode`].
     fn format(
         &self,
         record: &Record,
@@ -1225,7 +1408,7 @@ mod tests {
         let record = get_mock_record();
         let mut output = StringBuf::new();
         let mut fmt_ctx = FormatterContext::new();
-        fmt_ctx.locked_time_date = Some(TimeDateLazyLocked::new(record.time()));
+        fmt_ctx.locked_time_date = Some(TimeDateLazyLocked::new(record.time(), TimeZone::local()));
         let mut pat_ctx = PatternContext {
             fmt_ctx: &mut fmt_ctx,
         };
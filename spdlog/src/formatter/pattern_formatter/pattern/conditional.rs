@@ -0,0 +1,63 @@
+use crate::{
+    formatter::pattern_formatter::{Pattern, PatternContext},
+    Record, StringBuf,
+};
+
+/// A condition on which [`Conditional`] pattern formats its inner pattern.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Condition {
+    /// The logger has a name.
+    LoggerName,
+    /// The record has a source location.
+    SourceLocation,
+    /// The record has at least one key-value pair.
+    KV,
+}
+
+impl Condition {
+    #[must_use]
+    fn is_met(&self, record: &Record) -> bool {
+        match self {
+            Self::LoggerName => record.logger_name().is_some(),
+            Self::SourceLocation => record.source_location().is_some(),
+            Self::KV => !record.key_values().is_empty(),
+        }
+    }
+}
+
+/// A pattern that formats the wrapped pattern only when the given condition is
+/// met for the log record, otherwise nothing is written into the output.
+#[derive(Clone, Debug)]
+pub struct Conditional<P> {
+    condition: Condition,
+    inner: P,
+}
+
+impl<P> Conditional<P>
+where
+    P: Pattern,
+{
+    /// Create a new `Conditional` pattern that wraps the given inner pattern.
+    #[must_use]
+    pub fn new(condition: Condition, inner: P) -> Self {
+        Self { condition, inner }
+    }
+}
+
+impl<P> Pattern for Conditional<P>
+where
+    P: Pattern + Clone,
+{
+    fn format(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        if self.condition.is_met(record) {
+            self.inner.format(record, dest, ctx)?;
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,11 @@
-use std::{fmt::Write as _, marker::PhantomData};
+use std::{fmt::Write as _, iter, marker::PhantomData};
+
+use chrono::format::{Fixed, Item, Numeric, StrftimeItems};
 
 use crate::{
+    error::InvalidArgumentError,
     formatter::pattern_formatter::{Pattern, PatternContext},
+    sync::*,
     Error, Record, StringBuf,
 };
 
@@ -425,6 +429,119 @@ impl Pattern for Time {
     }
 }
 
+/// A pattern that writes the time of log records in a custom [strftime] format
+/// into the output. Example: `2022-04-01T22:28:02.372`.
+///
+/// This pattern is created by placeholder `{time:<format>}`. The parts of the
+/// format that do not change within a second are cached.
+///
+/// [strftime]: https://docs.rs/chrono/latest/chrono/format/strftime/index.html
+#[derive(Clone)]
+pub struct FormattedTime {
+    // Identifies the cached per-second parts, shared by clones
+    id: usize,
+    segments: Vec<FormattedTimeSegment>,
+}
+
+#[derive(Clone)]
+enum FormattedTimeSegment {
+    PerSecond(Vec<Item<'static>>),
+    SubSecond(Item<'static>),
+}
+
+impl FormattedTime {
+    /// Constructs a `FormattedTime` pattern with the given strftime format.
+    ///
+    /// # Error
+    ///
+    /// If the format is invalid, [`Error::InvalidArgument`] will be returned.
+    /// Patterns built by `pattern!` and `runtime_pattern!` macros have
+    /// validated the format in advance.
+    pub fn new(format: &str) -> crate::Result<Self> {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+        let mut segments = Vec::new();
+        let mut per_second = Vec::new();
+
+        for item in StrftimeItems::new(format) {
+            if matches!(item, Item::Error) {
+                return Err(Error::InvalidArgument(InvalidArgumentError::TimeFormat(
+                    format.to_owned(),
+                )));
+            }
+            if is_sub_second_item(&item) {
+                if !per_second.is_empty() {
+                    segments.push(FormattedTimeSegment::PerSecond(std::mem::take(
+                        &mut per_second,
+                    )));
+                }
+                segments.push(FormattedTimeSegment::SubSecond(item.to_owned()));
+            } else {
+                per_second.push(item.to_owned());
+            }
+        }
+        if !per_second.is_empty() {
+            segments.push(FormattedTimeSegment::PerSecond(per_second));
+        }
+
+        Ok(Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            segments,
+        })
+    }
+}
+
+impl Pattern for FormattedTime {
+    fn format(
+        &self,
+        _record: &Record,
+        dest: &mut StringBuf,
+        ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        let mut time_date = ctx.time_date();
+        let date_time = time_date.date_time();
+        let per_second = time_date.formatted_strs(self.id, |date_time| {
+            self.segments
+                .iter()
+                .filter_map(|segment| match segment {
+                    FormattedTimeSegment::PerSecond(items) => {
+                        Some(date_time.format_with_items(items.iter()).to_string())
+                    }
+                    FormattedTimeSegment::SubSecond(_) => None,
+                })
+                .collect()
+        });
+
+        let mut per_second = per_second.iter();
+        for segment in &self.segments {
+            match segment {
+                FormattedTimeSegment::PerSecond(_) => dest.write_str(per_second.next().unwrap()),
+                FormattedTimeSegment::SubSecond(item) => {
+                    write!(dest, "{}", date_time.format_with_items(iter::once(item)))
+                }
+            }
+            .map_err(Error::FormatRecord)?;
+        }
+        Ok(())
+    }
+}
+
+#[must_use]
+fn is_sub_second_item(item: &Item) -> bool {
+    matches!(
+        item,
+        Item::Numeric(Numeric::Nanosecond | Numeric::Internal(_), _)
+            | Item::Fixed(
+                Fixed::Nanosecond
+                    | Fixed::Nanosecond3
+                    | Fixed::Nanosecond6
+                    | Fixed::Nanosecond9
+                    | Fixed::RFC3339
+                    | Fixed::Internal(_)
+            )
+    )
+}
+
 /// A pattern that writes the timezone offset of log records into the output.
 /// Examples: `+08:00`, `+00:00`, `-06:00`.
 #[derive(Clone, Default)]
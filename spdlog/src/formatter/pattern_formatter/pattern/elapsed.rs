@@ -0,0 +1,66 @@
+use std::{fmt::Write as _, time::Duration};
+
+use crate::{
+    formatter::pattern_formatter::{Pattern, PatternContext},
+    sync::*,
+    Error, Record, StringBuf,
+};
+
+/// A pattern that writes the monotonic time elapsed from the first logger in
+/// the process being built to the creation of the record into the output, in
+/// seconds with microsecond precision. Example: `12.034871`.
+#[derive(Clone, Default)]
+pub struct Elapsed;
+
+impl Pattern for Elapsed {
+    fn format(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        _ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        write_duration(dest, record.elapsed())
+    }
+}
+
+/// A pattern that writes the monotonic time elapsed since the previous record
+/// formatted by this pattern into the output, in seconds with microsecond
+/// precision. Example: `0.000213`.
+///
+/// For the first record, the time elapsed since the first logger in the
+/// process was built is written, as [`Elapsed`] does. A cloned pattern does
+/// not share the previous record with the original one.
+#[derive(Default)]
+pub struct ElapsedDelta {
+    // In nanoseconds, relative to the same starting point as `Elapsed`
+    last: AtomicU64,
+}
+
+impl Clone for ElapsedDelta {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl Pattern for ElapsedDelta {
+    fn format(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        _ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        let current = u64::try_from(record.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        let last = self.last.fetch_max(current, Ordering::Relaxed);
+        write_duration(dest, Duration::from_nanos(current.saturating_sub(last)))
+    }
+}
+
+fn write_duration(dest: &mut StringBuf, duration: Duration) -> crate::Result<()> {
+    write!(
+        dest,
+        "{}.{:06}",
+        duration.as_secs(),
+        duration.subsec_micros()
+    )
+    .map_err(Error::FormatRecord)
+}
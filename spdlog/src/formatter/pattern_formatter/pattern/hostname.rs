@@ -0,0 +1,61 @@
+use std::fmt::Write as _;
+
+use crate::{
+    formatter::pattern_formatter::{Pattern, PatternContext},
+    sync::*,
+    Error, Record, StringBuf,
+};
+
+/// A pattern that writes the hostname of the current machine into the output.
+/// Example: `build-server-01`.
+///
+/// The hostname is queried once and then cached. If it cannot be determined,
+/// nothing is written.
+///
+/// # Implementation
+///
+/// On unix-like systems such as Linux and macOS, this pattern writes the result
+/// of `gethostname` to the output.
+///
+/// On Windows, this pattern writes the result of `GetComputerNameW` to the
+/// output.
+#[derive(Clone, Default)]
+pub struct Hostname;
+
+impl Pattern for Hostname {
+    fn format(
+        &self,
+        _record: &Record,
+        dest: &mut StringBuf,
+        _ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        static HOSTNAME: Lazy<String> = Lazy::new(|| get_hostname().unwrap_or_default());
+
+        dest.write_str(&HOSTNAME).map_err(Error::FormatRecord)
+    }
+}
+
+#[cfg(target_family = "unix")]
+#[must_use]
+fn get_hostname() -> Option<String> {
+    let mut buf = [0_u8; 256];
+    let ret = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+    if ret != 0 {
+        return None;
+    }
+    // The result may not be null-terminated if it was truncated
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    Some(String::from_utf8_lossy(&buf[..len]).into_owned())
+}
+
+#[cfg(target_os = "windows")]
+#[must_use]
+fn get_hostname() -> Option<String> {
+    let mut buf = [0_u16; 256];
+    let mut len = buf.len() as u32;
+    let ret = unsafe { winapi::um::winbase::GetComputerNameW(buf.as_mut_ptr(), &mut len) };
+    if ret == 0 {
+        return None;
+    }
+    Some(String::from_utf16_lossy(&buf[..len as usize]))
+}
@@ -11,11 +11,11 @@ impl Pattern for KV {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
         record
             .key_values()
-            .write_to(dest, false)
+            .write_to(dest, false, ctx.escape_mode())
             .map_err(Error::FormatRecord)
     }
 }
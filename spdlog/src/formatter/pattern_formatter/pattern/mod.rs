@@ -1,25 +1,37 @@
 //! This module provides all the built-in patterns.
 
+mod conditional;
 mod datetime;
+mod elapsed;
 mod eol;
 mod full;
+mod hostname;
 mod kv;
 mod level;
 mod logger_name;
 mod payload;
 mod process_id;
+mod process_name;
+mod sequence;
 mod srcloc;
 mod style_range;
 mod thread_id;
+mod thread_name;
 
+pub use conditional::*;
 pub use datetime::*;
+pub use elapsed::*;
 pub use eol::*;
 pub use full::*;
+pub use hostname::*;
 pub use kv::*;
 pub use level::*;
 pub use logger_name::*;
 pub use payload::*;
 pub use process_id::*;
+pub use process_name::*;
+pub use sequence::*;
 pub use srcloc::*;
 pub use style_range::*;
 pub use thread_id::*;
+pub use thread_name::*;
@@ -1,7 +1,8 @@
-use std::fmt::Write as _;
-
 use crate::{
-    formatter::pattern_formatter::{Pattern, PatternContext},
+    formatter::{
+        pattern_formatter::{Pattern, PatternContext},
+        write_escaped,
+    },
     Error, Record, StringBuf,
 };
 
@@ -15,9 +16,8 @@ impl Pattern for Payload {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        dest.write_str(record.payload())
-            .map_err(Error::FormatRecord)
+        write_escaped(dest, record.payload(), ctx.escape_mode()).map_err(Error::FormatRecord)
     }
 }
@@ -0,0 +1,39 @@
+use std::fmt::Write as _;
+
+use crate::{
+    formatter::pattern_formatter::{Pattern, PatternContext},
+    sync::*,
+    Error, Record, StringBuf,
+};
+
+/// A pattern that writes the current process's name into the output. Example:
+/// `my-app`.
+///
+/// # Implementation
+///
+/// This pattern writes the file stem of the path returned by
+/// [`std::env::current_exe`] to the output. The name is queried once and then
+/// cached. If it cannot be determined, nothing is written.
+#[derive(Clone, Default)]
+pub struct ProcessName;
+
+impl Pattern for ProcessName {
+    fn format(
+        &self,
+        _record: &Record,
+        dest: &mut StringBuf,
+        _ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        static PROCESS_NAME: Lazy<String> = Lazy::new(|| {
+            std::env::current_exe()
+                .ok()
+                .and_then(|path| {
+                    path.file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned())
+                })
+                .unwrap_or_default()
+        });
+
+        dest.write_str(&PROCESS_NAME).map_err(Error::FormatRecord)
+    }
+}
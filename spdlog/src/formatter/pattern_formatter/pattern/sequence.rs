@@ -0,0 +1,24 @@
+use std::fmt::Write as _;
+
+use crate::{
+    formatter::pattern_formatter::{Pattern, PatternContext},
+    Error, Record, StringBuf,
+};
+
+/// A pattern that writes the sequence number of the record within its logger
+/// into the output. Example: `42`.
+///
+/// See [`Record::seq`] for details.
+#[derive(Clone, Default)]
+pub struct Sequence;
+
+impl Pattern for Sequence {
+    fn format(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        _ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        write!(dest, "{}", record.seq()).map_err(Error::FormatRecord)
+    }
+}
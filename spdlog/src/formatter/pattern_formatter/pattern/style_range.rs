@@ -1,13 +1,18 @@
 use crate::{
     formatter::pattern_formatter::{Pattern, PatternContext},
+    terminal_style::Style,
     Record, StringBuf,
 };
 
 /// A pattern that wraps another pattern and apply style ranges to the content
 /// formatted by the wrapped pattern.
+///
+/// By default, the content is rendered in the style corresponding to the log
+/// level. Use [`StyleRange::with_style`] to specify an explicit style.
 #[derive(Clone, Debug, Default)]
 pub struct StyleRange<P> {
     inner: P,
+    style: Option<Style>,
 }
 
 impl<P> StyleRange<P>
@@ -17,7 +22,17 @@ where
     /// Create a new `StyleRange` pattern that wraps the given inner pattern.
     #[must_use]
     pub fn new(inner: P) -> Self {
-        Self { inner }
+        Self { inner, style: None }
+    }
+
+    /// Create a new `StyleRange` pattern that wraps the given inner pattern
+    /// and renders it in the given style.
+    #[must_use]
+    pub fn with_style(inner: P, style: Style) -> Self {
+        Self {
+            inner,
+            style: Some(style),
+        }
     }
 }
 
@@ -36,8 +51,14 @@ where
         self.inner.format(record, dest, ctx)?;
 
         let style_range_end = dest.len();
-        ctx.fmt_ctx
-            .set_style_range(Some(style_range_start..style_range_end));
+        match &self.style {
+            None => ctx
+                .fmt_ctx
+                .set_style_range(Some(style_range_start..style_range_end)),
+            Some(style) => ctx
+                .fmt_ctx
+                .add_styled_range(style_range_start..style_range_end, style.clone()),
+        }
 
         Ok(())
     }
@@ -0,0 +1,25 @@
+use std::fmt::Write as _;
+
+use crate::{
+    formatter::pattern_formatter::{Pattern, PatternContext},
+    Error, Record, StringBuf,
+};
+
+/// A pattern that writes the name of the thread that created the record into
+/// the output. Example: `worker-3`.
+///
+/// If the thread is unnamed, nothing is written.
+#[derive(Clone, Default)]
+pub struct ThreadName;
+
+impl Pattern for ThreadName {
+    fn format(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        _ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        dest.write_str(record.thread_name().unwrap_or(""))
+            .map_err(Error::FormatRecord)
+    }
+}
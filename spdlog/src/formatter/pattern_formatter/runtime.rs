@@ -1,13 +1,15 @@
 use spdlog_internal::pattern_parser::{
     error::TemplateError,
     parse::{Template, TemplateToken},
-    BuiltInFormatter, Error as PatternParserError, PatternKind as GenericPatternKind,
+    style::{StyleAttr, StyleColor},
+    BuiltInFormatter, Condition, Error as PatternParserError, PatternKind as GenericPatternKind,
     PatternRegistry as GenericPatternRegistry, Result as PatternParserResult,
 };
 
-use super::{Pattern, PatternContext, __pattern as pattern};
+use super::{__pattern as pattern, Pattern, PatternContext};
 use crate::{
     error::{BuildPatternError, Error},
+    terminal_style::{Color, Style},
     Record, Result, StringBuf,
 };
 
@@ -162,13 +164,13 @@ impl Synthesiser {
     }
 
     fn synthesize(&self, template: Template) -> PatternParserResult<Patterns> {
-        self.build_patterns(template, false)
+        self.build_patterns(template, &mut false)
     }
 
     fn build_patterns(
         &self,
         template: Template,
-        mut style_range_seen: bool,
+        style_range_seen: &mut bool,
     ) -> PatternParserResult<Patterns> {
         let mut patterns = Patterns::new();
 
@@ -177,20 +179,50 @@ impl Synthesiser {
                 TemplateToken::Literal(t) => Box::new(t.literal),
                 TemplateToken::Formatter(t) => {
                     let pattern = self.registry.find(t.has_custom_prefix, t.placeholder)?;
-                    match pattern {
-                        PatternKind::BuiltIn(builtin) => build_builtin_pattern(builtin),
-                        PatternKind::Custom { factory, .. } => factory(),
+                    pattern.check_arg(t.arg)?;
+                    match (pattern, t.arg) {
+                        (PatternKind::BuiltIn(builtin), Some(arg)) => {
+                            // Only `{time}` takes an argument currently.
+                            let time = pattern::FormattedTime::new(arg).map_err(|_| {
+                                PatternParserError::Template(
+                                    TemplateError::InvalidPatternArgument {
+                                        placeholder: builtin.placeholder().into(),
+                                        arg: arg.into(),
+                                    },
+                                )
+                            })?;
+                            Box::new(time)
+                        }
+                        (PatternKind::BuiltIn(builtin), None) => build_builtin_pattern(builtin),
+                        (PatternKind::Custom { factory, .. }, _) => factory(),
                     }
                 }
-                TemplateToken::StyleRange(style_range) => {
-                    if style_range_seen {
-                        return Err(PatternParserError::Template(
-                            TemplateError::MultipleStyleRange,
-                        ));
+                TemplateToken::StyleRange(style_range) => match style_range.style {
+                    None => {
+                        if *style_range_seen {
+                            return Err(PatternParserError::Template(
+                                TemplateError::MultipleStyleRange,
+                            ));
+                        }
+                        *style_range_seen = true;
+                        Box::new(pattern::StyleRange::new(
+                            self.build_patterns(style_range.body, style_range_seen)?,
+                        ))
                     }
-                    style_range_seen = true;
-                    Box::new(pattern::StyleRange::new(
-                        self.build_patterns(style_range.body, true)?,
+                    Some(style) => Box::new(pattern::StyleRange::with_style(
+                        self.build_patterns(style_range.body, style_range_seen)?,
+                        build_style(&style),
+                    )),
+                },
+                TemplateToken::Conditional(conditional) => {
+                    let condition = match Condition::parse(conditional.condition)? {
+                        Condition::LoggerName => pattern::Condition::LoggerName,
+                        Condition::SourceLocation => pattern::Condition::SourceLocation,
+                        Condition::KV => pattern::Condition::KV,
+                    };
+                    Box::new(pattern::Conditional::new(
+                        condition,
+                        self.build_patterns(conditional.body, style_range_seen)?,
                     ))
                 }
             };
@@ -201,6 +233,39 @@ impl Synthesiser {
     }
 }
 
+fn build_style(attrs: &[StyleAttr]) -> Style {
+    fn color(color: StyleColor) -> Color {
+        match color {
+            StyleColor::Black => Color::Black,
+            StyleColor::Red => Color::Red,
+            StyleColor::Green => Color::Green,
+            StyleColor::Yellow => Color::Yellow,
+            StyleColor::Blue => Color::Blue,
+            StyleColor::Magenta => Color::Magenta,
+            StyleColor::Cyan => Color::Cyan,
+            StyleColor::White => Color::White,
+        }
+    }
+
+    let mut builder = Style::builder();
+    for attr in attrs {
+        match *attr {
+            StyleAttr::Color(c) => builder.color(color(c)),
+            StyleAttr::BgColor(c) => builder.bg_color(color(c)),
+            StyleAttr::Bold => builder.bold(),
+            StyleAttr::Faint => builder.faint(),
+            StyleAttr::Italic => builder.italic(),
+            StyleAttr::Underline => builder.underline(),
+            StyleAttr::SlowBlink => builder.slow_blink(),
+            StyleAttr::RapidBlink => builder.rapid_blink(),
+            StyleAttr::Invert => builder.invert(),
+            StyleAttr::Conceal => builder.conceal(),
+            StyleAttr::Strikethrough => builder.strikethrough(),
+        };
+    }
+    builder.build()
+}
+
 fn build_builtin_pattern(builtin: &BuiltInFormatter) -> Box<dyn Pattern> {
     macro_rules! match_builtin {
         (  $($name:ident),+ $(,)? ) => {
@@ -248,7 +313,13 @@ fn build_builtin_pattern(builtin: &BuiltInFormatter) -> Box<dyn Pattern> {
         Payload,
         KV,
         ProcessId,
+        ProcessName,
         ThreadId,
+        ThreadName,
+        Hostname,
+        Elapsed,
+        ElapsedDelta,
+        Sequence,
         Eol
     )
 }
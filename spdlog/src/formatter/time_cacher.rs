@@ -1,26 +1,70 @@
 use std::{fmt, time::SystemTime};
 
-use chrono::prelude::*;
+use chrono::prelude::{DateTime, Datelike as _, FixedOffset, Local, Timelike as _, Utc};
 use once_cell::sync::Lazy;
 
-use crate::{formatter::FormatterContext, sync::*, Record};
+use crate::{
+    formatter::{FormatterContext, TimeZone, TimeZoneInner},
+    sync::*,
+    Record,
+};
 
-static LOCAL_TIME_CACHER: Lazy<Mutex<LocalTimeCacher>> =
-    Lazy::new(|| Mutex::new(LocalTimeCacher::new()));
+static TIME_CACHER: Lazy<Mutex<TimeCacher>> = Lazy::new(|| Mutex::new(TimeCacher::new()));
 
-pub(crate) fn fmt_with_time<R, F>(ctx: &mut FormatterContext, record: &Record, mut callback: F) -> R
+pub(crate) fn fmt_with_time<R, F>(
+    ctx: &mut FormatterContext,
+    record: &Record,
+    time_zone: TimeZone,
+    mut callback: F,
+) -> R
 where
     F: FnMut(TimeDate) -> R,
 {
     if let Some(time_date) = ctx.locked_time_date.as_mut() {
         callback(time_date.get())
     } else {
-        callback(LOCAL_TIME_CACHER.lock_expect().get(record.time()))
+        callback(TIME_CACHER.lock_expect().get(record.time(), time_zone))
+    }
+}
+
+pub(crate) struct TimeCacher {
+    local: ZoneTimeCacher,
+    // Only a few distinct time zones are expected, a linear search is enough.
+    others: Vec<ZoneTimeCacher>,
+}
+
+impl TimeCacher {
+    #[must_use]
+    fn new() -> Self {
+        Self {
+            local: ZoneTimeCacher::new(TimeZone::local()),
+            others: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    fn zone_mut(&mut self, time_zone: TimeZone) -> &mut ZoneTimeCacher {
+        if time_zone.inner() == TimeZoneInner::Local {
+            return &mut self.local;
+        }
+        match self.others.iter().position(|c| c.time_zone == time_zone) {
+            Some(index) => &mut self.others[index],
+            None => {
+                self.others.push(ZoneTimeCacher::new(time_zone));
+                self.others.last_mut().unwrap()
+            }
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn get(&mut self, system_time: SystemTime, time_zone: TimeZone) -> TimeDate<'_> {
+        self.zone_mut(time_zone).get(system_time)
     }
 }
 
 #[derive(Clone)]
-pub(crate) struct LocalTimeCacher {
+struct ZoneTimeCacher {
+    time_zone: TimeZone,
     stored_key: u64,
     cache_values: Option<CacheValues>,
 }
@@ -33,7 +77,7 @@ pub(crate) struct TimeDate<'a> {
 
 #[derive(Clone, Eq, PartialEq)]
 struct CacheValues {
-    local_time: DateTime<Local>,
+    date_time: DateTime<FixedOffset>,
     full_second_str: Option<String>,
     year: Option<i32>,
     year_str: Option<String>,
@@ -55,6 +99,8 @@ struct CacheValues {
     second_str: Option<String>,
     tz_offset_str: Option<String>,
     unix_timestamp_str: Option<String>,
+    // (pattern id, per-second parts) of `{time:<format>}` patterns
+    formatted_strs: Vec<(usize, Vec<String>)>,
 }
 
 #[derive(Clone, Copy, Eq, PartialEq)]
@@ -63,24 +109,25 @@ pub(crate) struct MultiName<T> {
     pub(crate) full: T,
 }
 
-impl LocalTimeCacher {
+impl ZoneTimeCacher {
     #[must_use]
-    fn new() -> LocalTimeCacher {
-        LocalTimeCacher {
+    fn new(time_zone: TimeZone) -> Self {
+        Self {
+            time_zone,
             stored_key: 0,
             cache_values: None,
         }
     }
 
     #[must_use]
-    pub(crate) fn get(&mut self, system_time: SystemTime) -> TimeDate<'_> {
+    fn get(&mut self, system_time: SystemTime) -> TimeDate<'_> {
         let since_epoch = system_time.duration_since(SystemTime::UNIX_EPOCH).unwrap();
         let nanosecond = since_epoch.subsec_nanos();
         let millisecond = nanosecond / 1_000_000;
 
         let cache_key = since_epoch.as_secs(); // Unix timestamp
-        if self.stored_key != cache_key {
-            self.cache_values = Some(CacheValues::new(system_time));
+        if self.stored_key != cache_key || self.cache_values.is_none() {
+            self.cache_values = Some(CacheValues::new(system_time, self.time_zone));
             self.stored_key = cache_key;
         }
 
@@ -99,7 +146,7 @@ macro_rules! impl_cache_fields_getter {
             match self.cached.$field {
                 Some(value) => value,
                 None => {
-                    let value = self.cached.local_time.$field();
+                    let value = self.cached.date_time.$field();
                     self.cached.$field = Some(value);
                     value
                 }
@@ -113,7 +160,7 @@ macro_rules! impl_cache_fields_str_getter {
         #[must_use]
         $(pub(crate) fn $str_field(&mut self) -> &str {
             if self.cached.$str_field.is_none() {
-                self.cached.$str_field = Some(format!($fmt, self.cached.local_time.$field()));
+                self.cached.$str_field = Some(format!($fmt, self.cached.date_time.$field()));
             }
             self.cached.$str_field.as_deref().unwrap()
         })*
@@ -124,7 +171,7 @@ impl TimeDate<'_> {
     #[must_use]
     pub(crate) fn full_second_str(&mut self) -> &str {
         if self.cached.full_second_str.is_none() {
-            // `local_time.format("%Y-%m-%d %H:%M:%S")` is slower than this way
+            // `date_time.format("%Y-%m-%d %H:%M:%S")` is slower than this way
             self.cached.full_second_str = Some(format!(
                 "{}-{:02}-{:02} {:02}:{:02}:{:02}",
                 self.year(),
@@ -176,7 +223,7 @@ impl TimeDate<'_> {
                     ];
 
                     let weekday_from_monday_0 =
-                        self.cached.local_time.weekday().num_days_from_monday() as usize;
+                        self.cached.date_time.weekday().num_days_from_monday() as usize;
 
                     MultiName {
                         short: SHORT[weekday_from_monday_0],
@@ -214,7 +261,7 @@ impl TimeDate<'_> {
                         "December",
                     ];
 
-                    let month_index = self.cached.local_time.month() as usize - 1;
+                    let month_index = self.cached.date_time.month() as usize - 1;
 
                     MultiName {
                         short: SHORT[month_index],
@@ -227,6 +274,16 @@ impl TimeDate<'_> {
         }
     }
 
+    /// The date time of the record in the selected time zone, including the
+    /// sub-second part.
+    #[must_use]
+    pub(crate) fn date_time(&mut self) -> DateTime<FixedOffset> {
+        self.cached
+            .date_time
+            .with_nanosecond(self.nanosecond)
+            .unwrap_or(self.cached.date_time)
+    }
+
     #[must_use]
     pub(crate) fn nanosecond(&mut self) -> u32 {
         self.nanosecond
@@ -269,7 +326,7 @@ impl TimeDate<'_> {
     pub(crate) fn tz_offset_str(&mut self) -> &str {
         if self.cached.tz_offset_str.is_none() {
             self.cached.tz_offset_str = {
-                let offset_secs = self.cached.local_time.offset().local_minus_utc();
+                let offset_secs = self.cached.date_time.offset().local_minus_utc();
                 let offset_secs_abs = offset_secs.abs();
 
                 let sign_str = if offset_secs >= 0 { "+" } else { "-" };
@@ -281,13 +338,41 @@ impl TimeDate<'_> {
         }
         self.cached.tz_offset_str.as_deref().unwrap()
     }
+
+    // Gets the per-second parts of the formatted time of the pattern with the
+    // given id, they are formatted by `format` once per second.
+    #[must_use]
+    pub(crate) fn formatted_strs<F>(&mut self, id: usize, format: F) -> &[String]
+    where
+        F: FnOnce(&DateTime<FixedOffset>) -> Vec<String>,
+    {
+        let formatted_strs = &mut self.cached.formatted_strs;
+        let index = match formatted_strs.iter().position(|(i, _)| *i == id) {
+            Some(index) => index,
+            None => {
+                formatted_strs.push((id, format(&self.cached.date_time)));
+                formatted_strs.len() - 1
+            }
+        };
+        &formatted_strs[index].1
+    }
 }
 
 impl CacheValues {
     #[must_use]
-    fn new(system_time: SystemTime) -> Self {
+    fn new(system_time: SystemTime, time_zone: TimeZone) -> Self {
+        let date_time = match time_zone.inner() {
+            TimeZoneInner::Local => DateTime::<Local>::from(system_time).into(),
+            TimeZoneInner::Utc => DateTime::<Utc>::from(system_time).into(),
+            TimeZoneInner::Fixed(offset) => {
+                DateTime::<Utc>::from(system_time).with_timezone(&offset)
+            }
+        };
+        // Only the whole second part is cached.
+        let date_time = date_time.with_nanosecond(0).unwrap_or(date_time);
+
         CacheValues {
-            local_time: system_time.into(),
+            date_time,
             full_second_str: None,
             year: None,
             year_str: None,
@@ -309,32 +394,38 @@ impl CacheValues {
             second_str: None,
             tz_offset_str: None,
             unix_timestamp_str: None,
+            formatted_strs: Vec::new(),
         }
     }
 }
 
 struct TimeDateLocked<'a> {
-    cached: MutexGuard<'a, LocalTimeCacher>,
+    cached: MutexGuard<'a, TimeCacher>,
     nanosecond: u32,
     millisecond: u32,
 }
 
 pub(crate) struct TimeDateLazyLocked<'a> {
     time: SystemTime,
+    time_zone: TimeZone,
     locked: Option<TimeDateLocked<'a>>,
 }
 
 impl TimeDateLazyLocked<'_> {
     #[must_use]
-    pub(crate) fn new(time: SystemTime) -> Self {
-        Self { time, locked: None }
+    pub(crate) fn new(time: SystemTime, time_zone: TimeZone) -> Self {
+        Self {
+            time,
+            time_zone,
+            locked: None,
+        }
     }
 
     #[must_use]
     pub(crate) fn get(&mut self) -> TimeDate<'_> {
         let locked = self.locked.get_or_insert_with(|| {
-            let mut cached = LOCAL_TIME_CACHER.lock_expect();
-            let time_date = cached.get(self.time);
+            let mut cached = TIME_CACHER.lock_expect();
+            let time_date = cached.get(self.time, self.time_zone);
             let (nanosecond, millisecond) = (time_date.nanosecond, time_date.millisecond);
             TimeDateLocked {
                 cached,
@@ -344,7 +435,12 @@ impl TimeDateLazyLocked<'_> {
         });
 
         TimeDate {
-            cached: locked.cached.cache_values.as_mut().unwrap(),
+            cached: locked
+                .cached
+                .zone_mut(self.time_zone)
+                .cache_values
+                .as_mut()
+                .unwrap(),
             nanosecond: locked.nanosecond,
             millisecond: locked.millisecond,
         }
@@ -355,6 +451,7 @@ impl fmt::Debug for TimeDateLazyLocked<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("TimeDateLazyLocked")
             .field("time", &self.time)
+            .field("time_zone", &self.time_zone)
             .finish()
     }
 }
@@ -365,7 +462,8 @@ mod tests {
 
     #[test]
     fn validation() {
-        let mut cacher = LocalTimeCacher::new();
+        let mut cacher = TimeCacher::new();
+        let fixed = FixedOffset::east_opt(-(3 * 3600 + 30 * 60)).unwrap();
 
         let begin = SystemTime::now();
         loop {
@@ -373,15 +471,27 @@ mod tests {
             if now.duration_since(begin).unwrap().as_secs() >= 3 {
                 break;
             }
-            let from_cache = cacher.get(now);
             let from_chrono = DateTime::<Local>::from(now);
 
+            let from_cache = cacher.get(now, TimeZone::local());
             assert_eq!(
-                from_cache.cached.local_time.with_nanosecond(0),
-                from_chrono.with_nanosecond(0)
+                from_cache.cached.date_time,
+                from_chrono.with_nanosecond(0).unwrap()
             );
             assert_eq!(from_cache.nanosecond, from_chrono.nanosecond());
             assert_eq!(from_cache.millisecond, from_chrono.nanosecond() / 1_000_000);
+
+            let mut from_cache = cacher.get(now, TimeZone::utc());
+            assert_eq!(from_cache.date_time(), DateTime::<Utc>::from(now));
+            assert_eq!(from_cache.tz_offset_str(), "+00:00");
+
+            let mut from_cache =
+                cacher.get(now, TimeZone::fixed_offset(-(3 * 3600 + 30 * 60)).unwrap());
+            assert_eq!(
+                from_cache.date_time(),
+                DateTime::<Utc>::from(now).with_timezone(&fixed)
+            );
+            assert_eq!(from_cache.tz_offset_str(), "-03:30");
         }
     }
 }
@@ -0,0 +1,83 @@
+use chrono::FixedOffset;
+
+use crate::{error::InvalidArgumentError, Error, Result};
+
+/// Represents a time zone in which formatters display the time of log records.
+///
+/// The default time zone is the local time zone.
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::formatter::{FullFormatter, TimeZone};
+///
+/// # fn main() -> Result<(), spdlog::Error> {
+/// let utc = FullFormatter::builder().time_zone(TimeZone::utc()).build();
+///
+/// // UTC+08:00
+/// let beijing = FullFormatter::builder()
+///     .time_zone(TimeZone::fixed_offset(8 * 3600)?)
+///     .build();
+/// # Ok(()) }
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct TimeZone(TimeZoneInner);
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub(crate) enum TimeZoneInner {
+    #[default]
+    Local,
+    Utc,
+    Fixed(FixedOffset),
+}
+
+impl TimeZone {
+    /// The local time zone of the system.
+    #[must_use]
+    pub fn local() -> Self {
+        Self(TimeZoneInner::Local)
+    }
+
+    /// The Coordinated Universal Time (UTC).
+    #[must_use]
+    pub fn utc() -> Self {
+        Self(TimeZoneInner::Utc)
+    }
+
+    /// A time zone with a fixed offset from UTC.
+    ///
+    /// The offset is in seconds and positive values are east of UTC. It must
+    /// be in the range `(-86400, 86400)`, otherwise an error will be
+    /// returned.
+    pub fn fixed_offset(seconds: i32) -> Result<Self> {
+        FixedOffset::east_opt(seconds)
+            .map(|offset| Self(TimeZoneInner::Fixed(offset)))
+            .ok_or_else(|| {
+                Error::InvalidArgument(InvalidArgumentError::TimeZone(format!(
+                    "offset {seconds} seconds is out of bounds"
+                )))
+            })
+    }
+
+    #[must_use]
+    pub(crate) fn inner(&self) -> TimeZoneInner {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_offset_bounds() {
+        assert!(TimeZone::fixed_offset(0).is_ok());
+        assert!(TimeZone::fixed_offset(-(14 * 3600)).is_ok());
+        assert!(TimeZone::fixed_offset(86399).is_ok());
+        assert!(matches!(
+            TimeZone::fixed_offset(86400),
+            Err(Error::InvalidArgument(InvalidArgumentError::TimeZone(_)))
+        ));
+        assert!(TimeZone::fixed_offset(-86400).is_err());
+    }
+}
@@ -88,11 +88,20 @@
 // TODO: This above link annotation is unnecessary, but Rustdoc has bug:
 //       https://github.com/rust-lang/cargo/issues/3475
 //       Remove it when the bug is fixed.
-use std::{borrow::Cow, fmt, slice};
+use std::{
+    borrow::Cow,
+    fmt::{self, Write as _},
+    ops::Range,
+    slice,
+};
 
 use value_bag::{OwnedValueBag, ValueBag};
 
-use crate::utils::RefStr;
+use crate::{
+    formatter::{write_escaped, EscapeMode, EscapeWriter},
+    sync::*,
+    utils::{self, RefStr},
+};
 
 /// Represents a key in a key-value pair.
 #[derive(Debug, Clone)]
@@ -145,14 +154,96 @@ impl KeyOwned {
 /// Represents a value in a key-value pair.
 pub type Value<'a> = ValueBag<'a>;
 pub(crate) type ValueOwned = OwnedValueBag;
+pub(crate) type PairOwned = (KeyOwned, ValueOwned);
+
+// A key-value pair whose strings are stored in a text buffer. The buffer is
+// shared by all pairs of a `RecordOwned`, so that they do not allocate for each
+// key and value, and can be reused.
+#[derive(Clone, Debug)]
+pub(crate) struct PackedPair {
+    key: PackedKey,
+    value: PackedValue,
+}
+
+#[derive(Clone, Debug)]
+enum PackedKey {
+    Static(&'static str),
+    Text(Range<usize>),
+}
+
+#[derive(Clone, Debug)]
+enum PackedValue {
+    Text(Range<usize>),
+    // Only values that are neither strings nor primitives allocate
+    Owned(ValueOwned),
+}
 
+impl PackedPair {
+    #[must_use]
+    pub(crate) fn new(text: &mut String, key: &Key, value: &Value) -> Self {
+        let key = match key.0.as_static() {
+            Some(key) => PackedKey::Static(key),
+            None => PackedKey::Text(utils::push_str_range(text, key.as_str())),
+        };
+        let value = match value.to_borrowed_str() {
+            Some(value) => PackedValue::Text(utils::push_str_range(text, value)),
+            None => PackedValue::Owned(value.to_owned()),
+        };
+        Self { key, value }
+    }
+
+    #[must_use]
+    pub(crate) fn key<'a>(&'a self, text: &'a str) -> &'a str {
+        match &self.key {
+            PackedKey::Static(key) => key,
+            PackedKey::Text(range) => &text[range.clone()],
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn get<'a>(&'a self, text: &'a str) -> Pair<'a> {
+        let key = match &self.key {
+            PackedKey::Static(key) => Key::__from_static_str(key),
+            PackedKey::Text(range) => Key::from_str(&text[range.clone()]),
+        };
+        let value = match &self.value {
+            PackedValue::Text(range) => Value::from_str(&text[range.clone()]),
+            PackedValue::Owned(value) => value.by_ref(),
+        };
+        (key, value)
+    }
+}
+
+// The key-values given at a logging callsite.
+#[derive(Clone, Debug)]
+pub(crate) enum CallsitePairs<'a> {
+    Borrowed(Cow<'a, [Pair<'a>]>),
+    // The pairs of a `RecordOwned` and its text buffer
+    Packed(&'a [PackedPair], &'a str),
+}
+
+impl CallsitePairs<'_> {
+    #[must_use]
+    pub(crate) fn by_ref(&self) -> CallsitePairs<'_> {
+        match self {
+            Self::Borrowed(pairs) => CallsitePairs::Borrowed(Cow::Borrowed(pairs)),
+            Self::Packed(pairs, text) => CallsitePairs::Packed(pairs, text),
+        }
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = Pair<'_>> {
+        KeyValues::with_callsite(self, &[], &[]).iter()
+    }
+}
+
+#[derive(Clone, Copy)]
 enum KeyValuesInner<'a> {
     Borrowed(&'a [Pair<'a>]),
-    Owned(&'a [(KeyOwned, ValueOwned)]),
+    Packed(&'a [PackedPair], &'a str),
 }
 enum KeyValuesIterInner<'a> {
     Borrowed(slice::Iter<'a, Pair<'a>>),
-    Owned(slice::Iter<'a, (KeyOwned, ValueOwned)>),
+    Packed(slice::Iter<'a, PackedPair>, &'a str),
 }
 
 /// Represents a collection of key-value pairs.
@@ -186,38 +277,91 @@ enum KeyValuesIterInner<'a> {
 ///     }
 /// }
 /// ```
-pub struct KeyValues<'a>(KeyValuesInner<'a>);
+pub struct KeyValues<'a> {
+    callsite: KeyValuesInner<'a>,
+    context: &'a [PairOwned],
+    logger: &'a [PairOwned],
+}
 
 impl<'a> KeyValues<'a> {
     /// Gets the number of key-value pairs.
+    ///
+    /// Pairs shadowed by a pair with the same key and a higher precedence are
+    /// not counted, see [`KeyValues::get`].
     #[must_use]
     pub fn len(&self) -> usize {
-        match self.0 {
+        let callsite = match self.callsite {
             KeyValuesInner::Borrowed(p) => p.len(),
-            KeyValuesInner::Owned(p) => p.len(),
-        }
+            KeyValuesInner::Packed(p, _) => p.len(),
+        };
+        // Only the pairs from the context and bound to the logger can be shadowed
+        let context = self
+            .context
+            .iter()
+            .filter(|(k, _)| !self.is_shadowed(k, false))
+            .count();
+        let logger = self
+            .logger
+            .iter()
+            .filter(|(k, _)| !self.is_shadowed(k, true))
+            .count();
+        callsite + context + logger
     }
 
     /// Checks if there are no key-value pairs.
     #[must_use]
     pub fn is_empty(&self) -> bool {
-        match self.0 {
+        let callsite = match self.callsite {
             KeyValuesInner::Borrowed(p) => p.is_empty(),
-            KeyValuesInner::Owned(p) => p.is_empty(),
-        }
+            KeyValuesInner::Packed(p, _) => p.is_empty(),
+        };
+        callsite && self.context.is_empty() && self.logger.is_empty()
     }
 
     /// Gets the value of the specified key.
+    ///
+    /// If the key appears more than once, the value from the logging callsite
+    /// takes precedence over the one from the scoped context, which in turn
+    /// takes precedence over the one bound to the logger.
     #[must_use]
     pub fn get(&self, key: Key) -> Option<Value<'a>> {
-        match self.0 {
+        self.get_callsite(&key)
+            .or_else(|| find_owned(self.context, &key))
+            .or_else(|| find_owned(self.logger, &key))
+    }
+
+    /// Gets an iterator over the key-value pairs.
+    ///
+    /// Pairs shadowed by a pair with the same key and a higher precedence are
+    /// skipped, so the iterator yields the same values as [`KeyValues::get`].
+    pub fn iter(&self) -> KeyValuesIter<'a> {
+        let callsite = match &self.callsite {
+            KeyValuesInner::Borrowed(p) => KeyValuesIterInner::Borrowed(p.iter()),
+            KeyValuesInner::Packed(p, text) => KeyValuesIterInner::Packed(p.iter(), text),
+        };
+        KeyValuesIter {
+            kvs: KeyValues {
+                callsite: self.callsite,
+                context: self.context,
+                logger: self.logger,
+            },
+            callsite,
+            context: self.context.iter(),
+            logger: self.logger.iter(),
+        }
+    }
+
+    #[must_use]
+    fn get_callsite(&self, key: &Key) -> Option<Value<'a>> {
+        match self.callsite {
             KeyValuesInner::Borrowed(p) => {
                 p.iter()
-                    .find_map(|(k, v)| if k == &key { Some(v.clone()) } else { None })
+                    .find_map(|(k, v)| if k == key { Some(v.clone()) } else { None })
             }
-            KeyValuesInner::Owned(p) => p.iter().find_map(|(k, v)| {
-                if k.as_ref() == key {
-                    Some(v.by_ref())
+            KeyValuesInner::Packed(p, text) => p.iter().find_map(|pair| {
+                let (k, v) = pair.get(text);
+                if k == *key {
+                    Some(v)
                 } else {
                     None
                 }
@@ -225,23 +369,63 @@ impl<'a> KeyValues<'a> {
         }
     }
 
-    /// Gets an iterator over the key-value pairs.
-    pub fn iter(&self) -> KeyValuesIter<'a> {
-        match &self.0 {
-            KeyValuesInner::Borrowed(p) => KeyValuesIter(KeyValuesIterInner::Borrowed(p.iter())),
-            KeyValuesInner::Owned(p) => KeyValuesIter(KeyValuesIterInner::Owned(p.iter())),
+    // Checks if a pair from the context, or bound to the logger if
+    // `check_context` is `true`, is shadowed by a pair with the same key and a
+    // higher precedence. Only the keys are compared.
+    #[must_use]
+    fn is_shadowed(&self, key: &KeyOwned, check_context: bool) -> bool {
+        let key = key.as_ref();
+        let key = key.as_str();
+        let in_callsite = match self.callsite {
+            KeyValuesInner::Borrowed(p) => p.iter().any(|(k, _)| k.as_str() == key),
+            KeyValuesInner::Packed(p, text) => p.iter().any(|pair| pair.key(text) == key),
+        };
+        in_callsite
+            || (check_context && self.context.iter().any(|(k, _)| k.as_ref().as_str() == key))
+    }
+
+    pub(crate) fn with_borrowed(
+        pairs: &'a [Pair<'a>],
+        context: &'a [PairOwned],
+        logger: &'a [PairOwned],
+    ) -> Self {
+        Self {
+            callsite: KeyValuesInner::Borrowed(pairs),
+            context,
+            logger,
         }
     }
 
-    pub(crate) fn with_borrowed(pairs: &'a [Pair<'a>]) -> Self {
-        Self(KeyValuesInner::Borrowed(pairs))
+    pub(crate) fn with_packed(
+        pairs: &'a [PackedPair],
+        text: &'a str,
+        context: &'a [PairOwned],
+        logger: &'a [PairOwned],
+    ) -> Self {
+        Self {
+            callsite: KeyValuesInner::Packed(pairs, text),
+            context,
+            logger,
+        }
     }
 
-    pub(crate) fn with_owned(pairs: &'a [(KeyOwned, ValueOwned)]) -> Self {
-        Self(KeyValuesInner::Owned(pairs))
+    pub(crate) fn with_callsite(
+        pairs: &'a CallsitePairs<'_>,
+        context: &'a [PairOwned],
+        logger: &'a [PairOwned],
+    ) -> Self {
+        match pairs {
+            CallsitePairs::Borrowed(pairs) => Self::with_borrowed(pairs, context, logger),
+            CallsitePairs::Packed(pairs, text) => Self::with_packed(pairs, text, context, logger),
+        }
     }
 
-    pub(crate) fn write_to(&self, dest: &mut impl fmt::Write, brackets: bool) -> fmt::Result {
+    pub(crate) fn write_to(
+        &self,
+        dest: &mut impl fmt::Write,
+        brackets: bool,
+        escape_mode: EscapeMode,
+    ) -> fmt::Result {
         let mut iter = self.iter();
         let first = iter.next();
         if let Some((key, value)) = first {
@@ -251,15 +435,11 @@ impl<'a> KeyValues<'a> {
 
             // Reduce branch prediction misses for performance
             // So we manually process the first KV pair
-            dest.write_str(key.as_str())?;
-            dest.write_str("=")?;
-            write!(dest, "{value}")?;
+            write_pair(dest, &key, &value, escape_mode)?;
 
             for (key, value) in iter {
                 dest.write_str(" ")?;
-                dest.write_str(key.as_str())?;
-                dest.write_str("=")?;
-                write!(dest, "{value}")?;
+                write_pair(dest, &key, &value, escape_mode)?;
             }
 
             if brackets {
@@ -270,6 +450,34 @@ impl<'a> KeyValues<'a> {
     }
 }
 
+fn write_pair(
+    dest: &mut impl fmt::Write,
+    key: &Key,
+    value: &Value,
+    escape_mode: EscapeMode,
+) -> fmt::Result {
+    if escape_mode == EscapeMode::Disabled {
+        dest.write_str(key.as_str())?;
+        dest.write_str("=")?;
+        write!(dest, "{value}")
+    } else {
+        write_escaped(dest, key.as_str(), escape_mode)?;
+        dest.write_str("=")?;
+        write!(EscapeWriter::new(dest, escape_mode), "{value}")
+    }
+}
+
+#[must_use]
+fn find_owned<'a>(pairs: &'a [PairOwned], key: &Key) -> Option<Value<'a>> {
+    pairs.iter().find_map(|(k, v)| {
+        if k.as_ref() == *key {
+            Some(v.by_ref())
+        } else {
+            None
+        }
+    })
+}
+
 impl<'a> IntoIterator for KeyValues<'a> {
     type Item = Pair<'a>;
     type IntoIter = KeyValuesIter<'a>;
@@ -281,29 +489,62 @@ impl<'a> IntoIterator for KeyValues<'a> {
 
 /// Represents an iterator over key-value pairs.
 #[must_use]
-pub struct KeyValuesIter<'a>(KeyValuesIterInner<'a>);
+pub struct KeyValuesIter<'a> {
+    // For skipping the shadowed pairs
+    kvs: KeyValues<'a>,
+    callsite: KeyValuesIterInner<'a>,
+    context: slice::Iter<'a, PairOwned>,
+    logger: slice::Iter<'a, PairOwned>,
+}
 
 impl<'a> Iterator for KeyValuesIter<'a> {
     type Item = Pair<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match &mut self.0 {
+        let callsite = match &mut self.callsite {
             // The 2 clones should be cheap
             KeyValuesIterInner::Borrowed(iter) => iter.next().map(|(k, v)| (k.clone(), v.clone())),
-            KeyValuesIterInner::Owned(iter) => iter.next().map(|(k, v)| (k.as_ref(), v.by_ref())),
+            KeyValuesIterInner::Packed(iter, text) => iter.next().map(|pair| pair.get(text)),
+        };
+        if callsite.is_some() {
+            return callsite;
         }
+
+        let kvs = &self.kvs;
+        self.context
+            .find(|(k, _)| !kvs.is_shadowed(k, false))
+            .or_else(|| self.logger.find(|(k, _)| !kvs.is_shadowed(k, true)))
+            .map(|(k, v)| (k.as_ref(), v.by_ref()))
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        match &self.0 {
-            KeyValuesIterInner::Borrowed(iter) => iter.size_hint(),
-            KeyValuesIterInner::Owned(iter) => iter.size_hint(),
-        }
+        let callsite = match &self.callsite {
+            KeyValuesIterInner::Borrowed(iter) => iter.len(),
+            KeyValuesIterInner::Packed(iter, _) => iter.len(),
+        };
+        // The shadowed pairs are skipped
+        (
+            callsite,
+            Some(callsite + self.context.len() + self.logger.len()),
+        )
     }
 }
 
 pub(crate) type Pair<'a> = (Key<'a>, Value<'a>);
 
+// Appends `pairs` to `base`, the values of existing keys are replaced.
+#[must_use]
+pub(crate) fn merge_owned(base: &[PairOwned], pairs: &[Pair]) -> Arc<[PairOwned]> {
+    let mut merged = base.to_vec();
+    for (key, value) in pairs {
+        match merged.iter_mut().find(|(k, _)| k.as_ref() == *key) {
+            Some((_, v)) => *v = value.to_owned(),
+            None => merged.push((key.to_owned(), value.to_owned())),
+        }
+    }
+    merged.into()
+}
+
 #[cfg(feature = "log")]
 pub(crate) struct LogCrateConverter<'a>(Vec<(log::kv::Key<'a>, ValueOwned)>);
 
@@ -378,4 +619,76 @@ mod tests {
         assert_eq!(Key::from_str("a"), Key::__from_static_str("a"));
         assert_ne!(Key::from_str("a"), Key::__from_static_str("b"));
     }
+
+    #[test]
+    fn packed_pair() {
+        let key = String::from("dynamic");
+        let pairs = [
+            (Key::__from_static_str("static"), Value::from_str("str")),
+            (Key::from_str(&key), Value::from(114514)),
+            (Key::__from_static_str("debug"), Value::from_debug(&(1, 2))),
+        ];
+
+        let mut text = String::new();
+        let packed = pairs
+            .iter()
+            .map(|(key, value)| PackedPair::new(&mut text, key, value))
+            .collect::<Vec<_>>();
+        assert_eq!(text, "strdynamic");
+        assert!(matches!(packed[0].key, PackedKey::Static("static")));
+        assert!(matches!(packed[2].value, PackedValue::Owned(_)));
+
+        let kvs = KeyValues::with_packed(&packed, &text, &[], &[]);
+        assert_eq!(kvs.len(), 3);
+        let unpacked = kvs
+            .iter()
+            .map(|(key, value)| (key.as_str().to_string(), value.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            unpacked,
+            [
+                ("static".to_string(), "str".to_string()),
+                ("dynamic".to_string(), "114514".to_string()),
+                ("debug".to_string(), "(1, 2)".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn shadowed_pairs() {
+        let callsite = [(Key::__from_static_str("a"), Value::from(1))];
+        let context = merge_owned(
+            &[],
+            &[
+                (Key::__from_static_str("a"), Value::from(2)),
+                (Key::__from_static_str("b"), Value::from(2)),
+            ],
+        );
+        let logger = merge_owned(
+            &[],
+            &[
+                (Key::__from_static_str("b"), Value::from(3)),
+                (Key::__from_static_str("c"), Value::from(3)),
+                (Key::__from_static_str("a"), Value::from(3)),
+            ],
+        );
+        let mut text = String::new();
+        let packed = callsite
+            .iter()
+            .map(|(key, value)| PackedPair::new(&mut text, key, value))
+            .collect::<Vec<_>>();
+
+        for kvs in [
+            KeyValues::with_borrowed(&callsite, &context, &logger),
+            KeyValues::with_packed(&packed, &text, &context, &logger),
+        ] {
+            let pairs = kvs
+                .iter()
+                .map(|(key, value)| format!("{}={value}", key.as_str()))
+                .collect::<Vec<_>>();
+            assert_eq!(pairs, ["a=1", "b=2", "c=3"]);
+            assert_eq!(kvs.len(), 3);
+        }
+        assert_eq!(KeyValues::with_borrowed(&[], &[], &logger).len(), 3);
+    }
 }
@@ -81,7 +81,7 @@ impl serde::Serialize for Level {
 
 impl Level {
     #[must_use]
-    fn from_usize(u: usize) -> Option<Level> {
+    pub(crate) fn from_usize(u: usize) -> Option<Level> {
         match u {
             0 => Some(Level::Critical),
             1 => Some(Level::Error),
@@ -166,6 +166,19 @@ impl From<log::Level> for Level {
     }
 }
 
+#[cfg(feature = "log")]
+impl From<Level> for log::Level {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::Critical | Level::Error => Self::Error,
+            Level::Warn => Self::Warn,
+            Level::Info => Self::Info,
+            Level::Debug => Self::Debug,
+            Level::Trace => Self::Trace,
+        }
+    }
+}
+
 impl fmt::Display for Level {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.write_str(self.as_str())
@@ -19,8 +19,11 @@
 //! - [Compile-time and runtime pattern formatter]
 //! - [Asynchronous support]
 //! - [Structured logging]
+//! - [Deferred binary logging](binary)
 //! - [Compatible with log crate](LogCrateProxy)
+//! - [Compatible with tracing crate](TracingLayer)
 //! - [Capturing logs in tests](sink::StdStreamSinkBuilder::via_print_macro)
+//! - [Asserting logs in tests](sink::CaptureSink)
 //!
 //! [Compile-time and runtime pattern formatter]: formatter/index.html#compile-time-and-runtime-pattern-formatter
 //! [Asynchronous support]: crate::sink::AsyncPoolSink
@@ -203,6 +206,17 @@
 //!
 //!  - `log` enables the compatibility with [log crate].
 //!
+//!  - `tracing` enables [`TracingLayer`], forwarding events from [tracing
+//!    crate] to `spdlog-rs`.
+//!
+//!  - `otlp` enables [`sink::OtlpSink`], exporting logs to an OpenTelemetry
+//!    collector over OTLP/HTTP.
+//!
+//!  - `audit` enables [`sink::AuditSink`], writing hash-chained audit logs.
+//!
+//!  - `test-util` enables [`sink::CaptureSink`] and macro [`assert_logged!`],
+//!    for checking the log output in tests.
+//!
 //!  - `native` enables platform-specific components, such as
 //!    [`sink::WinDebugSink`] for Windows, [`sink::JournaldSink`] for Linux,
 //!    etc. Note If the component requires additional system dependencies, then
@@ -276,6 +290,7 @@
 //! [open a discussion]: https://github.com/SpriteOvO/spdlog-rs/discussions/new
 //! [open an issue]: https://github.com/SpriteOvO/spdlog-rs/issues/new/choose
 //! [log crate]: https://crates.io/crates/log
+//! [tracing crate]: https://crates.io/crates/tracing
 //! [`Formatter`]: crate::formatter::Formatter
 //! [`RuntimePattern`]: crate::formatter::RuntimePattern
 //! [`StdStreamSinkBuilder::via_print_macro`]: sink::StdStreamSinkBuilder::via_print_macro
@@ -291,6 +306,8 @@
 // Credits: https://stackoverflow.com/a/57049687
 extern crate self as spdlog;
 
+pub mod binary;
+pub mod context;
 mod env_level;
 pub mod error;
 pub mod formatter;
@@ -305,6 +322,7 @@ pub mod re_export;
 mod record;
 pub mod sink;
 mod source_location;
+pub mod span;
 mod string_buf;
 mod sync;
 pub mod terminal_style;
@@ -312,6 +330,8 @@ pub mod terminal_style;
 mod test_utils;
 #[cfg(feature = "multi-thread")]
 mod thread_pool;
+#[cfg(feature = "tracing")]
+mod tracing_layer;
 mod utils;
 
 pub use error::{Error, ErrorHandler, Result};
@@ -324,11 +344,47 @@ pub use source_location::*;
 #[doc(hidden)]
 #[cfg(feature = "runtime-pattern")]
 pub use spdlog_internal as __internal;
+/// Wraps a function body in a [span].
+///
+/// The span is named after the function, and is entered at the beginning of
+/// each call and exited when the call returns. Async functions are not
+/// supported yet.
+///
+/// ## Named optional parameters
+///
+/// The attribute accepts the same named optional parameters as macro
+/// [`span!`], and the following additional ones:
+///
+/// | Name    | Type / Basic Syntax | Description                                       |
+/// |---------|---------------------|---------------------------------------------------|
+/// | `level` | [`Level`]           | The level of the span. Defaults to `Level::Info`  |
+/// | `name`  | `&'static str`      | The name of the span. Defaults to the function name |
+///
+/// Parameter `kv` can capture the arguments of the function.
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::{info, instrument, Level};
+///
+/// #[instrument(level: Level::Debug, kv: { user_id, filter:? })]
+/// fn load_orders(user_id: u64, filter: Option<&str>) -> Vec<u64> {
+///     info!("querying"); // Carries key-values `span`, `user_id` and `filter`
+///     vec![]
+/// }
+/// # load_orders(1, None);
+/// ```
+///
+/// [span]: crate::span
+/// [`span!`]: crate::span!
+pub use spdlog_macros::instrument;
 #[doc(hidden)]
 pub use spdlog_macros::normalize_forward as __normalize_forward;
 pub use string_buf::StringBuf;
 #[cfg(feature = "multi-thread")]
 pub use thread_pool::*;
+#[cfg(feature = "tracing")]
+pub use tracing_layer::*;
 
 /// Contains all log macros and common types.
 pub mod prelude {
@@ -342,9 +398,11 @@ pub mod prelude {
 
 use std::{
     borrow::Cow,
+    cell::RefCell,
     env::{self, VarError},
     ffi::OsStr,
-    fmt, panic,
+    fmt::{self, Write as _},
+    panic,
     result::Result as StdResult,
 };
 
@@ -796,13 +854,43 @@ pub fn __log(
     kvs: &[(kv::Key, kv::Value)],
     fmt_args: fmt::Arguments,
 ) {
-    // Use `Cow` to avoid allocation as much as we can
-    let payload: Cow<str> = fmt_args
-        .as_str()
-        .map(Cow::Borrowed) // No format arguments, so it is a `&'static str`
-        .unwrap_or_else(|| Cow::Owned(fmt_args.to_string()));
-    let record = Record::new(level, payload, srcloc, logger.name(), kvs);
-    logger.log(&record);
+    // Buffers larger than this are not kept for the next record.
+    const MAX_PAYLOAD_BUFFER: usize = 4096;
+
+    thread_local! {
+        static PAYLOAD_BUFFER: RefCell<String> = const { RefCell::new(String::new()) };
+    }
+
+    let log = |payload: Cow<str>| {
+        let record = Record::new(level, payload, srcloc.clone(), logger.name(), kvs);
+        logger.log(&record);
+    };
+
+    // No format arguments, so it is a `&'static str`
+    if let Some(payload) = fmt_args.as_str() {
+        return log(Cow::Borrowed(payload));
+    }
+
+    // Use the buffer of the current thread to avoid allocation as much as we can.
+    // It is unavailable if something is logged while formatting the arguments, or
+    // when the thread is exiting.
+    let logged = PAYLOAD_BUFFER
+        .try_with(|buf| {
+            let Ok(mut buf) = buf.try_borrow_mut() else {
+                return false;
+            };
+            buf.clear();
+            _ = buf.write_fmt(fmt_args);
+            log(Cow::Borrowed(&buf));
+            if buf.capacity() > MAX_PAYLOAD_BUFFER {
+                *buf = String::new();
+            }
+            true
+        })
+        .unwrap_or(false);
+    if !logged {
+        log(Cow::Owned(fmt_args.to_string()));
+    }
 }
 
 #[cfg(test)]
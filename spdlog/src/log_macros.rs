@@ -178,6 +178,153 @@ macro_rules! trace {
     };
 }
 
+/// Logs a message with deferred formatting.
+///
+/// Unlike [`log!`], the arguments are captured as raw values, and the payload
+/// is only formatted when it is needed by a sink or a formatter. If all sinks
+/// of the logger are [`BinaryFileSink`]s, the payload is never formatted in the
+/// process. See [module `binary`] for the supported arguments and format
+/// strings, which are checked at compile time.
+///
+/// ## Named optional parameters
+///
+/// | Name     | Type / Basic Syntax       | Description                                     |
+/// |----------|---------------------------|-------------------------------------------------|
+/// | `logger` | `Arc<Logger>` or `Logger` | If unspecified, the default logger will be used |
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::{binary, log_deferred, Level};
+///
+/// # let app_events = spdlog::default_logger();
+/// let (id, elapsed) = (42, 1.5);
+///
+/// // Using the global default logger
+/// log_deferred!(Level::Info, "request {} done in {}s", id, elapsed);
+///
+/// // Or using the specified logger, and a value formatted on the caller thread
+/// log_deferred!(logger: app_events, Level::Warn, "unexpected level {}", binary::display(&Level::Warn));
+/// ```
+///
+/// [`BinaryFileSink`]: crate::sink::BinaryFileSink
+/// [module `binary`]: crate::binary
+#[macro_export]
+macro_rules! log_deferred {
+    ($($input:tt)+) => {
+        $crate::__normalize_forward!(__log_deferred_impl => default[logger: $crate::default_logger()], $($input)+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_deferred_impl {
+    (logger: $logger:expr, $level:expr, $format:literal $(, $arg:expr)* $(,)?) => ({
+        static CALLSITE: $crate::binary::Callsite =
+            $crate::binary::Callsite::__new($format, <[&str]>::len(&[$(stringify!($arg)),*]));
+
+        let logger = &$logger;
+        let level = $level;
+        if $crate::STATIC_LEVEL_FILTER.__test_const(level) && logger.should_log(level) {
+            if false {
+                // Type-checks the arguments as `format_args!` does
+                let _ = format_args!($format $(, $arg)*);
+            }
+            $crate::binary::__log(
+                logger,
+                level,
+                $crate::source_location_current!(),
+                &CALLSITE,
+                &[$($crate::binary::DeferredArg::__to_arg(&$arg)),*],
+            );
+        }
+    });
+}
+
+/// Enters a span, which logs when it is entered and exited.
+///
+/// The macro takes a [`Level`] and a name, and returns a [`Span`] guard. The
+/// span exits when the guard is dropped. See [module `span`] for details.
+///
+/// ## Named optional parameters
+///
+/// | Name        | Type / Basic Syntax         | Description                                                  |
+/// |-------------|-----------------------------|--------------------------------------------------------------|
+/// | `logger`    | `Arc<Logger>` or `Logger`   | If unspecified, the default logger will be used              |
+/// | `kv`        | `{ key = value, ... }`      | Key-values attached to records created while the span is alive |
+/// | `log_enter` | `bool`                      | Whether to log the enter record. Defaults to `true`          |
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::{span, Level};
+///
+/// # let db = spdlog::default_logger();
+/// # let table = "users";
+/// // Using the global default logger
+/// let _span = span!(Level::Info, "load_config");
+///
+/// // Or using the specified logger, with key-values, and only logging on exit
+/// let _span = span!(logger: db, Level::Debug, "query", kv: { table }, log_enter: false);
+/// ```
+///
+/// [`Level`]: crate::Level
+/// [`Span`]: crate::span::Span
+/// [module `span`]: crate::span
+#[macro_export]
+macro_rules! span {
+    ($($input:tt)+) => {
+        $crate::__normalize_forward!(__span_impl => default[logger: $crate::span::__DefaultLogger, kv: {}, log_enter: true], $($input)+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __span_impl {
+    (logger: $logger:expr, kv: $kv:tt, log_enter: $log_enter:expr, $level:expr, $name:expr $(,)?) => {
+        $crate::span::Span::__new(
+            $crate::span::__ToSpanLogger::__to_span_logger(&$logger),
+            $level,
+            $crate::source_location_current!(),
+            $name,
+            $crate::__kv!($kv),
+            $log_enter,
+        )
+    };
+}
+
+// Used by attribute `instrument`, parameters `level` and `name` are named.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __instrument_impl {
+    (logger: $logger:expr, kv: $kv:tt, log_enter: $log_enter:expr, level: $level:expr, name: $name:expr $(,)?) => {
+        $crate::__span_impl!(logger: $logger, kv: $kv, log_enter: $log_enter, $level, $name)
+    };
+}
+
+/// Builds key-values with the same syntax as the `kv` parameter of logging
+/// macros.
+///
+/// It evaluates to a `&[(kv::Key, kv::Value)]`, which is mainly used with
+/// [`context::push`].
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::context;
+///
+/// # let (user_id, session) = (1, "abc");
+/// let _guard = context::push(spdlog::kv!{ user_id, session:? });
+/// ```
+///
+/// [`context::push`]: crate::context::push
+#[macro_export]
+macro_rules! kv {
+    ($($ttm:tt)*) => {
+        $crate::__kv!({ $($ttm)* })
+    };
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! __kv {
@@ -271,7 +418,7 @@ mod tests {
                             .map(|(k, v)| (k.inner(), v.to_string()))
                             .collect::<Vec<_>>(),
                         kv.iter()
-                            .map(|(k, v)| (RefStr::new_ref(k), v.to_string()))
+                            .map(|(k, v)| (RefStr::new_static(k), v.to_string()))
                             .collect::<Vec<_>>()
                     );
                     assert_eq!(record.payload(), payload);
@@ -3,7 +3,9 @@ use std::{fmt::Debug, result::Result as StdResult, time::Duration};
 use crate::{
     env_level,
     error::{Error, ErrorHandler, InvalidArgumentError, SetLoggerNameError},
+    kv,
     periodic_worker::PeriodicWorker,
+    record,
     sink::{Sink, Sinks},
     sync::*,
     AtomicLevelFilter, Level, LevelFilter, Record, Result,
@@ -113,6 +115,8 @@ pub struct Logger {
     flush_level_filter: AtomicLevelFilter,
     error_handler: RwLock<ErrorHandler>,
     periodic_flusher: Mutex<Option<(Duration, PeriodicWorker)>>,
+    kvs: Option<Arc<[kv::PairOwned]>>,
+    seq: AtomicU64,
 }
 
 impl Debug for Logger {
@@ -171,6 +175,7 @@ impl Logger {
             sinks: vec![],
             flush_level_filter: LevelFilter::Off,
             error_handler: ErrorHandler::default(),
+            kvs: None,
         }
     }
 
@@ -238,7 +243,8 @@ impl Logger {
         if !self.should_log(record.level()) {
             return;
         }
-        self.sink_record(record);
+        let seq = self.seq.fetch_add(1, Ordering::Relaxed);
+        self.sink_record(&record.with_logger_props(seq, self.kvs.as_ref()));
     }
 
     /// Flushes sinks explicitly.
@@ -366,6 +372,27 @@ impl Logger {
         &mut self.sinks
     }
 
+    /// Gets the key-values bound to the logger.
+    #[must_use]
+    pub fn key_values(&self) -> kv::KeyValues<'_> {
+        kv::KeyValues::with_borrowed(&[], &[], self.kvs.as_deref().unwrap_or_default())
+    }
+
+    /// Binds key-values to the logger.
+    ///
+    /// The bound key-values are attached to every record logged by the logger,
+    /// after the key-values given at the logging callsite. If a key is already
+    /// bound, its value is replaced.
+    ///
+    /// This is usually used in the `modifier` of [`Logger::fork_with`], see
+    /// also [`Logger::fork_with_kv`].
+    pub fn bind_kv(&mut self, pairs: &[kv::Pair]) {
+        self.kvs = Some(kv::merge_owned(
+            self.kvs.as_deref().unwrap_or_default(),
+            pairs,
+        ));
+    }
+
     /// Sets a error handler.
     ///
     /// If an error occurs while logging or flushing, this handler will be
@@ -466,6 +493,44 @@ impl Logger {
         })
     }
 
+    /// Forks a separates new logger with additional bound key-values.
+    ///
+    /// This function creates a new logger object that inherits logger
+    /// properties, including the bound key-values, from `Arc<Self>` and binds
+    /// the given key-values to the new logger object. The created new logger
+    /// object will be a separate object from `Arc<Self>`. (No ownership
+    /// sharing)
+    ///
+    /// This is a shorthand wrapper for [`Logger::fork_with`] and
+    /// [`Logger::bind_kv`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    #[doc = include_str!(concat!(env!("OUT_DIR"), "/test_utils/common_for_doc_test.rs"))]
+    /// # use spdlog::{formatter::pattern, prelude::*};
+    /// #
+    /// # use std::sync::Arc;
+    /// # let (app, sink) = test_utils::echo_logger_from_pattern(pattern!("{payload} {kv}{eol}"), None);
+    /// # let app = Arc::new(app);
+    /// let shard = 3;
+    /// let db = app.fork_with_kv(spdlog::kv!{ component = "db", shard });
+    ///
+    /// info!(logger: db, "connected", kv: { latency_ms = 12 });
+    /// # assert_eq!(
+    /// #     sink.clone_string().replace("\r", ""),
+    /// /* Output */ "connected latency_ms=12 component=db shard=3\n"
+    /// # );
+    /// ```
+    #[must_use]
+    pub fn fork_with_kv(self: &Arc<Self>, pairs: &[kv::Pair]) -> Arc<Self> {
+        self.fork_with(|new| {
+            new.bind_kv(pairs);
+            Ok(())
+        })
+        .expect("the modifier never fails")
+    }
+
     // This will lose the periodic flush property, if any.
     #[must_use]
     fn clone_lossy(&self) -> Self {
@@ -476,6 +541,8 @@ impl Logger {
             flush_level_filter: AtomicLevelFilter::new(self.flush_level_filter()),
             periodic_flusher: Mutex::new(None),
             error_handler: RwLock::new(self.error_handler.read_expect().clone()),
+            kvs: self.kvs.clone(),
+            seq: AtomicU64::new(0),
         }
     }
 
@@ -551,6 +618,7 @@ pub struct LoggerBuilder {
     sinks: Sinks,
     flush_level_filter: LevelFilter,
     error_handler: ErrorHandler,
+    kvs: Option<Arc<[kv::PairOwned]>>,
 }
 
 impl LoggerBuilder {
@@ -630,6 +698,20 @@ impl LoggerBuilder {
         self
     }
 
+    /// Binds key-values to the logger.
+    ///
+    /// This parameter is **optional**, and defaults to no key-values.
+    ///
+    /// See the documentation of [`Logger::bind_kv`] for the description of
+    /// this parameter.
+    pub fn kv(&mut self, pairs: &[kv::Pair]) -> &mut Self {
+        self.kvs = Some(kv::merge_owned(
+            self.kvs.as_deref().unwrap_or_default(),
+            pairs,
+        ));
+        self
+    }
+
     /// Builds a [`Logger`].
     pub fn build(&mut self) -> Result<Logger> {
         self.build_inner(self.preset_level(false))
@@ -666,6 +748,7 @@ impl LoggerBuilder {
         if let Some(name) = &self.name {
             check_logger_name(name).map_err(InvalidArgumentError::from)?;
         }
+        record::init_start_instant();
 
         let logger = Logger {
             name: self.name.clone(),
@@ -674,6 +757,8 @@ impl LoggerBuilder {
             flush_level_filter: AtomicLevelFilter::new(self.flush_level_filter),
             error_handler: RwLock::new(self.error_handler.clone()),
             periodic_flusher: Mutex::new(None),
+            kvs: self.kvs.clone(),
+            seq: AtomicU64::new(0),
         };
 
         if let Some(preset_level) = preset_level {
@@ -975,4 +1060,53 @@ mod tests {
         assert_eq!(test_sink.1.log_count(), 0);
         assert_eq!(test_sink.1.flush_count(), 1);
     }
+
+    #[test]
+    fn bound_kvs() {
+        fn kvs_of(kvs: crate::kv::KeyValues) -> Vec<String> {
+            kvs.iter()
+                .map(|(k, v)| format!("{}={v}", k.as_str()))
+                .collect()
+        }
+
+        let test_sink = Arc::new(TestSink::new());
+        let root = Arc::new(build_test_logger(|b| {
+            b.sink(test_sink.clone()).kv(crate::kv!(app = "demo"))
+        }));
+        let db = root.fork_with_kv(crate::kv!(component = "db", shard = 3));
+        let replica = db.fork_with_kv(crate::kv!(shard = 4));
+
+        assert_eq!(kvs_of(root.key_values()), ["app=demo"]);
+        assert_eq!(
+            kvs_of(db.key_values()),
+            ["app=demo", "component=db", "shard=3"]
+        );
+        assert_eq!(
+            kvs_of(replica.key_values()),
+            ["app=demo", "component=db", "shard=4"]
+        );
+
+        info!(logger: root, "root");
+        info!(logger: db, "db", kv: { shard = 0 });
+        info!(logger: replica, "replica");
+
+        let records = test_sink.records();
+        assert_eq!(kvs_of(records[0].key_values()), ["app=demo"]);
+        assert_eq!(
+            kvs_of(records[1].key_values()),
+            ["shard=0", "app=demo", "component=db"]
+        );
+        assert_eq!(records[1].key_values().len(), 3);
+        assert_eq!(
+            records[1]
+                .key_values()
+                .get(crate::kv::Key::__from_static_str("shard"))
+                .map(|v| v.to_string()),
+            Some("0".to_string())
+        );
+        assert_eq!(
+            kvs_of(records[2].key_values()),
+            ["app=demo", "component=db", "shard=4"]
+        );
+    }
 }
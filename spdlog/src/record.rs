@@ -1,10 +1,16 @@
 use std::{
     borrow::{Borrow as _, Cow},
     cell::RefCell,
-    time::SystemTime,
+    ops::Range,
+    time::{Duration, Instant, SystemTime},
 };
 
-use crate::{kv, Level, SourceLocation};
+use crate::{
+    binary::{codec, Arg, Deferred},
+    context, kv,
+    sync::*,
+    utils, Level, SourceLocation,
+};
 
 /// Represents a log record.
 ///
@@ -24,7 +30,11 @@ use crate::{kv, Level, SourceLocation};
 pub struct Record<'a> {
     logger_name: Option<&'a str>,
     payload: Cow<'a, str>,
-    kvs: Cow<'a, [kv::Pair<'a>]>,
+    // If present, the payload is formatted from it lazily
+    deferred: Option<&'a Deferred<'a>>,
+    kvs: kv::CallsitePairs<'a>,
+    logger_kvs: Option<&'a Arc<[kv::PairOwned]>>,
+    seq: u64,
     inner: Cow<'a, RecordInner>,
 }
 
@@ -33,7 +43,34 @@ struct RecordInner {
     level: Level,
     source_location: Option<SourceLocation>,
     time: SystemTime,
+    elapsed: Duration,
     tid: u64,
+    // Looked up lazily, see `RecordInner::thread_name`
+    thread_name: OnceCell<Option<Arc<str>>>,
+    context: Option<Arc<[kv::PairOwned]>>,
+}
+
+impl RecordInner {
+    #[must_use]
+    fn context(&self) -> &[kv::PairOwned] {
+        self.context.as_deref().unwrap_or_default()
+    }
+
+    // Cloning the thread name for every record is not cheap, so it is only looked
+    // up when needed. The lookup is only correct on the thread that created the
+    // record, so it is forced before the record is converted to an owned one.
+    #[must_use]
+    fn thread_name(&self) -> Option<&str> {
+        self.thread_name
+            .get_or_init(|| {
+                if get_current_tid() == self.tid {
+                    get_current_thread_name()
+                } else {
+                    None
+                }
+            })
+            .as_deref()
+    }
 }
 
 impl<'a> Record<'a> {
@@ -48,29 +85,64 @@ impl<'a> Record<'a> {
         Record {
             logger_name,
             payload: payload.into(),
-            kvs: Cow::Borrowed(kvs),
+            deferred: None,
+            kvs: kv::CallsitePairs::Borrowed(Cow::Borrowed(kvs)),
+            logger_kvs: None,
+            seq: 0,
             inner: Cow::Owned(RecordInner {
                 level,
                 source_location: srcloc,
                 time: SystemTime::now(),
+                elapsed: get_elapsed(),
                 tid: get_current_tid(),
+                thread_name: OnceCell::new(),
+                context: context::current_pairs(),
             }),
         }
     }
 
+    #[must_use]
+    pub(crate) fn new_deferred(
+        level: Level,
+        deferred: &'a Deferred<'a>,
+        srcloc: Option<SourceLocation>,
+        logger_name: Option<&'a str>,
+    ) -> Record<'a> {
+        Record {
+            deferred: Some(deferred),
+            ..Self::new(level, "", srcloc, logger_name, &[])
+        }
+    }
+
     /// Creates a [`RecordOwned`] that doesn't have lifetimes.
+    ///
+    /// The logger name, the payload and the string keys and values of
+    /// key-values are copied into a single buffer, instead of being allocated
+    /// separately.
+    ///
+    /// For records logged by [`log_deferred!`], the arguments are copied
+    /// without being formatted, and the payload of the returned record is
+    /// formatted on the first call of [`RecordOwned::payload`]. The arguments are
+    /// allocated separately from the buffer, so converting such records always
+    /// allocates.
+    ///
+    /// [`log_deferred!`]: crate::log_deferred
     #[must_use]
     pub fn to_owned(&self) -> RecordOwned {
-        RecordOwned {
-            logger_name: self.logger_name.map(|n| n.to_owned()),
-            payload: self.payload.to_string(),
-            kvs: self
-                .kvs
-                .iter()
-                .map(|(k, v)| (k.to_owned(), v.to_owned()))
-                .collect(),
+        // Looks up the thread name while on the thread that created the record
+        _ = self.inner.thread_name();
+        let mut owned = RecordOwned {
+            text: String::new(),
+            logger_name: None,
+            payload: 0..0,
+            deferred: None,
+            kvs: Vec::new(),
+            logger_kvs: self.logger_kvs.cloned(),
+            seq: self.seq,
             inner: self.inner.clone().into_owned(),
-        }
+        };
+        owned.copy_data(self);
+        owned
     }
 
     /// Gets the logger name.
@@ -86,9 +158,17 @@ impl<'a> Record<'a> {
     }
 
     /// Gets the payload.
+    ///
+    /// For records logged by [`log_deferred!`], the payload is formatted on
+    /// the first call.
+    ///
+    /// [`log_deferred!`]: crate::log_deferred
     #[must_use]
     pub fn payload(&self) -> &str {
-        self.payload.borrow()
+        match self.deferred {
+            Some(deferred) => deferred.payload(),
+            None => self.payload.borrow(),
+        }
     }
 
     /// Gets the source location.
@@ -109,20 +189,105 @@ impl<'a> Record<'a> {
         self.inner.tid
     }
 
+    /// Gets the name of the thread that created the record.
+    ///
+    /// Returns `None` if the thread is unnamed.
+    ///
+    /// The name is looked up on the first call, which is expected to be made on
+    /// the thread that created the record, otherwise `None` is returned.
+    /// [`Record::to_owned`] looks it up, so that [`RecordOwned`] keeps it.
+    #[must_use]
+    pub fn thread_name(&self) -> Option<&str> {
+        self.inner.thread_name()
+    }
+
+    /// Gets the sequence number assigned by the logger.
+    ///
+    /// Each logger numbers the records it logs, starting from 0. Records that
+    /// are filtered out by the logger's level filter do not consume a
+    /// sequence number.
+    #[must_use]
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
     /// Gets the key-values.
+    ///
+    /// The key-values given at the logging callsite come first, followed by
+    /// the key-values from the [scoped context] when the record was created,
+    /// and then the key-values bound to the logger.
+    ///
+    /// [scoped context]: crate::context
     #[must_use]
     pub fn key_values(&self) -> kv::KeyValues<'_> {
-        kv::KeyValues::with_borrowed(&self.kvs)
+        kv::KeyValues::with_callsite(
+            &self.kvs,
+            self.inner.context(),
+            self.logger_kvs.map_or(&[], |kvs| kvs),
+        )
     }
 
     // When adding more getters, also add to `RecordOwned`
 
+    // The monotonic time elapsed from the first logger in the process being
+    // built to the creation of the record.
+    #[must_use]
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.inner.elapsed
+    }
+
+    // The callsite and the unformatted arguments, if the record is logged by
+    // `log_deferred!`.
+    #[must_use]
+    pub(crate) fn deferred(&self) -> Option<&'a Deferred<'a>> {
+        self.deferred
+    }
+
+    // Attaches the properties assigned by the logger.
+    #[must_use]
+    pub(crate) fn with_logger_props<'b>(
+        &'b self,
+        seq: u64,
+        logger_kvs: Option<&'b Arc<[kv::PairOwned]>>,
+    ) -> Record<'b> {
+        Record {
+            logger_name: self.logger_name,
+            payload: Cow::Borrowed(&self.payload),
+            deferred: self.deferred,
+            kvs: self.kvs.by_ref(),
+            logger_kvs,
+            seq,
+            inner: Cow::Borrowed(&self.inner),
+        }
+    }
+
     #[must_use]
     pub(crate) fn replace_payload(&'a self, new: impl Into<Cow<'a, str>>) -> Self {
         Self {
             logger_name: self.logger_name,
             payload: new.into(),
+            deferred: None,
             kvs: self.kvs.clone(),
+            logger_kvs: self.logger_kvs,
+            seq: self.seq,
+            inner: Cow::Borrowed(&self.inner),
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn append_kvs<'b>(&'b self, extra: &[kv::Pair<'b>]) -> Record<'b> {
+        let kvs = self
+            .kvs
+            .iter()
+            .chain(extra.iter().cloned())
+            .collect::<Vec<_>>();
+        Record {
+            logger_name: self.logger_name,
+            payload: Cow::Borrowed(&self.payload),
+            deferred: self.deferred,
+            kvs: kv::CallsitePairs::Borrowed(Cow::Owned(kvs)),
+            logger_kvs: self.logger_kvs,
+            seq: self.seq,
             inner: Cow::Borrowed(&self.inner),
         }
     }
@@ -138,25 +303,115 @@ impl<'a> Record<'a> {
 // Rust naming convention. Use `record.to_owned()` instead.
 #[derive(Clone, Debug)]
 pub struct RecordOwned {
-    logger_name: Option<String>,
-    payload: String,
-    kvs: Vec<(kv::KeyOwned, kv::ValueOwned)>,
+    // Holds the logger name, the payload and the strings of `kvs`, which are
+    // referenced by ranges
+    text: String,
+    logger_name: Option<Range<usize>>,
+    payload: Range<usize>,
+    // If present, the payload is formatted from it lazily
+    deferred: Option<Deferred<'static>>,
+    kvs: Vec<kv::PackedPair>,
+    logger_kvs: Option<Arc<[kv::PairOwned]>>,
+    seq: u64,
     inner: RecordInner,
 }
 
 impl RecordOwned {
+    // Creates a record decoded by `BinaryDecoder`.
+    #[must_use]
+    pub(crate) fn new_decoded(
+        level: Level,
+        payload: String,
+        srcloc: Option<SourceLocation>,
+        logger_name: Option<String>,
+        time: SystemTime,
+        tid: u64,
+        kvs: &[(String, Arg)],
+    ) -> Self {
+        let mut text = payload;
+        let payload = 0..text.len();
+        let logger_name = logger_name.map(|name| utils::push_str_range(&mut text, &name));
+        let kvs = kvs
+            .iter()
+            .map(|(key, value)| {
+                let value = codec::arg_to_value(value);
+                kv::PackedPair::new(&mut text, &kv::Key::from_str(key), &value)
+            })
+            .collect();
+        RecordOwned {
+            text,
+            logger_name,
+            payload,
+            deferred: None,
+            kvs,
+            logger_kvs: None,
+            seq: 0,
+            inner: RecordInner {
+                level,
+                source_location: srcloc,
+                time,
+                elapsed: Duration::ZERO,
+                tid,
+                thread_name: OnceCell::with_value(None),
+                context: None,
+            },
+        }
+    }
+
+    // Replaces the contents with the record, reusing the buffers of `self`, so
+    // that it does not allocate once the buffers are large enough.
+    pub(crate) fn assign(&mut self, record: &Record) {
+        // Looks up the thread name while on the thread that created the record
+        _ = record.inner.thread_name();
+        self.copy_data(record);
+        self.logger_kvs = record.logger_kvs.cloned();
+        self.seq = record.seq;
+        self.inner.clone_from(&record.inner);
+    }
+
+    // Releases the data shared with other records, so that keeping the record
+    // for reuse does not keep them alive.
+    #[cfg(feature = "multi-thread")]
+    pub(crate) fn release_shared(&mut self) {
+        self.deferred = None;
+        self.logger_kvs = None;
+        self.inner.thread_name = OnceCell::new();
+        self.inner.context = None;
+    }
+
+    // Gets the size in bytes of the buffers held by the record.
+    #[cfg(feature = "multi-thread")]
+    #[must_use]
+    pub(crate) fn buffer_size(&self) -> usize {
+        self.text.capacity() + self.kvs.capacity() * std::mem::size_of::<kv::PackedPair>()
+    }
+
+    fn copy_data(&mut self, record: &Record) {
+        self.text.clear();
+        self.logger_name = record
+            .logger_name
+            .map(|name| utils::push_str_range(&mut self.text, name));
+        (self.payload, self.deferred) = match record.deferred {
+            Some(deferred) => (0..0, Some(deferred.to_static())),
+            None => (utils::push_str_range(&mut self.text, &record.payload), None),
+        };
+        self.kvs.clear();
+        for (key, value) in record.kvs.iter() {
+            self.kvs
+                .push(kv::PackedPair::new(&mut self.text, &key, &value));
+        }
+    }
+
     /// References as [`Record`] cheaply.
     #[must_use]
     pub fn as_ref(&self) -> Record<'_> {
         Record {
-            logger_name: self.logger_name.as_deref(),
-            payload: Cow::Borrowed(&self.payload),
-            kvs: Cow::Owned(
-                self.kvs
-                    .iter()
-                    .map(|(k, v)| (k.as_ref(), v.by_ref()))
-                    .collect::<Vec<_>>(),
-            ),
+            logger_name: self.logger_name(),
+            payload: Cow::Borrowed(&self.text[self.payload.clone()]),
+            deferred: self.deferred.as_ref(),
+            kvs: kv::CallsitePairs::Packed(&self.kvs, &self.text),
+            logger_kvs: self.logger_kvs.as_ref(),
+            seq: self.seq,
             inner: Cow::Borrowed(&self.inner),
         }
     }
@@ -164,7 +419,7 @@ impl RecordOwned {
     /// Gets the logger name.
     #[must_use]
     pub fn logger_name(&self) -> Option<&str> {
-        self.logger_name.as_deref()
+        self.logger_name.clone().map(|range| &self.text[range])
     }
 
     /// Gets the level.
@@ -174,9 +429,17 @@ impl RecordOwned {
     }
 
     /// Gets the payload.
+    ///
+    /// For records logged by [`log_deferred!`], the payload is formatted on
+    /// the first call.
+    ///
+    /// [`log_deferred!`]: crate::log_deferred
     #[must_use]
     pub fn payload(&self) -> &str {
-        self.payload.borrow()
+        match &self.deferred {
+            Some(deferred) => deferred.payload(),
+            None => &self.text[self.payload.clone()],
+        }
     }
 
     /// Gets the source location.
@@ -197,10 +460,39 @@ impl RecordOwned {
         self.inner.tid
     }
 
+    /// Gets the name of the thread that created the record.
+    ///
+    /// Returns `None` if the thread is unnamed.
+    #[must_use]
+    pub fn thread_name(&self) -> Option<&str> {
+        self.inner.thread_name()
+    }
+
+    /// Gets the sequence number assigned by the logger.
+    ///
+    /// Each logger numbers the records it logs, starting from 0. Records that
+    /// are filtered out by the logger's level filter do not consume a
+    /// sequence number.
+    #[must_use]
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
     /// Gets the key-values.
+    ///
+    /// The key-values given at the logging callsite come first, followed by
+    /// the key-values from the [scoped context] when the record was created,
+    /// and then the key-values bound to the logger.
+    ///
+    /// [scoped context]: crate::context
     #[must_use]
     pub fn key_values(&self) -> kv::KeyValues<'_> {
-        kv::KeyValues::with_owned(&self.kvs)
+        kv::KeyValues::with_packed(
+            &self.kvs,
+            &self.text,
+            self.inner.context(),
+            self.logger_kvs.as_deref().unwrap_or_default(),
+        )
     }
 
     // When adding more getters, also add to `Record`
@@ -243,9 +535,12 @@ impl<'a> LogCrateRecord<'a> {
                 level: record.level().into(),
                 source_location: SourceLocation::from_log_crate_record(record),
                 time,
+                elapsed: get_elapsed(),
                 // For records from `log` crate, they never seem to come from different threads, so
                 // getting the current TID here should be correct
                 tid: get_current_tid(),
+                thread_name: OnceCell::new(),
+                context: context::current_pairs(),
             }),
         }
     }
@@ -255,11 +550,15 @@ impl<'a> LogCrateRecord<'a> {
         Record {
             logger_name: self.logger_name,
             payload: self.payload.clone(),
-            kvs: self
-                .kvs
-                .iter()
-                .map(|(k, v)| (kv::Key::from_str(k.as_str()), v.by_ref()))
-                .collect(),
+            deferred: None,
+            kvs: kv::CallsitePairs::Borrowed(
+                self.kvs
+                    .iter()
+                    .map(|(k, v)| (kv::Key::from_str(k.as_str()), v.by_ref()))
+                    .collect(),
+            ),
+            logger_kvs: None,
+            seq: 0,
             inner: self.inner.clone(),
         }
     }
@@ -312,3 +611,22 @@ fn get_current_tid() -> u64 {
 
     TID.with(|tid| *tid.borrow_mut().get_or_insert_with(get_current_tid_inner))
 }
+
+fn get_current_thread_name() -> Option<Arc<str>> {
+    thread_local! {
+        static THREAD_NAME: Option<Arc<str>> = std::thread::current().name().map(Arc::from);
+    }
+
+    THREAD_NAME.with(|name| name.clone())
+}
+
+static START_INSTANT: Lazy<Instant> = Lazy::new(Instant::now);
+
+// Sets the starting point of `Record::elapsed`, if not set yet.
+pub(crate) fn init_start_instant() {
+    Lazy::force(&START_INSTANT);
+}
+
+fn get_elapsed() -> Duration {
+    START_INSTANT.elapsed()
+}
@@ -1,9 +1,11 @@
+use std::mem;
+
 use crate::{
     default_thread_pool,
     formatter::{Formatter, UnreachableFormatter},
     sink::{OverflowPolicy, Sink, SinkProp, SinkPropAccess, Sinks},
     sync::*,
-    Error, ErrorHandler, LevelFilter, Record, RecordOwned, Result, ThreadPool,
+    thread_pool, Error, ErrorHandler, LevelFilter, Record, RecordOwned, Result, ThreadPool,
 };
 
 /// A [combined sink], logging and flushing asynchronously (thread-pool-based).
@@ -37,11 +39,26 @@ use crate::{
 /// Errors that occur in `log` and `flush` will not be returned directly,
 /// instead the error handler will be called.
 ///
+/// # Deferred formatting
+///
+/// Records are copied by [`Record::to_owned`] before being sent to the thread
+/// pool. Payloads of records logged by the usual logging macros are formatted
+/// on the caller thread, since the arguments are borrowed from the caller.
+///
+/// To move the formatting to the thread pool, log records with
+/// [`log_deferred!`]: their raw argument values are copied instead, and the
+/// payloads are formatted on the worker threads when the internal sinks ask for
+/// them. Key-values are copied with their types preserved for primitives and
+/// strings, while values captured by `Display` or `Debug` (the `:` and `:?`
+/// modifiers) are formatted on the caller thread, as they may not be safe to
+/// send to another thread.
+///
 /// # Examples
 ///
 /// See [./examples] directory.
 ///
 /// [combined sink]: index.html#combined-sink
+/// [`log_deferred!`]: crate::log_deferred
 /// [`ThreadPoolBuilder::capacity`]: crate::ThreadPoolBuilder::capacity
 /// [./examples]: https://github.com/SpriteOvO/spdlog-rs/tree/main/spdlog/examples
 // The names `AsyncSink` and `AsyncRuntimeSink` is reserved for future use.
@@ -123,13 +140,14 @@ impl Sink for AsyncPoolSink {
     fn log(&self, record: &Record) -> Result<()> {
         self.assign_task(Task::Log {
             backend: self.clone_backend(),
-            record: record.to_owned(),
+            record: self.thread_pool.to_owned_record(record),
         })
     }
 
     fn flush(&self) -> Result<()> {
         self.assign_task(Task::Flush {
             backend: self.clone_backend(),
+            remaining: Arc::new(AtomicUsize::new(1)),
         })
     }
 
@@ -261,10 +279,10 @@ pub(crate) struct Backend {
 }
 
 impl Backend {
-    fn log(&self, record: &Record) -> Result<()> {
+    fn log_batch(&self, records: &[Record]) -> Result<()> {
         let mut result = Ok(());
         for sink in &self.sinks {
-            result = Error::push_result(result, sink.log(record));
+            result = Error::push_result(result, sink.log_batch(records));
         }
         result
     }
@@ -290,6 +308,9 @@ impl Backend {
     }
 }
 
+// `Log` tasks are the vast majority, boxing the record would only add an
+// allocation for each of them.
+#[allow(clippy::large_enum_variant)]
 pub(crate) enum Task {
     Log {
         backend: Arc<Backend>,
@@ -297,24 +318,45 @@ pub(crate) enum Task {
     },
     Flush {
         backend: Arc<Backend>,
+        // The number of copies of the task not yet reached, see `TaskQueue::push`
+        remaining: Arc<AtomicUsize>,
     },
 }
 
 impl Task {
     // calls this function in async threads
-    pub(crate) fn exec(self) {
-        match self {
-            Task::Log { backend, record } => {
-                if let Err(err) = backend.log(&record.as_ref()) {
-                    backend.handle_error(err)
+    //
+    // Consecutive `Log` tasks of the same sink are logged as a batch.
+    //
+    // `records_buf` is only used for its allocation, it is empty after the call.
+    pub(crate) fn exec_batch(tasks: &[Task], records_buf: &mut Vec<Record<'static>>) {
+        let mut records = thread_pool::recycle_vec(mem::take(records_buf));
+        let mut tasks = tasks.iter().peekable();
+        while let Some(task) = tasks.next() {
+            match task {
+                Task::Log { backend, record } => {
+                    records.clear();
+                    records.push(record.as_ref());
+                    let is_same_sink = |task: &&Task| matches!(task, Task::Log { backend: next, .. } if Arc::ptr_eq(next, backend));
+                    while let Some(Task::Log { record, .. }) = tasks.next_if(is_same_sink) {
+                        records.push(record.as_ref());
+                    }
+
+                    if let Err(err) = backend.log_batch(&records) {
+                        backend.handle_error(err)
+                    }
                 }
-            }
-            Task::Flush { backend } => {
-                if let Err(err) = backend.flush() {
-                    backend.handle_error(err)
+                Task::Flush { backend, remaining } => {
+                    // Only the last reached copy flushes the sinks
+                    if remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+                        if let Err(err) = backend.flush() {
+                            backend.handle_error(err)
+                        }
+                    }
                 }
             }
         }
+        *records_buf = thread_pool::recycle_vec(records);
     }
 }
 
@@ -323,7 +365,7 @@ mod tests {
     use std::{thread::sleep, time::Duration};
 
     use super::*;
-    use crate::{prelude::*, test_utils::*};
+    use crate::{log_deferred, prelude::*, sink::GetSinkProp, test_utils::*};
 
     #[test]
     fn default_thread_pool() {
@@ -408,6 +450,92 @@ mod tests {
         assert_eq!(counter_sink.flush_count(), 1);
     }
 
+    #[test]
+    fn thread_name() {
+        let test_sink = Arc::new(TestSink::new());
+        let thread_pool = ThreadPool::builder().build_arc().unwrap();
+        let logger = Arc::new(build_test_logger(|b| {
+            b.sink(
+                AsyncPoolSink::builder()
+                    .sink(test_sink.clone())
+                    .thread_pool(thread_pool)
+                    .build_arc()
+                    .unwrap(),
+            )
+        }));
+
+        // The thread name is looked up on the logging thread, not the worker thread
+        std::thread::Builder::new()
+            .name("logging-thread".into())
+            .spawn({
+                let logger = logger.clone();
+                move || info!(logger: logger, "named")
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        sleep(Duration::from_millis(50));
+
+        let records = test_sink.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].thread_name(), Some("logging-thread"));
+    }
+
+    #[test]
+    fn deferred_formatting() {
+        // Records whether the payload has been formatted before the sink
+        struct CheckSink {
+            prop: SinkProp,
+            records: Mutex<Vec<(bool, String)>>,
+        }
+
+        impl GetSinkProp for CheckSink {
+            fn prop(&self) -> &SinkProp {
+                &self.prop
+            }
+        }
+
+        impl Sink for CheckSink {
+            fn log(&self, record: &Record) -> Result<()> {
+                let formatted = record.deferred().unwrap().is_formatted();
+                self.records
+                    .lock_expect()
+                    .push((formatted, record.payload().to_string()));
+                Ok(())
+            }
+
+            fn flush(&self) -> Result<()> {
+                Ok(())
+            }
+        }
+
+        let check_sink = Arc::new(CheckSink {
+            prop: SinkProp::default(),
+            records: Mutex::new(vec![]),
+        });
+        let thread_pool = ThreadPool::builder().build_arc().unwrap();
+        let logger = build_test_logger(|b| {
+            b.sink(
+                AsyncPoolSink::builder()
+                    .sink(check_sink.clone())
+                    .thread_pool(thread_pool)
+                    .build_arc()
+                    .unwrap(),
+            )
+        });
+
+        let name = String::from("meow");
+        log_deferred!(logger: logger, Level::Info, "{} {:?} {}", 1, name, 2.5);
+        drop(name);
+        drop(logger);
+        sleep(Duration::from_millis(200));
+
+        assert_eq!(
+            *check_sink.records.lock_expect(),
+            [(false, "1 \"meow\" 2.5".to_string())]
+        );
+    }
+
     #[test]
     fn async_opeartions() {
         let counter_sink = Arc::new(TestSink::with_delay(Some(Duration::from_secs(1))));
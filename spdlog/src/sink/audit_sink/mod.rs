@@ -0,0 +1,1031 @@
+//! Provides a tamper-evident audit log sink.
+
+use std::{
+    borrow::Cow,
+    convert::Infallible,
+    fmt::{self, Display, Write as _},
+    fs::{self, File},
+    io::{self, BufWriter, Read as _, Seek as _, SeekFrom, Write as _},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use sha2::{Digest as _, Sha256};
+
+use crate::{
+    formatter::{Formatter, FormatterContext},
+    sink::{GetSinkProp, Sink, SinkProp},
+    sync::*,
+    utils::{self, FileOpenOptions},
+    Error, ErrorHandler, LevelFilter, Record, Result, StringBuf,
+};
+
+/// A sink with a file as the target, writing tamper-evident audit logs.
+///
+/// Each formatted record is written as an entry, preceded by a header carrying
+/// a sequence number and a SHA-256 hash that chains the entry to the previous
+/// one:
+///
+/// ```text
+/// <seq> <kind> <hash> <len> <payload>
+/// ```
+///
+/// - `seq` is the sequence number of the entry, starting from 1.
+/// - `kind` is `R` for a record, or `C` for a checkpoint.
+/// - `hash` is the hex-encoded SHA-256 over the hash of the previous entry (32
+///   zero bytes for the first entry), `seq` as a 64-bit big-endian integer,
+///   `kind` and `payload`.
+/// - `len` is the length of `payload` in bytes.
+/// - `payload` is the formatted record. A space is inserted after each newline
+///   inside it, and a newline is appended if it does not end with one.
+///
+/// So every line of the file that does not start with a space starts an entry,
+/// and a logged message can not contain a line that would be taken as an entry.
+///
+/// Editing, removing, inserting or reordering entries breaks the chain, which
+/// can be detected with [`AuditSink::verify`]. If the file already exists, the
+/// sink continues the chain of the last entry in it.
+///
+/// A chain alone can not detect a file that is rewritten entirely with
+/// recomputed hashes, or entries removed from the end. If
+/// [`checkpoint_interval`] is specified, checkpoint entries holding the running
+/// hash are written periodically. Copying them to a separate storage as they
+/// are written (e.g. another machine) allows such alterations to be detected
+/// by comparing the hashes.
+///
+/// The file and directories will be created recursively if they do not exist.
+///
+/// This sink is only available if crate feature `audit` is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use spdlog::{prelude::*, sink::AuditSink};
+///
+/// # fn main() -> Result<(), spdlog::Error> {
+/// # let path = std::env::temp_dir().join("spdlog-rs-audit-sink-doctest.log");
+/// # _ = std::fs::remove_file(&path);
+/// let sink = AuditSink::builder()
+///     .path(&path)
+///     .checkpoint_interval(Duration::from_secs(60))
+///     .build_arc()?;
+/// let logger = Logger::builder().sink(sink).build()?;
+///
+/// info!(logger: logger, "user 'alice' logged in");
+/// logger.flush();
+///
+/// let report = AuditSink::verify(&path)?;
+/// assert!(report.is_intact());
+/// # Ok(()) }
+/// ```
+///
+/// [`checkpoint_interval`]: AuditSinkBuilder::checkpoint_interval
+pub struct AuditSink {
+    prop: SinkProp,
+    checkpoint_interval: Option<Duration>,
+    inner: Mutex<AuditSinkInner>,
+}
+
+struct AuditSinkInner {
+    file: BufWriter<File>,
+    seq: u64,
+    hash: Hash,
+    next_checkpoint: Instant,
+    // Whether any record has been written since the last checkpoint
+    unchecked: bool,
+}
+
+impl AuditSink {
+    /// Gets a builder of `AuditSink` with default parameters:
+    ///
+    /// | Parameter             | Default Value               |
+    /// |-----------------------|-----------------------------|
+    /// | [level_filter]        | [`LevelFilter::All`]        |
+    /// | [formatter]           | [`FullFormatter`]           |
+    /// | [error_handler]       | [`ErrorHandler::default()`] |
+    /// |                       |                             |
+    /// | [path]                | *must be specified*         |
+    /// | [checkpoint_interval] | disabled                    |
+    /// | [file_mode]           | `0o666` (masked by umask)   |
+    ///
+    /// [level_filter]: AuditSinkBuilder::level_filter
+    /// [formatter]: AuditSinkBuilder::formatter
+    /// [`FullFormatter`]: crate::formatter::FullFormatter
+    /// [error_handler]: AuditSinkBuilder::error_handler
+    /// [path]: AuditSinkBuilder::path
+    /// [checkpoint_interval]: AuditSinkBuilder::checkpoint_interval
+    /// [file_mode]: AuditSinkBuilder::file_mode
+    #[must_use]
+    pub fn builder() -> AuditSinkBuilder<()> {
+        AuditSinkBuilder {
+            prop: SinkProp::default(),
+            path: (),
+            checkpoint_interval: None,
+            open_options: FileOpenOptions::default(),
+        }
+    }
+
+    /// Verifies the hash chain of an audit log file written by `AuditSink`.
+    ///
+    /// All entries in the file are checked, the returned [`AuditReport`]
+    /// contains every issue found. After an issue, the verification continues
+    /// from the next entry, trusting its header, so that each alteration is
+    /// reported individually.
+    ///
+    /// # Error
+    ///
+    /// If an error occurs reading the file, [`Error::ReadFile`] will be
+    /// returned.
+    pub fn verify<P: AsRef<Path>>(path: P) -> Result<AuditReport> {
+        let contents = fs::read(path).map_err(Error::ReadFile)?;
+        Ok(verify(&contents))
+    }
+}
+
+impl GetSinkProp for AuditSink {
+    fn prop(&self) -> &SinkProp {
+        &self.prop
+    }
+}
+
+impl Sink for AuditSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        let mut string_buf = StringBuf::new();
+        let mut ctx = FormatterContext::new();
+        self.prop
+            .formatter()
+            .format(record, &mut string_buf, &mut ctx)?;
+
+        let mut inner = self.inner.lock_expect();
+        if let Some(interval) = self.checkpoint_interval {
+            let now = Instant::now();
+            if inner.unchecked && now >= inner.next_checkpoint {
+                inner.write_checkpoint()?;
+                inner.next_checkpoint = now + interval;
+            }
+        }
+        inner.write_entry(EntryKind::Record, string_buf.as_bytes())?;
+        inner.unchecked = true;
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.inner
+            .lock_expect()
+            .file
+            .flush()
+            .map_err(Error::FlushBuffer)
+    }
+}
+
+impl Drop for AuditSink {
+    fn drop(&mut self) {
+        let mut inner = self.inner.lock_expect();
+        let checkpoint = if self.checkpoint_interval.is_some() && inner.unchecked {
+            inner.write_checkpoint()
+        } else {
+            Ok(())
+        };
+        let flush = inner.file.flush().map_err(Error::FlushBuffer);
+        if let Err(err) = Error::push_result(checkpoint, flush) {
+            self.prop.call_error_handler_internal("AuditSink", err)
+        }
+    }
+}
+
+impl AuditSinkInner {
+    fn write_entry(&mut self, kind: EntryKind, payload: &[u8]) -> Result<()> {
+        let payload = fold_payload(payload);
+        let payload = payload.as_ref();
+        let seq = self.seq + 1;
+        let hash = chain_hash(&self.hash, seq, kind, payload);
+        let header = format!(
+            "{seq} {} {} {} ",
+            kind.as_char(),
+            to_hex(&hash),
+            payload.len()
+        );
+        self.file
+            .write_all(header.as_bytes())
+            .and_then(|()| self.file.write_all(payload))
+            .map_err(Error::WriteRecord)?;
+
+        self.seq = seq;
+        self.hash = hash;
+        Ok(())
+    }
+
+    fn write_checkpoint(&mut self) -> Result<()> {
+        let time: DateTime<Utc> = SystemTime::now().into();
+        let payload = format!(
+            "checkpoint time={} running_hash={}\n",
+            time.to_rfc3339_opts(SecondsFormat::Micros, true),
+            to_hex(&self.hash)
+        );
+        self.write_entry(EntryKind::Checkpoint, payload.as_bytes())?;
+        self.unchecked = false;
+        Ok(())
+    }
+}
+
+/// The result of verifying an audit log file.
+///
+/// See [`AuditSink::verify`].
+#[derive(Clone, Debug)]
+pub struct AuditReport {
+    entries: u64,
+    checkpoints: u64,
+    last: Option<(u64, Hash)>,
+    issues: Vec<AuditIssue>,
+}
+
+impl AuditReport {
+    /// Whether no issue has been found.
+    #[must_use]
+    pub fn is_intact(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Gets the number of well-formed entries, including checkpoints.
+    #[must_use]
+    pub fn entries(&self) -> u64 {
+        self.entries
+    }
+
+    /// Gets the number of well-formed checkpoint entries.
+    #[must_use]
+    pub fn checkpoints(&self) -> u64 {
+        self.checkpoints
+    }
+
+    /// Gets the hex-encoded hash of the last well-formed entry.
+    ///
+    /// It can be compared with the running hash of a checkpoint stored
+    /// elsewhere, to detect entries removed from the end of the file.
+    ///
+    /// Returns `None` if the file contains no well-formed entry.
+    #[must_use]
+    pub fn last_hash(&self) -> Option<String> {
+        self.last.map(|(_, hash)| to_hex(&hash))
+    }
+
+    /// Gets the issues found, in the order of their positions in the file.
+    #[must_use]
+    pub fn issues(&self) -> &[AuditIssue] {
+        &self.issues
+    }
+}
+
+/// An issue found in an audit log file.
+///
+/// The `offset` is the position of the entry in the file, in bytes.
+///
+/// See [`AuditSink::verify`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum AuditIssue {
+    /// Entries are missing before the entry, its sequence number is greater
+    /// than expected.
+    Gap {
+        /// The position of the entry.
+        offset: u64,
+        /// The expected sequence number.
+        expected: u64,
+        /// The sequence number of the entry.
+        found: u64,
+    },
+    /// The entry is reordered or duplicated, its sequence number is less than
+    /// expected.
+    Reorder {
+        /// The position of the entry.
+        offset: u64,
+        /// The expected sequence number.
+        expected: u64,
+        /// The sequence number of the entry.
+        found: u64,
+    },
+    /// The entry has been edited, its hash does not match its content and the
+    /// previous entry.
+    Edited {
+        /// The position of the entry.
+        offset: u64,
+        /// The sequence number of the entry.
+        seq: u64,
+    },
+    /// The data is not a well-formed entry, e.g. the file has been truncated or
+    /// edited outside the payloads. The verification continues from the next
+    /// line.
+    Malformed {
+        /// The position of the data.
+        offset: u64,
+    },
+}
+
+impl Display for AuditIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gap {
+                offset,
+                expected,
+                found,
+            } => write!(
+                f,
+                "gap at offset {offset}: expected entry {expected} but found {found}"
+            ),
+            Self::Reorder {
+                offset,
+                expected,
+                found,
+            } => write!(
+                f,
+                "reorder at offset {offset}: expected entry {expected} but found {found}"
+            ),
+            Self::Edited { offset, seq } => {
+                write!(f, "entry {seq} at offset {offset} has been edited")
+            }
+            Self::Malformed { offset } => write!(f, "malformed entry at offset {offset}"),
+        }
+    }
+}
+
+const HASH_LEN: usize = 32;
+
+type Hash = [u8; HASH_LEN];
+
+// The maximum length of an entry header, with the longest `seq` and `len`.
+const MAX_HEADER_LEN: usize = 20 + 1 + 1 + 1 + HASH_LEN * 2 + 1 + 20 + 1;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum EntryKind {
+    Record,
+    Checkpoint,
+}
+
+impl EntryKind {
+    #[must_use]
+    fn as_char(&self) -> char {
+        match self {
+            Self::Record => 'R',
+            Self::Checkpoint => 'C',
+        }
+    }
+}
+
+struct Entry<'a> {
+    seq: u64,
+    kind: EntryKind,
+    hash: Hash,
+    payload: &'a [u8],
+}
+
+struct Header {
+    seq: u64,
+    kind: EntryKind,
+    hash: Hash,
+    header_len: usize,
+    payload_len: usize,
+}
+
+// Inserts a space after each newline inside the payload, and appends a newline
+// if it does not end with one, see the documentation of `AuditSink`.
+#[must_use]
+fn fold_payload(payload: &[u8]) -> Cow<'_, [u8]> {
+    let body = payload.strip_suffix(b"\n").unwrap_or(payload);
+    if body.len() != payload.len() && !body.contains(&b'\n') {
+        return Cow::Borrowed(payload);
+    }
+
+    let mut folded = Vec::with_capacity(payload.len() + 1);
+    for &byte in body {
+        folded.push(byte);
+        if byte == b'\n' {
+            folded.push(b' ');
+        }
+    }
+    folded.push(b'\n');
+    Cow::Owned(folded)
+}
+
+// Whether the payload is folded by `fold_payload`.
+#[must_use]
+fn is_folded(payload: &[u8]) -> bool {
+    payload.strip_suffix(b"\n").is_some_and(|body| {
+        body.windows(2).all(|w| w[0] != b'\n' || w[1] == b' ') && body.last() != Some(&b'\n')
+    })
+}
+
+#[must_use]
+fn chain_hash(prev: &Hash, seq: u64, kind: EntryKind, payload: &[u8]) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(prev);
+    hasher.update(seq.to_be_bytes());
+    hasher.update([kind.as_char() as u8]);
+    hasher.update(payload);
+    hasher.finalize().into()
+}
+
+// Parses the entry at the beginning of the data, returns it with its total
+// length in bytes.
+#[must_use]
+fn parse_entry(data: &[u8]) -> Option<(Entry<'_>, usize)> {
+    let header = parse_header(data)?;
+    let total_len = header.header_len.checked_add(header.payload_len)?;
+    let payload = data.get(header.header_len..total_len)?;
+    if !is_folded(payload) {
+        return None;
+    }
+
+    let entry = Entry {
+        seq: header.seq,
+        kind: header.kind,
+        hash: header.hash,
+        payload,
+    };
+    Some((entry, total_len))
+}
+
+// Parses the header of the entry at the beginning of the data, the payload is
+// not required to be in the data.
+#[must_use]
+fn parse_header(data: &[u8]) -> Option<Header> {
+    let mut fields = data.splitn(5, |&b| b == b' ');
+    let mut next_field = || std::str::from_utf8(fields.next()?).ok();
+
+    let seq_str = next_field()?;
+    let kind_str = next_field()?;
+    let hash_str = next_field()?;
+    let len_str = next_field()?;
+
+    // `parse` accepts a leading `+`
+    let is_decimal = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if !is_decimal(seq_str) || !is_decimal(len_str) {
+        return None;
+    }
+    let seq = seq_str.parse().ok()?;
+    let kind = match kind_str {
+        "R" => EntryKind::Record,
+        "C" => EntryKind::Checkpoint,
+        _ => return None,
+    };
+    let hash = from_hex(hash_str)?;
+    let payload_len = len_str.parse().ok()?;
+    // The separator after `len` must be present
+    fields.next()?;
+
+    let header_len = seq_str.len() + kind_str.len() + hash_str.len() + len_str.len() + 4;
+    Some(Header {
+        seq,
+        kind,
+        hash,
+        header_len,
+        payload_len,
+    })
+}
+
+// Finds the sequence number and the hash of the last entry of the file, by
+// scanning backwards for the last line that starts an entry. So only the last
+// entries are read, regardless of the size of the file. The chain is not
+// verified, see `AuditSink::verify`.
+//
+// Also returns whether the file ends with a partially written line, which must
+// be terminated before writing.
+fn resume_point(path: &Path) -> io::Result<(Option<(u64, Hash)>, bool)> {
+    const CHUNK_LEN: u64 = 64 * 1024;
+
+    let mut file = File::open(path)?;
+    let file_len = file.seek(SeekFrom::End(0))?;
+    if file_len == 0 {
+        return Ok((None, false));
+    }
+    let mut last_byte = [0];
+    file.seek(SeekFrom::Start(file_len - 1))?;
+    file.read_exact(&mut last_byte)?;
+    let torn = last_byte[0] != b'\n';
+
+    // The start of the entry following the candidates, the last entry must end
+    // there. A malformed or partially written entry is skipped by moving it.
+    let mut next_start = file_len;
+    let mut check = |file: &mut File, offset: u64| -> io::Result<Option<(u64, Hash)>> {
+        Ok(match read_line_start(file, offset)? {
+            LineStart::Continuation => None,
+            LineStart::Entry { seq, hash, end }
+                if end == next_start && (end != file_len || !torn) =>
+            {
+                Some((seq, hash))
+            }
+            LineStart::Entry { .. } | LineStart::Malformed => {
+                next_start = offset;
+                None
+            }
+        })
+    };
+
+    let mut chunk = vec![];
+    let mut end = file_len;
+    while end > 0 {
+        let start = end.saturating_sub(CHUNK_LEN);
+        chunk.resize((end - start) as usize, 0);
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)?;
+
+        let line_starts = chunk
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, &b)| b == b'\n')
+            .map(|(pos, _)| start + pos as u64 + 1)
+            .filter(|&offset| offset < file_len)
+            .collect::<Vec<_>>();
+        for offset in line_starts {
+            if let Some(last) = check(&mut file, offset)? {
+                return Ok((Some(last), torn));
+            }
+        }
+        end = start;
+    }
+    Ok((check(&mut file, 0)?, torn))
+}
+
+enum LineStart {
+    // A line inside the payload of an entry
+    Continuation,
+    Entry { seq: u64, hash: Hash, end: u64 },
+    Malformed,
+}
+
+fn read_line_start(file: &mut File, offset: u64) -> io::Result<LineStart> {
+    let mut header = [0; MAX_HEADER_LEN];
+    file.seek(SeekFrom::Start(offset))?;
+    let len = file.take(MAX_HEADER_LEN as u64).read(&mut header)?;
+    if header[..len].first() == Some(&b' ') {
+        return Ok(LineStart::Continuation);
+    }
+
+    let entry = parse_header(&header[..len]).and_then(|header| {
+        let end = offset
+            .checked_add(header.header_len as u64)?
+            .checked_add(header.payload_len as u64)?;
+        Some(LineStart::Entry {
+            seq: header.seq,
+            hash: header.hash,
+            end,
+        })
+    });
+    Ok(entry.unwrap_or(LineStart::Malformed))
+}
+
+#[must_use]
+fn verify(data: &[u8]) -> AuditReport {
+    let mut report = AuditReport {
+        entries: 0,
+        checkpoints: 0,
+        last: None,
+        issues: vec![],
+    };
+    // `None` if the previous data is malformed, the chain is then resynchronized
+    // with the next entry
+    let mut prev = Some((0, [0; HASH_LEN]));
+    let mut offset = 0;
+
+    while offset < data.len() {
+        let Some((entry, len)) = parse_entry(&data[offset..]) else {
+            report.issues.push(AuditIssue::Malformed {
+                offset: offset as u64,
+            });
+            // Skips to the next line that is not a continuation of a payload
+            offset = data[offset..]
+                .windows(2)
+                .position(|w| w[0] == b'\n' && w[1] != b' ')
+                .map_or(data.len(), |pos| offset + pos + 1);
+            prev = None;
+            continue;
+        };
+
+        if let Some((prev_seq, prev_hash)) = prev {
+            let expected = prev_seq + 1;
+            let issue = if entry.seq > expected {
+                Some(AuditIssue::Gap {
+                    offset: offset as u64,
+                    expected,
+                    found: entry.seq,
+                })
+            } else if entry.seq < expected {
+                Some(AuditIssue::Reorder {
+                    offset: offset as u64,
+                    expected,
+                    found: entry.seq,
+                })
+            } else if chain_hash(&prev_hash, entry.seq, entry.kind, entry.payload) != entry.hash {
+                Some(AuditIssue::Edited {
+                    offset: offset as u64,
+                    seq: entry.seq,
+                })
+            } else {
+                None
+            };
+            report.issues.extend(issue);
+        }
+
+        report.entries += 1;
+        if entry.kind == EntryKind::Checkpoint {
+            report.checkpoints += 1;
+        }
+        report.last = Some((entry.seq, entry.hash));
+        prev = report.last;
+        offset += len;
+    }
+
+    report
+}
+
+#[must_use]
+fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut hex, byte| {
+            _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
+
+#[must_use]
+fn from_hex(hex: &str) -> Option<Hash> {
+    if hex.len() != HASH_LEN * 2 {
+        return None;
+    }
+    let mut hash = [0; HASH_LEN];
+    for (byte, digits) in hash.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()?;
+    }
+    Some(hash)
+}
+
+// --------------------------------------------------
+
+/// #
+#[doc = include_str!("../../include/doc/generic-builder-note.md")]
+pub struct AuditSinkBuilder<ArgPath> {
+    prop: SinkProp,
+    path: ArgPath,
+    checkpoint_interval: Option<Duration>,
+    open_options: FileOpenOptions,
+}
+
+impl<ArgPath> AuditSinkBuilder<ArgPath> {
+    /// The path of the audit log file.
+    ///
+    /// This parameter is **required**.
+    #[must_use]
+    pub fn path<P>(self, path: P) -> AuditSinkBuilder<PathBuf>
+    where
+        P: Into<PathBuf>,
+    {
+        AuditSinkBuilder {
+            prop: self.prop,
+            path: path.into(),
+            checkpoint_interval: self.checkpoint_interval,
+            open_options: self.open_options,
+        }
+    }
+
+    /// Specifies the interval of writing checkpoint entries.
+    ///
+    /// When a record is logged, a checkpoint entry is written before it if the
+    /// interval has elapsed since the last checkpoint, and any record has been
+    /// written since then. A checkpoint entry is also written when the sink is
+    /// dropped. See [`AuditSink`] for the purpose of checkpoints.
+    ///
+    /// This parameter is **optional**, and defaults to disabled.
+    #[must_use]
+    pub fn checkpoint_interval(mut self, interval: Duration) -> Self {
+        self.checkpoint_interval = Some(interval);
+        self
+    }
+
+    /// Specifies the permission mode bits of the audit log file if it is
+    /// created by the sink, e.g. `0o640`.
+    ///
+    /// Like `open(2)`, the mode is masked by the umask of the process. The mode
+    /// of an existing file is not changed.
+    ///
+    /// This parameter is **optional**, and defaults to `0o666` (masked by the
+    /// umask).
+    #[cfg(unix)]
+    #[must_use]
+    pub fn file_mode(mut self, mode: u32) -> Self {
+        self.open_options.file_mode = Some(mode);
+        self
+    }
+
+    // Prop
+    //
+
+    /// Specifies a log level filter.
+    ///
+    /// This parameter is **optional**, and defaults to [`LevelFilter::All`].
+    #[must_use]
+    pub fn level_filter(self, level_filter: LevelFilter) -> Self {
+        self.prop.set_level_filter(level_filter);
+        self
+    }
+
+    /// Specifies a formatter.
+    ///
+    /// This parameter is **optional**, and defaults to [`FullFormatter`].
+    ///
+    /// [`FullFormatter`]: crate::formatter::FullFormatter
+    #[must_use]
+    pub fn formatter<F>(self, formatter: F) -> Self
+    where
+        F: Formatter + 'static,
+    {
+        self.prop.set_formatter(formatter);
+        self
+    }
+
+    /// Specifies an error handler.
+    ///
+    /// This parameter is **optional**, and defaults to
+    /// [`ErrorHandler::default()`].
+    #[must_use]
+    pub fn error_handler<F: Into<ErrorHandler>>(self, handler: F) -> Self {
+        self.prop.set_error_handler(handler);
+        self
+    }
+}
+
+impl AuditSinkBuilder<()> {
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required parameter `path`\n\n\
+    ")]
+    pub fn build(self, _: Infallible) {}
+
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required parameter `path`\n\n\
+    ")]
+    pub fn build_arc(self, _: Infallible) {}
+}
+
+impl AuditSinkBuilder<PathBuf> {
+    /// Builds an [`AuditSink`].
+    ///
+    /// If the file already exists, the chain is continued from its last
+    /// well-formed entry, which is found by reading backwards from the end of
+    /// the file, without verifying the chain. Use [`AuditSink::verify`] to
+    /// verify it. If the file ends with a partially written line, e.g. after a
+    /// crash while writing, the line is terminated.
+    ///
+    /// # Error
+    ///
+    /// If an error occurs opening, reading or writing the file,
+    /// [`Error::CreateDirectory`], [`Error::OpenFile`], [`Error::ReadFile`] or
+    /// [`Error::WriteRecord`] will be returned.
+    pub fn build(self) -> Result<AuditSink> {
+        let mut file = utils::open_file_bufw(&self.path, false, None, &self.open_options)?;
+        let (last, torn) = resume_point(&self.path).map_err(Error::ReadFile)?;
+        let (seq, hash) = last.unwrap_or((0, [0; HASH_LEN]));
+        if torn {
+            file.write_all(b"\n").map_err(Error::WriteRecord)?;
+        }
+
+        let sink = AuditSink {
+            prop: self.prop,
+            checkpoint_interval: self.checkpoint_interval,
+            inner: Mutex::new(AuditSinkInner {
+                file,
+                seq,
+                hash,
+                next_checkpoint: Instant::now() + self.checkpoint_interval.unwrap_or_default(),
+                unchecked: false,
+            }),
+        };
+        Ok(sink)
+    }
+
+    /// Builds a `Arc<AuditSink>`.
+    ///
+    /// This is a shorthand method for `.build().map(Arc::new)`.
+    pub fn build_arc(self) -> Result<Arc<AuditSink>> {
+        self.build().map(Arc::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, test_utils::*};
+
+    static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+        let path = TEST_LOGS_PATH.join("audit_sink");
+        _ = fs::remove_dir_all(&path);
+        fs::create_dir(&path).unwrap();
+        path
+    });
+
+    fn write_audit_log(path: &Path, messages: &[&str], checkpoint_interval: Option<Duration>) {
+        let mut builder = AuditSink::builder().path(path);
+        if let Some(interval) = checkpoint_interval {
+            builder = builder.checkpoint_interval(interval);
+        }
+        let sink = builder.build_arc().unwrap();
+        let logger = build_test_logger(|b| b.sink(sink));
+        for message in messages {
+            info!(logger: logger, "{message}");
+        }
+    }
+
+    #[test]
+    fn chain_and_resume() {
+        let path = LOGS_PATH.join("chain.log");
+        write_audit_log(&path, &["1", "2", "3"], None);
+        let report = AuditSink::verify(&path).unwrap();
+        assert!(report.is_intact());
+        assert_eq!(report.entries(), 3);
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines = contents.lines().collect::<Vec<_>>();
+        assert!(lines[0].starts_with("1 R "));
+        assert!(lines[2].starts_with("3 R "));
+        assert!(lines[2].ends_with("] 3"));
+
+        write_audit_log(&path, &["4"], None);
+        let resumed = AuditSink::verify(&path).unwrap();
+        assert!(resumed.is_intact());
+        assert_eq!(resumed.entries(), 4);
+        assert_ne!(resumed.last_hash(), report.last_hash());
+    }
+
+    #[test]
+    fn resume_from_last_entry() {
+        let path = LOGS_PATH.join("resume.log");
+
+        // Lines of a large payload looking like entries must not be taken as the last one
+        let fake_entry = format!("9 R {} 1 ", "0".repeat(HASH_LEN * 2));
+        let large = format!("{fake_entry}\n").repeat(2000);
+        write_audit_log(&path, &["1", &large, &fake_entry], None);
+        write_audit_log(&path, &["4"], None);
+        let report = AuditSink::verify(&path).unwrap();
+        assert!(report.is_intact());
+        assert_eq!(report.entries(), 4);
+
+        // Partially written entry
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"5 R 0123")
+            .unwrap();
+        write_audit_log(&path, &["5"], None);
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents
+            .lines()
+            .nth_back(1)
+            .unwrap()
+            .starts_with("5 R 0123"));
+        assert!(contents.lines().last().unwrap().starts_with("5 R "));
+        let report = AuditSink::verify(&path).unwrap();
+        assert_eq!(report.entries(), 5);
+        assert_eq!(report.issues().len(), 1);
+    }
+
+    #[test]
+    fn forged_entry() {
+        let path = LOGS_PATH.join("forged.log");
+
+        // A message ending with a line that would be a valid entry following the
+        // real one, if payloads were not folded
+        write_audit_log(&path, &["1"], None);
+        let report = AuditSink::verify(&path).unwrap();
+        let (seq, hash) = report.last.unwrap();
+        let forged_hash = chain_hash(&hash, seq + 2, EntryKind::Record, b"a\n");
+        let forged = format!("x\n{} R {} 2 a", seq + 2, to_hex(&forged_hash));
+        write_audit_log(&path, &[&forged], None);
+        write_audit_log(&path, &["3"], None);
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.contains(&format!("\n {} R ", seq + 2)));
+        let report = AuditSink::verify(&path).unwrap();
+        assert!(report.is_intact());
+        assert_eq!(report.entries(), 3);
+        assert!(contents.lines().last().unwrap().starts_with("3 R "));
+    }
+
+    #[test]
+    fn fold_payloads() {
+        assert_eq!(fold_payload(b"a\n").as_ref(), b"a\n");
+        assert_eq!(fold_payload(b"a").as_ref(), b"a\n");
+        assert_eq!(fold_payload(b"").as_ref(), b"\n");
+        assert_eq!(fold_payload(b"a\nb\n\n").as_ref(), b"a\n b\n \n");
+        for payload in [&b"a\n"[..], b"a\n b\n \n", b"\n"] {
+            assert!(is_folded(payload));
+        }
+        for payload in [&b"a"[..], b"a\nb\n", b"a\n\n", b""] {
+            assert!(!is_folded(payload));
+        }
+    }
+
+    #[test]
+    fn checkpoints() {
+        let path = LOGS_PATH.join("checkpoints.log");
+        write_audit_log(&path, &["1", "2"], Some(Duration::ZERO));
+
+        // R, C, R, C (on drop)
+        let report = AuditSink::verify(&path).unwrap();
+        assert!(report.is_intact());
+        assert_eq!(report.entries(), 4);
+        assert_eq!(report.checkpoints(), 2);
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines = contents.lines().collect::<Vec<_>>();
+        let first_hash = lines[0].split(' ').nth(2).unwrap();
+        assert!(lines[1].starts_with("2 C "));
+        assert!(lines[1].ends_with(&format!("running_hash={first_hash}")));
+    }
+
+    #[test]
+    fn detect_issues() {
+        let path = LOGS_PATH.join("issues.log");
+        write_audit_log(&path, &["1", "2", "3", "4", "5"], None);
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines = contents.split_inclusive('\n').collect::<Vec<_>>();
+        let offset_of = |lines: &[&str], index: usize| {
+            lines[..index].iter().map(|line| line.len()).sum::<usize>() as u64
+        };
+
+        let check = |lines: &[&str], expected: &[AuditIssue]| {
+            let report = verify(lines.concat().as_bytes());
+            assert_eq!(report.issues(), expected);
+        };
+
+        // Edit
+        let mut edited = lines.clone();
+        let line = lines[1].replace("] 2", "] 7");
+        edited[1] = &line;
+        check(
+            &edited,
+            &[AuditIssue::Edited {
+                offset: offset_of(&lines, 1),
+                seq: 2,
+            }],
+        );
+
+        // Removal
+        let mut removed = lines.clone();
+        removed.remove(2);
+        check(
+            &removed,
+            &[AuditIssue::Gap {
+                offset: offset_of(&lines, 2),
+                expected: 3,
+                found: 4,
+            }],
+        );
+
+        // Reorder
+        let mut reordered = lines.clone();
+        reordered.swap(1, 2);
+        check(
+            &reordered,
+            &[
+                AuditIssue::Gap {
+                    offset: offset_of(&lines, 1),
+                    expected: 2,
+                    found: 3,
+                },
+                AuditIssue::Reorder {
+                    offset: offset_of(&lines, 2),
+                    expected: 4,
+                    found: 2,
+                },
+                AuditIssue::Gap {
+                    offset: offset_of(&lines, 3),
+                    expected: 3,
+                    found: 4,
+                },
+            ],
+        );
+
+        // Insertion and truncation
+        let mut malformed = lines.clone();
+        malformed.insert(3, "forged\n");
+        malformed[5] = &lines[4][..10];
+        check(
+            &malformed,
+            &[
+                AuditIssue::Malformed {
+                    offset: offset_of(&lines, 3),
+                },
+                AuditIssue::Malformed {
+                    offset: offset_of(&lines, 4) + 7,
+                },
+            ],
+        );
+    }
+}
@@ -0,0 +1,439 @@
+//! Provides a binary file sink.
+
+use std::{
+    convert::Infallible,
+    fs::File,
+    io::{BufWriter, Write as _},
+    path::PathBuf,
+};
+
+use crate::{
+    binary::codec,
+    sink::{GetSinkProp, Sink, SinkProp},
+    sync::*,
+    utils::{self, FileOpenOptions},
+    Error, ErrorHandler, LevelFilter, Record, Result,
+};
+
+/// A sink writing records to a file in a compact binary format.
+///
+/// Records logged by [`log_deferred!`] are written without formatting their
+/// payloads: the format string and the source location of each callsite are
+/// written once, and each record only contains the raw argument values. Other
+/// records are written with their formatted payload. The key-values of records
+/// are written as well, including the ones from the [scoped context] and the
+/// ones bound to the logger. See [module `binary`] for details.
+///
+/// The file can be turned back into text by [`BinaryDecoder`] with any
+/// formatter, so this sink does not use its formatter.
+///
+/// The file and directories will be created recursively if they do not exist.
+/// If the file already exists and is not empty, records are appended to it,
+/// it must have been written by a `BinaryFileSink` as well.
+///
+/// [`log_deferred!`]: crate::log_deferred
+/// [scoped context]: crate::context
+/// [module `binary`]: crate::binary
+/// [`BinaryDecoder`]: crate::binary::BinaryDecoder
+pub struct BinaryFileSink {
+    prop: SinkProp,
+    inner: Mutex<BinaryFileSinkInner>,
+}
+
+struct BinaryFileSinkInner {
+    file: BufWriter<File>,
+    buf: Vec<u8>,
+    // Indexed by callsite ids
+    written_callsites: Vec<bool>,
+}
+
+impl BinaryFileSink {
+    /// Gets a builder of `BinaryFileSink` with default parameters:
+    ///
+    /// | Parameter       | Default Value               |
+    /// |-----------------|-----------------------------|
+    /// | [level_filter]  | [`LevelFilter::All`]        |
+    /// | [error_handler] | [`ErrorHandler::default()`] |
+    /// |                 |                             |
+    /// | [path]          | *must be specified*         |
+    /// | [truncate]      | `false`                     |
+    /// | [capacity]      | consistent with `std`       |
+    ///
+    /// [level_filter]: BinaryFileSinkBuilder::level_filter
+    /// [error_handler]: BinaryFileSinkBuilder::error_handler
+    /// [path]: BinaryFileSinkBuilder::path
+    /// [truncate]: BinaryFileSinkBuilder::truncate
+    /// [capacity]: BinaryFileSinkBuilder::capacity
+    #[must_use]
+    pub fn builder() -> BinaryFileSinkBuilder<()> {
+        BinaryFileSinkBuilder {
+            prop: SinkProp::default(),
+            path: (),
+            truncate: false,
+            capacity: None,
+        }
+    }
+}
+
+impl GetSinkProp for BinaryFileSink {
+    fn prop(&self) -> &SinkProp {
+        &self.prop
+    }
+}
+
+impl Sink for BinaryFileSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        let mut inner = self.inner.lock_expect();
+        let inner = &mut *inner;
+
+        inner.buf.clear();
+        let new_callsite = match record.deferred() {
+            Some(deferred) => {
+                let callsite = deferred.callsite();
+                let id = callsite.id();
+                let index = id as usize;
+                let is_new = !inner.written_callsites.get(index).copied().unwrap_or(false);
+                if is_new {
+                    codec::put_callsite(
+                        &mut inner.buf,
+                        id,
+                        callsite.format(),
+                        record.source_location(),
+                    );
+                }
+                codec::put_deferred_record(&mut inner.buf, id, record, deferred.args());
+                is_new.then_some(index)
+            }
+            None => {
+                codec::put_text_record(&mut inner.buf, record);
+                None
+            }
+        };
+
+        inner
+            .file
+            .write_all(&inner.buf)
+            .map_err(Error::WriteRecord)?;
+
+        // Marks the callsite as written only if the definition has been written
+        if let Some(index) = new_callsite {
+            if inner.written_callsites.len() <= index {
+                inner.written_callsites.resize(index + 1, false);
+            }
+            inner.written_callsites[index] = true;
+        }
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.inner
+            .lock_expect()
+            .file
+            .flush()
+            .map_err(Error::FlushBuffer)
+    }
+}
+
+impl Drop for BinaryFileSink {
+    fn drop(&mut self) {
+        if let Err(err) = self.inner.lock_expect().file.flush() {
+            self.prop
+                .call_error_handler_internal("BinaryFileSink", Error::FlushBuffer(err))
+        }
+    }
+}
+
+// --------------------------------------------------
+
+/// #
+#[doc = include_str!("../include/doc/generic-builder-note.md")]
+pub struct BinaryFileSinkBuilder<ArgPath> {
+    prop: SinkProp,
+    path: ArgPath,
+    truncate: bool,
+    capacity: Option<usize>,
+}
+
+impl<ArgPath> BinaryFileSinkBuilder<ArgPath> {
+    /// The path of the binary log file.
+    ///
+    /// This parameter is **required**.
+    #[must_use]
+    pub fn path<P>(self, path: P) -> BinaryFileSinkBuilder<PathBuf>
+    where
+        P: Into<PathBuf>,
+    {
+        BinaryFileSinkBuilder {
+            prop: self.prop,
+            path: path.into(),
+            truncate: self.truncate,
+            capacity: self.capacity,
+        }
+    }
+
+    /// Truncates the contents when opening an existing file.
+    ///
+    /// If it is `true`, the existing contents of the file will be discarded.
+    ///
+    /// This parameter is **optional**, and defaults to `false`.
+    #[must_use]
+    pub fn truncate(mut self, truncate: bool) -> Self {
+        self.truncate = truncate;
+        self
+    }
+
+    /// Specifies the internal buffer capacity.
+    ///
+    /// This parameter is **optional**, and defaults to the value consistent
+    /// with `std`.
+    #[must_use]
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    // Prop
+    //
+
+    /// Specifies a log level filter.
+    ///
+    /// This parameter is **optional**, and defaults to [`LevelFilter::All`].
+    #[must_use]
+    pub fn level_filter(self, level_filter: LevelFilter) -> Self {
+        self.prop.set_level_filter(level_filter);
+        self
+    }
+
+    /// Specifies an error handler.
+    ///
+    /// This parameter is **optional**, and defaults to
+    /// [`ErrorHandler::default()`].
+    #[must_use]
+    pub fn error_handler<F: Into<ErrorHandler>>(self, handler: F) -> Self {
+        self.prop.set_error_handler(handler);
+        self
+    }
+}
+
+impl BinaryFileSinkBuilder<()> {
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required parameter `path`\n\n\
+    ")]
+    pub fn build(self, _: Infallible) {}
+
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required parameter `path`\n\n\
+    ")]
+    pub fn build_arc(self, _: Infallible) {}
+}
+
+impl BinaryFileSinkBuilder<PathBuf> {
+    /// Builds a [`BinaryFileSink`].
+    ///
+    /// # Error
+    ///
+    /// If an error occurs opening the file, [`Error::CreateDirectory`],
+    /// [`Error::OpenFile`] or [`Error::QueryFileMetadata`] will be returned.
+    /// If an error occurs writing the header of the file,
+    /// [`Error::WriteRecord`] will be returned.
+    pub fn build(self) -> Result<BinaryFileSink> {
+        let mut file = utils::open_file_bufw(
+            &self.path,
+            self.truncate,
+            self.capacity,
+            &FileOpenOptions::default(),
+        )?;
+        let len = file
+            .get_ref()
+            .metadata()
+            .map_err(Error::QueryFileMetadata)?
+            .len();
+        if len == 0 {
+            file.write_all(codec::MAGIC).map_err(Error::WriteRecord)?;
+        }
+
+        let sink = BinaryFileSink {
+            prop: self.prop,
+            inner: Mutex::new(BinaryFileSinkInner {
+                file,
+                buf: Vec::new(),
+                written_callsites: Vec::new(),
+            }),
+        };
+
+        Ok(sink)
+    }
+
+    /// Builds a `Arc<BinaryFileSink>`.
+    ///
+    /// This is a shorthand method for `.build().map(Arc::new)`.
+    pub fn build_arc(self) -> Result<Arc<BinaryFileSink>> {
+        self.build().map(Arc::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        binary::{self, BinaryDecoder},
+        formatter::{pattern, PatternFormatter},
+        log_deferred,
+        prelude::*,
+        test_utils::*,
+    };
+
+    static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+        let path = TEST_LOGS_PATH.join("binary_file_sink");
+        _ = fs::remove_dir_all(&path);
+        fs::create_dir(&path).unwrap();
+        path
+    });
+
+    fn build_logger(path: &PathBuf, truncate: bool) -> Logger {
+        let sink = BinaryFileSink::builder()
+            .path(path)
+            .truncate(truncate)
+            .build_arc()
+            .unwrap();
+        build_test_logger(|b| b.sink(sink).name("binary").level_filter(LevelFilter::All))
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = LOGS_PATH.join("round_trip.bin");
+        let logger = build_logger(&path, true);
+        for i in 0..3 {
+            log_deferred!(logger: logger, Level::Info, "deferred {} {:?} {}", i, "str", -1.5);
+        }
+        warn!(logger: logger, "text {}", 1);
+        log_deferred!(logger: logger, Level::Error, "{} {}", i128::MIN, binary::debug(&[1, 2]));
+        drop(logger);
+
+        // Appending to the existing file, in which callsites are defined again
+        let logger = build_logger(&path, false);
+        log_deferred!(logger: logger, Level::Debug, "appended {}", true);
+        drop(logger);
+
+        let records = BinaryDecoder::open(&path)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let payloads = records.iter().map(|r| r.payload()).collect::<Vec<_>>();
+        assert_eq!(
+            payloads,
+            [
+                "deferred 0 \"str\" -1.5",
+                "deferred 1 \"str\" -1.5",
+                "deferred 2 \"str\" -1.5",
+                "text 1",
+                "-170141183460469231731687303715884105728 [1, 2]",
+                "appended true",
+            ]
+        );
+        let levels = records.iter().map(|r| r.level()).collect::<Vec<_>>();
+        assert_eq!(
+            levels,
+            [
+                Level::Info,
+                Level::Info,
+                Level::Info,
+                Level::Warn,
+                Level::Error,
+                Level::Debug
+            ]
+        );
+        assert!(records.iter().all(|r| r.logger_name() == Some("binary")));
+        #[cfg(feature = "source-location")]
+        assert!(records
+            .iter()
+            .all(|r| { r.source_location().unwrap().file_name() == "binary_file_sink.rs" }));
+
+        let mut text = Vec::new();
+        BinaryDecoder::open(&path)
+            .unwrap()
+            .write_formatted(
+                &PatternFormatter::new(pattern!("[{level}] {payload}\n")),
+                &mut text,
+            )
+            .unwrap();
+        assert!(String::from_utf8(text)
+            .unwrap()
+            .starts_with("[info] deferred 0 \"str\" -1.5\n[info] deferred 1"));
+    }
+
+    #[test]
+    fn key_values() {
+        let path = LOGS_PATH.join("key_values.bin");
+        let sink = BinaryFileSink::builder()
+            .path(&path)
+            .truncate(true)
+            .build_arc()
+            .unwrap();
+        let logger = build_test_logger(|b| b.sink(sink).kv(crate::kv!(app = "demo", shard = 1)));
+        {
+            let _guard = crate::context::push(crate::kv!(request_id = 42_u64));
+            info!(logger: logger, "text", kv: { shard = 2, ok = true, ratio = 0.5, c = 'x' });
+            log_deferred!(logger: logger, Level::Info, "deferred");
+        }
+        drop(logger);
+
+        let records = BinaryDecoder::open(&path)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let kvs = |i: usize| {
+            records[i]
+                .key_values()
+                .iter()
+                .map(|(k, v)| format!("{}={v}", k.as_str()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            kvs(0),
+            [
+                "shard=2",
+                "ok=true",
+                "ratio=0.5",
+                "c=x",
+                "request_id=42",
+                "app=demo"
+            ]
+        );
+        assert_eq!(kvs(1), ["request_id=42", "app=demo", "shard=1"]);
+        assert_eq!(
+            records[0]
+                .key_values()
+                .get(crate::kv::Key::__from_static_str("shard"))
+                .and_then(|v| v.to_u64()),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert!(matches!(
+            BinaryDecoder::new(&b"not a binary log"[..]),
+            Err(Error::DecodeBinaryLog(_))
+        ));
+
+        let path = LOGS_PATH.join("truncated.bin");
+        let logger = build_logger(&path, true);
+        log_deferred!(logger: logger, Level::Info, "{}", "truncated");
+        drop(logger);
+
+        let bytes = fs::read(&path).unwrap();
+        let mut decoder = BinaryDecoder::new(&bytes[..bytes.len() - 1]).unwrap();
+        assert!(matches!(
+            decoder.next(),
+            Some(Err(Error::DecodeBinaryLog(_)))
+        ));
+        assert!(decoder.next().is_none());
+    }
+}
@@ -0,0 +1,285 @@
+//! Provides a sink calling a closure for each record.
+
+use std::{convert::Infallible, marker::PhantomData};
+
+use crate::{
+    formatter::{Formatter, FormatterContext},
+    sink::{GetSinkProp, Sink, SinkProp},
+    sync::*,
+    ErrorHandler, LevelFilter, Record, Result, StringBuf,
+};
+
+type RecordCallback = Box<dyn Fn(&Record) + Send + Sync>;
+type FormattedCallback = Box<dyn Fn(&Record, &str) + Send + Sync>;
+
+enum Callback {
+    Record(RecordCallback),
+    Formatted(FormattedCallback),
+}
+
+/// A sink with a closure as the target.
+///
+/// The closure is called for each record logged to this sink, it is the
+/// simplest way to feed logs to a GUI log panel or an in-process alerting
+/// component without implementing [`Sink`] on your own.
+///
+/// The closure is called on the thread that logs the record, so it should
+/// return quickly. If the record needs to be processed on another thread,
+/// consider [`ChannelSink`].
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::{Arc, Mutex};
+///
+/// use spdlog::{prelude::*, sink::CallbackSink};
+///
+/// # fn main() -> Result<(), spdlog::Error> {
+/// let alerts = Arc::new(Mutex::new(vec![]));
+/// let sink = CallbackSink::builder()
+///     .callback({
+///         let alerts = alerts.clone();
+///         move |record| {
+///             if LevelFilter::MoreSevereEqual(Level::Error).test(record.level()) {
+///                 alerts.lock().unwrap().push(record.payload().to_string());
+///             }
+///         }
+///     })
+///     .build_arc()?;
+/// let logger = Logger::builder().sink(sink).build()?;
+///
+/// error!(logger: logger, "disk is full");
+/// info!(logger: logger, "user logged in");
+///
+/// assert_eq!(*alerts.lock().unwrap(), ["disk is full"]);
+/// # Ok(()) }
+/// ```
+///
+/// [`ChannelSink`]: crate::sink::ChannelSink
+pub struct CallbackSink {
+    prop: SinkProp,
+    callback: Callback,
+}
+
+impl CallbackSink {
+    /// Gets a builder of `CallbackSink` with default parameters:
+    ///
+    /// | Parameter       | Default Value               |
+    /// |-----------------|-----------------------------|
+    /// | [level_filter]  | [`LevelFilter::All`]        |
+    /// | [formatter]     | [`FullFormatter`]           |
+    /// | [error_handler] | [`ErrorHandler::default()`] |
+    /// |                 |                             |
+    /// | [callback]      | *must be specified*         |
+    ///
+    /// [level_filter]: CallbackSinkBuilder::level_filter
+    /// [formatter]: CallbackSinkBuilder::formatter
+    /// [`FullFormatter`]: crate::formatter::FullFormatter
+    /// [error_handler]: CallbackSinkBuilder::error_handler
+    /// [callback]: CallbackSinkBuilder::callback
+    #[must_use]
+    pub fn builder() -> CallbackSinkBuilder<()> {
+        CallbackSinkBuilder {
+            prop: SinkProp::default(),
+            callback: None,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl GetSinkProp for CallbackSink {
+    fn prop(&self) -> &SinkProp {
+        &self.prop
+    }
+}
+
+impl Sink for CallbackSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        match &self.callback {
+            Callback::Record(callback) => callback(record),
+            Callback::Formatted(callback) => {
+                let mut string_buf = StringBuf::new();
+                let mut ctx = FormatterContext::new();
+                self.prop
+                    .formatter()
+                    .format(record, &mut string_buf, &mut ctx)?;
+                callback(record, &string_buf);
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// #
+#[doc = include_str!("../include/doc/generic-builder-note.md")]
+pub struct CallbackSinkBuilder<ArgCallback> {
+    prop: SinkProp,
+    callback: Option<Callback>,
+    _phantom: PhantomData<ArgCallback>,
+}
+
+impl<ArgCallback> CallbackSinkBuilder<ArgCallback> {
+    /// Specifies a closure called with each record.
+    ///
+    /// The record is not formatted, the formatter of the sink is unused. If the
+    /// formatted string is needed, use [`formatted_callback`] instead.
+    ///
+    /// Either this parameter or [`formatted_callback`] is **required**.
+    ///
+    /// [`formatted_callback`]: CallbackSinkBuilder::formatted_callback
+    #[must_use]
+    pub fn callback<F>(self, callback: F) -> CallbackSinkBuilder<PhantomData<F>>
+    where
+        F: Fn(&Record) + Send + Sync + 'static,
+    {
+        CallbackSinkBuilder {
+            prop: self.prop,
+            callback: Some(Callback::Record(Box::new(callback))),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Specifies a closure called with each record and the string formatted by
+    /// the formatter of the sink.
+    ///
+    /// Either this parameter or [`callback`] is **required**.
+    ///
+    /// [`callback`]: CallbackSinkBuilder::callback
+    #[must_use]
+    pub fn formatted_callback<F>(self, callback: F) -> CallbackSinkBuilder<PhantomData<F>>
+    where
+        F: Fn(&Record, &str) + Send + Sync + 'static,
+    {
+        CallbackSinkBuilder {
+            prop: self.prop,
+            callback: Some(Callback::Formatted(Box::new(callback))),
+            _phantom: PhantomData,
+        }
+    }
+
+    // Prop
+    //
+
+    /// Specifies a log level filter.
+    ///
+    /// This parameter is **optional**, and defaults to [`LevelFilter::All`].
+    #[must_use]
+    pub fn level_filter(self, level_filter: LevelFilter) -> Self {
+        self.prop.set_level_filter(level_filter);
+        self
+    }
+
+    /// Specifies a formatter.
+    ///
+    /// This parameter is **optional**, and defaults to [`FullFormatter`]. It is
+    /// only used by [`formatted_callback`].
+    ///
+    /// [`FullFormatter`]: crate::formatter::FullFormatter
+    /// [`formatted_callback`]: CallbackSinkBuilder::formatted_callback
+    #[must_use]
+    pub fn formatter<F>(self, formatter: F) -> Self
+    where
+        F: Formatter + 'static,
+    {
+        self.prop.set_formatter(formatter);
+        self
+    }
+
+    /// Specifies an error handler.
+    ///
+    /// This parameter is **optional**, and defaults to
+    /// [`ErrorHandler::default()`].
+    #[must_use]
+    pub fn error_handler<F: Into<ErrorHandler>>(self, handler: F) -> Self {
+        self.prop.set_error_handler(handler);
+        self
+    }
+}
+
+impl CallbackSinkBuilder<()> {
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required parameter `callback` or `formatted_callback`\n\n\
+    ")]
+    pub fn build(self, _: Infallible) {}
+
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required parameter `callback` or `formatted_callback`\n\n\
+    ")]
+    pub fn build_arc(self, _: Infallible) {}
+}
+
+impl<F> CallbackSinkBuilder<PhantomData<F>> {
+    /// Builds a [`CallbackSink`].
+    pub fn build(self) -> Result<CallbackSink> {
+        let sink = CallbackSink {
+            prop: self.prop,
+            callback: self.callback.unwrap(),
+        };
+        Ok(sink)
+    }
+
+    /// Builds a `Arc<CallbackSink>`.
+    ///
+    /// This is a shorthand method for `.build().map(Arc::new)`.
+    pub fn build_arc(self) -> Result<Arc<CallbackSink>> {
+        self.build().map(Arc::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        formatter::{pattern, PatternFormatter},
+        prelude::*,
+        test_utils::*,
+    };
+
+    #[test]
+    fn callbacks() {
+        let records = Arc::new(Mutex::new(vec![]));
+        let sink = CallbackSink::builder()
+            .callback({
+                let records = records.clone();
+                move |record| records.lock_expect().push(record.payload().to_string())
+            })
+            .build_arc()
+            .unwrap();
+        let formatted = Arc::new(Mutex::new(vec![]));
+        let formatted_sink = CallbackSink::builder()
+            .formatted_callback({
+                let formatted = formatted.clone();
+                move |record, string| {
+                    formatted
+                        .lock_expect()
+                        .push((record.level(), string.to_string()))
+                }
+            })
+            .formatter(PatternFormatter::new(pattern!("[{level}] {payload}")))
+            .level_filter(LevelFilter::MoreSevereEqual(Level::Warn))
+            .build_arc()
+            .unwrap();
+        let logger = build_test_logger(|b| {
+            b.sink(sink)
+                .sink(formatted_sink)
+                .level_filter(LevelFilter::All)
+        });
+
+        info!(logger: logger, "hello");
+        error!(logger: logger, "world");
+
+        assert_eq!(*records.lock_expect(), ["hello", "world"]);
+        assert_eq!(
+            *formatted.lock_expect(),
+            [(Level::Error, "[error] world".to_string())]
+        );
+    }
+}
@@ -0,0 +1,541 @@
+//! Provides a sink capturing records for assertions in tests.
+
+use std::fmt::{self, Display};
+
+use crate::{
+    formatter::{Formatter, FormatterContext, FullFormatter},
+    kv,
+    sink::{GetSinkProp, Sink, SinkProp},
+    sync::*,
+    ErrorHandler, Level, LevelFilter, Record, RecordOwned, Result, StringBuf,
+};
+
+/// A sink capturing records in memory, for checking the log output in tests.
+///
+/// Captured records can be inspected with [`CaptureSink::records`], or
+/// asserted with macros [`assert_logged!`] and [`assert_not_logged!`].
+///
+/// This sink is only available if crate feature `test-util` is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::{prelude::*, sink::CaptureSink};
+///
+/// # fn main() -> Result<(), spdlog::Error> {
+/// let sink = CaptureSink::builder().build_arc()?;
+/// let logger = Logger::builder().sink(sink.clone()).build()?;
+///
+/// warn!(logger: logger, "connection timeout", kv: { port = 80 });
+///
+/// spdlog::assert_logged!(sink, Level::Warn, contains "timeout", kv: { port = 80 });
+/// spdlog::assert_not_logged!(sink, Level::Error);
+/// assert_eq!(sink.payloads(), ["connection timeout"]);
+/// # Ok(()) }
+/// ```
+///
+/// [`assert_logged!`]: crate::assert_logged
+/// [`assert_not_logged!`]: crate::assert_not_logged
+pub struct CaptureSink {
+    prop: SinkProp,
+    captured: Mutex<Vec<(RecordOwned, String)>>,
+}
+
+impl CaptureSink {
+    /// Gets a builder of `CaptureSink` with default parameters:
+    ///
+    /// | Parameter       | Default Value               |
+    /// |-----------------|-----------------------------|
+    /// | [level_filter]  | [`LevelFilter::All`]        |
+    /// | [formatter]     | [`FullFormatter`]           |
+    /// | [error_handler] | [`ErrorHandler::default()`] |
+    ///
+    /// [level_filter]: CaptureSinkBuilder::level_filter
+    /// [formatter]: CaptureSinkBuilder::formatter
+    /// [`FullFormatter`]: crate::formatter::FullFormatter
+    /// [error_handler]: CaptureSinkBuilder::error_handler
+    #[must_use]
+    pub fn builder() -> CaptureSinkBuilder {
+        let prop = SinkProp::default();
+        prop.set_formatter(FullFormatter::new());
+
+        CaptureSinkBuilder { prop }
+    }
+
+    /// Gets the captured records, in the order they were logged.
+    #[must_use]
+    pub fn records(&self) -> Vec<RecordOwned> {
+        self.captured
+            .lock_expect()
+            .iter()
+            .map(|(record, _)| record.clone())
+            .collect()
+    }
+
+    /// Gets the payloads of the captured records.
+    #[must_use]
+    pub fn payloads(&self) -> Vec<String> {
+        self.captured
+            .lock_expect()
+            .iter()
+            .map(|(record, _)| record.payload().to_string())
+            .collect()
+    }
+
+    /// Gets the captured records formatted by the formatter of the sink.
+    #[must_use]
+    pub fn formatted(&self) -> Vec<String> {
+        self.captured
+            .lock_expect()
+            .iter()
+            .map(|(_, formatted)| formatted.clone())
+            .collect()
+    }
+
+    /// Gets the captured records that match the given matcher.
+    #[must_use]
+    pub fn matching(&self, matcher: &RecordMatcher) -> Vec<RecordOwned> {
+        self.captured
+            .lock_expect()
+            .iter()
+            .filter(|(record, _)| matcher.matches(&record.as_ref()))
+            .map(|(record, _)| record.clone())
+            .collect()
+    }
+
+    /// Gets the number of captured records.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.captured.lock_expect().len()
+    }
+
+    /// Returns `true` if no record has been captured.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.captured.lock_expect().is_empty()
+    }
+
+    /// Discards the captured records.
+    pub fn clear(&self) {
+        self.captured.lock_expect().clear();
+    }
+
+    #[doc(hidden)]
+    #[track_caller]
+    pub fn __assert_logged(&self, matcher: &RecordMatcher, expected: bool) {
+        let captured = self.captured.lock_expect();
+        let found = captured
+            .iter()
+            .any(|(record, _)| matcher.matches(&record.as_ref()));
+        if found == expected {
+            return;
+        }
+
+        let mut message = if expected {
+            format!("no captured record matches `{matcher}`, captured records:")
+        } else {
+            format!("a captured record matches `{matcher}`, captured records:")
+        };
+        if captured.is_empty() {
+            message.push_str(" (none)");
+        }
+        for (record, _) in captured.iter() {
+            message.push_str(&format!("\n  [{}] {}", record.level(), record.payload()));
+            for (key, value) in record.key_values() {
+                message.push_str(&format!(" {}={value}", key.as_str()));
+            }
+        }
+        // Do not poison the mutex, the sink may still be used after a caught panic
+        drop(captured);
+        panic!("{message}");
+    }
+}
+
+impl GetSinkProp for CaptureSink {
+    fn prop(&self) -> &SinkProp {
+        &self.prop
+    }
+}
+
+impl Sink for CaptureSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        let mut string_buf = StringBuf::new();
+        let mut ctx = FormatterContext::new();
+        self.prop
+            .formatter()
+            .format(record, &mut string_buf, &mut ctx)?;
+
+        self.captured
+            .lock_expect()
+            .push((record.to_owned(), string_buf.to_string()));
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[allow(missing_docs)]
+pub struct CaptureSinkBuilder {
+    prop: SinkProp,
+}
+
+impl CaptureSinkBuilder {
+    // Prop
+    //
+
+    /// Specifies a log level filter.
+    ///
+    /// This parameter is **optional**, and defaults to [`LevelFilter::All`].
+    #[must_use]
+    pub fn level_filter(self, level_filter: LevelFilter) -> Self {
+        self.prop.set_level_filter(level_filter);
+        self
+    }
+
+    /// Specifies a formatter.
+    ///
+    /// This parameter is **optional**, and defaults to [`FullFormatter`].
+    ///
+    /// [`FullFormatter`]: crate::formatter::FullFormatter
+    #[must_use]
+    pub fn formatter<F>(self, formatter: F) -> Self
+    where
+        F: Formatter + 'static,
+    {
+        self.prop.set_formatter(formatter);
+        self
+    }
+
+    /// Specifies an error handler.
+    ///
+    /// This parameter is **optional**, and defaults to
+    /// [`ErrorHandler::default()`].
+    #[must_use]
+    pub fn error_handler<F: Into<ErrorHandler>>(self, handler: F) -> Self {
+        self.prop.set_error_handler(handler);
+        self
+    }
+
+    //
+
+    /// Builds a [`CaptureSink`].
+    pub fn build(self) -> Result<CaptureSink> {
+        let sink = CaptureSink {
+            prop: self.prop,
+            captured: Mutex::new(vec![]),
+        };
+        Ok(sink)
+    }
+
+    /// Builds a `Arc<CaptureSink>`.
+    ///
+    /// This is a shorthand method for `.build().map(Arc::new)`.
+    pub fn build_arc(self) -> Result<Arc<CaptureSink>> {
+        self.build().map(Arc::new)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum PayloadMatch {
+    Contains,
+    Eq,
+    StartsWith,
+    EndsWith,
+}
+
+/// A predicate on records, used by [`CaptureSink`].
+///
+/// A record matches if all the specified conditions are satisfied. Macros
+/// [`assert_logged!`] and [`assert_not_logged!`] build a matcher from their
+/// parameters.
+///
+/// Expected key-values are looked up with [`KeyValues::get`], and compared by
+/// their `Display` representations, so `port = 80` matches both the integer
+/// `80` and the string `"80"`.
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::{kv, sink::RecordMatcher, Level};
+///
+/// let matcher = RecordMatcher::new()
+///     .level(Level::Warn)
+///     .payload_contains("timeout")
+///     .key_values(kv!{ port = 80 });
+/// ```
+///
+/// [`assert_logged!`]: crate::assert_logged
+/// [`assert_not_logged!`]: crate::assert_not_logged
+/// [`KeyValues::get`]: crate::kv::KeyValues::get
+#[derive(Clone, Default)]
+pub struct RecordMatcher<'a> {
+    level: Option<Level>,
+    payload: Option<(PayloadMatch, &'a str)>,
+    kvs: &'a [(kv::Key<'a>, kv::Value<'a>)],
+}
+
+impl<'a> RecordMatcher<'a> {
+    /// Constructs a `RecordMatcher` matching any record.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Matches records with the given level.
+    #[must_use]
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Matches records whose payload contains the given string.
+    #[must_use]
+    pub fn payload_contains(self, payload: &'a str) -> Self {
+        self.payload(PayloadMatch::Contains, payload)
+    }
+
+    /// Matches records whose payload is equal to the given string.
+    #[must_use]
+    pub fn payload_eq(self, payload: &'a str) -> Self {
+        self.payload(PayloadMatch::Eq, payload)
+    }
+
+    /// Matches records whose payload starts with the given string.
+    #[must_use]
+    pub fn payload_starts_with(self, payload: &'a str) -> Self {
+        self.payload(PayloadMatch::StartsWith, payload)
+    }
+
+    /// Matches records whose payload ends with the given string.
+    #[must_use]
+    pub fn payload_ends_with(self, payload: &'a str) -> Self {
+        self.payload(PayloadMatch::EndsWith, payload)
+    }
+
+    /// Matches records containing all the given key-values.
+    ///
+    /// The [`kv!`] macro is a convenient way to build the key-values.
+    ///
+    /// [`kv!`]: crate::kv!
+    #[must_use]
+    pub fn key_values(mut self, kvs: &'a [(kv::Key<'a>, kv::Value<'a>)]) -> Self {
+        self.kvs = kvs;
+        self
+    }
+
+    /// Checks whether the given record matches.
+    #[must_use]
+    pub fn matches(&self, record: &Record) -> bool {
+        if self.level.is_some_and(|level| level != record.level()) {
+            return false;
+        }
+        if let Some((kind, expected)) = self.payload {
+            let payload = record.payload();
+            let matched = match kind {
+                PayloadMatch::Contains => payload.contains(expected),
+                PayloadMatch::Eq => payload == expected,
+                PayloadMatch::StartsWith => payload.starts_with(expected),
+                PayloadMatch::EndsWith => payload.ends_with(expected),
+            };
+            if !matched {
+                return false;
+            }
+        }
+        let kvs = record.key_values();
+        self.kvs.iter().all(|(key, expected)| {
+            kvs.get(key.clone())
+                .is_some_and(|value| value.to_string() == expected.to_string())
+        })
+    }
+
+    #[must_use]
+    fn payload(mut self, kind: PayloadMatch, payload: &'a str) -> Self {
+        self.payload = Some((kind, payload));
+        self
+    }
+}
+
+impl Display for RecordMatcher<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut conditions = vec![];
+        if let Some(level) = self.level {
+            conditions.push(format!("level {level}"));
+        }
+        if let Some((kind, payload)) = self.payload {
+            let kind = match kind {
+                PayloadMatch::Contains => "contains",
+                PayloadMatch::Eq => "eq",
+                PayloadMatch::StartsWith => "starts_with",
+                PayloadMatch::EndsWith => "ends_with",
+            };
+            conditions.push(format!("payload {kind} {payload:?}"));
+        }
+        for (key, value) in self.kvs {
+            conditions.push(format!("kv {}={value}", key.as_str()));
+        }
+        if conditions.is_empty() {
+            f.write_str("any")
+        } else {
+            f.write_str(&conditions.join(", "))
+        }
+    }
+}
+
+/// Asserts that a [`CaptureSink`] has captured a matching record.
+///
+/// The first parameter is the sink, followed by the expected level. The
+/// payload and key-values are optionally matched as well:
+///
+/// ```text
+/// assert_logged!(sink, level[, <op> payload][, kv: { key = value, ... }])
+/// ```
+///
+/// where `<op>` is one of `contains`, `eq`, `starts_with` and `ends_with`.
+/// The key-values accept the same syntax as the `kv` parameter of logging
+/// macros, see [`RecordMatcher`] for how they are compared.
+///
+/// On failure, the panic message lists all captured records.
+///
+/// This macro is only available if crate feature `test-util` is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::{assert_logged, prelude::*, sink::CaptureSink};
+///
+/// # fn main() -> Result<(), spdlog::Error> {
+/// let sink = CaptureSink::builder().build_arc()?;
+/// let logger = Logger::builder().sink(sink.clone()).build()?;
+///
+/// # let port = 80;
+/// warn!(logger: logger, "connection timeout", kv: { port });
+///
+/// assert_logged!(sink, Level::Warn);
+/// assert_logged!(sink, Level::Warn, starts_with "connection");
+/// assert_logged!(sink, Level::Warn, kv: { port = 80 });
+/// assert_logged!(sink, Level::Warn, contains "timeout", kv: { port });
+/// # Ok(()) }
+/// ```
+#[macro_export]
+macro_rules! assert_logged {
+    ($sink:expr, $($matcher:tt)+) => {
+        $crate::sink::CaptureSink::__assert_logged(&$sink, &$crate::__record_matcher!($($matcher)+), true)
+    };
+}
+
+/// Asserts that a [`CaptureSink`] has captured no matching record.
+///
+/// It accepts the same parameters as [`assert_logged!`].
+///
+/// This macro is only available if crate feature `test-util` is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::{assert_not_logged, prelude::*, sink::CaptureSink};
+///
+/// # fn main() -> Result<(), spdlog::Error> {
+/// let sink = CaptureSink::builder().build_arc()?;
+/// let logger = Logger::builder().sink(sink.clone()).build()?;
+///
+/// info!(logger: logger, "connected");
+///
+/// assert_not_logged!(sink, Level::Error);
+/// assert_not_logged!(sink, Level::Info, contains "timeout");
+/// # Ok(()) }
+/// ```
+///
+/// [`assert_logged!`]: crate::assert_logged
+#[macro_export]
+macro_rules! assert_not_logged {
+    ($sink:expr, $($matcher:tt)+) => {
+        $crate::sink::CaptureSink::__assert_logged(&$sink, &$crate::__record_matcher!($($matcher)+), false)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __record_matcher {
+    ($level:expr $(,)?) => {
+        $crate::sink::RecordMatcher::new().level($level)
+    };
+    ($level:expr, kv: { $($kv:tt)* } $(,)?) => {
+        $crate::__record_matcher!($level).key_values($crate::kv!($($kv)*))
+    };
+    ($level:expr, contains $payload:expr $(, kv: { $($kv:tt)* })? $(,)?) => {
+        $crate::__record_matcher!($level $(, kv: { $($kv)* })?).payload_contains($payload)
+    };
+    ($level:expr, eq $payload:expr $(, kv: { $($kv:tt)* })? $(,)?) => {
+        $crate::__record_matcher!($level $(, kv: { $($kv)* })?).payload_eq($payload)
+    };
+    ($level:expr, starts_with $payload:expr $(, kv: { $($kv:tt)* })? $(,)?) => {
+        $crate::__record_matcher!($level $(, kv: { $($kv)* })?).payload_starts_with($payload)
+    };
+    ($level:expr, ends_with $payload:expr $(, kv: { $($kv:tt)* })? $(,)?) => {
+        $crate::__record_matcher!($level $(, kv: { $($kv)* })?).payload_ends_with($payload)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{context, prelude::*, test_utils::*};
+
+    #[test]
+    fn capture_and_assert() {
+        let sink = CaptureSink::builder()
+            .formatter(crate::formatter::PatternFormatter::new(
+                crate::formatter::pattern!("[{level}] {payload}"),
+            ))
+            .build_arc()
+            .unwrap();
+        let logger = build_test_logger(|b| b.sink(sink.clone()).level_filter(LevelFilter::All));
+
+        let _guard = context::push(kv! { request_id = 7 });
+        warn!(logger: logger, "connection timeout", kv: { port = 80, host = "a" });
+        debug!(logger: logger, "retrying");
+
+        assert_eq!(sink.len(), 2);
+        assert_eq!(sink.payloads(), ["connection timeout", "retrying"]);
+        assert_eq!(
+            sink.formatted(),
+            ["[warn] connection timeout", "[debug] retrying"]
+        );
+
+        assert_logged!(sink, Level::Warn);
+        assert_logged!(sink, Level::Warn, contains "timeout", kv: { port = 80 });
+        assert_logged!(sink, Level::Warn, kv: { port = "80", request_id = 7 });
+        assert_logged!(sink, Level::Debug, eq "retrying",);
+        assert_logged!(sink, Level::Debug, ends_with "ing");
+        assert_not_logged!(sink, Level::Error);
+        assert_not_logged!(sink, Level::Warn, starts_with "timeout");
+        assert_not_logged!(sink, Level::Warn, kv: { port = 81 });
+        assert_not_logged!(sink, Level::Debug, kv: { missing = 1 });
+
+        let matcher = RecordMatcher::new().payload_contains("n");
+        assert_eq!(sink.matching(&matcher).len(), 2);
+
+        sink.clear();
+        assert!(sink.is_empty());
+    }
+
+    #[test]
+    fn failure_message() {
+        let sink = CaptureSink::builder().build_arc().unwrap();
+        let logger = build_test_logger(|b| b.sink(sink.clone()));
+        info!(logger: logger, "connected", kv: { port = 80 });
+
+        let message = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            assert_logged!(sink, Level::Warn, contains "timeout", kv: { port = 80 });
+        }))
+        .unwrap_err();
+        assert_eq!(
+            message.downcast_ref::<String>().unwrap(),
+            "no captured record matches `level warn, payload contains \"timeout\", kv port=80`, \
+             captured records:\n  [info] connected port=80"
+        );
+        assert_logged!(sink, Level::Info);
+    }
+}
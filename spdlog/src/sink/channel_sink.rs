@@ -0,0 +1,294 @@
+//! Provides a sink sending records into a channel.
+
+use std::{convert::Infallible, sync::mpsc};
+
+use crate::{
+    error::{SendToChannelError, SendToChannelErrorDropped},
+    formatter::Formatter,
+    sink::{GetSinkProp, Sink, SinkProp},
+    sync::*,
+    Error, ErrorHandler, LevelFilter, Record, RecordOwned, Result,
+};
+
+/// The sending half of a channel that [`ChannelSink`] sends records into.
+///
+/// It can be converted from:
+///
+/// - [`std::sync::mpsc::Sender<RecordOwned>`]
+/// - [`std::sync::mpsc::SyncSender<RecordOwned>`]
+/// - `crossbeam::channel::Sender<RecordOwned>`, if crate feature
+///   `multi-thread` is enabled.
+pub struct RecordSender(RecordSenderInner);
+
+enum RecordSenderInner {
+    // `mpsc::Sender` is not `Sync` before Rust 1.72, which is higher than our MSRV.
+    Std(Mutex<mpsc::Sender<RecordOwned>>),
+    StdSync(mpsc::SyncSender<RecordOwned>),
+    #[cfg(feature = "multi-thread")]
+    Crossbeam(crossbeam::channel::Sender<RecordOwned>),
+}
+
+impl RecordSender {
+    fn send(&self, record: RecordOwned) -> Result<()> {
+        let dropped = match &self.0 {
+            RecordSenderInner::Std(sender) => {
+                sender.lock_expect().send(record).map_err(|err| err.0)
+            }
+            RecordSenderInner::StdSync(sender) => sender.send(record).map_err(|err| err.0),
+            #[cfg(feature = "multi-thread")]
+            RecordSenderInner::Crossbeam(sender) => sender.send(record).map_err(|err| err.0),
+        };
+        dropped.map_err(|record| {
+            Error::SendToChannel(
+                SendToChannelError::Disconnected,
+                SendToChannelErrorDropped::Record(Box::new(record)),
+            )
+        })
+    }
+}
+
+impl From<mpsc::Sender<RecordOwned>> for RecordSender {
+    fn from(sender: mpsc::Sender<RecordOwned>) -> Self {
+        Self(RecordSenderInner::Std(Mutex::new(sender)))
+    }
+}
+
+impl From<mpsc::SyncSender<RecordOwned>> for RecordSender {
+    fn from(sender: mpsc::SyncSender<RecordOwned>) -> Self {
+        Self(RecordSenderInner::StdSync(sender))
+    }
+}
+
+#[cfg(feature = "multi-thread")]
+impl From<crossbeam::channel::Sender<RecordOwned>> for RecordSender {
+    fn from(sender: crossbeam::channel::Sender<RecordOwned>) -> Self {
+        Self(RecordSenderInner::Crossbeam(sender))
+    }
+}
+
+/// A sink with a channel as the target.
+///
+/// Each record logged to this sink is converted to a [`RecordOwned`] and sent
+/// into the channel, so that it can be received and processed on another
+/// thread, e.g. to show logs in a GUI log panel.
+///
+/// If the channel is bounded and full, logging blocks until there is space in
+/// the channel. If the receiver has been dropped,
+/// [`Error::SendToChannel`] is returned.
+///
+/// The formatter of this sink is unused, records are sent unformatted.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::mpsc;
+///
+/// use spdlog::{prelude::*, sink::ChannelSink};
+///
+/// # fn main() -> Result<(), spdlog::Error> {
+/// let (sender, receiver) = mpsc::channel();
+/// let sink = ChannelSink::builder().sender(sender).build_arc()?;
+/// let logger = Logger::builder().sink(sink).build()?;
+///
+/// info!(logger: logger, "hello");
+///
+/// let record = receiver.recv().unwrap();
+/// assert_eq!(record.payload(), "hello");
+/// # Ok(()) }
+/// ```
+pub struct ChannelSink {
+    prop: SinkProp,
+    sender: RecordSender,
+}
+
+impl ChannelSink {
+    /// Gets a builder of `ChannelSink` with default parameters:
+    ///
+    /// | Parameter       | Default Value               |
+    /// |-----------------|-----------------------------|
+    /// | [level_filter]  | [`LevelFilter::All`]        |
+    /// | [formatter]     | [`FullFormatter`]           |
+    /// | [error_handler] | [`ErrorHandler::default()`] |
+    /// |                 |                             |
+    /// | [sender]        | *must be specified*         |
+    ///
+    /// [level_filter]: ChannelSinkBuilder::level_filter
+    /// [formatter]: ChannelSinkBuilder::formatter
+    /// [`FullFormatter`]: crate::formatter::FullFormatter
+    /// [error_handler]: ChannelSinkBuilder::error_handler
+    /// [sender]: ChannelSinkBuilder::sender
+    #[must_use]
+    pub fn builder() -> ChannelSinkBuilder<()> {
+        ChannelSinkBuilder {
+            prop: SinkProp::default(),
+            sender: (),
+        }
+    }
+}
+
+impl GetSinkProp for ChannelSink {
+    fn prop(&self) -> &SinkProp {
+        &self.prop
+    }
+}
+
+impl Sink for ChannelSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        self.sender.send(record.to_owned())
+    }
+
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// #
+#[doc = include_str!("../include/doc/generic-builder-note.md")]
+pub struct ChannelSinkBuilder<ArgSender> {
+    prop: SinkProp,
+    sender: ArgSender,
+}
+
+impl<ArgSender> ChannelSinkBuilder<ArgSender> {
+    /// Specifies the sending half of the channel that records are sent into.
+    ///
+    /// See [`RecordSender`] for the supported channels.
+    ///
+    /// This parameter is **required**.
+    #[must_use]
+    pub fn sender<S>(self, sender: S) -> ChannelSinkBuilder<RecordSender>
+    where
+        S: Into<RecordSender>,
+    {
+        ChannelSinkBuilder {
+            prop: self.prop,
+            sender: sender.into(),
+        }
+    }
+
+    // Prop
+    //
+
+    /// Specifies a log level filter.
+    ///
+    /// This parameter is **optional**, and defaults to [`LevelFilter::All`].
+    #[must_use]
+    pub fn level_filter(self, level_filter: LevelFilter) -> Self {
+        self.prop.set_level_filter(level_filter);
+        self
+    }
+
+    /// Specifies a formatter.
+    ///
+    /// This parameter is **optional**, and defaults to [`FullFormatter`]. It is
+    /// unused by this sink.
+    ///
+    /// [`FullFormatter`]: crate::formatter::FullFormatter
+    #[must_use]
+    pub fn formatter<F>(self, formatter: F) -> Self
+    where
+        F: Formatter + 'static,
+    {
+        self.prop.set_formatter(formatter);
+        self
+    }
+
+    /// Specifies an error handler.
+    ///
+    /// This parameter is **optional**, and defaults to
+    /// [`ErrorHandler::default()`].
+    #[must_use]
+    pub fn error_handler<F: Into<ErrorHandler>>(self, handler: F) -> Self {
+        self.prop.set_error_handler(handler);
+        self
+    }
+}
+
+impl ChannelSinkBuilder<()> {
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required parameter `sender`\n\n\
+    ")]
+    pub fn build(self, _: Infallible) {}
+
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required parameter `sender`\n\n\
+    ")]
+    pub fn build_arc(self, _: Infallible) {}
+}
+
+impl ChannelSinkBuilder<RecordSender> {
+    /// Builds a [`ChannelSink`].
+    pub fn build(self) -> Result<ChannelSink> {
+        let sink = ChannelSink {
+            prop: self.prop,
+            sender: self.sender,
+        };
+        Ok(sink)
+    }
+
+    /// Builds a `Arc<ChannelSink>`.
+    ///
+    /// This is a shorthand method for `.build().map(Arc::new)`.
+    pub fn build_arc(self) -> Result<Arc<ChannelSink>> {
+        self.build().map(Arc::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::{prelude::*, test_utils::*};
+
+    #[test]
+    fn std_channels() {
+        let (sender, receiver) = mpsc::channel();
+        let sink = ChannelSink::builder().sender(sender).build_arc().unwrap();
+        let logger = build_test_logger(|b| b.sink(sink));
+
+        let handle = thread::spawn(move || {
+            receiver
+                .iter()
+                .map(|record| (record.level(), record.payload().to_string()))
+                .collect::<Vec<_>>()
+        });
+        info!(logger: logger, "hello");
+        warn!(logger: logger, "world");
+        drop(logger);
+        assert_eq!(
+            handle.join().unwrap(),
+            [
+                (Level::Info, "hello".to_string()),
+                (Level::Warn, "world".to_string())
+            ]
+        );
+
+        let (sender, receiver) = mpsc::sync_channel(1);
+        let sink = ChannelSink::builder().sender(sender).build().unwrap();
+        drop(receiver);
+        let record = Record::new(Level::Info, "lost", None, None, &[]);
+        assert!(matches!(
+            sink.log(&record),
+            Err(Error::SendToChannel(
+                SendToChannelError::Disconnected,
+                SendToChannelErrorDropped::Record(record)
+            )) if record.payload() == "lost"
+        ));
+    }
+
+    #[cfg(feature = "multi-thread")]
+    #[test]
+    fn crossbeam_channel() {
+        let (sender, receiver) = crossbeam::channel::unbounded();
+        let sink = ChannelSink::builder().sender(sender).build_arc().unwrap();
+        let logger = build_test_logger(|b| b.sink(sink));
+
+        info!(logger: logger, "hello");
+        assert_eq!(receiver.try_recv().unwrap().payload(), "hello");
+    }
+}
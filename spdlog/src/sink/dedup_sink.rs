@@ -195,7 +195,11 @@ impl Sink for DedupSink {
 
         self.log_record(record)?;
         state.skipped_count = 0;
-        state.last_record = Some(record.to_owned());
+        match &mut state.last_record {
+            // Reuses the buffers of the last record
+            Some(last_record) => last_record.assign(record),
+            None => state.last_record = Some(record.to_owned()),
+        }
 
         Ok(())
     }
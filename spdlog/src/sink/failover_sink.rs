@@ -0,0 +1,607 @@
+use std::{
+    convert::Infallible,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    formatter::Formatter,
+    kv,
+    sink::{Sink, SinkProp, SinkPropAccess},
+    sync::*,
+    Error, ErrorHandler, LevelFilter, Record, Result,
+};
+
+struct FailoverSinkState {
+    degraded: bool,
+    backoff: Duration,
+    // `None` if the backoff is too long to be represented
+    next_retry: Option<Instant>,
+}
+
+/// A [combined sink], writing records to a fallback sink while its primary sink
+/// fails.
+///
+/// Records are written to the primary sink normally. If the primary sink fails
+/// with [`Error::WriteRecord`], e.g. a file sink hits `ENOSPC` or a network
+/// sink goes down, the sink becomes *degraded*: the error is passed to the
+/// error handler, and the record is written to the fallback sink instead of
+/// being lost.
+///
+/// While degraded, records are written to the fallback sink, and the primary
+/// sink is retried with an exponential backoff, starting from [`retry_backoff`]
+/// and capped at [`max_retry_backoff`]. Once a retry succeeds, the sink
+/// switches back to the primary sink automatically.
+///
+/// Records written to the fallback sink are flagged with key-value
+/// `degraded = true`, unless disabled by [`flag_degraded`].
+///
+/// Errors other than [`Error::WriteRecord`] are returned as is, without
+/// failing over.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use spdlog::{prelude::*, sink::{FailoverSink, FileSink, StdStreamSink}};
+///
+/// # fn main() -> Result<(), spdlog::Error> {
+/// # let path = std::env::temp_dir().join("spdlog-rs-doctest-failover-sink.log");
+/// let sink = FailoverSink::builder()
+///     .primary(FileSink::builder().path(path).build_arc()?)
+///     .fallback(StdStreamSink::builder().stderr().build_arc()?)
+///     .build_arc()?;
+/// let logger = Logger::builder().sink(sink).build()?;
+/// # Ok(()) }
+/// ```
+///
+/// [combined sink]: index.html#combined-sink
+/// [`retry_backoff`]: FailoverSinkBuilder::retry_backoff
+/// [`max_retry_backoff`]: FailoverSinkBuilder::max_retry_backoff
+/// [`flag_degraded`]: FailoverSinkBuilder::flag_degraded
+pub struct FailoverSink {
+    prop: SinkProp,
+    primary: Arc<dyn Sink>,
+    fallback: Arc<dyn Sink>,
+    retry_backoff: Duration,
+    max_retry_backoff: Duration,
+    flag_degraded: bool,
+    state: Mutex<FailoverSinkState>,
+}
+
+impl FailoverSink {
+    /// Gets a builder of `FailoverSink` with default parameters:
+    ///
+    /// | Parameter           | Default Value               |
+    /// |---------------------|-----------------------------|
+    /// | [level_filter]      | [`LevelFilter::All`]        |
+    /// | [formatter]         | *the sub-sinks' own*        |
+    /// | [error_handler]     | [`ErrorHandler::default()`] |
+    /// |                     |                             |
+    /// | [primary]           | *must be specified*         |
+    /// | [fallback]          | *must be specified*         |
+    /// | [retry_backoff]     | `1s`                        |
+    /// | [max_retry_backoff] | `60s`                       |
+    /// | [flag_degraded]     | `true`                      |
+    ///
+    /// [level_filter]: FailoverSinkBuilder::level_filter
+    /// [formatter]: FailoverSinkBuilder::formatter
+    /// [error_handler]: FailoverSinkBuilder::error_handler
+    /// [primary]: FailoverSinkBuilder::primary
+    /// [fallback]: FailoverSinkBuilder::fallback
+    /// [retry_backoff]: FailoverSinkBuilder::retry_backoff
+    /// [max_retry_backoff]: FailoverSinkBuilder::max_retry_backoff
+    /// [flag_degraded]: FailoverSinkBuilder::flag_degraded
+    #[must_use]
+    pub fn builder() -> FailoverSinkBuilder<(), ()> {
+        FailoverSinkBuilder {
+            prop: SinkProp::default(),
+            primary: (),
+            fallback: (),
+            retry_backoff: Duration::from_secs(1),
+            max_retry_backoff: Duration::from_secs(60),
+            flag_degraded: true,
+            formatter: None,
+        }
+    }
+
+    /// Gets the primary sink.
+    #[must_use]
+    pub fn primary(&self) -> &Arc<dyn Sink> {
+        &self.primary
+    }
+
+    /// Gets the fallback sink.
+    #[must_use]
+    pub fn fallback(&self) -> &Arc<dyn Sink> {
+        &self.fallback
+    }
+
+    /// Returns `true` if the primary sink is failing and records are being
+    /// written to the fallback sink.
+    #[must_use]
+    pub fn is_degraded(&self) -> bool {
+        self.state.lock_expect().degraded
+    }
+
+    fn log_to(sink: &dyn Sink, record: &Record) -> Result<()> {
+        if sink.should_log(record.level()) {
+            sink.log(record)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn log_to_fallback(&self, record: &Record) -> Result<()> {
+        if self.flag_degraded {
+            let flag = [(
+                kv::Key::__from_static_str("degraded"),
+                kv::Value::from(true),
+            )];
+            Self::log_to(self.fallback.as_ref(), &record.append_kvs(&flag))
+        } else {
+            Self::log_to(self.fallback.as_ref(), record)
+        }
+    }
+
+    fn flush_with(&self, with: fn(&dyn Sink) -> Result<()>) -> Result<()> {
+        Error::push_result(with(self.primary.as_ref()), with(self.fallback.as_ref()))
+    }
+}
+
+impl SinkPropAccess for FailoverSink {
+    fn level_filter(&self) -> LevelFilter {
+        self.prop.level_filter()
+    }
+
+    fn set_level_filter(&self, level_filter: LevelFilter) {
+        self.prop.set_level_filter(level_filter);
+    }
+
+    fn set_formatter(&self, formatter: Box<dyn Formatter>) {
+        self.primary.set_formatter(formatter.clone());
+        self.fallback.set_formatter(formatter);
+    }
+
+    fn set_error_handler(&self, handler: ErrorHandler) {
+        self.prop.set_error_handler(handler);
+    }
+}
+
+impl Sink for FailoverSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        let try_primary = {
+            let state = self.state.lock_expect();
+            !state.degraded
+                || state
+                    .next_retry
+                    .is_some_and(|next_retry| Instant::now() >= next_retry)
+        };
+        if !try_primary {
+            return self.log_to_fallback(record);
+        }
+
+        // Do not hold the lock while logging, the primary sink may be slow to fail
+        match Self::log_to(self.primary.as_ref(), record) {
+            Ok(()) => {
+                let mut state = self.state.lock_expect();
+                if state.degraded {
+                    state.degraded = false;
+                    state.backoff = self.retry_backoff;
+                }
+                Ok(())
+            }
+            Err(Error::WriteRecord(err)) => {
+                {
+                    let mut state = self.state.lock_expect();
+                    state.backoff = if state.degraded {
+                        state.backoff.saturating_mul(2).min(self.max_retry_backoff)
+                    } else {
+                        self.retry_backoff
+                    };
+                    state.degraded = true;
+                    state.next_retry = Instant::now().checked_add(state.backoff);
+                }
+                self.prop
+                    .call_error_handler_internal("FailoverSink", Error::WriteRecord(err));
+                self.log_to_fallback(record)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.flush_with(|sink| sink.flush())
+    }
+
+    fn flush_on_exit(&self) -> Result<()> {
+        self.flush_with(|sink| sink.flush_on_exit())
+    }
+}
+
+impl Drop for FailoverSink {
+    fn drop(&mut self) {
+        if let Err(err) = self.flush() {
+            self.prop.call_error_handler_internal("FailoverSink", err);
+        }
+    }
+}
+
+/// #
+#[doc = include_str!("../include/doc/generic-builder-note.md")]
+pub struct FailoverSinkBuilder<ArgPrimary, ArgFallback> {
+    prop: SinkProp,
+    primary: ArgPrimary,
+    fallback: ArgFallback,
+    retry_backoff: Duration,
+    max_retry_backoff: Duration,
+    flag_degraded: bool,
+    formatter: Option<Box<dyn Formatter>>,
+}
+
+impl<ArgPrimary, ArgFallback> FailoverSinkBuilder<ArgPrimary, ArgFallback> {
+    /// Specifies the primary sink, which records are written to normally.
+    ///
+    /// This parameter is **required**.
+    #[must_use]
+    pub fn primary(self, sink: Arc<dyn Sink>) -> FailoverSinkBuilder<Arc<dyn Sink>, ArgFallback> {
+        FailoverSinkBuilder {
+            prop: self.prop,
+            primary: sink,
+            fallback: self.fallback,
+            retry_backoff: self.retry_backoff,
+            max_retry_backoff: self.max_retry_backoff,
+            flag_degraded: self.flag_degraded,
+            formatter: self.formatter,
+        }
+    }
+
+    /// Specifies the fallback sink, which records are written to while the
+    /// primary sink fails.
+    ///
+    /// This parameter is **required**.
+    #[must_use]
+    pub fn fallback(self, sink: Arc<dyn Sink>) -> FailoverSinkBuilder<ArgPrimary, Arc<dyn Sink>> {
+        FailoverSinkBuilder {
+            prop: self.prop,
+            primary: self.primary,
+            fallback: sink,
+            retry_backoff: self.retry_backoff,
+            max_retry_backoff: self.max_retry_backoff,
+            flag_degraded: self.flag_degraded,
+            formatter: self.formatter,
+        }
+    }
+
+    /// Specifies the delay before retrying the failing primary sink for the
+    /// first time, the delay doubles for each subsequent failed retry.
+    ///
+    /// This parameter is **optional**, and defaults to `1s`.
+    #[must_use]
+    pub fn retry_backoff(mut self, backoff: Duration) -> Self {
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Specifies the maximum delay between retries of the failing primary
+    /// sink.
+    ///
+    /// This parameter is **optional**, and defaults to `60s`.
+    #[must_use]
+    pub fn max_retry_backoff(mut self, backoff: Duration) -> Self {
+        self.max_retry_backoff = backoff;
+        self
+    }
+
+    /// Specifies whether to flag records written to the fallback sink with
+    /// key-value `degraded = true`.
+    ///
+    /// This parameter is **optional**, and defaults to `true`.
+    #[must_use]
+    pub fn flag_degraded(mut self, flag_degraded: bool) -> Self {
+        self.flag_degraded = flag_degraded;
+        self
+    }
+
+    // Prop
+    //
+
+    /// Specifies a log level filter.
+    ///
+    /// This parameter is **optional**, and defaults to [`LevelFilter::All`].
+    #[must_use]
+    pub fn level_filter(self, level_filter: LevelFilter) -> Self {
+        self.prop.set_level_filter(level_filter);
+        self
+    }
+
+    /// Specifies a formatter applied to both the primary and fallback sinks.
+    ///
+    /// This parameter is **optional**, and by default the primary and fallback
+    /// sinks keep the formatters they are created with.
+    #[must_use]
+    pub fn formatter<F>(mut self, formatter: F) -> Self
+    where
+        F: Formatter + 'static,
+    {
+        self.formatter = Some(Box::new(formatter));
+        self
+    }
+
+    /// Specifies an error handler.
+    ///
+    /// The errors of the primary sink that cause failing over are passed to
+    /// this handler.
+    ///
+    /// This parameter is **optional**, and defaults to
+    /// [`ErrorHandler::default()`].
+    #[must_use]
+    pub fn error_handler<F: Into<ErrorHandler>>(self, handler: F) -> Self {
+        self.prop.set_error_handler(handler);
+        self
+    }
+}
+
+impl<ArgFallback> FailoverSinkBuilder<(), ArgFallback> {
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required parameter `primary`\n\n\
+    ")]
+    pub fn build(self, _: Infallible) {}
+
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required parameter `primary`\n\n\
+    ")]
+    pub fn build_arc(self, _: Infallible) {}
+}
+
+impl FailoverSinkBuilder<Arc<dyn Sink>, ()> {
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required parameter `fallback`\n\n\
+    ")]
+    pub fn build(self, _: Infallible) {}
+
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required parameter `fallback`\n\n\
+    ")]
+    pub fn build_arc(self, _: Infallible) {}
+}
+
+impl FailoverSinkBuilder<Arc<dyn Sink>, Arc<dyn Sink>> {
+    /// Builds a [`FailoverSink`].
+    pub fn build(self) -> Result<FailoverSink> {
+        if let Some(formatter) = self.formatter {
+            self.primary.set_formatter(formatter.clone());
+            self.fallback.set_formatter(formatter);
+        }
+        Ok(FailoverSink {
+            prop: self.prop,
+            primary: self.primary,
+            fallback: self.fallback,
+            retry_backoff: self.retry_backoff,
+            max_retry_backoff: self.max_retry_backoff,
+            flag_degraded: self.flag_degraded,
+            state: Mutex::new(FailoverSinkState {
+                degraded: false,
+                backoff: self.retry_backoff,
+                next_retry: None,
+            }),
+        })
+    }
+
+    /// Builds a `Arc<FailoverSink>`.
+    ///
+    /// This is a shorthand method for `.build().map(Arc::new)`.
+    pub fn build_arc(self) -> Result<Arc<FailoverSink>> {
+        self.build().map(Arc::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    };
+
+    use super::*;
+    use crate::{prelude::*, sink::GetSinkProp, test_utils::*};
+
+    struct FlakySink {
+        prop: SinkProp,
+        failing: AtomicBool,
+        inner: TestSink,
+    }
+
+    impl GetSinkProp for FlakySink {
+        fn prop(&self) -> &SinkProp {
+            &self.prop
+        }
+    }
+
+    impl Sink for FlakySink {
+        fn log(&self, record: &Record) -> Result<()> {
+            if self.failing.load(Ordering::Relaxed) {
+                Err(Error::WriteRecord(io::Error::new(
+                    io::ErrorKind::Other,
+                    "no space left",
+                )))
+            } else {
+                self.inner.log(record)
+            }
+        }
+
+        fn flush(&self) -> Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    fn payloads_with_flags(sink: &TestSink) -> Vec<String> {
+        sink.records()
+            .iter()
+            .map(|record| {
+                let flag = record
+                    .key_values()
+                    .get(kv::Key::__from_static_str("degraded"))
+                    .map_or(String::new(), |value| format!(" degraded={value}"));
+                format!("{}{flag}", record.payload())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn failover_and_recover() {
+        let primary = Arc::new(FlakySink {
+            prop: SinkProp::default(),
+            failing: AtomicBool::new(false),
+            inner: TestSink::new(),
+        });
+        let fallback = Arc::new(TestSink::new());
+        let handled = Arc::new(AtomicUsize::new(0));
+        let sink = FailoverSink::builder()
+            .primary(primary.clone())
+            .fallback(fallback.clone())
+            .retry_backoff(Duration::ZERO)
+            .error_handler({
+                let handled = handled.clone();
+                move |_| {
+                    handled.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+            .build_arc()
+            .unwrap();
+        let logger = build_test_logger(|b| b.sink(sink.clone()));
+
+        info!(logger: logger, "1");
+        primary.failing.store(true, Ordering::Relaxed);
+        info!(logger: logger, "2");
+        assert!(sink.is_degraded());
+        info!(logger: logger, "3");
+        primary.failing.store(false, Ordering::Relaxed);
+        info!(logger: logger, "4");
+        assert!(!sink.is_degraded());
+
+        assert_eq!(primary.inner.payloads(), ["1", "4"]);
+        assert_eq!(
+            payloads_with_flags(&fallback),
+            ["2 degraded=true", "3 degraded=true"]
+        );
+        assert_eq!(handled.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn retry_backoff() {
+        let primary = Arc::new(FlakySink {
+            prop: SinkProp::default(),
+            failing: AtomicBool::new(true),
+            inner: TestSink::new(),
+        });
+        let fallback = Arc::new(TestSink::new());
+        let sink = FailoverSink::builder()
+            .primary(primary.clone())
+            .fallback(fallback.clone())
+            .retry_backoff(Duration::from_secs(3600))
+            .flag_degraded(false)
+            .error_handler(|_| {})
+            .build()
+            .unwrap();
+
+        let record = Record::new(Level::Info, "1", None, None, &[]);
+        sink.log(&record).unwrap();
+        primary.failing.store(false, Ordering::Relaxed);
+        // The primary sink is not retried until the backoff elapses
+        sink.log(&record.replace_payload("2")).unwrap();
+        assert!(sink.is_degraded());
+
+        assert!(primary.inner.payloads().is_empty());
+        assert_eq!(payloads_with_flags(&fallback), ["1", "2"]);
+
+        // Other errors are returned without failing over
+        let formatter_error = FailoverSink::builder()
+            .primary(Arc::new(ErrorSink))
+            .fallback(fallback.clone())
+            .build()
+            .unwrap();
+        assert!(matches!(
+            formatter_error.log(&record),
+            Err(Error::FormatRecord(_))
+        ));
+        assert!(!formatter_error.is_degraded());
+    }
+
+    #[test]
+    fn long_backoff() {
+        let primary = Arc::new(FlakySink {
+            prop: SinkProp::default(),
+            failing: AtomicBool::new(true),
+            inner: TestSink::new(),
+        });
+        let sink = FailoverSink::builder()
+            .primary(primary)
+            .fallback(Arc::new(TestSink::new()))
+            .retry_backoff(Duration::ZERO)
+            .max_retry_backoff(Duration::MAX)
+            .error_handler(|_| {})
+            .build()
+            .unwrap();
+        let record = Record::new(Level::Info, "1", None, None, &[]);
+
+        // Doubling the backoff saturates instead of overflowing
+        sink.log(&record).unwrap();
+        sink.state.lock_expect().backoff = Duration::MAX / 2 + Duration::from_secs(1);
+        sink.log(&record).unwrap();
+        assert_eq!(sink.state.lock_expect().backoff, Duration::MAX);
+        assert!(sink.is_degraded());
+    }
+
+    #[test]
+    fn formatter() {
+        let primary = Arc::new(StringSink::new());
+        let fallback = Arc::new(StringSink::new());
+        let sink = FailoverSink::builder()
+            .primary(primary.clone())
+            .fallback(fallback.clone())
+            .flag_degraded(false)
+            .formatter(NoModFormatter::new())
+            .build()
+            .unwrap();
+        let record = Record::new(Level::Info, "payload", None, None, &[]);
+
+        // Both the primary and fallback sinks use the formatter of the builder
+        sink.log(&record).unwrap();
+        sink.fallback().log(&record).unwrap();
+        assert_eq!(primary.clone_string(), "payload");
+        assert_eq!(fallback.clone_string(), "payload");
+    }
+
+    struct ErrorSink;
+
+    impl SinkPropAccess for ErrorSink {
+        fn level_filter(&self) -> LevelFilter {
+            LevelFilter::All
+        }
+
+        fn set_level_filter(&self, _: LevelFilter) {}
+
+        fn set_formatter(&self, _: Box<dyn Formatter>) {}
+
+        fn set_error_handler(&self, _: ErrorHandler) {}
+    }
+
+    impl Sink for ErrorSink {
+        fn log(&self, _: &Record) -> Result<()> {
+            Err(Error::FormatRecord(std::fmt::Error))
+        }
+
+        fn flush(&self) -> Result<()> {
+            Ok(())
+        }
+    }
+}
@@ -5,13 +5,21 @@ use std::{
     fs::File,
     io::{BufWriter, Write as _},
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 use crate::{
+    error::InvalidArgumentError,
     formatter::{Formatter, FormatterContext},
-    sink::{GetSinkProp, Sink, SinkProp},
+    periodic_worker::PeriodicWorker,
+    sink::{
+        file_sync::{self, FileSyncer},
+        reopen::FileReopener,
+        shared_file, GetSinkProp, Sink, SinkProp, SyncPolicy,
+    },
     sync::*,
-    utils, Error, ErrorHandler, LevelFilter, Record, Result, StringBuf,
+    utils::FileOpenOptions,
+    Error, ErrorHandler, LevelFilter, Record, Result, StringBuf,
 };
 
 /// A sink with a file as the target.
@@ -20,30 +28,70 @@ use crate::{
 /// multiple files, see  [`RotatingFileSink`].
 ///
 /// The file and directories will be created recursively if they do not exist.
+/// On Unix, their permissions can be specified by [`file_mode`] and
+/// [`dir_mode`].
+///
+/// The file is opened in append mode (`O_APPEND`) unless [`truncate`] is
+/// enabled, and it is not inherited by child processes (`O_CLOEXEC`).
+///
+/// If the file is written by multiple processes at the same time, enable
+/// [`multi_process`] to prevent records from being interleaved.
+///
+/// The file stays open until the sink is dropped. If the file is rotated by an
+/// external tool such as `logrotate`, the file needs to be reopened by calling
+/// [`FileSink::reopen`] or [`reopen_all_files`], or automatically by enabling
+/// [`reopen_on_change`].
+///
+/// By default, flushing the sink does not guarantee that the records are
+/// durable on the storage device, see [`sync_policy`] for durability controls.
 ///
 /// # Examples
 ///
 /// See [./examples] directory.
 ///
 /// [`RotatingFileSink`]: crate::sink::RotatingFileSink
+/// [`file_mode`]: FileSinkBuilder::file_mode
+/// [`dir_mode`]: FileSinkBuilder::dir_mode
+/// [`truncate`]: FileSinkBuilder::truncate
+/// [`multi_process`]: FileSinkBuilder::multi_process
+/// [`reopen_all_files`]: crate::sink::reopen_all_files
+/// [`reopen_on_change`]: FileSinkBuilder::reopen_on_change
+/// [`sync_policy`]: FileSinkBuilder::sync_policy
 /// [./examples]: https://github.com/SpriteOvO/spdlog-rs/tree/main/spdlog/examples
 pub struct FileSink {
     prop: SinkProp,
-    file: Mutex<BufWriter<File>>,
+    path: PathBuf,
+    capacity: Option<usize>,
+    open_options: FileOpenOptions,
+    inner: Arc<Mutex<FileSinkInner>>,
+    _sync_worker: Option<PeriodicWorker>,
+}
+
+struct FileSinkInner {
+    file: BufWriter<File>,
+    reopener: FileReopener,
+    syncer: FileSyncer,
 }
 
 impl FileSink {
     /// Gets a builder of `FileSink` with default parameters:
     ///
-    /// | Parameter       | Default Value               |
-    /// |-----------------|-----------------------------|
-    /// | [level_filter]  | [`LevelFilter::All`]        |
-    /// | [formatter]     | [`FullFormatter`]           |
-    /// | [error_handler] | [`ErrorHandler::default()`] |
-    /// |                 |                             |
-    /// | [path]          | *must be specified*         |
-    /// | [truncate]      | `false`                     |
-    /// | [capacity]      | consistent with `std`       |
+    /// | Parameter          | Default Value               |
+    /// |--------------------|-----------------------------|
+    /// | [level_filter]     | [`LevelFilter::All`]        |
+    /// | [formatter]        | [`FullFormatter`]           |
+    /// | [error_handler]    | [`ErrorHandler::default()`] |
+    /// |                    |                             |
+    /// | [path]             | *must be specified*         |
+    /// | [truncate]         | `false`                     |
+    /// | [capacity]         | consistent with `std`       |
+    /// | [file_mode]        | `0o666` (masked by umask)   |
+    /// | [dir_mode]         | `0o777` (masked by umask)   |
+    /// | [follow_symlinks]  | `true`                      |
+    /// | [multi_process]    | `false`                     |
+    /// | [reopen_on_change] | disabled                    |
+    /// | [sync_policy]      | [`SyncPolicy::Never`]       |
+    /// | [sync_data_only]   | `false`                     |
     ///
     /// [level_filter]: FileSinkBuilder::level_filter
     /// [formatter]: FileSinkBuilder::formatter
@@ -52,6 +100,13 @@ impl FileSink {
     /// [path]: FileSinkBuilder::path
     /// [truncate]: FileSinkBuilder::truncate
     /// [capacity]: FileSinkBuilder::capacity
+    /// [file_mode]: FileSinkBuilder::file_mode
+    /// [dir_mode]: FileSinkBuilder::dir_mode
+    /// [follow_symlinks]: FileSinkBuilder::follow_symlinks
+    /// [multi_process]: FileSinkBuilder::multi_process
+    /// [reopen_on_change]: FileSinkBuilder::reopen_on_change
+    /// [sync_policy]: FileSinkBuilder::sync_policy
+    /// [sync_data_only]: FileSinkBuilder::sync_data_only
     #[must_use]
     pub fn builder() -> FileSinkBuilder<()> {
         FileSinkBuilder {
@@ -59,6 +114,10 @@ impl FileSink {
             path: (),
             truncate: false,
             capacity: None,
+            open_options: FileOpenOptions::default(),
+            reopen_check_interval: None,
+            sync_policy: SyncPolicy::Never,
+            sync_data_only: false,
         }
     }
 
@@ -85,6 +144,50 @@ impl FileSink {
             .truncate(truncate)
             .build()
     }
+
+    /// Reopens the file.
+    ///
+    /// The buffered records are flushed to the previously opened file, then the
+    /// file at the path is opened (created if it does not exist) in append mode,
+    /// and subsequent records are written to it.
+    ///
+    /// # Error
+    ///
+    /// If an error occurs opening the file, [`Error::CreateDirectory`],
+    /// [`Error::OpenFile`] will be returned.
+    pub fn reopen(&self) -> Result<()> {
+        self.reopen_locked(&mut self.inner.lock_expect())
+    }
+
+    fn reopen_locked(&self, inner: &mut FileSinkInner) -> Result<()> {
+        let closed = inner
+            .file
+            .flush()
+            .map_err(Error::FlushBuffer)
+            .and_then(|()| inner.syncer.before_close(&mut inner.file));
+        if let Err(err) = closed {
+            self.prop.call_error_handler_internal("FileSink", err);
+        }
+        inner.file =
+            inner
+                .syncer
+                .open_file_bufw(&self.path, false, self.capacity, &self.open_options)?;
+        inner.reopener.reset(inner.file.get_ref());
+        Ok(())
+    }
+
+    fn write_records(&self, bytes: &[u8], records: u64) -> Result<()> {
+        let mut inner = self.inner.lock_expect();
+        let inner = &mut *inner;
+        if inner.reopener.should_reopen(&self.path) {
+            self.reopen_locked(inner)?;
+        }
+        shared_file::write_record(&mut inner.file, bytes, self.open_options.multi_process)?;
+
+        inner
+            .syncer
+            .after_write(&mut inner.file, records, bytes.len())
+    }
 }
 
 impl GetSinkProp for FileSink {
@@ -101,24 +204,42 @@ impl Sink for FileSink {
             .formatter()
             .format(record, &mut string_buf, &mut ctx)?;
 
-        self.file
-            .lock_expect()
-            .write_all(string_buf.as_bytes())
-            .map_err(Error::WriteRecord)?;
+        self.write_records(string_buf.as_bytes(), 1)
+    }
 
-        Ok(())
+    // Formats all records into one buffer, and writes it with a single lock of
+    // the file.
+    fn log_batch(&self, records: &[Record]) -> Result<()> {
+        let mut string_buf = StringBuf::new();
+        let (formatted, result) = self.prop.format_batch(records, &mut string_buf);
+        if formatted.is_empty() {
+            return result;
+        }
+        Error::push_result(
+            result,
+            self.write_records(string_buf.as_bytes(), formatted.len() as u64),
+        )
     }
 
     fn flush(&self) -> Result<()> {
-        self.file.lock_expect().flush().map_err(Error::FlushBuffer)
+        let mut inner = self.inner.lock_expect();
+        let inner = &mut *inner;
+        inner.file.flush().map_err(Error::FlushBuffer)?;
+        inner.syncer.after_flush(&mut inner.file)
     }
 }
 
 impl Drop for FileSink {
     fn drop(&mut self) {
-        if let Err(err) = self.file.lock_expect().flush() {
-            self.prop
-                .call_error_handler_internal("FileSink", Error::FlushBuffer(err))
+        let mut inner = self.inner.lock_expect();
+        let inner = &mut *inner;
+        let closed = inner
+            .file
+            .flush()
+            .map_err(Error::FlushBuffer)
+            .and_then(|()| inner.syncer.before_close(&mut inner.file));
+        if let Err(err) = closed {
+            self.prop.call_error_handler_internal("FileSink", err)
         }
     }
 }
@@ -132,6 +253,10 @@ pub struct FileSinkBuilder<ArgPath> {
     path: ArgPath,
     truncate: bool,
     capacity: Option<usize>,
+    open_options: FileOpenOptions,
+    reopen_check_interval: Option<Duration>,
+    sync_policy: SyncPolicy,
+    sync_data_only: bool,
 }
 
 impl<ArgPath> FileSinkBuilder<ArgPath> {
@@ -148,6 +273,10 @@ impl<ArgPath> FileSinkBuilder<ArgPath> {
             path: path.into(),
             truncate: self.truncate,
             capacity: self.capacity,
+            open_options: self.open_options,
+            reopen_check_interval: self.reopen_check_interval,
+            sync_policy: self.sync_policy,
+            sync_data_only: self.sync_data_only,
         }
     }
 
@@ -172,6 +301,113 @@ impl<ArgPath> FileSinkBuilder<ArgPath> {
         self
     }
 
+    /// Specifies the permission mode bits of the log file if it is created by
+    /// the sink, e.g. `0o640`.
+    ///
+    /// Like `open(2)`, the mode is masked by the umask of the process. The mode
+    /// of an existing file is not changed.
+    ///
+    /// This parameter is **optional**, and defaults to `0o666` (masked by the
+    /// umask).
+    #[cfg(unix)]
+    #[must_use]
+    pub fn file_mode(mut self, mode: u32) -> Self {
+        self.open_options.file_mode = Some(mode);
+        self
+    }
+
+    /// Specifies the permission mode bits of the directories created along the
+    /// path of the log file, e.g. `0o750`.
+    ///
+    /// Like `mkdir(2)`, the mode is masked by the umask of the process.
+    ///
+    /// This parameter is **optional**, and defaults to `0o777` (masked by the
+    /// umask).
+    #[cfg(unix)]
+    #[must_use]
+    pub fn dir_mode(mut self, mode: u32) -> Self {
+        self.open_options.dir_mode = Some(mode);
+        self
+    }
+
+    /// Specifies whether to follow the log file path if it is a symbolic link.
+    ///
+    /// If it is `false`, opening a log file whose path is a symbolic link fails
+    /// with [`Error::OpenFile`], which prevents the sink from being redirected
+    /// to an unexpected file. On Unix, the file is opened with `O_NOFOLLOW`.
+    /// Symbolic links in the parent directories are still followed.
+    ///
+    /// This parameter is **optional**, and defaults to `true`.
+    #[must_use]
+    pub fn follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.open_options.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// Specifies whether the log file is written by multiple processes at the
+    /// same time, e.g. forked or spawned worker processes.
+    ///
+    /// If it is `true`, each record bypasses the internal buffer and is
+    /// appended to the file with a single `write` while holding an advisory
+    /// lock of the file (`flock`), so that records from different processes
+    /// are never interleaved. The file is always opened in append mode, even if
+    /// [`truncate`] is enabled.
+    ///
+    /// A sink built before `fork()` can be used by both the parent and the
+    /// child processes. A process that did not open the file reopens it before
+    /// its first write, since the lock belongs to the open file description
+    /// inherited by the child and would not exclude the parent otherwise.
+    ///
+    /// The lock is only available on Unix. On other platforms, records are
+    /// still appended with a single `write`.
+    ///
+    /// This parameter is **optional**, and defaults to `false`.
+    ///
+    /// [`truncate`]: FileSinkBuilder::truncate
+    #[must_use]
+    pub fn multi_process(mut self, multi_process: bool) -> Self {
+        self.open_options.multi_process = multi_process;
+        self
+    }
+
+    /// Reopens the file automatically if it has been changed externally.
+    ///
+    /// Every `interval`, when a record is logged, the sink checks whether the
+    /// file at the path has been removed, replaced by another file (e.g.
+    /// `logrotate` with `create`), or truncated (e.g. `logrotate` with
+    /// `copytruncate`), and reopens the file if so. See [`FileSink::reopen`].
+    ///
+    /// Detecting the replacement requires the inode and device of the file,
+    /// which are only available on Unix.
+    ///
+    /// This parameter is **optional**, and defaults to disabled.
+    #[must_use]
+    pub fn reopen_on_change(mut self, interval: Duration) -> Self {
+        self.reopen_check_interval = Some(interval);
+        self
+    }
+
+    /// Specifies when the file is synchronized to the storage device.
+    ///
+    /// See [`SyncPolicy`] for details.
+    ///
+    /// This parameter is **optional**, and defaults to [`SyncPolicy::Never`].
+    #[must_use]
+    pub fn sync_policy(mut self, sync_policy: SyncPolicy) -> Self {
+        self.sync_policy = sync_policy;
+        self
+    }
+
+    /// Specifies whether to synchronize only the file content and the metadata
+    /// needed to read it (`fdatasync`) instead of all metadata (`fsync`).
+    ///
+    /// This parameter is **optional**, and defaults to `false`.
+    #[must_use]
+    pub fn sync_data_only(mut self, sync_data_only: bool) -> Self {
+        self.sync_data_only = sync_data_only;
+        self
+    }
+
     // Prop
     //
 
@@ -230,14 +466,41 @@ impl FileSinkBuilder<PathBuf> {
     ///
     /// # Error
     ///
-    /// If an error occurs opening the file, [`Error::CreateDirectory`] or
-    /// [`Error::OpenFile`] will be returned.
+    /// If the argument `sync_policy` is invalid,
+    /// [`Error::InvalidArgument`] will be returned.
+    ///
+    /// If an error occurs opening the file, [`Error::CreateDirectory`],
+    /// [`Error::OpenFile`] or [`Error::SyncFile`] will be returned.
     pub fn build(self) -> Result<FileSink> {
-        let file = utils::open_file_bufw(self.path, self.truncate, self.capacity)?;
+        self.sync_policy
+            .validate()
+            .map_err(|err| Error::InvalidArgument(InvalidArgumentError::SyncPolicy(err)))?;
+
+        let syncer = FileSyncer::new(self.sync_policy, self.sync_data_only);
+        let file =
+            syncer.open_file_bufw(&self.path, self.truncate, self.capacity, &self.open_options)?;
+        let reopener = FileReopener::new(
+            self.reopen_check_interval,
+            self.open_options.multi_process,
+            file.get_ref(),
+        );
+
+        let inner = Arc::new(Mutex::new(FileSinkInner {
+            file,
+            reopener,
+            syncer,
+        }));
+        let sync_worker = file_sync::spawn_sync_worker(self.sync_policy, &inner, |inner| {
+            inner.syncer.sync_in_background(&mut inner.file)
+        });
 
         let sink = FileSink {
             prop: self.prop,
-            file: Mutex::new(file),
+            path: self.path,
+            capacity: self.capacity,
+            open_options: self.open_options,
+            inner,
+            _sync_worker: sync_worker,
         };
 
         Ok(sink)
@@ -250,3 +513,91 @@ impl FileSinkBuilder<PathBuf> {
         self.build().map(Arc::new)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{test_utils::*, Level};
+
+    static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+        let path = TEST_LOGS_PATH.join("file_sink");
+        _ = fs::remove_dir_all(&path);
+        fs::create_dir(&path).unwrap();
+        path
+    });
+
+    #[cfg(unix)]
+    #[test]
+    fn permissions() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let path = LOGS_PATH.join("private").join("permissions.log");
+        FileSink::builder()
+            .path(&path)
+            .file_mode(0o600)
+            .dir_mode(0o700)
+            .build()
+            .unwrap();
+
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&path), 0o600);
+        assert_eq!(mode(path.parent().unwrap()), 0o700);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuse_symlinks() {
+        let target = LOGS_PATH.join("target.log");
+        let link = LOGS_PATH.join("link.log");
+        fs::write(&target, "").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        assert!(FileSink::builder().path(&link).build().is_ok());
+        assert!(matches!(
+            FileSink::builder()
+                .path(&link)
+                .follow_symlinks(false)
+                .build(),
+            Err(Error::OpenFile(_))
+        ));
+    }
+
+    #[test]
+    fn log_batch() {
+        let path = LOGS_PATH.join("log_batch.log");
+        let sink = FileSink::builder()
+            .path(&path)
+            .formatter(NoModFormatter::new())
+            .build()
+            .unwrap();
+
+        let records =
+            ["a", "bc", "def"].map(|payload| Record::new(Level::Info, payload, None, None, &[]));
+        sink.log_batch(&records).unwrap();
+        sink.log_batch(&[]).unwrap();
+        sink.log(&records[0]).unwrap();
+        sink.flush().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "abcdefa");
+    }
+
+    #[test]
+    fn sync_on_drop() {
+        let path = LOGS_PATH.join("sync_on_drop.log");
+        let sink = FileSink::builder()
+            .path(&path)
+            .formatter(NoModFormatter::new())
+            .sync_policy(SyncPolicy::EveryRecords(1000))
+            .build()
+            .unwrap();
+
+        let record = Record::new(Level::Info, "hello", None, None, &[]);
+        sink.log(&record).unwrap();
+        let syncs = file_sync::test_sync_count();
+        drop(sink);
+        assert_eq!(file_sync::test_sync_count(), syncs + 1);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "hello");
+    }
+}
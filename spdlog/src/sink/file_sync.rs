@@ -0,0 +1,359 @@
+//! Provides durability controls for file sinks.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write as _},
+    path::Path,
+    result::Result as StdResult,
+    time::Duration,
+};
+
+use crate::{
+    periodic_worker::PeriodicWorker,
+    sync::*,
+    utils::{self, FileOpenOptions},
+    Error, Result,
+};
+
+/// Sync policies for [`FileSink`] and [`RotatingFileSink`].
+///
+/// Flushing a file sink only writes the buffered records into the page cache
+/// of the operating system, which may be lost if the system crashes or loses
+/// power. The sync policy determines when the file is synchronized to the
+/// storage device, with `fsync` (or `fdatasync`, see [`sync_data_only`]).
+///
+/// If the policy is not [`SyncPolicy::Never`], the parent directory is also
+/// synchronized after the sink creates or renames a file, so that the
+/// directory entry of the file is durable as well.
+///
+/// # Error
+///
+/// Note that some parameters have range requirements, functions that receive it
+/// will return an error if the requirements are not met.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use spdlog::sink::SyncPolicy;
+///
+/// // Syncing on every call of `Sink::flush`.
+/// SyncPolicy::OnFlush;
+///
+/// // Syncing every 100 records.
+/// SyncPolicy::EveryRecords(100);
+///
+/// // Syncing every 1 MB.
+/// SyncPolicy::EveryBytes(1024 * 1024);
+///
+/// // Syncing every 5 seconds on a background thread.
+/// SyncPolicy::Periodic(Duration::from_secs(5));
+/// ```
+///
+/// [`FileSink`]: crate::sink::FileSink
+/// [`RotatingFileSink`]: crate::sink::RotatingFileSink
+/// [`sync_data_only`]: crate::sink::FileSinkBuilder::sync_data_only
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum SyncPolicy {
+    /// Never synchronizes the file, leaves it to the operating system.
+    #[default]
+    Never,
+    /// Synchronizes the file on every call of [`Sink::flush`].
+    ///
+    /// [`Sink::flush`]: crate::sink::Sink::flush
+    OnFlush,
+    /// Synchronizes the file every specified number of records.
+    ///
+    /// The number of records should be greater than 0.
+    EveryRecords(u64),
+    /// Synchronizes the file every specified number of bytes written.
+    ///
+    /// The number of bytes should be greater than 0.
+    EveryBytes(u64),
+    /// Synchronizes the file periodically on a background thread, if any record
+    /// has been written since the last synchronization.
+    ///
+    /// The period should be greater than 0. An error that occurs on the
+    /// background thread is returned by the next call of [`Sink::log`] or
+    /// [`Sink::flush`].
+    ///
+    /// [`Sink::log`]: crate::sink::Sink::log
+    /// [`Sink::flush`]: crate::sink::Sink::flush
+    Periodic(Duration),
+}
+
+impl SyncPolicy {
+    pub(crate) fn validate(&self) -> StdResult<(), String> {
+        match self {
+            Self::Never | Self::OnFlush => {}
+            Self::EveryRecords(records) => {
+                if *records == 0 {
+                    return Err(
+                        "policy 'every records' expect records to be (0, u64::MAX] but got 0"
+                            .to_string(),
+                    );
+                }
+            }
+            Self::EveryBytes(bytes) => {
+                if *bytes == 0 {
+                    return Err(
+                        "policy 'every bytes' expect bytes to be (0, u64::MAX] but got 0"
+                            .to_string(),
+                    );
+                }
+            }
+            Self::Periodic(period) => {
+                if period.is_zero() {
+                    return Err("policy 'periodic' expect period to be non-zero".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Tracks the writes of a file sink, and synchronizes the file according to the
+// sync policy.
+pub(crate) struct FileSyncer {
+    policy: SyncPolicy,
+    data_only: bool,
+    unsynced_records: u64,
+    unsynced_bytes: u64,
+    background_error: Option<Error>,
+}
+
+impl FileSyncer {
+    #[must_use]
+    pub(crate) fn new(policy: SyncPolicy, data_only: bool) -> Self {
+        Self {
+            policy,
+            data_only,
+            unsynced_records: 0,
+            unsynced_bytes: 0,
+            background_error: None,
+        }
+    }
+
+    // Opens the file, and synchronizes its parent directory if the file is newly
+    // created.
+    pub(crate) fn open_file_bufw(
+        &self,
+        path: &Path,
+        truncate: bool,
+        capacity: Option<usize>,
+        options: &FileOpenOptions,
+    ) -> Result<BufWriter<File>> {
+        let created = self.policy != SyncPolicy::Never && !path.exists();
+        let file = utils::open_file_bufw(path, truncate, capacity, options)?;
+        if created {
+            sync_parent_dir(path)?;
+        }
+        Ok(file)
+    }
+
+    // Should be called after a file has been renamed to the path.
+    pub(crate) fn after_rename(&self, path: &Path) -> Result<()> {
+        if self.policy != SyncPolicy::Never {
+            sync_parent_dir(path)?;
+        }
+        Ok(())
+    }
+
+    // Should be called after records have been written, `records` is the number
+    // of them.
+    pub(crate) fn after_write(
+        &mut self,
+        file: &mut BufWriter<File>,
+        records: u64,
+        len: usize,
+    ) -> Result<()> {
+        self.unsynced_records += records;
+        self.unsynced_bytes += len as u64;
+
+        match self.policy {
+            SyncPolicy::EveryRecords(records) if self.unsynced_records >= records => {
+                self.sync(file)
+            }
+            SyncPolicy::EveryBytes(bytes) if self.unsynced_bytes >= bytes => self.sync(file),
+            _ => Ok(()),
+        }?;
+        self.take_background_error()
+    }
+
+    // Should be called after the buffer has been flushed by `Sink::flush`.
+    pub(crate) fn after_flush(&mut self, file: &mut BufWriter<File>) -> Result<()> {
+        self.take_background_error()?;
+        match self.policy {
+            SyncPolicy::OnFlush => self.sync(file),
+            _ => Ok(()),
+        }
+    }
+
+    // Should be called before the file is closed, e.g. reopened or rotated.
+    pub(crate) fn before_close(&mut self, file: &mut BufWriter<File>) -> Result<()> {
+        if self.policy != SyncPolicy::Never && self.is_dirty() {
+            self.sync(file)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(crate) fn sync_in_background(&mut self, file: &mut BufWriter<File>) {
+        if self.is_dirty() {
+            if let Err(err) = self.sync(file) {
+                self.background_error.get_or_insert(err);
+            }
+        }
+    }
+
+    fn take_background_error(&mut self) -> Result<()> {
+        match self.background_error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    #[must_use]
+    fn is_dirty(&self) -> bool {
+        self.unsynced_records != 0
+    }
+
+    fn sync(&mut self, file: &mut BufWriter<File>) -> Result<()> {
+        file.flush().map_err(Error::FlushBuffer)?;
+        let file = file.get_ref();
+        if self.data_only {
+            file.sync_data()
+        } else {
+            file.sync_all()
+        }
+        .map_err(Error::SyncFile)?;
+        #[cfg(test)]
+        SYNC_COUNT.with(|count| count.set(count.get() + 1));
+        self.unsynced_records = 0;
+        self.unsynced_bytes = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    // The number of synchronizations on the current thread
+    static SYNC_COUNT: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+}
+
+#[cfg(test)]
+#[must_use]
+pub(crate) fn test_sync_count() -> u64 {
+    SYNC_COUNT.with(|count| count.get())
+}
+
+// Spawns a worker synchronizing the file periodically, if the policy is
+// `SyncPolicy::Periodic`.
+#[must_use]
+pub(crate) fn spawn_sync_worker<T: Send + 'static>(
+    policy: SyncPolicy,
+    inner: &Arc<Mutex<T>>,
+    sync: fn(&mut T),
+) -> Option<PeriodicWorker> {
+    let SyncPolicy::Periodic(period) = policy else {
+        return None;
+    };
+    let weak = Arc::downgrade(inner);
+    let callback = move || match weak.upgrade() {
+        Some(inner) => {
+            sync(&mut inner.lock_expect());
+            true
+        }
+        None => false, // The sink is dropped, return `false` to quit the worker thread.
+    };
+    Some(PeriodicWorker::new(callback, period))
+}
+
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    File::open(parent)
+        .and_then(|dir| dir.sync_all())
+        .map_err(Error::SyncFile)
+}
+
+// Directories cannot be opened as files to be synchronized on non-Unix platforms.
+#[cfg(not(unix))]
+fn sync_parent_dir(_: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, thread};
+
+    use super::*;
+    use crate::{prelude::*, sink::FileSink, test_utils::*};
+
+    #[test]
+    fn validate() {
+        assert!(SyncPolicy::Never.validate().is_ok());
+        assert!(SyncPolicy::OnFlush.validate().is_ok());
+        assert!(SyncPolicy::EveryRecords(1).validate().is_ok());
+        assert!(SyncPolicy::EveryRecords(0).validate().is_err());
+        assert!(SyncPolicy::EveryBytes(1).validate().is_ok());
+        assert!(SyncPolicy::EveryBytes(0).validate().is_err());
+        assert!(SyncPolicy::Periodic(Duration::from_millis(1))
+            .validate()
+            .is_ok());
+        assert!(SyncPolicy::Periodic(Duration::ZERO).validate().is_err());
+    }
+
+    #[test]
+    fn count_writes() {
+        let path = TEST_LOGS_PATH.join("file_sync_count_writes.log");
+        let mut syncer = FileSyncer::new(SyncPolicy::EveryRecords(2), true);
+        let mut file = syncer
+            .open_file_bufw(&path, true, None, &FileOpenOptions::default())
+            .unwrap();
+
+        file.write_all(b"1").unwrap();
+        syncer.after_write(&mut file, 1, 1).unwrap();
+        assert!(syncer.is_dirty());
+        file.write_all(b"2").unwrap();
+        syncer.after_write(&mut file, 1, 1).unwrap();
+        assert!(!syncer.is_dirty());
+        // The buffer is flushed by syncing
+        assert_eq!(fs::read_to_string(&path).unwrap(), "12");
+
+        let mut syncer = FileSyncer::new(SyncPolicy::EveryBytes(4), false);
+        syncer.after_write(&mut file, 1, 3).unwrap();
+        assert!(syncer.is_dirty());
+        syncer.after_write(&mut file, 1, 1).unwrap();
+        assert!(!syncer.is_dirty());
+    }
+
+    #[test]
+    fn periodic() {
+        let path = TEST_LOGS_PATH.join("file_sync_periodic.log");
+        let sink = FileSink::builder()
+            .path(&path)
+            .truncate(true)
+            .formatter(NoModFormatter::new())
+            .sync_policy(SyncPolicy::Periodic(Duration::from_millis(10)))
+            .build_arc()
+            .unwrap();
+        let logger = build_test_logger(|b| b.sink(sink));
+
+        info!(logger: logger, "hello");
+        thread::sleep(Duration::from_millis(200));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "hello");
+
+        assert!(matches!(
+            FileSink::builder()
+                .path(&path)
+                .sync_policy(SyncPolicy::EveryRecords(0))
+                .build(),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{
+    formatter::{pattern, Formatter, FormatterContext, PatternFormatter},
+    kv,
+    sink::{GetSinkProp, Sink, SinkProp},
+    sync::*,
+    ErrorHandler, LevelFilter, Record, Result, StringBuf,
+};
+
+/// A sink with the global logger of [log crate] as the target.
+///
+/// It is the opposite direction of [`LogCrateProxy`], records are re-emitted
+/// through [`log::logger()`], so that they end up in the pipeline of whatever
+/// `log` backend the application installed.
+///
+/// - The formatted record becomes the message, by default only the payload is
+///   formatted, since the `log` backend usually has its own format.
+/// - The target is the logger name, or the module path if the logger has no
+///   name.
+/// - The key-values of the record are passed as `log` key-values.
+///
+/// Records whose level is filtered out by [`log::max_level()`] are discarded.
+///
+/// This sink is only available if crate feature `log` is enabled. Do not use it
+/// with a logger that [`LogCrateProxy`] forwards to, otherwise records loop
+/// between the two.
+///
+/// # Log Level Mapping
+///
+/// | spdlog-rs  | log     |
+/// |------------|---------|
+/// | `Critical` | `Error` |
+/// | `Error`    | `Error` |
+/// | `Warn`     | `Warn`  |
+/// | `Info`     | `Info`  |
+/// | `Debug`    | `Debug` |
+/// | `Trace`    | `Trace` |
+///
+/// [log crate]: https://crates.io/crates/log
+/// [`LogCrateProxy`]: crate::LogCrateProxy
+/// [`log::logger()`]: https://docs.rs/log/latest/log/fn.logger.html
+/// [`log::max_level()`]: https://docs.rs/log/latest/log/fn.max_level.html
+pub struct LogCrateSink {
+    prop: SinkProp,
+}
+
+impl LogCrateSink {
+    /// Gets a builder of `LogCrateSink` with default parameters:
+    ///
+    /// | Parameter       | Default Value                             |
+    /// |-----------------|-------------------------------------------|
+    /// | [level_filter]  | [`LevelFilter::All`]                      |
+    /// | [formatter]     | [`PatternFormatter`] `("{payload}")`      |
+    /// | [error_handler] | [`ErrorHandler::default()`]               |
+    ///
+    /// [level_filter]: LogCrateSinkBuilder::level_filter
+    /// [formatter]: LogCrateSinkBuilder::formatter
+    /// [`PatternFormatter`]: crate::formatter::PatternFormatter
+    /// [error_handler]: LogCrateSinkBuilder::error_handler
+    #[must_use]
+    pub fn builder() -> LogCrateSinkBuilder {
+        let prop = SinkProp::default();
+        prop.set_formatter(PatternFormatter::new(pattern!("{payload}")));
+
+        LogCrateSinkBuilder { prop }
+    }
+}
+
+impl GetSinkProp for LogCrateSink {
+    fn prop(&self) -> &SinkProp {
+        &self.prop
+    }
+}
+
+impl Sink for LogCrateSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        let level = log::Level::from(record.level());
+        if level > log::max_level() {
+            return Ok(());
+        }
+
+        let mut string_buf = StringBuf::new();
+        let mut ctx = FormatterContext::new();
+        self.prop
+            .formatter()
+            .format(record, &mut string_buf, &mut ctx)?;
+
+        let srcloc = record.source_location();
+        let target = record
+            .logger_name()
+            .or_else(|| srcloc.map(|srcloc| srcloc.module_path()))
+            .unwrap_or_default();
+        let kvs = record.key_values().into_iter().collect();
+
+        log::logger().log(
+            &log::Record::builder()
+                .args(format_args!("{string_buf}"))
+                .level(level)
+                .target(target)
+                .module_path_static(srcloc.map(|srcloc| srcloc.module_path()))
+                .file_static(srcloc.map(|srcloc| srcloc.file()))
+                .line(srcloc.map(|srcloc| srcloc.line()))
+                .key_values(&LogCrateKeyValues(kvs))
+                .build(),
+        );
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        log::logger().flush();
+        Ok(())
+    }
+}
+
+struct LogCrateKeyValues<'a>(Vec<(kv::Key<'a>, kv::Value<'a>)>);
+
+impl log::kv::Source for LogCrateKeyValues<'_> {
+    fn visit<'kvs>(
+        &'kvs self,
+        visitor: &mut dyn log::kv::VisitSource<'kvs>,
+    ) -> std::result::Result<(), log::kv::Error> {
+        for (key, value) in &self.0 {
+            let key = log::kv::Key::from_str(key.as_str());
+            let value = if let Some(value) = value.to_borrowed_str() {
+                log::kv::Value::from(value)
+            } else if let Some(value) = value.to_bool() {
+                log::kv::Value::from(value)
+            } else if let Some(value) = value.to_i64() {
+                log::kv::Value::from(value)
+            } else if let Some(value) = value.to_u64() {
+                log::kv::Value::from(value)
+            } else if let Some(value) = value.to_f64() {
+                log::kv::Value::from(value)
+            } else {
+                visitor.visit_pair(key, log::kv::Value::from_dyn_display(value))?;
+                continue;
+            };
+            visitor.visit_pair(key, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[allow(missing_docs)]
+pub struct LogCrateSinkBuilder {
+    prop: SinkProp,
+}
+
+impl LogCrateSinkBuilder {
+    // Prop
+    //
+
+    /// Specifies a log level filter.
+    ///
+    /// This parameter is **optional**, and defaults to [`LevelFilter::All`].
+    #[must_use]
+    pub fn level_filter(self, level_filter: LevelFilter) -> Self {
+        self.prop.set_level_filter(level_filter);
+        self
+    }
+
+    /// Specifies a formatter.
+    ///
+    /// This parameter is **optional**, and defaults to [`PatternFormatter`]
+    /// `("{payload}")`.
+    ///
+    /// [`PatternFormatter`]: crate::formatter::PatternFormatter
+    #[must_use]
+    pub fn formatter<F>(self, formatter: F) -> Self
+    where
+        F: Formatter + 'static,
+    {
+        self.prop.set_formatter(formatter);
+        self
+    }
+
+    /// Specifies an error handler.
+    ///
+    /// This parameter is **optional**, and defaults to
+    /// [`ErrorHandler::default()`].
+    #[must_use]
+    pub fn error_handler<F: Into<ErrorHandler>>(self, handler: F) -> Self {
+        self.prop.set_error_handler(handler);
+        self
+    }
+
+    //
+
+    /// Builds a [`LogCrateSink`].
+    pub fn build(self) -> Result<LogCrateSink> {
+        let sink = LogCrateSink { prop: self.prop };
+        Ok(sink)
+    }
+
+    /// Builds a `Arc<LogCrateSink>`.
+    ///
+    /// This is a shorthand method for `.build().map(Arc::new)`.
+    pub fn build_arc(self) -> Result<Arc<LogCrateSink>> {
+        self.build().map(Arc::new)
+    }
+}
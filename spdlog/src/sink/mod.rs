@@ -35,15 +35,33 @@
 mod android_sink;
 #[cfg(feature = "multi-thread")]
 pub(crate) mod async_sink;
+#[cfg(feature = "audit")]
+mod audit_sink;
+mod binary_file_sink;
+mod callback_sink;
+#[cfg(feature = "test-util")]
+mod capture_sink;
+mod channel_sink;
 mod dedup_sink;
+mod failover_sink;
 mod file_sink;
+mod file_sync;
 #[cfg(any(
     all(target_os = "linux", feature = "native", feature = "libsystemd"),
     all(doc, not(doctest))
 ))]
 mod journald_sink;
+#[cfg(feature = "log")]
+mod log_crate_sink;
+#[cfg(feature = "otlp")]
+mod otlp_sink;
+mod reopen;
 mod rotating_file_sink;
+mod routing_sink;
+mod shared_file;
 mod std_stream_sink;
+#[cfg(feature = "tracing")]
+mod tracing_sink;
 #[cfg(any(all(windows, feature = "native"), all(doc, not(doctest))))]
 mod win_debug_sink;
 mod write_sink;
@@ -57,23 +75,40 @@ use std::ops::Deref;
 pub use android_sink::*;
 #[cfg(feature = "multi-thread")]
 pub use async_sink::*;
+#[cfg(feature = "audit")]
+pub use audit_sink::*;
+pub use binary_file_sink::*;
+pub use callback_sink::*;
+#[cfg(feature = "test-util")]
+pub use capture_sink::*;
+pub use channel_sink::*;
 pub use dedup_sink::*;
+pub use failover_sink::*;
 pub use file_sink::*;
+pub use file_sync::*;
 #[cfg(any(
     all(target_os = "linux", feature = "native", feature = "libsystemd"),
     all(doc, not(doctest))
 ))]
 pub use journald_sink::*;
+#[cfg(feature = "log")]
+pub use log_crate_sink::*;
+#[cfg(feature = "otlp")]
+pub use otlp_sink::*;
+pub use reopen::*;
 pub use rotating_file_sink::*;
+pub use routing_sink::*;
 pub use std_stream_sink::*;
+#[cfg(feature = "tracing")]
+pub use tracing_sink::*;
 #[cfg(any(all(windows, feature = "native"), all(doc, not(doctest))))]
 pub use win_debug_sink::*;
 pub use write_sink::*;
 
 use crate::{
-    formatter::{Formatter, FullFormatter},
+    formatter::{Formatter, FormatterContext, FullFormatter},
     sync::*,
-    AtomicLevelFilter, Error, ErrorHandler, Level, LevelFilter, Record, Result,
+    AtomicLevelFilter, Error, ErrorHandler, Level, LevelFilter, Record, Result, StringBuf,
 };
 
 /// Contains definitions of sink properties.
@@ -151,6 +186,32 @@ impl SinkProp {
         self.error_handler.read_expect().call_internal(from, err)
     }
 
+    // Formats the records into one buffer with a single acquisition of the
+    // formatter, and returns the formatted records with their end offsets in
+    // the buffer. Records that fail to be formatted are skipped, and their
+    // errors are returned.
+    pub(crate) fn format_batch<'r, 'a>(
+        &self,
+        records: &'r [Record<'a>],
+        dest: &mut StringBuf,
+    ) -> (Vec<(&'r Record<'a>, usize)>, Result<()>) {
+        let formatter = self.formatter();
+        let mut formatted = Vec::with_capacity(records.len());
+        let mut result = Ok(());
+        for record in records {
+            let start = dest.len();
+            let mut ctx = FormatterContext::new();
+            match formatter.format(record, dest, &mut ctx) {
+                Ok(()) => formatted.push((record, dest.len())),
+                Err(err) => {
+                    dest.truncate(start);
+                    result = Error::push_err(result, err);
+                }
+            }
+        }
+        (formatted, result)
+    }
+
     /// Sets a error handler.
     ///
     /// Most errors that occur in `Sink` will be returned as directly as
@@ -243,6 +304,25 @@ pub trait Sink: SinkPropAccess + Sync + Send {
     /// Logs a record.
     fn log(&self, record: &Record) -> Result<()>;
 
+    /// Logs a batch of records.
+    ///
+    /// Combined sinks processing records in batches call this method instead
+    /// of [`Sink::log`], e.g. [`AsyncPoolSink`] passes the records taken from
+    /// its thread pool at once. Sinks can override it to amortize per-record
+    /// costs, such as acquiring a lock or issuing a system call.
+    ///
+    /// The default implementation calls [`Sink::log`] for each record, and
+    /// returns all errors that occurred.
+    ///
+    /// [`AsyncPoolSink`]: crate::sink::AsyncPoolSink
+    fn log_batch(&self, records: &[Record]) -> Result<()> {
+        let mut result = Ok(());
+        for record in records {
+            result = Error::push_result(result, self.log(record));
+        }
+        result
+    }
+
     /// Flushes any buffered records.
     fn flush(&self) -> Result<()>;
 
@@ -0,0 +1,240 @@
+//! Encodes logs into OTLP `ExportLogsServiceRequest` messages.
+//!
+//! See https://github.com/open-telemetry/opentelemetry-proto/blob/main/opentelemetry/proto/logs/v1/logs.proto
+
+use serde_json::{json, Map, Value as JsonValue};
+
+use super::{AnyValue, ExportRecord};
+
+// Groups records by their instrumentation scope, in the order of appearance.
+#[must_use]
+fn group_by_scope(records: &[ExportRecord]) -> Vec<(Option<&str>, Vec<&ExportRecord>)> {
+    let mut groups: Vec<(Option<&str>, Vec<&ExportRecord>)> = vec![];
+    for record in records {
+        let scope = record.scope.as_deref();
+        match groups.iter_mut().find(|(s, _)| *s == scope) {
+            Some((_, group)) => group.push(record),
+            None => groups.push((scope, vec![record])),
+        }
+    }
+    groups
+}
+
+// Protobuf
+//
+
+#[derive(Default)]
+struct ProtoWriter(Vec<u8>);
+
+impl ProtoWriter {
+    const WIRE_VARINT: u8 = 0;
+    const WIRE_FIXED64: u8 = 1;
+    const WIRE_LEN: u8 = 2;
+
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.0.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+    }
+
+    fn tag(&mut self, field: u32, wire_type: u8) {
+        self.varint(u64::from(field << 3 | u32::from(wire_type)));
+    }
+
+    fn uint64(&mut self, field: u32, value: u64) {
+        if value != 0 {
+            self.tag(field, Self::WIRE_VARINT);
+            self.varint(value);
+        }
+    }
+
+    fn fixed64(&mut self, field: u32, value: u64) {
+        if value != 0 {
+            self.tag(field, Self::WIRE_FIXED64);
+            self.0.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    fn bytes(&mut self, field: u32, value: &[u8]) {
+        self.tag(field, Self::WIRE_LEN);
+        self.varint(value.len() as u64);
+        self.0.extend_from_slice(value);
+    }
+
+    fn message(&mut self, field: u32, f: impl FnOnce(&mut ProtoWriter)) {
+        let mut nested = ProtoWriter::default();
+        f(&mut nested);
+        self.bytes(field, &nested.0);
+    }
+
+    fn any_value(&mut self, field: u32, value: &AnyValue) {
+        self.message(field, |w| match value {
+            AnyValue::String(value) => w.bytes(1, value.as_bytes()),
+            AnyValue::Bool(value) => {
+                // Written even if `false`, since the field is in a `oneof`
+                w.tag(2, Self::WIRE_VARINT);
+                w.varint(u64::from(*value));
+            }
+            AnyValue::Int(value) => {
+                w.tag(3, Self::WIRE_VARINT);
+                w.varint(*value as u64);
+            }
+            AnyValue::Double(value) => {
+                w.tag(4, Self::WIRE_FIXED64);
+                w.0.extend_from_slice(&value.to_bits().to_le_bytes());
+            }
+        });
+    }
+
+    fn key_values(&mut self, field: u32, attributes: &[(String, AnyValue)]) {
+        for (key, value) in attributes {
+            self.message(field, |w| {
+                w.bytes(1, key.as_bytes());
+                w.any_value(2, value);
+            });
+        }
+    }
+}
+
+#[must_use]
+pub(super) fn to_protobuf(resource: &[(String, AnyValue)], records: &[ExportRecord]) -> Vec<u8> {
+    let mut request = ProtoWriter::default();
+    // ExportLogsServiceRequest.resource_logs
+    request.message(1, |resource_logs| {
+        // ResourceLogs.resource
+        resource_logs.message(1, |w| w.key_values(1, resource));
+        for (scope, records) in group_by_scope(records) {
+            // ResourceLogs.scope_logs
+            resource_logs.message(2, |scope_logs| {
+                // ScopeLogs.scope
+                scope_logs.message(1, |w| {
+                    if let Some(scope) = scope {
+                        w.bytes(1, scope.as_bytes());
+                    }
+                });
+                for record in records {
+                    // ScopeLogs.log_records
+                    scope_logs.message(2, |w| {
+                        w.fixed64(1, record.time_unix_nano);
+                        w.uint64(2, record.severity_number);
+                        w.bytes(3, record.severity_text.as_bytes());
+                        w.any_value(5, &AnyValue::String(record.body.clone()));
+                        w.key_values(6, &record.attributes);
+                        if let Some(trace_id) = &record.trace_id {
+                            w.bytes(9, trace_id);
+                        }
+                        if let Some(span_id) = &record.span_id {
+                            w.bytes(10, span_id);
+                        }
+                        w.fixed64(11, record.observed_time_unix_nano);
+                    });
+                }
+            });
+        }
+    });
+    request.0
+}
+
+// JSON
+//
+
+#[must_use]
+fn json_any_value(value: &AnyValue) -> JsonValue {
+    match value {
+        AnyValue::String(value) => json!({ "stringValue": value }),
+        AnyValue::Bool(value) => json!({ "boolValue": value }),
+        // 64-bit integers are encoded as strings in the JSON mapping of protobuf
+        AnyValue::Int(value) => json!({ "intValue": value.to_string() }),
+        AnyValue::Double(value) => json!({ "doubleValue": value }),
+    }
+}
+
+#[must_use]
+fn json_key_values(attributes: &[(String, AnyValue)]) -> JsonValue {
+    attributes
+        .iter()
+        .map(|(key, value)| json!({ "key": key, "value": json_any_value(value) }))
+        .collect()
+}
+
+#[must_use]
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[must_use]
+pub(super) fn to_json(resource: &[(String, AnyValue)], records: &[ExportRecord]) -> Vec<u8> {
+    let scope_logs = group_by_scope(records)
+        .into_iter()
+        .map(|(scope, records)| {
+            let log_records = records
+                .into_iter()
+                .map(|record| {
+                    let mut log_record = Map::new();
+                    log_record.insert(
+                        "timeUnixNano".into(),
+                        record.time_unix_nano.to_string().into(),
+                    );
+                    log_record.insert(
+                        "observedTimeUnixNano".into(),
+                        record.observed_time_unix_nano.to_string().into(),
+                    );
+                    log_record.insert("severityNumber".into(), record.severity_number.into());
+                    log_record.insert("severityText".into(), record.severity_text.into());
+                    log_record.insert("body".into(), json!({ "stringValue": record.body }));
+                    log_record.insert("attributes".into(), json_key_values(&record.attributes));
+                    if let Some(trace_id) = &record.trace_id {
+                        log_record.insert("traceId".into(), hex(trace_id).into());
+                    }
+                    if let Some(span_id) = &record.span_id {
+                        log_record.insert("spanId".into(), hex(span_id).into());
+                    }
+                    JsonValue::Object(log_record)
+                })
+                .collect::<Vec<_>>();
+            let scope = match scope {
+                Some(name) => json!({ "name": name }),
+                None => json!({}),
+            };
+            json!({ "scope": scope, "logRecords": log_records })
+        })
+        .collect::<Vec<_>>();
+
+    let request = json!({
+        "resourceLogs": [{
+            "resource": { "attributes": json_key_values(resource) },
+            "scopeLogs": scope_logs,
+        }]
+    });
+    serde_json::to_vec(&request).expect("serializing a JSON value never fails")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protobuf_primitives() {
+        let mut w = ProtoWriter::default();
+        w.varint(300);
+        assert_eq!(w.0, [0xac, 0x02]);
+
+        let mut w = ProtoWriter::default();
+        w.key_values(6, &[("k".into(), AnyValue::Int(-1))]);
+        assert_eq!(
+            w.0,
+            [
+                0x32, 16, // attributes, length
+                0x0a, 1, b'k', // key
+                0x12, 11, // value, length
+                0x18, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01 // int_value
+            ]
+        );
+
+        let mut w = ProtoWriter::default();
+        w.any_value(5, &AnyValue::Bool(false));
+        assert_eq!(w.0, [0x2a, 2, 0x10, 0]);
+    }
+}
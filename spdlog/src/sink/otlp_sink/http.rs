@@ -0,0 +1,192 @@
+//! A minimal HTTP/1.1 client, only capable of what OTLP/HTTP needs.
+
+use std::{
+    io::{self, BufRead as _, BufReader, Read as _, Write as _},
+    net::{TcpStream, ToSocketAddrs as _},
+    time::Instant,
+};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(super) struct Endpoint {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl Endpoint {
+    // Only plain `http://` URLs are supported, since there is no TLS implementation
+    // in the dependencies.
+    pub(super) fn parse(url: &str) -> Result<Self, String> {
+        // They would be written into the request line and the `Host` header as is
+        if url
+            .chars()
+            .any(|c| c.is_ascii_whitespace() || c.is_control())
+        {
+            return Err(format!("'{url}' contains whitespace or control characters"));
+        }
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| format!("'{url}' is not a 'http://' URL"))?;
+        let (authority, path) = match rest.find('/') {
+            Some(pos) => rest.split_at(pos),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            // Do not split IPv6 addresses without a port, e.g. `[::1]`
+            Some((host, port)) if !port.ends_with(']') => (
+                host,
+                port.parse()
+                    .map_err(|_| format!("invalid port in '{url}'"))?,
+            ),
+            _ => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(format!("missing host in '{url}'"));
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+}
+
+// Checks that a header can be written into a request as is, returns an error
+// message if it cannot.
+pub(super) fn validate_header(name: &str, value: &str) -> Result<(), String> {
+    // https://www.rfc-editor.org/rfc/rfc9110#name-tokens
+    let is_tchar = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+    if name.is_empty() || !name.chars().all(is_tchar) {
+        return Err(format!("header name '{name}' is not a valid token"));
+    }
+    if value.chars().any(|c| c.is_control() && c != '\t') {
+        return Err(format!(
+            "value of header '{name}' contains control characters"
+        ));
+    }
+    Ok(())
+}
+
+/// Sends a `POST` request and returns the status code of the response.
+///
+/// Each operation times out at the deadline, so the request takes not much
+/// longer than it.
+pub(super) fn post(
+    endpoint: &Endpoint,
+    headers: &[(String, String)],
+    content_type: &str,
+    body: &[u8],
+    deadline: Instant,
+) -> io::Result<u16> {
+    let timeout = || {
+        deadline
+            .checked_duration_since(Instant::now())
+            .filter(|remaining| !remaining.is_zero())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::TimedOut, "export timed out"))
+    };
+
+    let host = endpoint.host.trim_start_matches('[').trim_end_matches(']');
+    let addrs = (host, endpoint.port).to_socket_addrs()?;
+
+    let mut last_err = None;
+    let mut stream = None;
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, timeout()?) {
+            Ok(s) => {
+                stream = Some(s);
+                break;
+            }
+            Err(err) => last_err = Some(err),
+        }
+    }
+    let mut stream = stream.ok_or_else(|| {
+        last_err.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "endpoint resolved to no address")
+        })
+    })?;
+    stream.set_write_timeout(Some(timeout()?))?;
+
+    let mut request = format!(
+        "POST {} HTTP/1.1\r\n\
+         Host: {}:{}\r\n\
+         Content-Type: {content_type}\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n",
+        endpoint.path,
+        endpoint.host,
+        endpoint.port,
+        body.len()
+    );
+    for (name, value) in headers {
+        request += &format!("{name}: {value}\r\n");
+    }
+    request += "\r\n";
+    stream.write_all(request.as_bytes())?;
+    stream.write_all(body)?;
+    stream.flush()?;
+    stream.set_read_timeout(Some(timeout()?))?;
+
+    let mut reader = BufReader::new(stream);
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line)?;
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid HTTP status line '{}'", status_line.trim_end()),
+            )
+        })?;
+    // Drain the response, so that the server does not see a reset connection
+    let _ = reader.read_to_end(&mut vec![]);
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header() {
+        assert!(validate_header("Authorization", "Bearer abc\tdef").is_ok());
+        assert!(validate_header("X-Api-Key", "").is_ok());
+        assert!(validate_header("", "value").is_err());
+        assert!(validate_header("Bad Name", "value").is_err());
+        assert!(validate_header("Bad:Name", "value").is_err());
+        assert!(validate_header("X-Injected", "a\r\nEvil: 1").is_err());
+        assert!(validate_header("X-Injected", "a\nb").is_err());
+    }
+
+    #[test]
+    fn parse_endpoint() {
+        let endpoint = |host: &str, port, path: &str| Endpoint {
+            host: host.into(),
+            port,
+            path: path.into(),
+        };
+
+        assert_eq!(
+            Endpoint::parse("http://localhost:4318/v1/logs"),
+            Ok(endpoint("localhost", 4318, "/v1/logs"))
+        );
+        assert_eq!(
+            Endpoint::parse("http://example.com"),
+            Ok(endpoint("example.com", 80, "/"))
+        );
+        assert_eq!(
+            Endpoint::parse("http://[::1]:4318/v1/logs"),
+            Ok(endpoint("[::1]", 4318, "/v1/logs"))
+        );
+        assert_eq!(
+            Endpoint::parse("http://[::1]/"),
+            Ok(endpoint("[::1]", 80, "/"))
+        );
+        assert!(Endpoint::parse("https://localhost:4318").is_err());
+        assert!(Endpoint::parse("http://localhost:port").is_err());
+        assert!(Endpoint::parse("http:///v1/logs").is_err());
+        assert!(Endpoint::parse("http://localhost/v1/logs\r\nX-Injected: 1").is_err());
+        assert!(Endpoint::parse("http://local host/").is_err());
+    }
+}
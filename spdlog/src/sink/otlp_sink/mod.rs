@@ -0,0 +1,964 @@
+//! Provides a sink exporting logs to an OpenTelemetry collector.
+
+mod encode;
+mod http;
+
+use std::{
+    convert::Infallible,
+    io, mem, thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use self::http::Endpoint;
+use crate::{
+    error::InvalidArgumentError,
+    formatter::{pattern, Formatter, FormatterContext, PatternFormatter},
+    kv,
+    sink::{GetSinkProp, Sink, SinkProp},
+    sync::*,
+    Error, ErrorHandler, Level, LevelFilter, Record, Result, StringBuf,
+};
+
+/// The protocol [`OtlpSink`] uses to export logs.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum OtlpProtocol {
+    /// OTLP/HTTP with binary protobuf encoded payloads.
+    #[default]
+    HttpProtobuf,
+    /// OTLP/HTTP with JSON encoded payloads.
+    HttpJson,
+}
+
+impl OtlpProtocol {
+    #[must_use]
+    fn content_type(&self) -> &'static str {
+        match self {
+            Self::HttpProtobuf => "application/x-protobuf",
+            Self::HttpJson => "application/json",
+        }
+    }
+}
+
+/// A sink exporting logs to an [OpenTelemetry] collector over OTLP/HTTP.
+///
+/// Each record is mapped to an OpenTelemetry `LogRecord`:
+///
+/// - The formatted record becomes the body, by default only the payload is
+///   formatted.
+/// - The level is mapped to the severity number, see the table below, and the
+///   level name becomes the severity text.
+/// - The key-values of the record become attributes. If a key appears more
+///   than once, the one [`KeyValues::get`] would return is used.
+/// - Key-values `trace_id` and `span_id` set the trace context of the log, if
+///   they are hex strings of 32 and 16 digits respectively. They are usually
+///   pushed into the [`context`] by the tracing integration of the
+///   application.
+/// - The source location and the thread become the `code.*` and `thread.*`
+///   attributes of the [semantic conventions].
+/// - The logger name becomes the name of the instrumentation scope.
+///
+/// Records are buffered and exported in batches, a batch is exported when it
+/// reaches the [batch size], when the sink is flushed, and when the sink is
+/// dropped. A failed export is retried on connection errors and on HTTP
+/// statuses that the OTLP specification considers retryable (429, 502, 503
+/// and 504), with an exponential backoff, until the export [timeout] elapses,
+/// or the [drop timeout] for the export on drop. Batches that fail eventually
+/// are discarded and the error is returned.
+///
+/// Only plain `http://` endpoints are supported. To export to a collector over
+/// TLS, run a local collector or proxy that forwards the logs.
+///
+/// This sink is only available if crate feature `otlp` is enabled.
+///
+/// # Blocking
+///
+/// This sink performs network I/O synchronously on the calling thread:
+///
+/// - Logging blocks whenever the record completes a batch, until the batch is
+///   exported or the [timeout] elapses.
+/// - Flushing blocks until the buffered records are exported or the [timeout]
+///   elapses.
+/// - Dropping blocks until the buffered records are exported or the
+///   [drop timeout] elapses, which is shorter so that an unreachable collector
+///   does not delay the exit of the application for long.
+///
+/// An unreachable collector would stall the application on every batch, so
+/// this sink should be wrapped in an [`AsyncPoolSink`] to export on a
+/// background thread:
+///
+/// ```no_run
+/// # #[cfg(feature = "multi-thread")]
+/// # fn main() -> Result<(), spdlog::Error> {
+/// use spdlog::{
+///     prelude::*,
+///     sink::{AsyncPoolSink, OtlpSink},
+/// };
+///
+/// let sink = OtlpSink::builder()
+///     .endpoint("http://localhost:4318/v1/logs")
+///     .build_arc()?;
+/// let async_sink = AsyncPoolSink::builder().sink(sink).build_arc()?;
+/// let logger = Logger::builder().sink(async_sink).build()?;
+/// info!(logger: logger, "exported on a background thread");
+/// # Ok(()) }
+/// # #[cfg(not(feature = "multi-thread"))]
+/// # fn main() {}
+/// ```
+///
+/// # Severity Mapping
+///
+/// | spdlog-rs  | OpenTelemetry     |
+/// |------------|-------------------|
+/// | `Critical` | `FATAL` (21)      |
+/// | `Error`    | `ERROR` (17)      |
+/// | `Warn`     | `WARN` (13)       |
+/// | `Info`     | `INFO` (9)        |
+/// | `Debug`    | `DEBUG` (5)       |
+/// | `Trace`    | `TRACE` (1)       |
+///
+/// # Examples
+///
+/// ```no_run
+/// use spdlog::{prelude::*, sink::OtlpSink};
+///
+/// # fn main() -> Result<(), spdlog::Error> {
+/// let sink = OtlpSink::builder()
+///     .endpoint("http://localhost:4318/v1/logs")
+///     .resource_attribute("service.name", "checkout")
+///     .build_arc()?;
+/// let logger = spdlog::default_logger().fork_with(|logger| {
+///     logger.sinks_mut().push(sink);
+///     Ok(())
+/// })?;
+/// info!(logger: logger, "order placed", kv: { order_id = 42 });
+/// # Ok(()) }
+/// ```
+///
+/// [OpenTelemetry]: https://opentelemetry.io/docs/specs/otel/logs/data-model/
+/// [`KeyValues::get`]: crate::kv::KeyValues::get
+/// [`context`]: crate::context
+/// [semantic conventions]: https://opentelemetry.io/docs/specs/semconv/general/attributes/
+/// [batch size]: OtlpSinkBuilder::batch_size
+/// [timeout]: OtlpSinkBuilder::timeout
+/// [drop timeout]: OtlpSinkBuilder::drop_timeout
+/// [`AsyncPoolSink`]: crate::sink::AsyncPoolSink
+pub struct OtlpSink {
+    prop: SinkProp,
+    endpoint: Endpoint,
+    protocol: OtlpProtocol,
+    headers: Vec<(String, String)>,
+    resource: Vec<(String, AnyValue)>,
+    batch_size: usize,
+    max_retries: u32,
+    retry_backoff: Duration,
+    timeout: Duration,
+    drop_timeout: Duration,
+    buffer: Mutex<Vec<ExportRecord>>,
+}
+
+impl OtlpSink {
+    /// Gets a builder of `OtlpSink` with default parameters:
+    ///
+    /// | Parameter            | Default Value                         |
+    /// |----------------------|---------------------------------------|
+    /// | [level_filter]       | [`LevelFilter::All`]                  |
+    /// | [formatter]          | [`PatternFormatter`] `("{payload}")`  |
+    /// | [error_handler]      | [`ErrorHandler::default()`]           |
+    /// |                      |                                       |
+    /// | [endpoint]           | *must be specified*                   |
+    /// | [protocol]           | [`OtlpProtocol::HttpProtobuf`]        |
+    /// | [header]             | *none*                                |
+    /// | [resource_attribute] | `service.name` = `"unknown_service"`  |
+    /// | [batch_size]         | `512`                                 |
+    /// | [max_retries]        | `3`                                   |
+    /// | [retry_backoff]      | `100ms`                               |
+    /// | [timeout]            | `10s`                                 |
+    /// | [drop_timeout]       | `1s`                                  |
+    ///
+    /// [level_filter]: OtlpSinkBuilder::level_filter
+    /// [formatter]: OtlpSinkBuilder::formatter
+    /// [`PatternFormatter`]: crate::formatter::PatternFormatter
+    /// [error_handler]: OtlpSinkBuilder::error_handler
+    /// [endpoint]: OtlpSinkBuilder::endpoint
+    /// [protocol]: OtlpSinkBuilder::protocol
+    /// [header]: OtlpSinkBuilder::header
+    /// [resource_attribute]: OtlpSinkBuilder::resource_attribute
+    /// [batch_size]: OtlpSinkBuilder::batch_size
+    /// [max_retries]: OtlpSinkBuilder::max_retries
+    /// [retry_backoff]: OtlpSinkBuilder::retry_backoff
+    /// [timeout]: OtlpSinkBuilder::timeout
+    /// [drop_timeout]: OtlpSinkBuilder::drop_timeout
+    #[must_use]
+    pub fn builder() -> OtlpSinkBuilder<()> {
+        let prop = SinkProp::default();
+        prop.set_formatter(PatternFormatter::new(pattern!("{payload}")));
+
+        OtlpSinkBuilder {
+            prop,
+            endpoint: (),
+            protocol: OtlpProtocol::default(),
+            headers: vec![],
+            resource: vec![(
+                "service.name".to_string(),
+                AnyValue::String("unknown_service".to_string()),
+            )],
+            batch_size: 512,
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(100),
+            timeout: Duration::from_secs(10),
+            drop_timeout: Duration::from_secs(1),
+        }
+    }
+
+    fn export(&self, records: &[ExportRecord], timeout: Duration) -> io::Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let body = match self.protocol {
+            OtlpProtocol::HttpProtobuf => encode::to_protobuf(&self.resource, records),
+            OtlpProtocol::HttpJson => encode::to_json(&self.resource, records),
+        };
+
+        let deadline = Instant::now() + timeout;
+        let mut backoff = self.retry_backoff;
+        let mut retries = 0;
+        loop {
+            let result = http::post(
+                &self.endpoint,
+                &self.headers,
+                self.protocol.content_type(),
+                &body,
+                deadline,
+            );
+            let err = match result {
+                Ok(200..=299) => return Ok(()),
+                Ok(status @ (429 | 502 | 503 | 504)) => io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("OTLP endpoint responded with retryable HTTP status {status}"),
+                ),
+                Ok(status) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("OTLP endpoint responded with HTTP status {status}"),
+                    ))
+                }
+                Err(err) => err,
+            };
+            if retries >= self.max_retries || Instant::now() + backoff >= deadline {
+                return Err(err);
+            }
+            retries += 1;
+            thread::sleep(backoff);
+            backoff = backoff.saturating_mul(2);
+        }
+    }
+
+    #[must_use]
+    fn take_buffer(&self) -> Vec<ExportRecord> {
+        mem::take(&mut *self.buffer.lock_expect())
+    }
+}
+
+impl GetSinkProp for OtlpSink {
+    fn prop(&self) -> &SinkProp {
+        &self.prop
+    }
+}
+
+impl Sink for OtlpSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        let mut string_buf = StringBuf::new();
+        let mut ctx = FormatterContext::new();
+        self.prop
+            .formatter()
+            .format(record, &mut string_buf, &mut ctx)?;
+
+        let export_record = ExportRecord::new(record, string_buf.to_string());
+        let batch = {
+            let mut buffer = self.buffer.lock_expect();
+            buffer.push(export_record);
+            if buffer.len() < self.batch_size {
+                return Ok(());
+            }
+            mem::take(&mut *buffer)
+        };
+        self.export(&batch, self.timeout)
+            .map_err(Error::WriteRecord)
+    }
+
+    // The whole batch is buffered at once, and exported in a single request if
+    // the batch size is reached.
+    fn log_batch(&self, records: &[Record]) -> Result<()> {
+        let mut string_buf = StringBuf::new();
+        let (formatted, result) = self.prop.format_batch(records, &mut string_buf);
+        if formatted.is_empty() {
+            return result;
+        }
+        let mut start = 0;
+        let export_records = formatted.into_iter().map(|(record, end)| {
+            let payload = string_buf[start..end].to_string();
+            start = end;
+            ExportRecord::new(record, payload)
+        });
+
+        let batch = {
+            let mut buffer = self.buffer.lock_expect();
+            buffer.extend(export_records);
+            if buffer.len() < self.batch_size {
+                return result;
+            }
+            mem::take(&mut *buffer)
+        };
+        Error::push_result(
+            result,
+            self.export(&batch, self.timeout)
+                .map_err(Error::WriteRecord),
+        )
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.export(&self.take_buffer(), self.timeout)
+            .map_err(Error::FlushBuffer)
+    }
+}
+
+impl Drop for OtlpSink {
+    fn drop(&mut self) {
+        if let Err(err) = self.export(&self.take_buffer(), self.drop_timeout) {
+            self.prop
+                .call_error_handler_internal("OtlpSink", Error::FlushBuffer(err))
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum AnyValue {
+    String(String),
+    Bool(bool),
+    Int(i64),
+    Double(f64),
+}
+
+impl AnyValue {
+    #[must_use]
+    fn from_kv(value: &kv::Value) -> Self {
+        if let Some(value) = value.to_borrowed_str() {
+            Self::String(value.to_string())
+        } else if let Some(value) = value.to_bool() {
+            Self::Bool(value)
+        } else if let Some(value) = value.to_i64() {
+            Self::Int(value)
+        } else if let Some(value) = value.to_f64() {
+            Self::Double(value)
+        } else {
+            Self::String(value.to_string())
+        }
+    }
+}
+
+// A record converted to the OpenTelemetry log data model, buffered until being
+// exported.
+struct ExportRecord {
+    time_unix_nano: u64,
+    observed_time_unix_nano: u64,
+    severity_number: u64,
+    severity_text: &'static str,
+    body: String,
+    attributes: Vec<(String, AnyValue)>,
+    trace_id: Option<[u8; 16]>,
+    span_id: Option<[u8; 8]>,
+    scope: Option<String>,
+}
+
+impl ExportRecord {
+    #[must_use]
+    fn new(record: &Record, body: String) -> Self {
+        let mut trace_id = None;
+        let mut span_id = None;
+        let mut attributes: Vec<(String, AnyValue)> = vec![];
+        for (key, value) in record.key_values() {
+            let key = key.as_str();
+            if attributes.iter().any(|(k, _)| k == key) {
+                continue;
+            }
+            match key {
+                "trace_id" => {
+                    if trace_id.is_some() {
+                        continue;
+                    }
+                    trace_id = parse_hex_id(&value.to_string());
+                    if trace_id.is_some() {
+                        continue;
+                    }
+                }
+                "span_id" => {
+                    if span_id.is_some() {
+                        continue;
+                    }
+                    span_id = parse_hex_id(&value.to_string());
+                    if span_id.is_some() {
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            attributes.push((key.to_string(), AnyValue::from_kv(&value)));
+        }
+
+        if let Some(srcloc) = record.source_location() {
+            attributes.push((
+                "code.file.path".to_string(),
+                AnyValue::String(srcloc.file().to_string()),
+            ));
+            attributes.push((
+                "code.line.number".to_string(),
+                AnyValue::Int(i64::from(srcloc.line())),
+            ));
+        }
+        attributes.push(("thread.id".to_string(), AnyValue::Int(record.tid() as i64)));
+        if let Some(thread_name) = record.thread_name() {
+            attributes.push((
+                "thread.name".to_string(),
+                AnyValue::String(thread_name.to_string()),
+            ));
+        }
+
+        Self {
+            time_unix_nano: unix_nano(record.time()),
+            observed_time_unix_nano: unix_nano(SystemTime::now()),
+            severity_number: severity_number(record.level()),
+            severity_text: record.level().as_str(),
+            body,
+            attributes,
+            trace_id,
+            span_id,
+            scope: record.logger_name().map(str::to_string),
+        }
+    }
+}
+
+#[must_use]
+fn severity_number(level: Level) -> u64 {
+    match level {
+        Level::Critical => 21,
+        Level::Error => 17,
+        Level::Warn => 13,
+        Level::Info => 9,
+        Level::Debug => 5,
+        Level::Trace => 1,
+    }
+}
+
+#[must_use]
+fn unix_nano(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos() as u64)
+}
+
+// Parses an ID from a hex string, an all-zero ID is invalid in OpenTelemetry.
+#[must_use]
+fn parse_hex_id<const N: usize>(hex: &str) -> Option<[u8; N]> {
+    if hex.len() != N * 2 || !hex.is_ascii() {
+        return None;
+    }
+    let mut id = [0; N];
+    for (byte, digits) in id.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        let digits = std::str::from_utf8(digits).ok()?;
+        *byte = u8::from_str_radix(digits, 16).ok()?;
+    }
+    id.iter().any(|byte| *byte != 0).then_some(id)
+}
+
+// --------------------------------------------------
+
+/// #
+#[doc = include_str!("../../include/doc/generic-builder-note.md")]
+pub struct OtlpSinkBuilder<ArgEndpoint> {
+    prop: SinkProp,
+    endpoint: ArgEndpoint,
+    protocol: OtlpProtocol,
+    headers: Vec<(String, String)>,
+    resource: Vec<(String, AnyValue)>,
+    batch_size: usize,
+    max_retries: u32,
+    retry_backoff: Duration,
+    timeout: Duration,
+    drop_timeout: Duration,
+}
+
+impl<ArgEndpoint> OtlpSinkBuilder<ArgEndpoint> {
+    /// The URL that logs are exported to, e.g.
+    /// `http://localhost:4318/v1/logs`.
+    ///
+    /// The URL is used as is, the `/v1/logs` path is not appended
+    /// automatically. Only `http://` URLs are supported.
+    ///
+    /// This parameter is **required**.
+    #[must_use]
+    pub fn endpoint<S>(self, endpoint: S) -> OtlpSinkBuilder<String>
+    where
+        S: Into<String>,
+    {
+        OtlpSinkBuilder {
+            prop: self.prop,
+            endpoint: endpoint.into(),
+            protocol: self.protocol,
+            headers: self.headers,
+            resource: self.resource,
+            batch_size: self.batch_size,
+            max_retries: self.max_retries,
+            retry_backoff: self.retry_backoff,
+            timeout: self.timeout,
+            drop_timeout: self.drop_timeout,
+        }
+    }
+
+    /// Specifies the protocol that logs are exported with.
+    ///
+    /// This parameter is **optional**, and defaults to
+    /// [`OtlpProtocol::HttpProtobuf`].
+    #[must_use]
+    pub fn protocol(mut self, protocol: OtlpProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Adds a HTTP header sent with every export request, e.g. for
+    /// authentication.
+    ///
+    /// The name must be a valid HTTP token, and the value must not contain
+    /// control characters other than horizontal tabs, such as `\r` and `\n`.
+    /// Otherwise, [`Error::InvalidArgument`] will be returned when building.
+    ///
+    /// This parameter is **optional**, and can be specified multiple times.
+    #[must_use]
+    pub fn header<N, V>(mut self, name: N, value: V) -> Self
+    where
+        N: Into<String>,
+        V: Into<String>,
+    {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds an attribute of the resource that produces the logs, e.g.
+    /// `service.name`.
+    ///
+    /// If the key has been added before, the value replaces the old one.
+    ///
+    /// This parameter is **optional**, and can be specified multiple times.
+    /// Attribute `service.name` defaults to `"unknown_service"`.
+    #[must_use]
+    pub fn resource_attribute<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let (key, value) = (key.into(), AnyValue::String(value.into()));
+        match self.resource.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => self.resource.push((key, value)),
+        }
+        self
+    }
+
+    /// Specifies the number of buffered records that triggers an export.
+    ///
+    /// If it is `0` or `1`, every record is exported immediately.
+    ///
+    /// This parameter is **optional**, and defaults to `512`.
+    #[must_use]
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Specifies the maximum number of retries of a failed export.
+    ///
+    /// This parameter is **optional**, and defaults to `3`.
+    #[must_use]
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Specifies the delay before the first retry, the delay doubles for each
+    /// subsequent retry.
+    ///
+    /// This parameter is **optional**, and defaults to `100ms`.
+    #[must_use]
+    pub fn retry_backoff(mut self, retry_backoff: Duration) -> Self {
+        self.retry_backoff = retry_backoff;
+        self
+    }
+
+    /// Specifies the maximum time of an export, including all attempts and the
+    /// delays between retries.
+    ///
+    /// No more retries are attempted if the next one would start after the
+    /// timeout elapses.
+    ///
+    /// This parameter is **optional**, and defaults to `10s`.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Specifies the maximum time of the export of the remaining buffered
+    /// records when the sink is dropped, instead of [timeout].
+    ///
+    /// Dropping the sink blocks the dropping thread, typically while the
+    /// application is exiting, so it defaults to a shorter time.
+    ///
+    /// This parameter is **optional**, and defaults to `1s`.
+    ///
+    /// [timeout]: OtlpSinkBuilder::timeout
+    #[must_use]
+    pub fn drop_timeout(mut self, drop_timeout: Duration) -> Self {
+        self.drop_timeout = drop_timeout;
+        self
+    }
+
+    // Prop
+    //
+
+    /// Specifies a log level filter.
+    ///
+    /// This parameter is **optional**, and defaults to [`LevelFilter::All`].
+    #[must_use]
+    pub fn level_filter(self, level_filter: LevelFilter) -> Self {
+        self.prop.set_level_filter(level_filter);
+        self
+    }
+
+    /// Specifies a formatter.
+    ///
+    /// This parameter is **optional**, and defaults to [`PatternFormatter`]
+    /// `("{payload}")`.
+    ///
+    /// [`PatternFormatter`]: crate::formatter::PatternFormatter
+    #[must_use]
+    pub fn formatter<F>(self, formatter: F) -> Self
+    where
+        F: Formatter + 'static,
+    {
+        self.prop.set_formatter(formatter);
+        self
+    }
+
+    /// Specifies an error handler.
+    ///
+    /// This parameter is **optional**, and defaults to
+    /// [`ErrorHandler::default()`].
+    #[must_use]
+    pub fn error_handler<F: Into<ErrorHandler>>(self, handler: F) -> Self {
+        self.prop.set_error_handler(handler);
+        self
+    }
+}
+
+impl OtlpSinkBuilder<()> {
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required parameter `endpoint`\n\n\
+    ")]
+    pub fn build(self, _: Infallible) {}
+
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required parameter `endpoint`\n\n\
+    ")]
+    pub fn build_arc(self, _: Infallible) {}
+}
+
+impl OtlpSinkBuilder<String> {
+    /// Builds a [`OtlpSink`].
+    ///
+    /// # Error
+    ///
+    /// If the endpoint is not a valid `http://` URL, or a header is invalid,
+    /// [`Error::InvalidArgument`] will be returned.
+    pub fn build(self) -> Result<OtlpSink> {
+        let endpoint = Endpoint::parse(&self.endpoint).map_err(InvalidArgumentError::Endpoint)?;
+        for (name, value) in &self.headers {
+            http::validate_header(name, value).map_err(InvalidArgumentError::Header)?;
+        }
+
+        let sink = OtlpSink {
+            prop: self.prop,
+            endpoint,
+            protocol: self.protocol,
+            headers: self.headers,
+            resource: self.resource,
+            batch_size: self.batch_size,
+            max_retries: self.max_retries,
+            retry_backoff: self.retry_backoff,
+            timeout: self.timeout,
+            drop_timeout: self.drop_timeout,
+            buffer: Mutex::new(vec![]),
+        };
+        Ok(sink)
+    }
+
+    /// Builds a `Arc<OtlpSink>`.
+    ///
+    /// This is a shorthand method for `.build().map(Arc::new)`.
+    pub fn build_arc(self) -> Result<Arc<OtlpSink>> {
+        self.build().map(Arc::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead as _, BufReader, Read as _, Write as _},
+        net::TcpListener,
+        sync::mpsc,
+    };
+
+    use super::*;
+    use crate::{context, info, kv, test_utils::*, warn};
+
+    struct Request {
+        content_type: String,
+        body: Vec<u8>,
+    }
+
+    // A stand-in of an OTLP collector, responding to requests with the given
+    // statuses in order.
+    fn serve(statuses: Vec<u16>) -> (String, mpsc::Receiver<Request>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/v1/logs", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            for status in statuses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let (mut content_type, mut content_length) = (String::new(), 0);
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(": ") {
+                        match name {
+                            "Content-Type" => content_type = value.to_string(),
+                            "Content-Length" => content_length = value.parse().unwrap(),
+                            _ => {}
+                        }
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                reader
+                    .get_mut()
+                    .write_all(
+                        format!("HTTP/1.1 {status} X\r\nContent-Length: 0\r\n\r\n").as_bytes(),
+                    )
+                    .unwrap();
+                tx.send(Request { content_type, body }).unwrap();
+            }
+        });
+        (endpoint, rx)
+    }
+
+    #[test]
+    fn export_json_with_retries() {
+        let (endpoint, requests) = serve(vec![503, 200]);
+        let sink = OtlpSink::builder()
+            .endpoint(endpoint)
+            .protocol(OtlpProtocol::HttpJson)
+            .resource_attribute("service.name", "checkout")
+            .batch_size(2)
+            .retry_backoff(Duration::from_millis(1))
+            .build_arc()
+            .unwrap();
+        let logger = build_test_logger(|b| {
+            b.sink(sink.clone())
+                .name("orders")
+                .level_filter(LevelFilter::All)
+        });
+
+        let _guard = context::push(kv! {
+            trace_id = "4bf92f3577b34da6a3ce929d0e0736a4",
+            span_id = "00f067aa0ba902b7",
+        });
+        info!(logger: logger, "order placed", kv: { order_id = 42, express = true });
+        warn!(logger: logger, "stock low", kv: { ratio = 0.5, sku = "A-1", span_id = "x" });
+
+        let failed = requests.recv().unwrap();
+        let request = requests.recv().unwrap();
+        assert_eq!(failed.body, request.body);
+        assert_eq!(request.content_type, "application/json");
+
+        let json: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        let resource_logs = &json["resourceLogs"][0];
+        assert_eq!(
+            resource_logs["resource"]["attributes"],
+            serde_json::json!([{ "key": "service.name", "value": { "stringValue": "checkout" } }])
+        );
+        let scope_logs = &resource_logs["scopeLogs"][0];
+        assert_eq!(scope_logs["scope"]["name"], "orders");
+
+        let records = scope_logs["logRecords"].as_array().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["severityNumber"], 9);
+        assert_eq!(records[0]["severityText"], "info");
+        assert_eq!(records[0]["body"]["stringValue"], "order placed");
+        assert_eq!(records[0]["traceId"], "4bf92f3577b34da6a3ce929d0e0736a4");
+        assert_eq!(records[0]["spanId"], "00f067aa0ba902b7");
+        let attributes = &records[0]["attributes"];
+        assert_eq!(attributes[0]["key"], "order_id");
+        assert_eq!(attributes[0]["value"]["intValue"], "42");
+        assert_eq!(attributes[1]["key"], "express");
+        assert_eq!(attributes[1]["value"]["boolValue"], true);
+
+        assert_eq!(records[1]["severityNumber"], 13);
+        // The invalid `span_id` from the callsite is kept as an attribute, and the
+        // one from the context is ignored
+        assert!(records[1].get("spanId").is_none());
+        let attributes = &records[1]["attributes"];
+        assert_eq!(attributes[0]["value"]["doubleValue"], 0.5);
+        assert_eq!(attributes[1]["value"]["stringValue"], "A-1");
+        assert_eq!(attributes[2]["key"], "span_id");
+        assert_eq!(attributes[2]["value"]["stringValue"], "x");
+    }
+
+    #[test]
+    fn export_protobuf_on_flush() {
+        let (endpoint, requests) = serve(vec![200]);
+        let sink = OtlpSink::builder()
+            .endpoint(endpoint)
+            .header("Authorization", "Bearer token")
+            .build_arc()
+            .unwrap();
+        let logger = build_test_logger(|b| b.sink(sink.clone()));
+
+        info!(logger: logger, "hello");
+        assert!(requests.try_recv().is_err());
+        logger.flush();
+
+        let request = requests.recv().unwrap();
+        assert_eq!(request.content_type, "application/x-protobuf");
+        let contains = |needle: &[u8]| request.body.windows(needle.len()).any(|w| w == needle);
+        assert!(contains(b"service.name"));
+        assert!(contains(b"unknown_service"));
+        // `LogRecord.body`, a `AnyValue` with a `string_value`
+        assert!(contains(&[0x2a, 7, 0x0a, 5, b'h', b'e', b'l', b'l', b'o']));
+    }
+
+    #[test]
+    fn export_batch_at_once() {
+        let (endpoint, requests) = serve(vec![200]);
+        let sink = OtlpSink::builder()
+            .endpoint(endpoint)
+            .protocol(OtlpProtocol::HttpJson)
+            .batch_size(2)
+            .build()
+            .unwrap();
+
+        let records =
+            ["a", "b", "c"].map(|payload| Record::new(Level::Info, payload, None, None, &[]));
+        sink.log_batch(&records).unwrap();
+
+        let request = requests.recv().unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        let records = json["resourceLogs"][0]["scopeLogs"][0]["logRecords"]
+            .as_array()
+            .unwrap();
+        let bodies = records
+            .iter()
+            .map(|record| record["body"]["stringValue"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(bodies, ["a", "b", "c"]);
+        assert!(sink.take_buffer().is_empty());
+    }
+
+    #[test]
+    fn non_retryable_status() {
+        let (endpoint, requests) = serve(vec![400]);
+        let sink = OtlpSink::builder()
+            .endpoint(endpoint)
+            .batch_size(1)
+            .build()
+            .unwrap();
+
+        let record = Record::new(Level::Info, "hello", None, None, &[]);
+        assert!(matches!(sink.log(&record), Err(Error::WriteRecord(_))));
+        assert!(requests.recv().is_ok());
+    }
+
+    #[test]
+    fn invalid_endpoint() {
+        assert!(matches!(
+            OtlpSink::builder()
+                .endpoint("https://localhost:4318")
+                .build(),
+            Err(Error::InvalidArgument(InvalidArgumentError::Endpoint(_)))
+        ));
+    }
+
+    #[test]
+    fn invalid_header() {
+        let build = |name: &str, value: &str| {
+            OtlpSink::builder()
+                .endpoint("http://localhost:4318/v1/logs")
+                .header(name, value)
+                .build()
+        };
+        assert!(build("Authorization", "Bearer token").is_ok());
+        assert!(matches!(
+            build("Authorization", "Bearer token\r\nX-Injected: 1"),
+            Err(Error::InvalidArgument(InvalidArgumentError::Header(_)))
+        ));
+        assert!(matches!(
+            build("X-Injected: 1\r\nAuthorization", "Bearer token"),
+            Err(Error::InvalidArgument(InvalidArgumentError::Header(_)))
+        ));
+    }
+
+    #[test]
+    fn export_timeout() {
+        let (endpoint, _requests) = serve(vec![503; 100]);
+        let sink = OtlpSink::builder()
+            .endpoint(endpoint)
+            .batch_size(1)
+            .max_retries(100)
+            .retry_backoff(Duration::from_millis(50))
+            .timeout(Duration::from_millis(300))
+            .build()
+            .unwrap();
+
+        let record = Record::new(Level::Info, "hello", None, None, &[]);
+        let start = Instant::now();
+        assert!(matches!(sink.log(&record), Err(Error::WriteRecord(_))));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn drop_timeout() {
+        let (endpoint, _requests) = serve(vec![503; 100]);
+        let sink = OtlpSink::builder()
+            .endpoint(endpoint)
+            .max_retries(100)
+            .retry_backoff(Duration::from_millis(50))
+            .drop_timeout(Duration::from_millis(300))
+            .error_handler(|_| {})
+            .build()
+            .unwrap();
+
+        let record = Record::new(Level::Info, "hello", None, None, &[]);
+        sink.log(&record).unwrap();
+        // The export on drop gives up long before the default timeout of 10s
+        let start = Instant::now();
+        drop(sink);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
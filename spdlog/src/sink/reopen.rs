@@ -0,0 +1,302 @@
+//! Provides reopening files of file sinks, for compatibility with external log
+//! rotation tools such as `logrotate`.
+
+use std::{
+    fs::{self, File, Metadata},
+    path::Path,
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+#[cfg(unix)]
+use std::{io, mem, ptr};
+
+#[cfg(unix)]
+use crate::{Error, Result};
+
+static REOPEN_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+/// Requests all [`FileSink`]s and [`RotatingFileSink`]s to reopen their files.
+///
+/// Each sink reopens its file when the next record is logged to it, so that
+/// after the file has been renamed or removed by an external log rotation tool,
+/// subsequent records are written to a new file at the configured path.
+///
+/// This function only increments an atomic counter, so it is cheap and can be
+/// called from anywhere. To reopen a specific sink immediately, use
+/// [`FileSink::reopen`] or [`RotatingFileSink::reopen`].
+///
+/// [`FileSink`]: crate::sink::FileSink
+/// [`FileSink::reopen`]: crate::sink::FileSink::reopen
+/// [`RotatingFileSink`]: crate::sink::RotatingFileSink
+/// [`RotatingFileSink::reopen`]: crate::sink::RotatingFileSink::reopen
+pub fn reopen_all_files() {
+    REOPEN_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Signals that can be handled by [`reopen_files_on_signal`].
+#[cfg(unix)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[non_exhaustive]
+pub enum ReopenSignal {
+    /// `SIGHUP`, conventionally sent by `logrotate` in `postrotate` scripts.
+    Hangup,
+    /// `SIGUSR1`.
+    User1,
+    /// `SIGUSR2`.
+    User2,
+}
+
+/// Installs a handler for the signal, which calls [`reopen_all_files`] when the
+/// signal is received.
+///
+/// The handler only increments an atomic counter, the files are reopened when
+/// the next record is logged to each sink.
+///
+/// Note that the handler replaces any previously installed handler for the
+/// signal, including the default action of terminating the process.
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::sink::{self, ReopenSignal};
+///
+/// # fn main() -> Result<(), spdlog::Error> {
+/// // With a `logrotate` config containing
+/// //
+/// //     postrotate
+/// //         kill -HUP $(cat /run/my-service.pid)
+/// //     endscript
+/// sink::reopen_files_on_signal(ReopenSignal::Hangup)?;
+/// # Ok(()) }
+/// ```
+///
+/// # Error
+///
+/// If an error occurs installing the handler, [`Error::SetSignalHandler`] will
+/// be returned.
+#[cfg(unix)]
+pub fn reopen_files_on_signal(signal: ReopenSignal) -> Result<()> {
+    extern "C" fn handler(_: libc::c_int) {
+        reopen_all_files();
+    }
+
+    let signum = match signal {
+        ReopenSignal::Hangup => libc::SIGHUP,
+        ReopenSignal::User1 => libc::SIGUSR1,
+        ReopenSignal::User2 => libc::SIGUSR2,
+    };
+
+    // SAFETY: The handler only performs an atomic operation, which is
+    // async-signal-safe.
+    let ret = unsafe {
+        let mut action: libc::sigaction = mem::zeroed();
+        action.sa_sigaction = handler as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(signum, &action, ptr::null_mut())
+    };
+    if ret != 0 {
+        return Err(Error::SetSignalHandler(io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+// Decides whether a file sink should reopen its file, either because
+// `reopen_all_files` has been called since the file was opened, because the
+// file at the path has been replaced, removed or truncated, or because the
+// process has been forked since a file shared with other processes was opened.
+//
+// A forked process inherits the open file description, and `flock` locks
+// belong to it, so the parent and the child would never exclude each other.
+// Reopening gives the child its own description.
+pub(crate) struct FileReopener {
+    generation: usize,
+    multi_process: bool,
+    opened_by: u32,
+    check_interval: Option<Duration>,
+    next_check: Instant,
+    identity: Option<(u64, u64)>,
+    len: u64,
+}
+
+impl FileReopener {
+    #[must_use]
+    pub(crate) fn new(check_interval: Option<Duration>, multi_process: bool, file: &File) -> Self {
+        let mut reopener = Self {
+            generation: 0,
+            multi_process,
+            opened_by: 0,
+            check_interval,
+            next_check: Instant::now(),
+            identity: None,
+            len: 0,
+        };
+        reopener.reset(file);
+        reopener
+    }
+
+    // Should be called every time a file is (re)opened.
+    pub(crate) fn reset(&mut self, file: &File) {
+        self.generation = REOPEN_GENERATION.load(Ordering::Relaxed);
+        if self.multi_process {
+            self.opened_by = process::id();
+        }
+        if let Some(check_interval) = self.check_interval {
+            let metadata = file.metadata().ok();
+            self.next_check = Instant::now() + check_interval;
+            self.identity = metadata.as_ref().and_then(identity);
+            self.len = metadata.map_or(0, |metadata| metadata.len());
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn should_reopen(&mut self, path: &Path) -> bool {
+        if self.generation != REOPEN_GENERATION.load(Ordering::Relaxed) {
+            return true;
+        }
+        if self.multi_process && self.opened_by != process::id() {
+            return true;
+        }
+        let Some(check_interval) = self.check_interval else {
+            return false;
+        };
+
+        let now = Instant::now();
+        if now < self.next_check {
+            return false;
+        }
+        self.next_check = now + check_interval;
+
+        match fs::metadata(path) {
+            Ok(metadata) => {
+                let len = metadata.len();
+                if identity(&metadata) != self.identity || len < self.len {
+                    true
+                } else {
+                    self.len = len;
+                    false
+                }
+            }
+            // The file has been removed or renamed
+            Err(_) => true,
+        }
+    }
+}
+
+#[cfg(unix)]
+#[must_use]
+pub(crate) fn identity(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt as _;
+
+    Some((metadata.dev(), metadata.ino()))
+}
+
+// The file index on Windows is unstable in `std`, replacement is detected by the
+// removal or shrink of the file only.
+#[cfg(not(unix))]
+#[must_use]
+pub(crate) fn identity(_: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{
+        prelude::*,
+        sink::{FileSink, RotatingFileSink, RotationPolicy, Sink as _},
+        sync::*,
+        test_utils::*,
+    };
+
+    static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+        let path = TEST_LOGS_PATH.join("reopen");
+        _ = fs::remove_dir_all(&path);
+        fs::create_dir(&path).unwrap();
+        path
+    });
+
+    // Renaming an opened file is not allowed on Windows
+    #[cfg(unix)]
+    #[test]
+    fn reopen_file_sink() {
+        let path = LOGS_PATH.join("file_sink.log");
+        let rotated = LOGS_PATH.join("file_sink.log.1");
+        let sink = FileSink::builder()
+            .path(&path)
+            .formatter(NoModFormatter::new())
+            .build_arc()
+            .unwrap();
+        let logger = build_test_logger(|b| b.sink(sink.clone()));
+
+        info!(logger: logger, "1");
+        sink.flush().unwrap();
+        fs::rename(&path, &rotated).unwrap();
+        sink.reopen().unwrap();
+        info!(logger: logger, "2");
+        sink.flush().unwrap();
+
+        assert_eq!(fs::read_to_string(&rotated).unwrap(), "1");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "2");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reopen_on_change() {
+        let path = LOGS_PATH.join("on_change.log");
+        let rotated = LOGS_PATH.join("on_change.log.1");
+        let sink = FileSink::builder()
+            .path(&path)
+            .formatter(NoModFormatter::new())
+            .reopen_on_change(Duration::ZERO)
+            .build_arc()
+            .unwrap();
+        let logger = build_test_logger(|b| b.sink(sink.clone()));
+
+        // logrotate `create`
+        info!(logger: logger, "1");
+        sink.flush().unwrap();
+        fs::rename(&path, &rotated).unwrap();
+        info!(logger: logger, "2");
+        sink.flush().unwrap();
+        assert_eq!(fs::read_to_string(&rotated).unwrap(), "1");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "2");
+
+        // logrotate `copytruncate`
+        info!(logger: logger, "3");
+        sink.flush().unwrap();
+        fs::write(&path, "").unwrap();
+        info!(logger: logger, "4");
+        sink.flush().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "4");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reopen_all_on_signal() {
+        let path = LOGS_PATH.join("signal.log");
+        let rotated = LOGS_PATH.join("signal_1.log");
+        let sink = RotatingFileSink::builder()
+            .base_path(&path)
+            .rotation_policy(RotationPolicy::FileSize(u64::MAX))
+            .formatter(NoModFormatter::new())
+            .build_arc()
+            .unwrap();
+        let logger = build_test_logger(|b| b.sink(sink.clone()));
+
+        info!(logger: logger, "1");
+        sink.flush().unwrap();
+        fs::rename(&path, &rotated).unwrap();
+
+        reopen_files_on_signal(ReopenSignal::User2).unwrap();
+        assert_eq!(unsafe { libc::raise(libc::SIGUSR2) }, 0);
+
+        info!(logger: logger, "2");
+        sink.flush().unwrap();
+        assert_eq!(fs::read_to_string(&rotated).unwrap(), "1");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "2");
+    }
+}
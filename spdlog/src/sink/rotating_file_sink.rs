@@ -6,7 +6,7 @@ use std::{
     ffi::OsString,
     fs::{self, File},
     hash::Hash,
-    io::{BufWriter, Write as _},
+    io::{self, BufWriter, Write as _},
     path::{Path, PathBuf},
     result::Result as StdResult,
     time::{Duration, SystemTime},
@@ -17,9 +17,16 @@ use chrono::prelude::*;
 use crate::{
     error::InvalidArgumentError,
     formatter::{Formatter, FormatterContext},
-    sink::{GetSinkProp, Sink, SinkProp},
+    periodic_worker::PeriodicWorker,
+    sink::{
+        file_sync::{self, FileSyncer},
+        reopen::FileReopener,
+        shared_file::{self, FileLock},
+        GetSinkProp, Sink, SinkProp, SyncPolicy,
+    },
     sync::*,
-    utils, Error, ErrorHandler, LevelFilter, Record, Result, StringBuf,
+    utils::FileOpenOptions,
+    Error, ErrorHandler, LevelFilter, Record, Result, StringBuf,
 };
 
 /// Rotation policies for [`RotatingFileSink`].
@@ -85,10 +92,16 @@ const MINUTE_1: Duration = Duration::from_secs(SECONDS_PER_MINUTE);
 const HOUR_1: Duration = Duration::from_secs(SECONDS_PER_HOUR);
 const DAY_1: Duration = Duration::from_secs(SECONDS_PER_DAY);
 
+// Formatted records with their end offsets in the buffer.
+type FormattedRecords<'r, 'a> = [(&'r Record<'a>, usize)];
+
 trait Rotator {
+    // Records in the batch are written to the file at once, unless a rotation
+    // is needed in the middle of the batch.
     #[allow(clippy::ptr_arg)]
-    fn log(&self, record: &Record, string_buf: &StringBuf) -> Result<()>;
+    fn log_batch(&self, records: &FormattedRecords, string_buf: &StringBuf) -> Result<()>;
     fn flush(&self) -> Result<()>;
+    fn reopen(&self) -> Result<()>;
     fn drop_flush(&mut self) -> Result<()> {
         self.flush()
     }
@@ -99,24 +112,35 @@ enum RotatorKind {
     TimePoint(RotatorTimePoint),
 }
 
+struct RotatorFileOptions {
+    capacity: Option<usize>,
+    open_options: FileOpenOptions,
+    reopen_check_interval: Option<Duration>,
+    syncer: FileSyncer,
+}
+
 struct RotatorFileSize {
     base_path: PathBuf,
     max_size: u64,
     max_files: usize,
     capacity: Option<usize>,
-    inner: Mutex<RotatorFileSizeInner>,
+    open_options: FileOpenOptions,
+    inner: Arc<Mutex<RotatorFileSizeInner>>,
 }
 
 struct RotatorFileSizeInner {
     file: Option<BufWriter<File>>,
     current_size: u64,
+    reopener: FileReopener,
+    syncer: FileSyncer,
 }
 
 struct RotatorTimePoint {
     base_path: PathBuf,
     time_point: TimePoint,
     max_files: usize,
-    inner: Mutex<RotatorTimePointInner>,
+    open_options: FileOpenOptions,
+    inner: Arc<Mutex<RotatorTimePointInner>>,
 }
 
 #[derive(Copy, Clone)]
@@ -128,8 +152,11 @@ enum TimePoint {
 
 struct RotatorTimePointInner {
     file: BufWriter<File>,
+    file_path: PathBuf,
+    reopener: FileReopener,
     rotation_time_point: SystemTime,
     file_paths: Option<LinkedList<PathBuf>>,
+    syncer: FileSyncer,
 }
 
 /// A sink with a file as the target, split files according to the rotation
@@ -144,14 +171,20 @@ struct RotatorTimePointInner {
 /// **rotation policy** determines when and how log files are created or
 /// deleted.
 ///
+/// If the files are written by multiple processes at the same time, enable
+/// [`multi_process`] to prevent records from being interleaved and files from
+/// being rotated by more than one process at once.
+///
 /// # Examples
 ///
 /// See [./examples] directory.
 ///
+/// [`multi_process`]: RotatingFileSinkBuilder::multi_process
 /// [./examples]: https://github.com/SpriteOvO/spdlog-rs/tree/main/spdlog/examples
 pub struct RotatingFileSink {
     prop: SinkProp,
     rotator: RotatorKind,
+    _sync_worker: Option<PeriodicWorker>,
 }
 
 /// #
@@ -163,22 +196,33 @@ pub struct RotatingFileSinkBuilder<ArgBP, ArgRP> {
     max_files: usize,
     rotate_on_open: bool,
     capacity: Option<usize>,
+    open_options: FileOpenOptions,
+    reopen_check_interval: Option<Duration>,
+    sync_policy: SyncPolicy,
+    sync_data_only: bool,
 }
 
 impl RotatingFileSink {
     /// Gets a builder of `RotatingFileSink` with default parameters:
     ///
-    /// | Parameter         | Default Value               |
-    /// |-------------------|-----------------------------|
-    /// | [level_filter]    | [`LevelFilter::All`]        |
-    /// | [formatter]       | [`FullFormatter`]           |
-    /// | [error_handler]   | [`ErrorHandler::default()`] |
-    /// |                   |                             |
-    /// | [base_path]       | *must be specified*         |
-    /// | [rotation_policy] | *must be specified*         |
-    /// | [max_files]       | `0`                         |
-    /// | [rotate_on_open]  | `false`                     |
-    /// | [capacity]        | consistent with `std`       |
+    /// | Parameter          | Default Value               |
+    /// |--------------------|-----------------------------|
+    /// | [level_filter]     | [`LevelFilter::All`]        |
+    /// | [formatter]        | [`FullFormatter`]           |
+    /// | [error_handler]    | [`ErrorHandler::default()`] |
+    /// |                    |                             |
+    /// | [base_path]        | *must be specified*         |
+    /// | [rotation_policy]  | *must be specified*         |
+    /// | [max_files]        | `0`                         |
+    /// | [rotate_on_open]   | `false`                     |
+    /// | [capacity]         | consistent with `std`       |
+    /// | [file_mode]        | `0o666` (masked by umask)   |
+    /// | [dir_mode]         | `0o777` (masked by umask)   |
+    /// | [follow_symlinks]  | `true`                      |
+    /// | [multi_process]    | `false`                     |
+    /// | [reopen_on_change] | disabled                    |
+    /// | [sync_policy]      | [`SyncPolicy::Never`]       |
+    /// | [sync_data_only]   | `false`                     |
     ///
     /// [level_filter]: RotatingFileSinkBuilder::level_filter
     /// [formatter]: RotatingFileSinkBuilder::formatter
@@ -189,6 +233,13 @@ impl RotatingFileSink {
     /// [max_files]: RotatingFileSinkBuilder::max_files
     /// [rotate_on_open]: RotatingFileSinkBuilder::rotate_on_open
     /// [capacity]: RotatingFileSinkBuilder::capacity
+    /// [file_mode]: RotatingFileSinkBuilder::file_mode
+    /// [dir_mode]: RotatingFileSinkBuilder::dir_mode
+    /// [follow_symlinks]: RotatingFileSinkBuilder::follow_symlinks
+    /// [multi_process]: RotatingFileSinkBuilder::multi_process
+    /// [reopen_on_change]: RotatingFileSinkBuilder::reopen_on_change
+    /// [sync_policy]: RotatingFileSinkBuilder::sync_policy
+    /// [sync_data_only]: RotatingFileSinkBuilder::sync_data_only
     #[must_use]
     pub fn builder() -> RotatingFileSinkBuilder<(), ()> {
         RotatingFileSinkBuilder {
@@ -198,6 +249,10 @@ impl RotatingFileSink {
             max_files: 0,
             rotate_on_open: false,
             capacity: None,
+            open_options: FileOpenOptions::default(),
+            reopen_check_interval: None,
+            sync_policy: SyncPolicy::Never,
+            sync_data_only: false,
         }
     }
 
@@ -244,6 +299,26 @@ impl RotatingFileSink {
             .build()
     }
 
+    /// Reopens the current file.
+    ///
+    /// It is useful when the rotation is performed by an external tool such as
+    /// `logrotate` instead of this sink. The buffered records are flushed to the
+    /// previously opened file, then the file at the current path is opened
+    /// (created if it does not exist) in append mode, and subsequent records
+    /// are written to it.
+    ///
+    /// See also [`reopen_all_files`].
+    ///
+    /// # Error
+    ///
+    /// If an error occurs opening the file, [`Error::CreateDirectory`] or
+    /// [`Error::OpenFile`] will be returned.
+    ///
+    /// [`reopen_all_files`]: crate::sink::reopen_all_files
+    pub fn reopen(&self) -> Result<()> {
+        self.rotator.reopen()
+    }
+
     #[cfg(test)]
     #[must_use]
     fn _current_size(&self) -> u64 {
@@ -269,7 +344,17 @@ impl Sink for RotatingFileSink {
             .formatter()
             .format(record, &mut string_buf, &mut ctx)?;
 
-        self.rotator.log(record, &string_buf)
+        self.rotator
+            .log_batch(&[(record, string_buf.len())], &string_buf)
+    }
+
+    fn log_batch(&self, records: &[Record]) -> Result<()> {
+        let mut string_buf = StringBuf::new();
+        let (formatted, result) = self.prop.format_batch(records, &mut string_buf);
+        if formatted.is_empty() {
+            return result;
+        }
+        Error::push_result(result, self.rotator.log_batch(&formatted, &string_buf))
     }
 
     fn flush(&self) -> Result<()> {
@@ -319,11 +404,31 @@ impl RotationPolicy {
     }
 }
 
+impl RotatorKind {
+    #[must_use]
+    fn spawn_sync_worker(&self, policy: SyncPolicy) -> Option<PeriodicWorker> {
+        match self {
+            Self::FileSize(rotator) => {
+                file_sync::spawn_sync_worker(policy, &rotator.inner, |inner| {
+                    if let Some(file) = inner.file.as_mut() {
+                        inner.syncer.sync_in_background(file);
+                    }
+                })
+            }
+            Self::TimePoint(rotator) => {
+                file_sync::spawn_sync_worker(policy, &rotator.inner, |inner| {
+                    inner.syncer.sync_in_background(&mut inner.file);
+                })
+            }
+        }
+    }
+}
+
 impl Rotator for RotatorKind {
-    fn log(&self, record: &Record, string_buf: &StringBuf) -> Result<()> {
+    fn log_batch(&self, records: &FormattedRecords, string_buf: &StringBuf) -> Result<()> {
         match self {
-            Self::FileSize(rotator) => rotator.log(record, string_buf),
-            Self::TimePoint(rotator) => rotator.log(record, string_buf),
+            Self::FileSize(rotator) => rotator.log_batch(records, string_buf),
+            Self::TimePoint(rotator) => rotator.log_batch(records, string_buf),
         }
     }
 
@@ -334,6 +439,13 @@ impl Rotator for RotatorKind {
         }
     }
 
+    fn reopen(&self) -> Result<()> {
+        match self {
+            Self::FileSize(rotator) => rotator.reopen(),
+            Self::TimePoint(rotator) => rotator.reopen(),
+        }
+    }
+
     fn drop_flush(&mut self) -> Result<()> {
         match self {
             Self::FileSize(rotator) => rotator.drop_flush(),
@@ -348,9 +460,20 @@ impl RotatorFileSize {
         max_size: u64,
         max_files: usize,
         rotate_on_open: bool,
-        capacity: Option<usize>,
+        file_options: RotatorFileOptions,
     ) -> Result<Self> {
-        let file = utils::open_file_bufw(&base_path, false, capacity)?;
+        let RotatorFileOptions {
+            capacity,
+            open_options,
+            reopen_check_interval,
+            syncer,
+        } = file_options;
+        let file = syncer.open_file_bufw(&base_path, false, capacity, &open_options)?;
+        let reopener = FileReopener::new(
+            reopen_check_interval,
+            open_options.multi_process,
+            file.get_ref(),
+        );
         let current_size = file
             .get_ref()
             .metadata()
@@ -362,26 +485,58 @@ impl RotatorFileSize {
             max_size,
             max_files,
             capacity,
-            inner: Mutex::new(RotatorFileSizeInner {
+            open_options,
+            inner: Arc::new(Mutex::new(RotatorFileSizeInner {
                 file: Some(file),
                 current_size,
-            }),
+                reopener,
+                syncer,
+            })),
         };
 
         if rotate_on_open && current_size > 0 {
-            res.rotate(&mut res.inner.lock_expect())?;
+            res.rotate_any(&mut res.inner.lock_expect())?;
             res.inner.lock_expect().current_size = 0;
         }
 
         Ok(res)
     }
 
-    fn reopen(&self) -> Result<BufWriter<File>> {
-        // always truncate
-        utils::open_file_bufw(&self.base_path, true, self.capacity)
+    fn reopen_truncated(&self, opened_file: &mut RotatorFileSizeInner) -> Result<()> {
+        // always truncate, unless the file may have been created and written by
+        // another process
+        let file = opened_file.syncer.open_file_bufw(
+            &self.base_path,
+            !self.open_options.multi_process,
+            self.capacity,
+            &self.open_options,
+        )?;
+        opened_file.reopener.reset(file.get_ref());
+        opened_file.file = Some(file);
+        Ok(())
+    }
+
+    fn reopen_appended(&self, opened_file: &mut RotatorFileSizeInner) -> Result<()> {
+        opened_file.close()?;
+        let file = opened_file.syncer.open_file_bufw(
+            &self.base_path,
+            false,
+            self.capacity,
+            &self.open_options,
+        )?;
+        opened_file.current_size = file
+            .get_ref()
+            .metadata()
+            .map_err(Error::QueryFileMetadata)?
+            .len();
+        opened_file.reopener.reset(file.get_ref());
+        opened_file.file = Some(file);
+        Ok(())
     }
 
     fn rotate(&self, opened_file: &mut MutexGuard<RotatorFileSizeInner>) -> Result<()> {
+        let closed = opened_file.close();
+
         let inner = || {
             for i in (1..self.max_files).rev() {
                 let src = Self::calc_file_path(&self.base_path, i - 1);
@@ -394,21 +549,47 @@ impl RotatorFileSize {
                     fs::remove_file(&dst).map_err(Error::RemoveFile)?;
                 }
 
-                fs::rename(src, dst).map_err(Error::RenameFile)?;
+                fs::rename(src, &dst).map_err(Error::RenameFile)?;
             }
-            Ok(())
+            opened_file.syncer.after_rename(&self.base_path)
         };
 
-        opened_file.file = None;
-
         let res = inner();
         if res.is_err() {
             opened_file.current_size = 0;
         }
 
-        opened_file.file = Some(self.reopen()?);
+        self.reopen_truncated(opened_file)?;
 
-        res
+        Error::push_result(closed, res)
+    }
+
+    // Rotates the file shared with other processes. The file is locked during
+    // the rotation, and if it has already been rotated by another process while
+    // waiting for the lock, the new file is opened instead.
+    fn rotate_shared(&self, opened_file: &mut MutexGuard<RotatorFileSizeInner>) -> Result<()> {
+        let file = opened_file
+            .file
+            .as_ref()
+            .unwrap()
+            .get_ref()
+            .try_clone()
+            .map_err(Error::LockFile)?;
+        let _lock = FileLock::lock(&file)?;
+
+        if shared_file::is_replaced(&file, &self.base_path) {
+            self.reopen_appended(opened_file)
+        } else {
+            self.rotate(opened_file)
+        }
+    }
+
+    fn rotate_any(&self, opened_file: &mut MutexGuard<RotatorFileSizeInner>) -> Result<()> {
+        if self.open_options.multi_process {
+            self.rotate_shared(opened_file)
+        } else {
+            self.rotate(opened_file)
+        }
     }
 
     #[must_use]
@@ -438,47 +619,96 @@ impl RotatorFileSize {
         path
     }
 
+    fn write_chunk(
+        &self,
+        inner: &mut RotatorFileSizeInner,
+        bytes: &[u8],
+        records: u64,
+    ) -> Result<()> {
+        if records == 0 {
+            return Ok(());
+        }
+        let file = inner.file.as_mut().unwrap();
+        shared_file::write_record(file, bytes, self.open_options.multi_process)?;
+        inner.syncer.after_write(file, records, bytes.len())
+    }
+
     // if `self.inner.file` is `None`, try to reopen the file.
     fn lock_inner(&self) -> Result<MutexGuard<'_, RotatorFileSizeInner>> {
         let mut inner = self.inner.lock_expect();
         if inner.file.is_none() {
-            inner.file = Some(self.reopen()?);
+            self.reopen_truncated(&mut inner)?;
         }
         Ok(inner)
     }
 }
 
+impl RotatorFileSizeInner {
+    // The buffered records are flushed to the closed file on drop.
+    fn close(&mut self) -> Result<()> {
+        match self.file.take() {
+            Some(mut file) => self.syncer.before_close(&mut file),
+            None => Ok(()),
+        }
+    }
+}
+
 impl Rotator for RotatorFileSize {
-    fn log(&self, _record: &Record, string_buf: &StringBuf) -> Result<()> {
+    fn log_batch(&self, records: &FormattedRecords, string_buf: &StringBuf) -> Result<()> {
         let mut inner = self.lock_inner()?;
+        if inner.reopener.should_reopen(&self.base_path) {
+            self.reopen_appended(&mut inner)?;
+        }
 
-        inner.current_size += string_buf.len() as u64;
-        if inner.current_size > self.max_size {
-            self.rotate(&mut inner)?;
-            inner.current_size = string_buf.len() as u64;
+        if self.open_options.multi_process {
+            // Other processes append to the file as well
+            inner.current_size = inner
+                .file
+                .as_ref()
+                .unwrap()
+                .get_ref()
+                .metadata()
+                .map_err(Error::QueryFileMetadata)?
+                .len();
         }
 
-        inner
-            .file
-            .as_mut()
-            .unwrap()
-            .write_all(string_buf.as_bytes())
-            .map_err(Error::WriteRecord)
+        let bytes = string_buf.as_bytes();
+        // The records in `bytes[chunk_start..record_start]` are not written yet
+        let (mut chunk_start, mut chunk_records, mut record_start) = (0, 0, 0);
+        for &(_, record_end) in records {
+            let len = (record_end - record_start) as u64;
+            inner.current_size += len;
+            if inner.current_size > self.max_size {
+                self.write_chunk(&mut inner, &bytes[chunk_start..record_start], chunk_records)?;
+                self.rotate_any(&mut inner)?;
+                inner.current_size = len;
+                chunk_start = record_start;
+                chunk_records = 0;
+            }
+            chunk_records += 1;
+            record_start = record_end;
+        }
+        self.write_chunk(&mut inner, &bytes[chunk_start..record_start], chunk_records)
     }
 
     fn flush(&self) -> Result<()> {
-        self.lock_inner()?
-            .file
-            .as_mut()
-            .unwrap()
-            .flush()
-            .map_err(Error::FlushBuffer)
+        let mut inner = self.lock_inner()?;
+        let inner = &mut *inner;
+        let file = inner.file.as_mut().unwrap();
+        file.flush().map_err(Error::FlushBuffer)?;
+        inner.syncer.after_flush(file)
+    }
+
+    fn reopen(&self) -> Result<()> {
+        self.reopen_appended(&mut self.inner.lock_expect())
     }
 
     fn drop_flush(&mut self) -> Result<()> {
         let mut inner = self.inner.lock_expect();
+        let inner = &mut *inner;
         if let Some(file) = inner.file.as_mut() {
-            file.flush().map_err(Error::FlushBuffer)
+            file.flush().map_err(Error::FlushBuffer)?;
+            inner.syncer.before_close(file)
         } else {
             Ok(())
         }
@@ -492,23 +722,38 @@ impl RotatorTimePoint {
         time_point: TimePoint,
         max_files: usize,
         truncate: bool,
-        capacity: Option<usize>,
+        file_options: RotatorFileOptions,
     ) -> Result<Self> {
+        let RotatorFileOptions {
+            capacity,
+            open_options,
+            reopen_check_interval,
+            syncer,
+        } = file_options;
         let now = override_now.unwrap_or_else(SystemTime::now);
         let file_path = Self::calc_file_path(base_path.as_path(), time_point, now);
-        let file = utils::open_file_bufw(file_path, truncate, capacity)?;
+        let file = syncer.open_file_bufw(&file_path, truncate, capacity, &open_options)?;
+        let reopener = FileReopener::new(
+            reopen_check_interval,
+            open_options.multi_process,
+            file.get_ref(),
+        );
 
         let inner = RotatorTimePointInner {
             file,
+            file_path,
+            reopener,
             rotation_time_point: Self::next_rotation_time_point(time_point, now),
             file_paths: None,
+            syncer,
         };
 
         let mut res = Self {
             base_path,
             time_point,
             max_files,
-            inner: Mutex::new(inner),
+            open_options,
+            inner: Arc::new(Mutex::new(inner)),
         };
 
         res.init_previous_file_paths(max_files, now);
@@ -516,6 +761,17 @@ impl RotatorTimePoint {
         Ok(res)
     }
 
+    fn reopen_appended(&self, inner: &mut RotatorTimePointInner) -> Result<()> {
+        // The buffered records are flushed to the previous file on drop
+        inner.syncer.before_close(&mut inner.file)?;
+        inner.file =
+            inner
+                .syncer
+                .open_file_bufw(&inner.file_path, false, None, &self.open_options)?;
+        inner.reopener.reset(inner.file.get_ref());
+        Ok(())
+    }
+
     fn init_previous_file_paths(&mut self, max_files: usize, mut now: SystemTime) {
         if max_files > 0 {
             let mut file_paths = LinkedList::new();
@@ -531,7 +787,7 @@ impl RotatorTimePoint {
                 now = now.checked_sub(self.time_point.delta_std()).unwrap()
             }
 
-            self.inner.get_mut_expect().file_paths = Some(file_paths);
+            self.inner.lock_expect().file_paths = Some(file_paths);
         }
     }
 
@@ -574,17 +830,40 @@ impl RotatorTimePoint {
         rotation_time.into()
     }
 
-    fn push_new_remove_old(
+    fn write_chunk(
         &self,
-        new: PathBuf,
-        inner: &mut MutexGuard<RotatorTimePointInner>,
+        inner: &mut RotatorTimePointInner,
+        bytes: &[u8],
+        records: u64,
+        new_file_path: &mut Option<PathBuf>,
     ) -> Result<()> {
+        if records == 0 {
+            return Ok(());
+        }
+        shared_file::write_record(&mut inner.file, bytes, self.open_options.multi_process)?;
+        inner
+            .syncer
+            .after_write(&mut inner.file, records, bytes.len())?;
+
+        match new_file_path.take() {
+            Some(new) if inner.file_paths.is_some() => self.push_new_remove_old(new, inner),
+            _ => Ok(()),
+        }
+    }
+
+    fn push_new_remove_old(&self, new: PathBuf, inner: &mut RotatorTimePointInner) -> Result<()> {
         let file_paths = inner.file_paths.as_mut().unwrap();
 
         while file_paths.len() >= self.max_files {
             let old = file_paths.pop_front().unwrap();
             if old.exists() {
-                fs::remove_file(old).map_err(Error::RemoveFile)?;
+                // It may have been removed by another process in the meantime
+                match fs::remove_file(old) {
+                    Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                        return Err(Error::RemoveFile(err))
+                    }
+                    _ => {}
+                }
             }
         }
         file_paths.push_back(new);
@@ -652,42 +931,87 @@ impl RotatorTimePoint {
 }
 
 impl Rotator for RotatorTimePoint {
-    fn log(&self, record: &Record, string_buf: &StringBuf) -> Result<()> {
+    fn log_batch(&self, records: &FormattedRecords, string_buf: &StringBuf) -> Result<()> {
         let mut inner = self.inner.lock_expect();
+        let inner = &mut *inner;
 
-        let mut file_path = None;
-        let record_time = record.time();
-        let should_rotate = record_time >= inner.rotation_time_point;
-
-        if should_rotate {
-            file_path = Some(Self::calc_file_path(
-                &self.base_path,
-                self.time_point,
-                record_time,
-            ));
-            inner.file = BufWriter::new(utils::open_file(file_path.as_ref().unwrap(), true)?);
-            inner.rotation_time_point =
-                Self::next_rotation_time_point(self.time_point, record_time);
-        }
-
-        inner
-            .file
-            .write_all(string_buf.as_bytes())
-            .map_err(Error::WriteRecord)?;
+        let mut closed = Ok(());
+        let should_rotate = |inner: &RotatorTimePointInner, record: &Record| {
+            record.time() >= inner.rotation_time_point
+        };
 
-        if should_rotate && inner.file_paths.is_some() {
-            self.push_new_remove_old(file_path.unwrap(), &mut inner)?;
+        // Reopening is unnecessary if the file is about to be rotated
+        if records
+            .first()
+            .is_some_and(|(record, _)| !should_rotate(inner, record))
+            && inner.reopener.should_reopen(&inner.file_path)
+        {
+            self.reopen_appended(inner)?;
         }
 
-        Ok(())
+        let bytes = string_buf.as_bytes();
+        // The records in `bytes[chunk_start..record_start]` are not written yet
+        let (mut chunk_start, mut chunk_records, mut record_start) = (0, 0, 0);
+        // The path of the rotated file, pushed to `file_paths` once it is written
+        let mut new_file_path = None;
+        for &(record, record_end) in records {
+            if should_rotate(inner, record) {
+                self.write_chunk(
+                    inner,
+                    &bytes[chunk_start..record_start],
+                    chunk_records,
+                    &mut new_file_path,
+                )?;
+
+                let record_time = record.time();
+                let file_path = Self::calc_file_path(&self.base_path, self.time_point, record_time);
+                // The buffered records are flushed to the previous file on drop
+                closed = Error::push_result(closed, inner.syncer.before_close(&mut inner.file));
+                // The new file may have been created and written by another process
+                inner.file = inner.syncer.open_file_bufw(
+                    &file_path,
+                    !self.open_options.multi_process,
+                    None,
+                    &self.open_options,
+                )?;
+                inner.file_path = file_path.clone();
+                inner.reopener.reset(inner.file.get_ref());
+                inner.rotation_time_point =
+                    Self::next_rotation_time_point(self.time_point, record_time);
+                new_file_path = Some(file_path);
+
+                chunk_start = record_start;
+                chunk_records = 0;
+            }
+            chunk_records += 1;
+            record_start = record_end;
+        }
+        self.write_chunk(
+            inner,
+            &bytes[chunk_start..record_start],
+            chunk_records,
+            &mut new_file_path,
+        )?;
+
+        closed
     }
 
     fn flush(&self) -> Result<()> {
-        self.inner
-            .lock_expect()
-            .file
-            .flush()
-            .map_err(Error::FlushBuffer)
+        let mut inner = self.inner.lock_expect();
+        let inner = &mut *inner;
+        inner.file.flush().map_err(Error::FlushBuffer)?;
+        inner.syncer.after_flush(&mut inner.file)
+    }
+
+    fn reopen(&self) -> Result<()> {
+        self.reopen_appended(&mut self.inner.lock_expect())
+    }
+
+    fn drop_flush(&mut self) -> Result<()> {
+        let mut inner = self.inner.lock_expect();
+        let inner = &mut *inner;
+        inner.file.flush().map_err(Error::FlushBuffer)?;
+        inner.syncer.before_close(&mut inner.file)
     }
 }
 
@@ -744,6 +1068,10 @@ impl<ArgBP, ArgRP> RotatingFileSinkBuilder<ArgBP, ArgRP> {
             max_files: self.max_files,
             rotate_on_open: self.rotate_on_open,
             capacity: self.capacity,
+            open_options: self.open_options,
+            reopen_check_interval: self.reopen_check_interval,
+            sync_policy: self.sync_policy,
+            sync_data_only: self.sync_data_only,
         }
     }
 
@@ -762,6 +1090,10 @@ impl<ArgBP, ArgRP> RotatingFileSinkBuilder<ArgBP, ArgRP> {
             max_files: self.max_files,
             rotate_on_open: self.rotate_on_open,
             capacity: self.capacity,
+            open_options: self.open_options,
+            reopen_check_interval: self.reopen_check_interval,
+            sync_policy: self.sync_policy,
+            sync_data_only: self.sync_data_only,
         }
     }
 
@@ -804,6 +1136,115 @@ impl<ArgBP, ArgRP> RotatingFileSinkBuilder<ArgBP, ArgRP> {
         self
     }
 
+    /// Specifies the permission mode bits of the log files created by the sink,
+    /// e.g. `0o640`.
+    ///
+    /// Like `open(2)`, the mode is masked by the umask of the process. The mode
+    /// of an existing file is not changed.
+    ///
+    /// This parameter is **optional**, and defaults to `0o666` (masked by the
+    /// umask).
+    #[cfg(unix)]
+    #[must_use]
+    pub fn file_mode(mut self, mode: u32) -> Self {
+        self.open_options.file_mode = Some(mode);
+        self
+    }
+
+    /// Specifies the permission mode bits of the directories created along the
+    /// path of the log files, e.g. `0o750`.
+    ///
+    /// Like `mkdir(2)`, the mode is masked by the umask of the process.
+    ///
+    /// This parameter is **optional**, and defaults to `0o777` (masked by the
+    /// umask).
+    #[cfg(unix)]
+    #[must_use]
+    pub fn dir_mode(mut self, mode: u32) -> Self {
+        self.open_options.dir_mode = Some(mode);
+        self
+    }
+
+    /// Specifies whether to follow the log file paths if they are symbolic links.
+    ///
+    /// If it is `false`, opening a log file whose path is a symbolic link fails
+    /// with [`Error::OpenFile`], which prevents the sink from being redirected
+    /// to unexpected files. On Unix, the file is opened with `O_NOFOLLOW`.
+    /// Symbolic links in the parent directories are still followed.
+    ///
+    /// This parameter is **optional**, and defaults to `true`.
+    #[must_use]
+    pub fn follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.open_options.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// Specifies whether the log files are written by multiple processes at the
+    /// same time, e.g. forked or spawned worker processes.
+    ///
+    /// If it is `true`, each record bypasses the internal buffer and is
+    /// appended to the current file with a single `write` while holding an
+    /// advisory lock of the file (`flock`), so that records from different
+    /// processes are never interleaved. For [`RotationPolicy::FileSize`], the
+    /// file is also locked during the rotation, and a process that finds the
+    /// file already rotated by another process opens the new file instead of
+    /// rotating again. Files created by rotations are opened in append mode
+    /// instead of being truncated, since other processes may have written to
+    /// them already.
+    ///
+    /// A sink built before `fork()` can be used by both the parent and the
+    /// child processes. A process that did not open the file reopens it before
+    /// its first write, since the lock belongs to the open file description
+    /// inherited by the child and would not exclude the parent otherwise.
+    ///
+    /// The lock is only available on Unix. On other platforms, records are
+    /// still appended with a single `write`, but rotations are not coordinated.
+    ///
+    /// This parameter is **optional**, and defaults to `false`.
+    #[must_use]
+    pub fn multi_process(mut self, multi_process: bool) -> Self {
+        self.open_options.multi_process = multi_process;
+        self
+    }
+
+    /// Reopens the current file automatically if it has been changed
+    /// externally.
+    ///
+    /// Every `interval`, when a record is logged, the sink checks whether the
+    /// current file has been removed, replaced by another file, or truncated,
+    /// and reopens the file if so. See [`RotatingFileSink::reopen`].
+    ///
+    /// Detecting the replacement requires the inode and device of the file,
+    /// which are only available on Unix.
+    ///
+    /// This parameter is **optional**, and defaults to disabled.
+    #[must_use]
+    pub fn reopen_on_change(mut self, interval: Duration) -> Self {
+        self.reopen_check_interval = Some(interval);
+        self
+    }
+
+    /// Specifies when the current file is synchronized to the storage device.
+    ///
+    /// See [`SyncPolicy`] for details.
+    ///
+    /// This parameter is **optional**, and defaults to [`SyncPolicy::Never`].
+    #[must_use]
+    pub fn sync_policy(mut self, sync_policy: SyncPolicy) -> Self {
+        self.sync_policy = sync_policy;
+        self
+    }
+
+    /// Specifies whether to synchronize only the file content and the metadata
+    /// needed to read it (`fdatasync`) instead of all metadata (`fsync`).
+    ///
+    /// This parameter is **optional**, and defaults to `false`.
+    #[must_use]
+    pub fn sync_data_only(mut self, sync_data_only: bool) -> Self {
+        self.sync_data_only = sync_data_only;
+        self
+    }
+
     // Prop
     //
 
@@ -878,9 +1319,11 @@ impl RotatingFileSinkBuilder<PathBuf, RotationPolicy> {
     ///
     /// # Error
     ///
-    /// If the argument `rotation_policy` is invalid, or an error occurs opening
-    /// the file, [`Error::CreateDirectory`] or [`Error::OpenFile`] will be
-    /// returned.
+    /// If the argument `rotation_policy` or `sync_policy` is invalid,
+    /// [`Error::InvalidArgument`] will be returned.
+    ///
+    /// If an error occurs opening the file, [`Error::CreateDirectory`],
+    /// [`Error::OpenFile`] or [`Error::SyncFile`] will be returned.
     pub fn build(self) -> Result<RotatingFileSink> {
         self.build_with_initial_time(None)
     }
@@ -896,6 +1339,15 @@ impl RotatingFileSinkBuilder<PathBuf, RotationPolicy> {
         self.rotation_policy
             .validate()
             .map_err(|err| Error::InvalidArgument(InvalidArgumentError::RotationPolicy(err)))?;
+        self.sync_policy
+            .validate()
+            .map_err(|err| Error::InvalidArgument(InvalidArgumentError::SyncPolicy(err)))?;
+        let file_options = RotatorFileOptions {
+            capacity: None,
+            open_options: self.open_options,
+            reopen_check_interval: self.reopen_check_interval,
+            syncer: FileSyncer::new(self.sync_policy, self.sync_data_only),
+        };
 
         let rotator = match self.rotation_policy {
             RotationPolicy::FileSize(max_size) => RotatorKind::FileSize(RotatorFileSize::new(
@@ -903,7 +1355,7 @@ impl RotatingFileSinkBuilder<PathBuf, RotationPolicy> {
                 max_size,
                 self.max_files,
                 self.rotate_on_open,
-                None,
+                file_options,
             )?),
             RotationPolicy::Daily { hour, minute } => {
                 RotatorKind::TimePoint(RotatorTimePoint::new(
@@ -912,7 +1364,7 @@ impl RotatingFileSinkBuilder<PathBuf, RotationPolicy> {
                     TimePoint::Daily { hour, minute },
                     self.max_files,
                     self.rotate_on_open,
-                    None,
+                    file_options,
                 )?)
             }
             RotationPolicy::Hourly => RotatorKind::TimePoint(RotatorTimePoint::new(
@@ -921,7 +1373,7 @@ impl RotatingFileSinkBuilder<PathBuf, RotationPolicy> {
                 TimePoint::Hourly,
                 self.max_files,
                 self.rotate_on_open,
-                None,
+                file_options,
             )?),
             RotationPolicy::Period(duration) => RotatorKind::TimePoint(RotatorTimePoint::new(
                 override_now,
@@ -929,13 +1381,16 @@ impl RotatingFileSinkBuilder<PathBuf, RotationPolicy> {
                 TimePoint::Period(duration),
                 self.max_files,
                 self.rotate_on_open,
-                None,
+                file_options,
             )?),
         };
 
+        let sync_worker = rotator.spawn_sync_worker(self.sync_policy);
+
         let res = RotatingFileSink {
             prop: self.prop,
             rotator,
+            _sync_worker: sync_worker,
         };
 
         Ok(res)
@@ -1430,6 +1885,59 @@ mod tests {
         }
     }
 
+    #[test]
+    fn log_batch() {
+        let path = BASE_LOGS_PATH.join("log_batch");
+        _ = fs::remove_dir_all(&path);
+        fs::create_dir(&path).unwrap();
+
+        let read_files = |prefix: &str| {
+            let mut contents = fs::read_dir(&path)
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .filter(|path| {
+                    path.file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .starts_with(prefix)
+                })
+                .map(|path| fs::read_to_string(path).unwrap())
+                .collect::<Vec<_>>();
+            contents.sort();
+            contents
+        };
+
+        let size_sink = RotatingFileSink::builder()
+            .base_path(path.join("size.log"))
+            .rotation_policy(RotationPolicy::FileSize(8))
+            .max_files(3)
+            .formatter(NoModFormatter::new())
+            .build()
+            .unwrap();
+        let records = ["abc", "def", "ghi", "jkl", "mno", "pqr"]
+            .map(|payload| Record::new(Level::Info, payload, None, None, &[]));
+        size_sink.log_batch(&records).unwrap();
+        size_sink.flush().unwrap();
+        // Rotated in the middle of the batch
+        assert_eq!(read_files("size"), ["abcdef", "ghijkl", "mnopqr"]);
+
+        let hourly_sink = RotatingFileSink::builder()
+            .base_path(path.join("hourly.log"))
+            .rotation_policy(RotationPolicy::Hourly)
+            .formatter(NoModFormatter::new())
+            .build()
+            .unwrap();
+        let mut records = records;
+        let initial_time = records[0].time();
+        records[2].set_time(initial_time + HOUR_1 + SECOND_1);
+        records[3].set_time(initial_time + HOUR_1 + 2 * SECOND_1);
+        records[4].set_time(initial_time + 2 * HOUR_1 + SECOND_1);
+        records[5].set_time(initial_time + 2 * HOUR_1 + 2 * SECOND_1);
+        hourly_sink.log_batch(&records).unwrap();
+        hourly_sink.flush().unwrap();
+        assert_eq!(read_files("hourly"), ["abcdef", "ghijkl", "mnopqr"]);
+    }
+
     #[test]
     fn test_builder_optional_params() {
         // workaround for the missing `no_run` attribute
@@ -0,0 +1,675 @@
+use std::{
+    cell::RefCell, collections::HashMap, convert::Infallible, fmt::Write as _, marker::PhantomData,
+};
+
+use crate::{
+    formatter::Formatter,
+    kv,
+    sink::{Sink, SinkProp, SinkPropAccess},
+    sync::*,
+    Error, ErrorHandler, LevelFilter, Record, Result,
+};
+
+type SinkFactory = Box<dyn Fn(&str) -> Result<Arc<dyn Sink>> + Send + Sync>;
+
+/// The key that [`RoutingSink`] routes records by.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum RoutingKey {
+    /// Routes by the name of the logger that logs the record.
+    LoggerName,
+    /// Routes by the level of the record, e.g. `"info"`.
+    Level,
+    /// Routes by the value of the key-value with the given key, e.g. `tenant`.
+    ///
+    /// The value is looked up with [`KeyValues::get`], so key-values from the
+    /// [scoped context] and the ones bound to the logger are considered too.
+    ///
+    /// [`KeyValues::get`]: crate::kv::KeyValues::get
+    /// [scoped context]: crate::context
+    KeyValue(String),
+}
+
+impl RoutingKey {
+    // Calls `f` with the key of the record. Key-values that are not strings are
+    // formatted into a buffer of the current thread, so that getting the key
+    // does not allocate.
+    fn with<R>(&self, record: &Record, f: impl FnOnce(Option<&str>) -> R) -> R {
+        thread_local! {
+            static KEY_BUFFER: RefCell<String> = const { RefCell::new(String::new()) };
+        }
+
+        let value = match self {
+            Self::LoggerName => return f(record.logger_name()),
+            Self::Level => return f(Some(record.level().as_str())),
+            Self::KeyValue(key) => match record.key_values().get(kv::Key::from_str(key)) {
+                Some(value) => value,
+                None => return f(None),
+            },
+        };
+        if let Some(value) = value.to_borrowed_str() {
+            return f(Some(value));
+        }
+
+        // The buffer is unavailable if the key is taken again while it is borrowed,
+        // e.g. by a nested `RoutingSink` created by the factory, or when the thread
+        // is exiting.
+        let mut f = Some(f);
+        let result = KEY_BUFFER
+            .try_with(|buf| {
+                let mut buf = buf.try_borrow_mut().ok()?;
+                buf.clear();
+                _ = write!(buf, "{value}");
+                Some((f.take().unwrap())(Some(&buf)))
+            })
+            .ok()
+            .flatten();
+        match result {
+            Some(result) => result,
+            None => (f.take().unwrap())(Some(&value.to_string())),
+        }
+    }
+}
+
+struct RoutingSinkState {
+    // Sub-sinks with their last used ticks, for evicting the least recently used
+    sinks: HashMap<String, (Arc<dyn Sink>, u64)>,
+    tick: u64,
+}
+
+struct Route {
+    sink: Arc<dyn Sink>,
+    evicted: Option<Arc<dyn Sink>>,
+}
+
+/// A [combined sink], routing each record to a sub-sink chosen by a key taken
+/// from the record.
+///
+/// Sub-sinks are created lazily by a factory closure, when a record with a
+/// new key arrives, e.g. one [`FileSink`] per tenant at `logs/{tenant}.log`.
+/// Records without the key are routed by the [fallback key].
+///
+/// At most [`max_sinks`] sub-sinks are kept, if the limit is exceeded, the
+/// least recently used sub-sink is flushed and dropped, and will be created
+/// again by the factory if its key appears again. This caps the number of open
+/// files when routing into files.
+///
+/// A formatter set on this sink is applied to all sub-sinks, including the ones
+/// created afterwards. Otherwise, sub-sinks keep the formatters they are
+/// created with.
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::{
+///     prelude::*,
+///     sink::{FileSink, RoutingKey, RoutingSink},
+/// };
+///
+/// # fn main() -> Result<(), spdlog::Error> {
+/// # let dir = std::env::temp_dir().join("spdlog-rs-doctest-routing-sink");
+/// let sink = RoutingSink::builder()
+///     .key(RoutingKey::KeyValue("tenant".into()))
+///     .factory(move |tenant| {
+///         // The key comes from the records, validate it before using it in paths
+///         if !tenant.chars().all(|c| c.is_ascii_alphanumeric()) {
+///             return Err(spdlog::Error::Downstream(
+///                 format!("invalid tenant '{tenant}'").into(),
+///             ));
+///         }
+///         Ok(FileSink::builder()
+///             .path(dir.join(format!("{tenant}.log")))
+///             .build_arc()?)
+///     })
+///     .build_arc()?;
+/// let logger = Logger::builder().sink(sink).build()?;
+///
+/// info!(logger: logger, "order placed", kv: { tenant = "acme" });
+/// info!(logger: logger, "order placed", kv: { tenant = "globex" });
+/// # Ok(()) }
+/// ```
+///
+/// [combined sink]: index.html#combined-sink
+/// [`FileSink`]: crate::sink::FileSink
+/// [fallback key]: RoutingSinkBuilder::fallback_key
+/// [`max_sinks`]: RoutingSinkBuilder::max_sinks
+pub struct RoutingSink {
+    prop: SinkProp,
+    key: RoutingKey,
+    fallback_key: String,
+    factory: SinkFactory,
+    max_sinks: usize,
+    formatter: RwLock<Option<Box<dyn Formatter>>>,
+    state: Mutex<RoutingSinkState>,
+}
+
+impl RoutingSink {
+    /// Gets a builder of `RoutingSink` with default parameters:
+    ///
+    /// | Parameter        | Default Value               |
+    /// |------------------|-----------------------------|
+    /// | [level_filter]   | [`LevelFilter::All`]        |
+    /// | [formatter]      | *the sub-sinks' own*        |
+    /// | [error_handler]  | [`ErrorHandler::default()`] |
+    /// |                  |                             |
+    /// | [key]            | *must be specified*         |
+    /// | [factory]        | *must be specified*         |
+    /// | [fallback_key]   | `"default"`                 |
+    /// | [max_sinks]      | `64`                        |
+    ///
+    /// [level_filter]: RoutingSinkBuilder::level_filter
+    /// [formatter]: RoutingSinkBuilder::formatter
+    /// [error_handler]: RoutingSinkBuilder::error_handler
+    /// [key]: RoutingSinkBuilder::key
+    /// [factory]: RoutingSinkBuilder::factory
+    /// [fallback_key]: RoutingSinkBuilder::fallback_key
+    /// [max_sinks]: RoutingSinkBuilder::max_sinks
+    #[must_use]
+    pub fn builder() -> RoutingSinkBuilder<(), ()> {
+        RoutingSinkBuilder {
+            prop: SinkProp::default(),
+            formatter: None,
+            key: (),
+            factory: None,
+            fallback_key: "default".to_string(),
+            max_sinks: 64,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Gets the sub-sinks currently kept, with their keys.
+    #[must_use]
+    pub fn sinks(&self) -> Vec<(String, Arc<dyn Sink>)> {
+        self.state
+            .lock_expect()
+            .sinks
+            .iter()
+            .map(|(key, (sink, _))| (key.clone(), sink.clone()))
+            .collect()
+    }
+
+    // Gets the sub-sink for the key, creating it and evicting the least recently
+    // used one if needed.
+    fn get_or_create(&self, key: &str) -> Result<Route> {
+        {
+            let mut state = self.state.lock_expect();
+            state.tick += 1;
+            let tick = state.tick;
+            if let Some((sink, last_used)) = state.sinks.get_mut(key) {
+                *last_used = tick;
+                return Ok(Route {
+                    sink: sink.clone(),
+                    evicted: None,
+                });
+            }
+        }
+
+        // Call the factory outside the lock, so that a slow factory (e.g. opening
+        // files) does not block routing the records of other keys
+        let new_sink = (self.factory)(key)?;
+
+        // Lock in the same order as `set_formatter`, so that the formatter cannot
+        // be changed between applying it and inserting the sub-sink
+        let formatter = self.formatter.read_expect();
+        let mut state = self.state.lock_expect();
+        state.tick += 1;
+        let tick = state.tick;
+
+        // Another thread may have created a sub-sink for the key in the meantime
+        if let Some((sink, last_used)) = state.sinks.get_mut(key) {
+            *last_used = tick;
+            return Ok(Route {
+                sink: sink.clone(),
+                evicted: None,
+            });
+        }
+
+        if let Some(formatter) = &*formatter {
+            new_sink.set_formatter(formatter.clone());
+        }
+
+        let mut evicted = None;
+        if state.sinks.len() >= self.max_sinks {
+            let lru_key = state
+                .sinks
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(lru_key) = lru_key {
+                evicted = state.sinks.remove(&lru_key).map(|(sink, _)| sink);
+            }
+        }
+        state
+            .sinks
+            .insert(key.to_string(), (new_sink.clone(), tick));
+        Ok(Route {
+            sink: new_sink,
+            evicted,
+        })
+    }
+
+    fn flush_with(&self, with: fn(&dyn Sink) -> Result<()>) -> Result<()> {
+        let sinks = self
+            .state
+            .lock_expect()
+            .sinks
+            .values()
+            .map(|(sink, _)| sink.clone())
+            .collect::<Vec<_>>();
+
+        #[allow(clippy::manual_try_fold)] // https://github.com/rust-lang/rust-clippy/issues/11554
+        sinks.iter().fold(Ok(()), |result, sink| {
+            Error::push_result(result, with(sink.as_ref()))
+        })
+    }
+}
+
+impl SinkPropAccess for RoutingSink {
+    fn level_filter(&self) -> LevelFilter {
+        self.prop.level_filter()
+    }
+
+    fn set_level_filter(&self, level_filter: LevelFilter) {
+        self.prop.set_level_filter(level_filter);
+    }
+
+    fn set_formatter(&self, formatter: Box<dyn Formatter>) {
+        // Lock order: `formatter`, then `state`
+        let mut current = self.formatter.write_expect();
+        for (sink, _) in self.state.lock_expect().sinks.values() {
+            sink.set_formatter(formatter.clone());
+        }
+        *current = Some(formatter);
+    }
+
+    fn set_error_handler(&self, handler: ErrorHandler) {
+        self.prop.set_error_handler(handler);
+    }
+}
+
+impl Sink for RoutingSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        let Route { sink, evicted } = self.key.with(record, |key| {
+            self.get_or_create(key.unwrap_or(&self.fallback_key))
+        })?;
+
+        // Log outside the lock, so that sub-sinks do not block each other
+        let mut result = if sink.should_log(record.level()) {
+            sink.log(record)
+        } else {
+            Ok(())
+        };
+        if let Some(evicted) = evicted {
+            result = Error::push_result(result, evicted.flush());
+        }
+        result
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.flush_with(|sink| sink.flush())
+    }
+
+    fn flush_on_exit(&self) -> Result<()> {
+        self.flush_with(|sink| sink.flush_on_exit())
+    }
+}
+
+impl Drop for RoutingSink {
+    fn drop(&mut self) {
+        if let Err(err) = self.flush() {
+            self.prop.call_error_handler_internal("RoutingSink", err);
+        }
+    }
+}
+
+/// #
+#[doc = include_str!("../include/doc/generic-builder-note.md")]
+pub struct RoutingSinkBuilder<ArgKey, ArgFactory> {
+    prop: SinkProp,
+    formatter: Option<Box<dyn Formatter>>,
+    key: ArgKey,
+    factory: Option<SinkFactory>,
+    fallback_key: String,
+    max_sinks: usize,
+    _phantom: PhantomData<ArgFactory>,
+}
+
+impl<ArgKey, ArgFactory> RoutingSinkBuilder<ArgKey, ArgFactory> {
+    /// Specifies the key that records are routed by.
+    ///
+    /// This parameter is **required**.
+    #[must_use]
+    pub fn key(self, key: RoutingKey) -> RoutingSinkBuilder<RoutingKey, ArgFactory> {
+        RoutingSinkBuilder {
+            prop: self.prop,
+            formatter: self.formatter,
+            key,
+            factory: self.factory,
+            fallback_key: self.fallback_key,
+            max_sinks: self.max_sinks,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Specifies a closure creating the sub-sink for a key.
+    ///
+    /// The key is taken from the records, so it should be validated before
+    /// being used to build paths. If the closure returns an error, the record
+    /// is discarded and the error is returned from logging.
+    ///
+    /// This parameter is **required**.
+    #[must_use]
+    pub fn factory<F>(self, factory: F) -> RoutingSinkBuilder<ArgKey, PhantomData<F>>
+    where
+        F: Fn(&str) -> Result<Arc<dyn Sink>> + Send + Sync + 'static,
+    {
+        RoutingSinkBuilder {
+            prop: self.prop,
+            formatter: self.formatter,
+            key: self.key,
+            factory: Some(Box::new(factory)),
+            fallback_key: self.fallback_key,
+            max_sinks: self.max_sinks,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Specifies the key used for records that do not have the routing key,
+    /// e.g. records from unnamed loggers when routing by logger name.
+    ///
+    /// This parameter is **optional**, and defaults to `"default"`.
+    #[must_use]
+    pub fn fallback_key<S>(mut self, fallback_key: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.fallback_key = fallback_key.into();
+        self
+    }
+
+    /// Specifies the maximum number of sub-sinks kept at the same time.
+    ///
+    /// If the limit is exceeded, the least recently used sub-sink is flushed
+    /// and dropped. It is treated as `1` if `0` is given.
+    ///
+    /// This parameter is **optional**, and defaults to `64`.
+    #[must_use]
+    pub fn max_sinks(mut self, max_sinks: usize) -> Self {
+        self.max_sinks = max_sinks.max(1);
+        self
+    }
+
+    // Prop
+    //
+
+    /// Specifies a log level filter.
+    ///
+    /// This parameter is **optional**, and defaults to [`LevelFilter::All`].
+    #[must_use]
+    pub fn level_filter(self, level_filter: LevelFilter) -> Self {
+        self.prop.set_level_filter(level_filter);
+        self
+    }
+
+    /// Specifies a formatter applied to all sub-sinks.
+    ///
+    /// This parameter is **optional**, and by default sub-sinks keep the
+    /// formatters they are created with.
+    #[must_use]
+    pub fn formatter<F>(mut self, formatter: F) -> Self
+    where
+        F: Formatter + 'static,
+    {
+        self.formatter = Some(Box::new(formatter));
+        self
+    }
+
+    /// Specifies an error handler.
+    ///
+    /// This parameter is **optional**, and defaults to
+    /// [`ErrorHandler::default()`].
+    #[must_use]
+    pub fn error_handler<F: Into<ErrorHandler>>(self, handler: F) -> Self {
+        self.prop.set_error_handler(handler);
+        self
+    }
+}
+
+impl<ArgFactory> RoutingSinkBuilder<(), ArgFactory> {
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required parameter `key`\n\n\
+    ")]
+    pub fn build(self, _: Infallible) {}
+
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required parameter `key`\n\n\
+    ")]
+    pub fn build_arc(self, _: Infallible) {}
+}
+
+impl RoutingSinkBuilder<RoutingKey, ()> {
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required parameter `factory`\n\n\
+    ")]
+    pub fn build(self, _: Infallible) {}
+
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required parameter `factory`\n\n\
+    ")]
+    pub fn build_arc(self, _: Infallible) {}
+}
+
+impl<F> RoutingSinkBuilder<RoutingKey, PhantomData<F>> {
+    /// Builds a [`RoutingSink`].
+    pub fn build(self) -> Result<RoutingSink> {
+        Ok(RoutingSink {
+            prop: self.prop,
+            key: self.key,
+            fallback_key: self.fallback_key,
+            factory: self.factory.unwrap(),
+            max_sinks: self.max_sinks,
+            formatter: RwLock::new(self.formatter),
+            state: Mutex::new(RoutingSinkState {
+                sinks: HashMap::new(),
+                tick: 0,
+            }),
+        })
+    }
+
+    /// Builds a `Arc<RoutingSink>`.
+    ///
+    /// This is a shorthand method for `.build().map(Arc::new)`.
+    pub fn build_arc(self) -> Result<Arc<RoutingSink>> {
+        self.build().map(Arc::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, thread, time::Duration};
+
+    use super::*;
+    use crate::{prelude::*, test_utils::*};
+
+    #[test]
+    fn route_and_evict() {
+        let created = Arc::new(Mutex::new(Vec::<(String, Arc<TestSink>)>::new()));
+        let sink = RoutingSink::builder()
+            .key(RoutingKey::KeyValue("tenant".into()))
+            .factory({
+                let created = created.clone();
+                move |key| {
+                    let sink = Arc::new(TestSink::new());
+                    created.lock_expect().push((key.to_string(), sink.clone()));
+                    Ok(sink)
+                }
+            })
+            .max_sinks(2)
+            .build_arc()
+            .unwrap();
+        let logger = build_test_logger(|b| b.sink(sink.clone()));
+
+        info!(logger: logger, "1", kv: { tenant = "a" });
+        info!(logger: logger, "2", kv: { tenant = "b" });
+        info!(logger: logger, "3", kv: { tenant = "a" });
+        info!(logger: logger, "4");
+        // `b` was evicted, the least recently used one
+        info!(logger: logger, "5", kv: { tenant = "b" });
+
+        let created = created.lock_expect();
+        let summary = created
+            .iter()
+            .map(|(key, sink)| (key.as_str(), sink.payloads(), sink.flush_count()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("a", vec!["1".to_string(), "3".to_string()], 1),
+                ("b", vec!["2".to_string()], 1),
+                ("default", vec!["4".to_string()], 0),
+                ("b", vec!["5".to_string()], 0),
+            ]
+        );
+
+        let mut keys = sink
+            .sinks()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, ["b", "default"]);
+    }
+
+    #[test]
+    fn route_by_formatted_value() {
+        let created = Arc::new(Mutex::new(Vec::<(String, Arc<TestSink>)>::new()));
+        let sink = RoutingSink::builder()
+            .key(RoutingKey::KeyValue("shard".into()))
+            .factory({
+                let created = created.clone();
+                move |key| {
+                    let sink = Arc::new(TestSink::new());
+                    created.lock_expect().push((key.to_string(), sink.clone()));
+                    Ok(sink)
+                }
+            })
+            .build_arc()
+            .unwrap();
+        let logger = build_test_logger(|b| b.sink(sink));
+
+        // Values that are not strings are formatted into a reused buffer
+        info!(logger: logger, "1", kv: { shard = 10 });
+        info!(logger: logger, "2", kv: { shard = 2 });
+        info!(logger: logger, "3", kv: { shard = 10 });
+        info!(logger: logger, "4", kv: { shard = true });
+
+        let created = created.lock_expect();
+        let summary = created
+            .iter()
+            .map(|(key, sink)| (key.as_str(), sink.payloads()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("10", vec!["1".to_string(), "3".to_string()]),
+                ("2", vec!["2".to_string()]),
+                ("true", vec!["4".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn route_by_logger_name_and_level() {
+        let by_name = RoutingSink::builder()
+            .key(RoutingKey::LoggerName)
+            .factory(|_| Ok(Arc::new(TestSink::new())))
+            .build_arc()
+            .unwrap();
+        let by_level = RoutingSink::builder()
+            .key(RoutingKey::Level)
+            .factory(|key| {
+                if key == "trace" {
+                    Err(Error::Downstream("rejected".into()))
+                } else {
+                    Ok(Arc::new(TestSink::new()))
+                }
+            })
+            .build_arc()
+            .unwrap();
+        let logger = build_test_logger(|b| {
+            b.sink(by_name.clone())
+                .sink(by_level.clone())
+                .name("network")
+                .level_filter(LevelFilter::All)
+        });
+
+        info!(logger: logger, "hello");
+        warn!(logger: logger, "world");
+
+        let keys = |sink: &RoutingSink| {
+            let mut keys = sink
+                .sinks()
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>();
+            keys.sort();
+            keys
+        };
+        assert_eq!(keys(&by_name), ["network"]);
+        assert_eq!(keys(&by_level), ["info", "warn"]);
+
+        let record = Record::new(Level::Trace, "rejected", None, None, &[]);
+        assert!(matches!(by_level.log(&record), Err(Error::Downstream(_))));
+    }
+
+    #[test]
+    fn create_outside_lock() {
+        let (tx, rx) = mpsc::channel::<()>();
+        let rx = Mutex::new(rx);
+        let sink = RoutingSink::builder()
+            .key(RoutingKey::LoggerName)
+            .factory(move |key| {
+                if key == "slow" {
+                    rx.lock_expect()
+                        .recv_timeout(Duration::from_secs(10))
+                        .expect("routing was blocked by the factory");
+                }
+                Ok(Arc::new(TestSink::new()))
+            })
+            .build_arc()
+            .unwrap();
+
+        let slow = thread::spawn({
+            let sink = sink.clone();
+            move || {
+                let record = Record::new(Level::Info, "slow", None, Some("slow"), &[]);
+                sink.log(&record).unwrap();
+            }
+        });
+        // Wait for the factory of `slow` to be called
+        thread::sleep(Duration::from_millis(100));
+
+        // Neither routing other keys nor setting the formatter is blocked
+        let record = Record::new(Level::Info, "fast", None, Some("fast"), &[]);
+        sink.log(&record).unwrap();
+        sink.set_formatter(Box::new(NoModFormatter::new()));
+        tx.send(()).unwrap();
+        slow.join().unwrap();
+
+        let mut keys = sink
+            .sinks()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, ["fast", "slow"]);
+    }
+}
@@ -0,0 +1,242 @@
+//! Provides writing files shared with other processes.
+
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write as _},
+    path::Path,
+};
+
+use crate::{sink::reopen, Error, Result};
+
+// Holds an advisory exclusive lock of a file until dropped.
+//
+// The lock is only cooperative between processes that lock the file as well.
+// On non-Unix platforms, it does nothing.
+pub(crate) struct FileLock<'a> {
+    #[cfg_attr(not(unix), allow(dead_code))]
+    file: &'a File,
+}
+
+impl<'a> FileLock<'a> {
+    pub(crate) fn lock(file: &'a File) -> Result<Self> {
+        #[cfg(unix)]
+        {
+            use std::{io, os::unix::io::AsRawFd as _};
+
+            loop {
+                // SAFETY: The file descriptor is valid as long as the file is borrowed.
+                if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } == 0 {
+                    break;
+                }
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::Interrupted {
+                    return Err(Error::LockFile(err));
+                }
+            }
+        }
+        Ok(Self { file })
+    }
+}
+
+impl Drop for FileLock<'_> {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::AsRawFd as _;
+
+            // The lock is released anyway when the file is closed, so the error is ignored.
+            // SAFETY: The file descriptor is valid as long as the file is borrowed.
+            unsafe { libc::flock(self.file.as_raw_fd(), libc::LOCK_UN) };
+        }
+    }
+}
+
+// Writes a formatted record to the file.
+//
+// If the file is shared with other processes, the record bypasses the buffer
+// and is written with a single `write` while holding the lock of the file, so
+// that it is never interleaved with records from other processes. The file must
+// be opened in append mode for this.
+pub(crate) fn write_record(
+    file: &mut BufWriter<File>,
+    bytes: &[u8],
+    multi_process: bool,
+) -> Result<()> {
+    if multi_process {
+        let file = file.get_ref();
+        let _lock = FileLock::lock(file)?;
+        (&*file).write_all(bytes).map_err(Error::WriteRecord)
+    } else {
+        file.write_all(bytes).map_err(Error::WriteRecord)
+    }
+}
+
+// Returns `true` if the file at the path is no longer the opened file, e.g. it
+// has been rotated by another process.
+//
+// Only detectable on Unix, always returns `false` on other platforms.
+#[must_use]
+pub(crate) fn is_replaced(file: &File, path: &Path) -> bool {
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(opened), Ok(current)) => reopen::identity(&opened) != reopen::identity(&current),
+        (Ok(_), Err(_)) => true,
+        (Err(_), _) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, thread};
+
+    use super::*;
+    use crate::{
+        prelude::*,
+        sink::{FileSink, RotatingFileSink, RotationPolicy, Sink},
+        sync::*,
+        test_utils::*,
+    };
+
+    static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+        let path = TEST_LOGS_PATH.join("shared_file");
+        _ = fs::remove_dir_all(&path);
+        fs::create_dir(&path).unwrap();
+        path
+    });
+
+    const THREADS: usize = 4;
+    const RECORDS: usize = 100;
+
+    // Each sink opens its own file description, like different processes do.
+    fn log_concurrently(sinks: Vec<Arc<dyn Sink>>) {
+        thread::scope(|scope| {
+            for (i, sink) in sinks.iter().enumerate() {
+                for _ in 0..THREADS {
+                    let logger = build_test_logger(|b| b.sink(sink.clone()));
+                    let ch = (b'a' + i as u8) as char;
+                    scope.spawn(move || {
+                        for _ in 0..RECORDS {
+                            info!(logger: logger, "{}\n", ch.to_string().repeat(1000));
+                        }
+                    });
+                }
+            }
+        });
+    }
+
+    fn assert_whole_records(contents: &str) -> usize {
+        let lines = contents.lines().collect::<Vec<_>>();
+        for line in &lines {
+            assert_eq!(line.len(), 1000);
+            assert!(line.chars().all(|ch| ch == line.chars().next().unwrap()));
+        }
+        lines.len()
+    }
+
+    #[test]
+    fn file_sink() {
+        let path = LOGS_PATH.join("file_sink.log");
+        let sinks = (0..2)
+            .map(|_| {
+                FileSink::builder()
+                    .path(&path)
+                    .truncate(true)
+                    .multi_process(true)
+                    .formatter(NoModFormatter::new())
+                    .build_arc()
+                    .unwrap() as Arc<dyn Sink>
+            })
+            .collect();
+        log_concurrently(sinks);
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_eq!(assert_whole_records(&contents), 2 * THREADS * RECORDS);
+    }
+
+    #[test]
+    fn rotating_file_sink() {
+        let base_path = LOGS_PATH.join("rotating.log");
+        let sinks = (0..2)
+            .map(|_| {
+                RotatingFileSink::builder()
+                    .base_path(&base_path)
+                    .rotation_policy(RotationPolicy::FileSize(100 * 1024))
+                    .max_files(100)
+                    .multi_process(true)
+                    .formatter(NoModFormatter::new())
+                    .build_arc()
+                    .unwrap() as Arc<dyn Sink>
+            })
+            .collect();
+        log_concurrently(sinks);
+
+        let (files, records) = count_rotated_records("rotating");
+        assert!(files > 1);
+        assert_eq!(records, 2 * THREADS * RECORDS);
+    }
+
+    // Returns the number of files whose name starts with the prefix and the
+    // number of records in them.
+    fn count_rotated_records(prefix: &str) -> (usize, usize) {
+        let mut files = 0;
+        let mut records = 0;
+        for entry in fs::read_dir(&*LOGS_PATH).unwrap() {
+            let path = entry.unwrap().path();
+            if path
+                .file_name()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .starts_with(prefix)
+            {
+                files += 1;
+                records += assert_whole_records(&fs::read_to_string(&path).unwrap());
+            }
+        }
+        (files, records)
+    }
+
+    // The sink is built before forking, so all processes inherit its file.
+    #[cfg(unix)]
+    #[test]
+    fn forked_processes() {
+        const PROCESSES: usize = 4;
+
+        let base_path = LOGS_PATH.join("forked.log");
+        let sink = RotatingFileSink::builder()
+            .base_path(&base_path)
+            .rotation_policy(RotationPolicy::FileSize(10 * 1024))
+            .max_files(1000)
+            .multi_process(true)
+            .formatter(NoModFormatter::new())
+            .build_arc()
+            .unwrap();
+        let logger = build_test_logger(|b| b.sink(sink));
+
+        let children = (0..PROCESSES)
+            .map(|i| {
+                // SAFETY: The child only logs to the sink built above and exits
+                // without running destructors.
+                match unsafe { libc::fork() } {
+                    -1 => panic!("fork failed: {}", std::io::Error::last_os_error()),
+                    0 => {
+                        let ch = (b'a' + i as u8) as char;
+                        for _ in 0..RECORDS {
+                            info!(logger: logger, "{}\n", ch.to_string().repeat(1000));
+                        }
+                        unsafe { libc::_exit(0) }
+                    }
+                    pid => pid,
+                }
+            })
+            .collect::<Vec<_>>();
+        for pid in children {
+            let mut status = 0;
+            assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+            assert!(libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0);
+        }
+
+        let (files, records) = count_rotated_records("forked");
+        assert!(files > 1);
+        assert_eq!(records, PROCESSES * RECORDS);
+    }
+}
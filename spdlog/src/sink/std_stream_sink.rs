@@ -3,6 +3,7 @@
 use std::{
     convert::Infallible,
     io::{self, IsTerminal as _, Write},
+    ops::Range,
     // Import `str` module for function `std::str::from_utf8`, because method `str::from_utf8` is
     // stabilized since Rust 1.87.
     //
@@ -11,7 +12,7 @@ use std::{
 };
 
 use crate::{
-    formatter::{Formatter, FormatterContext},
+    formatter::{EscapeMode, Formatter, FormatterContext},
     sink::{GetSinkProp, Sink, SinkProp},
     sync::*,
     terminal_style::{LevelStyles, Style, StyleMode},
@@ -130,6 +131,7 @@ pub struct StdStreamSink {
     std_stream: StdStream,
     should_render_style: bool,
     level_styles: LevelStyles,
+    escape_mode: EscapeMode,
 }
 
 impl StdStreamSink {
@@ -144,6 +146,7 @@ impl StdStreamSink {
     /// | [std_stream]      | *must be specified*                                                 |
     /// | [style_mode]      | [`StyleMode::Auto`]                                                 |
     /// | [via_print_macro] | `false`, or `true` if feature gate `std-stream-captured` is enabled |
+    /// | [escape_mode]     | [`EscapeMode::Disabled`]                                            |
     ///
     /// [level_filter]: StdStreamSinkBuilder::level_filter
     /// [formatter]: StdStreamSinkBuilder::formatter
@@ -152,6 +155,7 @@ impl StdStreamSink {
     /// [std_stream]: StdStreamSinkBuilder::std_stream
     /// [style_mode]: StdStreamSinkBuilder::style_mode
     /// [via_print_macro]: StdStreamSinkBuilder::via_print_macro
+    /// [escape_mode]: StdStreamSinkBuilder::escape_mode
     #[must_use]
     pub fn builder() -> StdStreamSinkBuilder<()> {
         StdStreamSinkBuilder {
@@ -159,6 +163,7 @@ impl StdStreamSink {
             std_stream: (),
             style_mode: StyleMode::Auto,
             via_print_macro: cfg!(feature = "std-stream-captured"),
+            escape_mode: EscapeMode::Disabled,
         }
     }
 
@@ -186,6 +191,13 @@ impl StdStreamSink {
         self.should_render_style = Self::should_render_style(style_mode, self.std_stream);
     }
 
+    /// Sets the escape mode requested from the formatter.
+    ///
+    /// See [`StdStreamSinkBuilder::escape_mode`] for details.
+    pub fn set_escape_mode(&mut self, escape_mode: EscapeMode) {
+        self.escape_mode = escape_mode;
+    }
+
     #[must_use]
     fn should_render_style(style_mode: StyleMode, stream: StdStream) -> bool {
         let is_terminal = match stream {
@@ -211,6 +223,7 @@ impl Sink for StdStreamSink {
     fn log(&self, record: &Record) -> Result<()> {
         let mut string_buf = StringBuf::new();
         let mut ctx = FormatterContext::new();
+        ctx.set_escape_mode(self.escape_mode);
         self.prop
             .formatter()
             .format(record, &mut string_buf, &mut ctx)?;
@@ -246,17 +259,11 @@ impl StdStreamSink {
         (|| {
             // TODO: Simplify the if block when our MSRV reaches let-chain support.
             if self.should_render_style {
-                if let Some(style_range) = ctx.style_range() {
-                    let style = self.level_styles.style(record.level());
-
-                    dest.write_all(string_buf[..style_range.start].as_bytes())?;
-                    style.write_start(&mut dest)?;
-                    dest.write_all(string_buf[style_range.start..style_range.end].as_bytes())?;
-                    style.write_end(&mut dest)?;
-                    dest.write_all(string_buf[style_range.end..].as_bytes())?;
-                } else {
-                    dest.write_all(string_buf.as_bytes())?;
-                }
+                let level_style = ctx
+                    .style_range()
+                    .map(|range| (range, self.level_styles.style(record.level())));
+
+                write_styled(&mut dest, string_buf, level_style, ctx.styled_ranges())?;
             } else {
                 dest.write_all(string_buf.as_bytes())?;
             }
@@ -275,6 +282,50 @@ impl StdStreamSink {
     }
 }
 
+fn write_styled<W: Write>(
+    dest: &mut W,
+    text: &str,
+    level_style: Option<(Range<usize>, &Style)>,
+    styled_ranges: &[(Range<usize>, Style)],
+) -> io::Result<()> {
+    let write_range = |dest: &mut W, range: Range<usize>, style: &Style| {
+        style.write_start(dest)?;
+        dest.write_all(&text.as_bytes()[range])?;
+        style.write_end(dest)
+    };
+
+    if styled_ranges.is_empty() {
+        match level_style {
+            Some((range, style)) => {
+                dest.write_all(&text.as_bytes()[..range.start])?;
+                write_range(dest, range.clone(), style)?;
+                dest.write_all(&text.as_bytes()[range.end..])?;
+            }
+            None => dest.write_all(text.as_bytes())?,
+        }
+        return Ok(());
+    }
+
+    let mut ranges = styled_ranges
+        .iter()
+        .map(|(range, style)| (range.clone(), style))
+        .chain(level_style)
+        .collect::<Vec<_>>();
+    ranges.sort_by_key(|(range, _)| range.start);
+
+    let mut written = 0;
+    for (range, style) in ranges {
+        // Overlapping ranges are ignored
+        if range.start < written {
+            continue;
+        }
+        dest.write_all(&text.as_bytes()[written..range.start])?;
+        written = range.end;
+        write_range(dest, range, style)?;
+    }
+    dest.write_all(&text.as_bytes()[written..])
+}
+
 // --------------------------------------------------
 
 /// #
@@ -284,6 +335,7 @@ pub struct StdStreamSinkBuilder<ArgSS> {
     std_stream: ArgSS,
     style_mode: StyleMode,
     via_print_macro: bool,
+    escape_mode: EscapeMode,
 }
 
 impl<ArgSS> StdStreamSinkBuilder<ArgSS> {
@@ -313,6 +365,7 @@ impl<ArgSS> StdStreamSinkBuilder<ArgSS> {
             std_stream,
             style_mode: self.style_mode,
             via_print_macro: self.via_print_macro,
+            escape_mode: self.escape_mode,
         }
     }
 
@@ -352,6 +405,21 @@ impl<ArgSS> StdStreamSinkBuilder<ArgSS> {
         self
     }
 
+    /// Specifies the escape mode requested from the formatter.
+    ///
+    /// The formatter escapes untrusted text in log records, such as payloads
+    /// and key-values, with the stricter one of this mode and its own
+    /// configured mode. Usually [`EscapeMode::Terminal`] is desired if the
+    /// output is displayed in a terminal. See [`EscapeMode`] for details.
+    ///
+    /// This parameter is **optional**, and defaults to
+    /// [`EscapeMode::Disabled`].
+    #[must_use]
+    pub fn escape_mode(mut self, escape_mode: EscapeMode) -> Self {
+        self.escape_mode = escape_mode;
+        self
+    }
+
     // Prop
     //
 
@@ -417,6 +485,7 @@ impl StdStreamSinkBuilder<StdStream> {
                 self.std_stream,
             ),
             level_styles: LevelStyles::default(),
+            escape_mode: self.escape_mode,
         })
     }
 
@@ -468,3 +537,46 @@ fn enable_ansi_escape_sequences() -> bool {
 fn enable_ansi_escape_sequences() -> bool {
     true
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal_style::Color;
+
+    #[test]
+    fn write_styled_ranges() {
+        let level = Style::builder().color(Color::Green).build();
+        let dim = Style::builder().faint().build();
+        let cyan = Style::builder().color(Color::Cyan).build();
+
+        let render = |level_style, styled_ranges: &[(Range<usize>, Style)]| {
+            let mut dest = Vec::new();
+            write_styled(
+                &mut dest,
+                "[time] [info] logger: msg",
+                level_style,
+                styled_ranges,
+            )
+            .unwrap();
+            String::from_utf8(dest).unwrap()
+        };
+
+        assert_eq!(render(None, &[]), "[time] [info] logger: msg");
+        assert_eq!(
+            render(Some((8..12, &level)), &[]),
+            "[time] [\x1b[32minfo\x1b[m] logger: msg"
+        );
+        assert_eq!(
+            render(
+                Some((8..12, &level)),
+                &[(14..20, cyan.clone()), (0..6, dim.clone())]
+            ),
+            "\x1b[2m[time]\x1b[m [\x1b[32minfo\x1b[m] \x1b[36mlogger\x1b[m: msg"
+        );
+        // The overlapping range is ignored
+        assert_eq!(
+            render(None, &[(0..6, dim), (4..10, cyan)]),
+            "\x1b[2m[time]\x1b[m [info] logger: msg"
+        );
+    }
+}
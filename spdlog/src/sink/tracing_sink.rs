@@ -0,0 +1,580 @@
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher as _, Hash as _, Hasher as _},
+};
+
+use once_cell::sync::OnceCell;
+use tracing_core::{
+    callsite::{self, Callsite, Identifier},
+    field::{self, FieldSet, Value},
+    metadata::Kind,
+    subscriber::Interest,
+    Event, Metadata,
+};
+
+use crate::{
+    formatter::{pattern, EscapeMode, Formatter, FormatterContext, PatternFormatter},
+    kv,
+    sink::{GetSinkProp, Sink, SinkProp},
+    sync::*,
+    ErrorHandler, LevelFilter, Record, Result, SourceLocation, StringBuf,
+};
+
+/// A sink with the current dispatcher of [tracing crate] as the target.
+///
+/// It is the opposite direction of [`TracingLayer`], records are re-emitted as
+/// `tracing` events, so that they end up in the pipeline of whatever
+/// subscriber the application installed.
+///
+/// - The formatted record becomes the `message` field, by default only the
+///   payload is formatted, since the subscriber usually has its own format.
+/// - The target is the logger name, or the module path if the logger has no
+///   name.
+/// - The key-values of the record become fields of the event. Since `tracing`
+///   supports at most 32 fields for an event, key-values exceeding the limit
+///   are dropped.
+///
+/// `tracing` requires the metadata of each event, including the target and the
+/// field names, to be `'static`. This sink creates the metadata on demand and
+/// keeps it alive for the rest of the process, once for each distinct
+/// combination of the logger name, level, source location and key names. To
+/// bound the memory if logger names or keys are generated dynamically, at most
+/// 1024 combinations are created by a sink. Records of further combinations
+/// are emitted with the target `spdlog`, the logger name or module path in
+/// field `spdlog.target`, and the formatted key-values in field
+/// `spdlog.key_values`.
+///
+/// This sink is only available if crate feature `tracing` is enabled. Do not
+/// use it with a logger that [`TracingLayer`] forwards to, otherwise records
+/// loop between the two.
+///
+/// # Log Level Mapping
+///
+/// | spdlog-rs  | tracing |
+/// |------------|---------|
+/// | `Critical` | `ERROR` |
+/// | `Error`    | `ERROR` |
+/// | `Warn`     | `WARN`  |
+/// | `Info`     | `INFO`  |
+/// | `Debug`    | `DEBUG` |
+/// | `Trace`    | `TRACE` |
+///
+/// [tracing crate]: https://crates.io/crates/tracing
+/// [`TracingLayer`]: crate::TracingLayer
+pub struct TracingSink {
+    prop: SinkProp,
+    callsites: CallsiteCache,
+}
+
+impl TracingSink {
+    /// Gets a builder of `TracingSink` with default parameters:
+    ///
+    /// | Parameter       | Default Value                             |
+    /// |-----------------|-------------------------------------------|
+    /// | [level_filter]  | [`LevelFilter::All`]                      |
+    /// | [formatter]     | [`PatternFormatter`] `("{payload}")`      |
+    /// | [error_handler] | [`ErrorHandler::default()`]               |
+    ///
+    /// [level_filter]: TracingSinkBuilder::level_filter
+    /// [formatter]: TracingSinkBuilder::formatter
+    /// [`PatternFormatter`]: crate::formatter::PatternFormatter
+    /// [error_handler]: TracingSinkBuilder::error_handler
+    #[must_use]
+    pub fn builder() -> TracingSinkBuilder {
+        let prop = SinkProp::default();
+        prop.set_formatter(PatternFormatter::new(pattern!("{payload}")));
+
+        TracingSinkBuilder { prop }
+    }
+}
+
+impl GetSinkProp for TracingSink {
+    fn prop(&self) -> &SinkProp {
+        &self.prop
+    }
+}
+
+impl Sink for TracingSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        let level = tracing_core::Level::from(record.level());
+        if level > tracing_core::LevelFilter::current() {
+            return Ok(());
+        }
+
+        let kvs = record.key_values();
+        let srcloc = record.source_location();
+        let target = record
+            .logger_name()
+            .or_else(|| srcloc.map(|srcloc| srcloc.module_path()))
+            .unwrap_or_default();
+        let key = CallsiteKey {
+            target,
+            level,
+            srcloc,
+            kvs: &kvs,
+        };
+        let callsite = self.callsites.get_or_register(&key);
+        let metadata = callsite.metadata();
+
+        tracing_core::dispatcher::get_default(|dispatch| -> Result<()> {
+            if !dispatch.enabled(metadata) {
+                return Ok(());
+            }
+
+            let mut string_buf = StringBuf::new();
+            let mut ctx = FormatterContext::new();
+            self.prop
+                .formatter()
+                .format(record, &mut string_buf, &mut ctx)?;
+            let message = field::display(&string_buf);
+
+            let fields = metadata.fields();
+            let mut iter = fields.iter();
+            let message_field = iter.next().unwrap();
+            // Unused slots are padded with an absent value, which is skipped by visitors
+            let mut value_set: [(&field::Field, Option<&dyn Value>); MAX_FIELDS] =
+                [(&message_field, None); MAX_FIELDS];
+            value_set[0].1 = Some(&message);
+
+            if callsite.generic {
+                let mut key_values = String::new();
+                _ = kvs.write_to(&mut key_values, false, EscapeMode::Disabled);
+                let key_values = key_values.as_str();
+                let (target_field, kvs_field) = (iter.next().unwrap(), iter.next().unwrap());
+                value_set[1] = (&target_field, Some(&target));
+                value_set[2] = (&kvs_field, Some(&key_values));
+                dispatch.event(&Event::new(metadata, &fields.value_set(&value_set)));
+            } else {
+                let values = kvs
+                    .iter()
+                    .take(MAX_FIELDS - 1)
+                    .map(FieldValue::from)
+                    .collect::<Vec<_>>();
+                let fields_and_values = iter.zip(values.iter()).collect::<Vec<_>>();
+                for (slot, (field, value)) in value_set[1..].iter_mut().zip(&fields_and_values) {
+                    *slot = (field, Some(value.as_value()));
+                }
+                dispatch.event(&Event::new(metadata, &fields.value_set(&value_set)));
+            }
+            Ok(())
+        })
+    }
+
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+// Including the `message` field.
+const MAX_FIELDS: usize = 32;
+
+// The maximum number of callsites created by a sink.
+const MAX_CALLSITES: usize = 1024;
+
+const GENERIC_TARGET: &str = "spdlog";
+const GENERIC_FIELDS: &[&str] = &["message", "spdlog.target", "spdlog.key_values"];
+
+// Borrowed from the record, so that looking up an existing callsite does not
+// allocate.
+struct CallsiteKey<'a> {
+    target: &'a str,
+    level: tracing_core::Level,
+    srcloc: Option<&'a SourceLocation>,
+    kvs: &'a kv::KeyValues<'a>,
+}
+
+impl CallsiteKey<'_> {
+    fn keys(&self) -> impl Iterator<Item = kv::Key<'_>> {
+        self.kvs.iter().take(MAX_FIELDS - 1).map(|(key, _)| key)
+    }
+
+    #[must_use]
+    fn matches(&self, metadata: &Metadata) -> bool {
+        let mut names = metadata.fields().iter().skip(1).map(|field| field.name());
+        metadata.target() == self.target
+            && *metadata.level() == self.level
+            && metadata.module_path() == self.srcloc.map(|srcloc| srcloc.module_path())
+            && metadata.file() == self.srcloc.map(|srcloc| srcloc.file())
+            && metadata.line() == self.srcloc.map(|srcloc| srcloc.line())
+            && self.keys().all(|key| names.next() == Some(key.as_str()))
+            && names.next().is_none()
+    }
+}
+
+struct CallsiteCache {
+    max_len: usize,
+    hash_builder: std::collections::hash_map::RandomState,
+    // Keyed by the hash of `CallsiteKey`, colliding callsites share a bucket
+    callsites: RwLock<HashMap<u64, Vec<&'static DynamicCallsite>>>,
+    len: AtomicUsize,
+}
+
+impl CallsiteCache {
+    #[must_use]
+    fn new(max_len: usize) -> Self {
+        Self {
+            max_len,
+            hash_builder: Default::default(),
+            callsites: RwLock::new(HashMap::new()),
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    #[must_use]
+    fn get_or_register(&self, key: &CallsiteKey) -> &'static DynamicCallsite {
+        let hash = self.hash(key);
+        let find = |callsites: &HashMap<u64, Vec<&'static DynamicCallsite>>| {
+            callsites
+                .get(&hash)
+                .and_then(|bucket| bucket.iter().find(|cs| key.matches(cs.metadata())))
+                .copied()
+        };
+
+        if let Some(callsite) = find(&self.callsites.read_expect()) {
+            return callsite;
+        }
+        if self.len.load(Ordering::Relaxed) >= self.max_len {
+            return DynamicCallsite::generic(key.level);
+        }
+
+        // Registering notifies the subscriber, which may log, so no lock is held
+        let callsite = DynamicCallsite::register(key);
+        let mut callsites = self.callsites.write_expect();
+        if let Some(existing) = find(&callsites) {
+            // Registered concurrently, the new callsite is wasted but harmless
+            return existing;
+        }
+        callsites.entry(hash).or_default().push(callsite);
+        self.len.fetch_add(1, Ordering::Relaxed);
+        callsite
+    }
+
+    #[must_use]
+    fn hash(&self, key: &CallsiteKey) -> u64 {
+        let mut hasher = self.hash_builder.build_hasher();
+        key.target.hash(&mut hasher);
+        key.level.hash(&mut hasher);
+        key.srcloc
+            .map(|srcloc| (srcloc.module_path(), srcloc.file(), srcloc.line()))
+            .hash(&mut hasher);
+        for key in key.keys() {
+            key.as_str().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+// A callsite created at runtime. It is leaked, since `tracing` requires
+// callsites to be `'static`.
+struct DynamicCallsite {
+    metadata: OnceCell<Metadata<'static>>,
+    // Whether this is the callsite used once `MAX_CALLSITES` is reached
+    generic: bool,
+}
+
+impl DynamicCallsite {
+    #[must_use]
+    fn register(key: &CallsiteKey) -> &'static Self {
+        fn leak(s: &str) -> &'static str {
+            Box::leak(s.to_string().into_boxed_str())
+        }
+
+        let names = std::iter::once("message")
+            .chain(key.keys().map(|key| leak(key.as_str())))
+            .collect::<Vec<_>>();
+        let (module_path, file, line) = match key.srcloc {
+            Some(srcloc) => (
+                Some(srcloc.module_path()),
+                Some(srcloc.file()),
+                Some(srcloc.line()),
+            ),
+            None => (None, None, None),
+        };
+        Self::new(
+            leak(key.target),
+            key.level,
+            (module_path, file, line),
+            names.leak(),
+            false,
+        )
+    }
+
+    // One callsite is shared by each level, for all sinks.
+    #[must_use]
+    fn generic(level: tracing_core::Level) -> &'static Self {
+        static CALLSITES: [OnceCell<&'static DynamicCallsite>; 5] = [
+            OnceCell::new(),
+            OnceCell::new(),
+            OnceCell::new(),
+            OnceCell::new(),
+            OnceCell::new(),
+        ];
+
+        let index = match level {
+            tracing_core::Level::ERROR => 0,
+            tracing_core::Level::WARN => 1,
+            tracing_core::Level::INFO => 2,
+            tracing_core::Level::DEBUG => 3,
+            tracing_core::Level::TRACE => 4,
+        };
+        CALLSITES[index].get_or_init(|| {
+            Self::new(
+                GENERIC_TARGET,
+                level,
+                (None, None, None),
+                GENERIC_FIELDS,
+                true,
+            )
+        })
+    }
+
+    #[must_use]
+    fn new(
+        target: &'static str,
+        level: tracing_core::Level,
+        (module_path, file, line): (Option<&'static str>, Option<&'static str>, Option<u32>),
+        names: &'static [&'static str],
+        generic: bool,
+    ) -> &'static Self {
+        let callsite: &'static Self = Box::leak(Box::new(Self {
+            metadata: OnceCell::new(),
+            generic,
+        }));
+        let metadata = Metadata::new(
+            "spdlog event",
+            target,
+            level,
+            file,
+            line,
+            module_path,
+            FieldSet::new(names, Identifier(callsite)),
+            Kind::EVENT,
+        );
+        assert!(callsite.metadata.set(metadata).is_ok());
+        callsite::register(callsite);
+        callsite
+    }
+}
+
+impl Callsite for DynamicCallsite {
+    fn set_interest(&self, _: Interest) {
+        // The interest is not cached, `Dispatch::enabled` is checked for each record
+    }
+
+    fn metadata(&self) -> &Metadata<'_> {
+        self.metadata.get().unwrap()
+    }
+}
+
+// `tracing_core::field::Value` is sealed, so key-values are converted to the
+// primitive types implementing it.
+enum FieldValue<'a> {
+    Str(&'a str),
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Display(field::DisplayValue<kv::Value<'a>>),
+}
+
+impl<'a> From<(kv::Key<'a>, kv::Value<'a>)> for FieldValue<'a> {
+    fn from((_, value): (kv::Key<'a>, kv::Value<'a>)) -> Self {
+        if let Some(value) = value.to_borrowed_str() {
+            Self::Str(value)
+        } else if let Some(value) = value.to_bool() {
+            Self::Bool(value)
+        } else if let Some(value) = value.to_i64() {
+            Self::I64(value)
+        } else if let Some(value) = value.to_u64() {
+            Self::U64(value)
+        } else if let Some(value) = value.to_f64() {
+            Self::F64(value)
+        } else {
+            Self::Display(field::display(value))
+        }
+    }
+}
+
+impl FieldValue<'_> {
+    #[must_use]
+    fn as_value(&self) -> &dyn Value {
+        match self {
+            Self::Str(value) => value,
+            Self::Bool(value) => value,
+            Self::I64(value) => value,
+            Self::U64(value) => value,
+            Self::F64(value) => value,
+            Self::Display(value) => value,
+        }
+    }
+}
+
+#[allow(missing_docs)]
+pub struct TracingSinkBuilder {
+    prop: SinkProp,
+}
+
+impl TracingSinkBuilder {
+    // Prop
+    //
+
+    /// Specifies a log level filter.
+    ///
+    /// This parameter is **optional**, and defaults to [`LevelFilter::All`].
+    #[must_use]
+    pub fn level_filter(self, level_filter: LevelFilter) -> Self {
+        self.prop.set_level_filter(level_filter);
+        self
+    }
+
+    /// Specifies a formatter.
+    ///
+    /// This parameter is **optional**, and defaults to [`PatternFormatter`]
+    /// `("{payload}")`.
+    ///
+    /// [`PatternFormatter`]: crate::formatter::PatternFormatter
+    #[must_use]
+    pub fn formatter<F>(self, formatter: F) -> Self
+    where
+        F: Formatter + 'static,
+    {
+        self.prop.set_formatter(formatter);
+        self
+    }
+
+    /// Specifies an error handler.
+    ///
+    /// This parameter is **optional**, and defaults to
+    /// [`ErrorHandler::default()`].
+    #[must_use]
+    pub fn error_handler<F: Into<ErrorHandler>>(self, handler: F) -> Self {
+        self.prop.set_error_handler(handler);
+        self
+    }
+
+    //
+
+    /// Builds a [`TracingSink`].
+    pub fn build(self) -> Result<TracingSink> {
+        let sink = TracingSink {
+            prop: self.prop,
+            callsites: CallsiteCache::new(MAX_CALLSITES),
+        };
+        Ok(sink)
+    }
+
+    /// Builds a `Arc<TracingSink>`.
+    ///
+    /// This is a shorthand method for `.build().map(Arc::new)`.
+    pub fn build_arc(self) -> Result<Arc<TracingSink>> {
+        self.build().map(Arc::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt;
+
+    use tracing_subscriber::{layer::SubscriberExt as _, Layer};
+
+    use super::*;
+    use crate::{info, test_utils::*, warn};
+
+    #[derive(Default)]
+    struct Capture(Arc<Mutex<Vec<String>>>);
+
+    impl<S: tracing_core::Subscriber> Layer<S> for Capture {
+        fn on_event(&self, event: &Event<'_>, _: tracing_subscriber::layer::Context<'_, S>) {
+            struct Visitor(String);
+            impl field::Visit for Visitor {
+                fn record_i64(&mut self, field: &field::Field, value: i64) {
+                    self.0 += &format!(" {field}={value}(i64)");
+                }
+                fn record_bool(&mut self, field: &field::Field, value: bool) {
+                    self.0 += &format!(" {field}={value}(bool)");
+                }
+                fn record_str(&mut self, field: &field::Field, value: &str) {
+                    self.0 += &format!(" {field}={value}(str)");
+                }
+                fn record_debug(&mut self, field: &field::Field, value: &dyn fmt::Debug) {
+                    self.0 += &format!(" {field}={value:?}");
+                }
+            }
+
+            let metadata = event.metadata();
+            let mut visitor = Visitor(String::new());
+            event.record(&mut visitor);
+            self.0.lock_expect().push(format!(
+                "[{}] [{}]{}",
+                metadata.level(),
+                metadata.target(),
+                visitor.0
+            ));
+        }
+    }
+
+    #[test]
+    fn forward_to_tracing() {
+        let sink = TracingSink::builder().build_arc().unwrap();
+        let named = build_test_logger(|b| b.sink(sink.clone()).name("network"));
+        let unnamed = build_test_logger(|b| b.sink(sink.clone()));
+
+        let events = Arc::new(Mutex::new(vec![]));
+        let subscriber = tracing_subscriber::registry().with(Capture(events.clone()).with_filter(
+            tracing_subscriber::filter::filter_fn(|metadata| metadata.target() != "filtered"),
+        ));
+        tracing::subscriber::with_default(subscriber, || {
+            for _ in 0..2 {
+                info!(logger: named, "connected to {}", "example.com", kv: { port = 443, tls = true });
+            }
+            warn!(logger: unnamed, "retrying", kv: { reason:? = Some(1) });
+            let filtered = build_test_logger(|b| b.sink(sink.clone()).name("filtered"));
+            info!(logger: filtered, "filtered out by the subscriber");
+        });
+
+        let unnamed_target = if cfg!(feature = "source-location") {
+            module_path!()
+        } else {
+            ""
+        };
+        assert_eq!(
+            *events.lock_expect(),
+            [
+                "[INFO] [network] message=connected to example.com port=443(i64) tls=true(bool)"
+                    .to_string(),
+                "[INFO] [network] message=connected to example.com port=443(i64) tls=true(bool)"
+                    .to_string(),
+                format!("[WARN] [{unnamed_target}] message=retrying reason=Some(1)"),
+            ]
+        );
+        assert_eq!(sink.callsites.len.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn callsite_limit() {
+        let mut sink = TracingSink::builder().build().unwrap();
+        sink.callsites = CallsiteCache::new(1);
+        let sink = Arc::new(sink);
+        let first = build_test_logger(|b| b.sink(sink.clone()).name("first"));
+        let second = build_test_logger(|b| b.sink(sink.clone()).name("second"));
+
+        let events = Arc::new(Mutex::new(vec![]));
+        let subscriber = tracing_subscriber::registry().with(Capture(events.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            // From the same callsite, so that only the logger name differs
+            for logger in [&first, &second, &first] {
+                info!(logger: logger, "message", kv: { a = 1, b = "x" });
+            }
+        });
+
+        assert_eq!(
+            *events.lock_expect(),
+            [
+                "[INFO] [first] message=message a=1(i64) b=x(str)",
+                "[INFO] [spdlog] message=message spdlog.target=second(str) spdlog.key_values=a=1 b=x(str)",
+                "[INFO] [first] message=message a=1(i64) b=x(str)",
+            ]
+        );
+        assert_eq!(sink.callsites.len.load(Ordering::Relaxed), 1);
+    }
+}
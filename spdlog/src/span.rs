@@ -0,0 +1,319 @@
+//! Provides spans for timing scopes.
+//!
+//! A span represents a named scope, usually a unit of work. It is created by
+//! macro [`span!`] or attribute [`instrument`], and lives until the returned
+//! guard is dropped:
+//!
+//! - When the span is entered, a record `"{name} entered"` is logged. This can
+//!   be disabled by passing `log_enter: false`.
+//! - When the span is exited, a record `"{name} exited"` is logged, with an
+//!   additional key-value `elapsed`, the duration the span was alive.
+//!
+//! While the span is alive, key-value `span` and the key-values given to the
+//! span are pushed into the [context], so they are attached to every record
+//! created on the current thread, including the enter and exit records. If
+//! spans are nested, the value of `span` is the path of the span names joined
+//! by `:`, e.g. `handle_request:query`.
+//!
+//! If the level of a span is filtered out by the logger, the span does
+//! nothing, and is not included in the path of its nested spans.
+//!
+//! # Examples
+//!
+//! ```
+//! use spdlog::{info, span, Level};
+//! # use spdlog::formatter::{pattern, PatternFormatter};
+#![doc = include_str!(concat!(env!("OUT_DIR"), "/test_utils/common_for_doc_test.rs"))]
+//!
+//! # let (doctest, sink) = test_utils::echo_logger_from_pattern(pattern!("{payload} {kv}{eol}"), None);
+//! # let doctest = &doctest;
+//! # let query = |table: &str| {
+//! let _span = span!(logger: doctest, Level::Info, "query", kv: { table }, log_enter: false);
+//! info!(logger: doctest, "rows fetched", kv: { rows = 3 });
+//! // ...
+//! # };
+//! # let _outer = span!(logger: doctest, Level::Info, "handle_request");
+//! # query("users");
+//! # let output = sink.clone_string().replace("\r", "");
+//! # let output = regex::Regex::new(r"elapsed=[0-9.]+[nµm]?s").unwrap().replace_all(&output, "elapsed=..");
+//! # assert_eq!(output,
+//! /* Output */ "handle_request entered span=handle_request
+//! rows fetched rows=3 span=handle_request:query table=users
+//! query exited elapsed=.. span=handle_request:query table=users\n"
+//! # );
+//! ```
+//!
+//! [`span!`]: crate::span!
+//! [`instrument`]: crate::instrument
+//! [context]: crate::context
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    context::{self, ContextGuard},
+    kv::{self, Key},
+    Level, Logger, SourceLocation,
+};
+
+const SPAN_KEY: &str = "span";
+const PATH_SEPARATOR: char = ':';
+
+/// A guard representing an entered span.
+///
+/// It is returned by macro [`span!`] and logs the exit record when dropped. See
+/// the [module level documentation](self) for more details.
+///
+/// Since the key-values of the span are pushed into the context of the current
+/// thread, a span must be dropped on the thread it was created.
+///
+/// [`span!`]: crate::span!
+#[must_use = "the span exits immediately if the guard is not held"]
+pub struct Span<'a> {
+    inner: Option<SpanInner<'a>>,
+}
+
+struct SpanInner<'a> {
+    logger: SpanLogger<'a>,
+    level: Level,
+    srcloc: Option<SourceLocation>,
+    name: &'static str,
+    start: Instant,
+    // Dropped after the exit record is logged, so that the record still carries
+    // the key-values of the span.
+    _context: ContextGuard,
+}
+
+impl<'a> Span<'a> {
+    // Users should use macro `span!` or attribute `instrument` instead.
+    #[doc(hidden)]
+    pub fn __new(
+        logger: SpanLogger<'a>,
+        level: Level,
+        srcloc: Option<SourceLocation>,
+        name: &'static str,
+        kvs: &[kv::Pair],
+        log_enter: bool,
+    ) -> Self {
+        let enabled = crate::STATIC_LEVEL_FILTER.__test_const(level)
+            && logger.with(|logger| logger.should_log(level));
+        if !enabled {
+            return Self { inner: None };
+        }
+
+        let path = match context::Context::current()
+            .key_values()
+            .get(Key::__from_static_str(SPAN_KEY))
+        {
+            Some(parent) => format!("{parent}{PATH_SEPARATOR}{name}"),
+            None => name.to_string(),
+        };
+        let mut pairs = Vec::with_capacity(kvs.len() + 1);
+        pairs.push((Key::__from_static_str(SPAN_KEY), kv::Value::from(&*path)));
+        pairs.extend(kvs.iter().cloned());
+        let context = context::push(&pairs);
+
+        let inner = SpanInner {
+            logger,
+            level,
+            srcloc,
+            name,
+            start: Instant::now(),
+            _context: context,
+        };
+        if log_enter {
+            inner.log(&[], format_args!("{name} entered"));
+        }
+        Self { inner: Some(inner) }
+    }
+
+    /// Gets the time elapsed since the span was entered.
+    ///
+    /// Returns `None` if the span is disabled.
+    #[must_use]
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.inner.as_ref().map(|inner| inner.start.elapsed())
+    }
+}
+
+impl Drop for Span<'_> {
+    fn drop(&mut self) {
+        if let Some(inner) = &self.inner {
+            let elapsed = inner.start.elapsed();
+            inner.log(
+                &[(
+                    Key::__from_static_str("elapsed"),
+                    kv::Value::from_debug(&elapsed),
+                )],
+                format_args!("{} exited", inner.name),
+            );
+        }
+    }
+}
+
+impl SpanInner<'_> {
+    fn log(&self, kvs: &[kv::Pair], fmt_args: std::fmt::Arguments) {
+        self.logger
+            .with(|logger| crate::__log(logger, self.level, self.srcloc.clone(), kvs, fmt_args));
+    }
+}
+
+// The logger of a span. The default logger is loaded each time it is used, so
+// that the guard does not need to hold it.
+#[doc(hidden)]
+pub enum SpanLogger<'a> {
+    Borrowed(&'a Logger),
+    Default,
+}
+
+impl SpanLogger<'_> {
+    fn with<R>(&self, f: impl FnOnce(&Logger) -> R) -> R {
+        match self {
+            Self::Borrowed(logger) => f(logger),
+            Self::Default => f(&crate::default_logger()),
+        }
+    }
+}
+
+// Used as the default value of parameter `logger` of macro `span!`.
+#[doc(hidden)]
+pub struct __DefaultLogger;
+
+#[doc(hidden)]
+pub trait __ToSpanLogger {
+    fn __to_span_logger(&self) -> SpanLogger<'_>;
+}
+
+impl __ToSpanLogger for Logger {
+    fn __to_span_logger(&self) -> SpanLogger<'_> {
+        SpanLogger::Borrowed(self)
+    }
+}
+
+impl __ToSpanLogger for crate::sync::Arc<Logger> {
+    fn __to_span_logger(&self) -> SpanLogger<'_> {
+        SpanLogger::Borrowed(self)
+    }
+}
+
+impl __ToSpanLogger for __DefaultLogger {
+    fn __to_span_logger(&self) -> SpanLogger<'_> {
+        SpanLogger::Default
+    }
+}
+
+impl<T: __ToSpanLogger + ?Sized> __ToSpanLogger for &T {
+    fn __to_span_logger(&self) -> SpanLogger<'_> {
+        (**self).__to_span_logger()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{info, span, sync::*, test_utils::*, LevelFilter};
+
+    fn kvs_of(record: &crate::RecordOwned) -> Vec<String> {
+        record
+            .key_values()
+            .iter()
+            .filter(|(k, _)| k.as_str() != "elapsed")
+            .map(|(k, v)| format!("{}={v}", k.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn enter_exit_and_nesting() {
+        let sink = Arc::new(TestSink::new());
+        let logger = Arc::new(build_test_logger(|b| {
+            b.sink(sink.clone())
+                .level_filter(LevelFilter::MoreSevereEqual(Level::Info))
+        }));
+
+        {
+            let outer = span!(logger: logger, Level::Info, "outer", kv: { id = 1 });
+            assert!(outer.elapsed().is_some());
+            {
+                let _inner = span!(logger: logger, Level::Warn, "inner", log_enter: false);
+                info!(logger: logger, "work");
+            }
+            let disabled = span!(logger: logger, Level::Debug, "disabled");
+            assert!(disabled.elapsed().is_none());
+            info!(logger: logger, "after");
+        }
+        info!(logger: logger, "outside");
+
+        let records = sink.records();
+        let summary = records
+            .iter()
+            .map(|record| (record.level(), record.payload().to_string(), kvs_of(record)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                (
+                    Level::Info,
+                    "outer entered".into(),
+                    vec!["span=outer", "id=1"]
+                ),
+                (Level::Info, "work".into(), vec!["span=outer:inner", "id=1"]),
+                (
+                    Level::Warn,
+                    "inner exited".into(),
+                    vec!["span=outer:inner", "id=1"]
+                ),
+                (Level::Info, "after".into(), vec!["span=outer", "id=1"]),
+                (
+                    Level::Info,
+                    "outer exited".into(),
+                    vec!["span=outer", "id=1"]
+                ),
+                (Level::Info, "outside".into(), vec![]),
+            ]
+            .map(|(level, payload, kvs): (_, String, Vec<&str>)| {
+                (level, payload, kvs.into_iter().map(String::from).collect())
+            })
+        );
+
+        let exits = [&records[2], &records[4]];
+        for record in exits {
+            assert!(record
+                .key_values()
+                .get(Key::__from_static_str("elapsed"))
+                .is_some());
+        }
+    }
+
+    #[test]
+    fn instrument_function() {
+        let sink = Arc::new(TestSink::new());
+        let logger = build_test_logger(|b| b.sink(sink.clone()));
+
+        #[crate::instrument(logger: logger, kv: { id, tag:? }, log_enter: false)]
+        fn work(logger: &Logger, id: u32, tag: Option<&str>) -> u32 {
+            info!(logger: logger, "working");
+            id * 2
+        }
+
+        #[crate::instrument(logger: logger, level: Level::Warn, name: "renamed")]
+        fn named(logger: &Logger) {}
+
+        assert_eq!(work(&logger, 21, Some("x")), 42);
+        named(&logger);
+
+        let records = sink.records();
+        let summary = records
+            .iter()
+            .map(|record| format!("{} {}", record.payload(), kvs_of(record).join(" ")))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                "working span=work id=21 tag=Some(\"x\")",
+                "work exited span=work id=21 tag=Some(\"x\")",
+                "renamed entered span=renamed",
+                "renamed exited span=renamed",
+            ]
+        );
+        assert_eq!(records[3].level(), Level::Warn);
+    }
+}
@@ -19,7 +19,6 @@ pub use self::atomic::*;
 
 pub trait MutexExtend<T: ?Sized> {
     fn lock_expect(&self) -> MutexGuard<'_, T>;
-    fn get_mut_expect(&mut self) -> &mut T;
 }
 
 pub trait RwLockExtend<T: ?Sized> {
@@ -33,9 +32,6 @@ impl<T: ?Sized> MutexExtend<T> for Mutex<T> {
     fn lock_expect(&self) -> MutexGuard<'_, T> {
         self.lock().expect(LOCK_POISONED_MESSAGE)
     }
-    fn get_mut_expect(&mut self) -> &mut T {
-        self.get_mut().expect(LOCK_POISONED_MESSAGE)
-    }
 }
 
 impl<T: ?Sized> RwLockExtend<T> for RwLock<T> {
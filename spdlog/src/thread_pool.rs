@@ -1,22 +1,41 @@
 use std::{
+    mem::ManuallyDrop,
     num::NonZeroUsize,
     thread::{self, JoinHandle},
 };
 
-use crossbeam::channel::{self as mpmc, Receiver, Sender};
+use crossbeam::queue::ArrayQueue;
 use once_cell::sync::Lazy;
 
 use crate::{
-    error::Error,
+    error::{Error, SendToChannelError},
     sink::{OverflowPolicy, Task},
     sync::*,
-    Result,
+    Record, RecordOwned, Result,
 };
 
 /// A thread pool for processing operations asynchronously.
 ///
 /// Currently only used in [`AsyncPoolSink`].
 ///
+/// Operations are queued in [shards], each thread submitting operations is
+/// assigned a shard, so that threads sharing a shard contend with each other
+/// only. Worker threads take operations from the shards in batches, and pass
+/// consecutive records of the same [`AsyncPoolSink`] to its sinks at once via
+/// [`Sink::log_batch`].
+///
+/// Each shard is processed by a single worker thread, so operations submitted
+/// by the same thread are processed in order, while the order of operations
+/// submitted by different threads is not preserved. Flushes are the exception,
+/// the records submitted by any thread before a flush are processed before the
+/// sinks are flushed.
+///
+/// Records are reused after being processed by the shard they are submitted to,
+/// so that their buffers do not have to be allocated again for subsequent
+/// records. Records larger than a threshold are not reused, to avoid keeping
+/// memory for rare large records. The arguments of records logged by
+/// [`log_deferred!`] are still allocated for each record.
+///
 /// # Examples
 ///
 /// ```
@@ -33,12 +52,15 @@ use crate::{
 /// # Ok(()) }
 /// ```
 ///
+/// [shards]: ThreadPoolBuilder::shards
+/// [`log_deferred!`]: crate::log_deferred
 /// [`AsyncPoolSink`]: crate::sink::AsyncPoolSink
+/// [`Sink::log_batch`]: crate::sink::Sink::log_batch
 pub struct ThreadPool(ArcSwapOption<ThreadPoolInner>);
 
 struct ThreadPoolInner {
     threads: Vec<Option<JoinHandle<()>>>,
-    sender: Option<Sender<Task>>,
+    queue: Arc<TaskQueue>,
 }
 
 type Callback = Arc<dyn Fn() + Send + Sync + 'static>;
@@ -46,13 +68,53 @@ type Callback = Arc<dyn Fn() + Send + Sync + 'static>;
 #[allow(missing_docs)]
 pub struct ThreadPoolBuilder {
     capacity: NonZeroUsize,
+    shards: NonZeroUsize,
     threads: NonZeroUsize,
     on_thread_spawn: Option<Callback>,
     on_thread_finish: Option<Callback>,
 }
 
 struct Worker {
-    receiver: Receiver<Task>,
+    queue: Arc<TaskQueue>,
+    // Indexes of the shards processed by this worker only
+    shards: Vec<usize>,
+    // The shard to start taking tasks from, rotated for fairness
+    start_shard: usize,
+    // Reused to pass records to sinks, see `recycle_vec`
+    records: Vec<Record<'static>>,
+    // The shard index and number of tasks of each part of the batch
+    origins: Vec<(usize, usize)>,
+}
+
+// Maximum number of tasks a worker takes from the queue at once.
+const MAX_BATCH: usize = 256;
+
+// Records whose buffers are larger than this number of bytes are not reused.
+const MAX_RECYCLED_RECORD_SIZE: usize = 4096;
+
+// A queue made of bounded shards, each producer thread pushes tasks to its own
+// shard.
+//
+// A thread going to wait increments the `waiting_*` counter, issues a fence and
+// checks the condition again while holding `lock`. A thread changing the
+// condition issues a fence and notifies while holding `lock` if the counter is
+// non-zero. So either the waiting thread sees the change, or the changing
+// thread sees the counter and notifies after the waiting thread starts to wait.
+struct TaskQueue {
+    shards: Box<[Shard]>,
+    closed: AtomicBool,
+    waiting_workers: AtomicUsize,
+    waiting_producers: AtomicUsize,
+    lock: Mutex<()>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+struct Shard {
+    tasks: ArrayQueue<Task>,
+    // Records that have been processed, kept for reuse by the producers of this
+    // shard
+    records: ArrayQueue<RecordOwned>,
 }
 
 impl ThreadPool {
@@ -61,16 +123,19 @@ impl ThreadPool {
     /// | Parameter          | Default Value                     |
     /// |--------------------|-----------------------------------|
     /// | [capacity]         | `8192` (may change in the future) |
+    /// | [shards]           | `1`                               |
     /// | [on_thread_spawn]  | `None`                            |
     /// | [on_thread_finish] | `None`                            |
     ///
     /// [capacity]: ThreadPoolBuilder::capacity
+    /// [shards]: ThreadPoolBuilder::shards
     /// [on_thread_spawn]: ThreadPoolBuilder::on_thread_spawn
     /// [on_thread_finish]: ThreadPoolBuilder::on_thread_finish
     #[must_use]
     pub fn builder() -> ThreadPoolBuilder {
         ThreadPoolBuilder {
             capacity: NonZeroUsize::new(8192).unwrap(),
+            shards: NonZeroUsize::new(1).unwrap(),
             threads: NonZeroUsize::new(1).unwrap(),
             on_thread_spawn: None,
             on_thread_finish: None,
@@ -85,13 +150,26 @@ impl ThreadPool {
 
     pub(super) fn assign_task(&self, task: Task, overflow_policy: OverflowPolicy) -> Result<()> {
         let inner = self.0.load();
-        let sender = inner.as_ref().unwrap().sender.as_ref().unwrap();
+        let queue = &inner.as_ref().unwrap().queue;
 
-        match overflow_policy {
-            OverflowPolicy::Block => sender.send(task).map_err(Error::from_crossbeam_send),
-            OverflowPolicy::DropIncoming => sender
-                .try_send(task)
-                .map_err(Error::from_crossbeam_try_send),
+        let block = match overflow_policy {
+            OverflowPolicy::Block => true,
+            OverflowPolicy::DropIncoming => false,
+        };
+        queue.push(task, block)
+    }
+
+    // Converts the record to `RecordOwned` for a task, reusing the buffers of a
+    // processed record if available.
+    #[must_use]
+    pub(super) fn to_owned_record(&self, record: &Record) -> RecordOwned {
+        let inner = self.0.load();
+        match inner.as_ref().unwrap().queue.current_shard().records.pop() {
+            Some(mut owned) => {
+                owned.assign(record);
+                owned
+            }
+            None => record.to_owned(),
         }
     }
 
@@ -99,9 +177,9 @@ impl ThreadPool {
         if let Some(inner) = self.0.swap(None) {
             let mut inner = Arc::into_inner(inner).unwrap();
 
-            // drop our sender, threads will break the loop after receiving and processing
-            // the remaining tasks
-            inner.sender.take();
+            // close the queue, threads will break the loop after processing the remaining
+            // tasks
+            inner.queue.close();
 
             for thread in &mut inner.threads {
                 if let Some(thread) = thread.take() {
@@ -119,19 +197,41 @@ impl Drop for ThreadPool {
 }
 
 impl ThreadPoolBuilder {
-    /// Specifies the capacity of the operation channel.
+    /// Specifies the capacity of each shard of the operation queue.
     ///
     /// This parameter is **optional**, and defaults to `8192` (may change in
     /// the future).
     ///
-    /// When a new operation is incoming, but the channel is full, it will be
-    /// handled by sink according to the [`OverflowPolicy`] that has been set.
+    /// When a new operation is incoming, but the shard of the current thread is
+    /// full, it will be handled by sink according to the [`OverflowPolicy`]
+    /// that has been set.
+    ///
+    /// Memory for all shards is allocated when the thread pool is built, so up
+    /// to `capacity * shards` operations can be queued in total.
     #[must_use]
     pub fn capacity(&mut self, capacity: NonZeroUsize) -> &mut Self {
         self.capacity = capacity;
         self
     }
 
+    /// Specifies the number of shards of the operation queue.
+    ///
+    /// This parameter is **optional**, and defaults to `1`.
+    ///
+    /// Threads submitting operations are assigned to the shards in turn, and
+    /// only contend with the threads assigned to the same shard. Increasing the
+    /// number of shards reduces the contention when many threads log
+    /// concurrently, e.g. setting it to [`thread::available_parallelism`].
+    /// Each shard has the full [capacity].
+    ///
+    /// [`thread::available_parallelism`]: std::thread::available_parallelism
+    /// [capacity]: ThreadPoolBuilder::capacity
+    #[must_use]
+    pub fn shards(&mut self, shards: NonZeroUsize) -> &mut Self {
+        self.shards = shards;
+        self
+    }
+
     // The current Sinks are not beneficial with more than one thread, so the method
     // is not public.
     #[must_use]
@@ -166,11 +266,16 @@ impl ThreadPoolBuilder {
 
     /// Builds a [`ThreadPool`].
     pub fn build(&self) -> Result<ThreadPool> {
-        let (sender, receiver) = mpmc::bounded(self.capacity.get());
+        // Every worker processes at least one shard
+        let shards = self.shards.max(self.threads).get();
+        let queue = Arc::new(TaskQueue::new(self.capacity.get(), shards));
 
         let mut threads = Vec::new();
+        let mut index = 0;
         threads.resize_with(self.threads.get(), || {
-            let receiver = receiver.clone();
+            let queue = Arc::clone(&queue);
+            let shards = (index..shards).step_by(self.threads.get()).collect();
+            index += 1;
             let on_thread_spawn = self.on_thread_spawn.clone();
             let on_thread_finish = self.on_thread_finish.clone();
 
@@ -179,7 +284,14 @@ impl ThreadPoolBuilder {
                     f();
                 }
 
-                Worker { receiver }.run();
+                Worker {
+                    queue,
+                    shards,
+                    start_shard: 0,
+                    records: Vec::new(),
+                    origins: Vec::new(),
+                }
+                .run();
 
                 if let Some(f) = on_thread_finish {
                     f();
@@ -188,10 +300,7 @@ impl ThreadPoolBuilder {
         });
 
         Ok(ThreadPool(ArcSwapOption::new(Some(Arc::new(
-            ThreadPoolInner {
-                threads,
-                sender: Some(sender),
-            },
+            ThreadPoolInner { threads, queue },
         )))))
     }
 
@@ -204,13 +313,215 @@ impl ThreadPoolBuilder {
 }
 
 impl Worker {
-    fn run(&self) {
-        while let Ok(task) = self.receiver.recv() {
-            task.exec();
+    fn run(&mut self) {
+        let mut batch = Vec::with_capacity(MAX_BATCH);
+        loop {
+            self.origins.clear();
+            self.queue.pop_batch(
+                &mut batch,
+                &mut self.origins,
+                &self.shards,
+                self.start_shard,
+            );
+            self.start_shard = self.start_shard.wrapping_add(1);
+
+            if batch.is_empty() {
+                if !self.queue.wait_tasks(&self.shards) {
+                    break;
+                }
+                continue;
+            }
+            self.queue.notify_producers();
+            Task::exec_batch(&batch, &mut self.records);
+
+            let mut tasks = batch.drain(..);
+            for &(shard, count) in &self.origins {
+                for task in tasks.by_ref().take(count) {
+                    if let Task::Log { mut record, .. } = task {
+                        if record.buffer_size() <= MAX_RECYCLED_RECORD_SIZE {
+                            record.release_shared();
+                            // Dropped if there are enough records to reuse
+                            _ = self.queue.shards[shard].records.push(record);
+                        }
+                    }
+                }
+            }
         }
     }
 }
 
+impl TaskQueue {
+    #[must_use]
+    fn new(capacity: usize, shards: usize) -> Self {
+        Self {
+            shards: (0..shards)
+                .map(|_| Shard {
+                    tasks: ArrayQueue::new(capacity),
+                    // Records are returned once a batch is processed
+                    records: ArrayQueue::new(capacity.min(MAX_BATCH)),
+                })
+                .collect(),
+            closed: AtomicBool::new(false),
+            waiting_workers: AtomicUsize::new(0),
+            waiting_producers: AtomicUsize::new(0),
+            lock: Mutex::new(()),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        }
+    }
+
+    // Gets the shard assigned to the current thread.
+    #[must_use]
+    fn current_shard(&self) -> &Shard {
+        &self.shards[current_shard() % self.shards.len()]
+    }
+
+    // Pushes a task to the shard of the current thread.
+    //
+    // A flush is a barrier across all shards, so a copy of it is pushed to each
+    // other shard as well, and only the last copy reached by the workers flushes
+    // the sinks. By then, the records submitted to any shard before the flush
+    // have been processed.
+    fn push(&self, task: Task, block: bool) -> Result<()> {
+        let current = current_shard() % self.shards.len();
+        if let Task::Flush { backend, remaining } = &task {
+            remaining.store(self.shards.len(), Ordering::Relaxed);
+            for (index, shard) in self.shards.iter().enumerate() {
+                if index == current {
+                    continue;
+                }
+                let copy = Task::Flush {
+                    backend: Arc::clone(backend),
+                    remaining: Arc::clone(remaining),
+                };
+                if self.push_to(shard, copy, block).is_err() {
+                    // The records in this shard are not waited for
+                    remaining.fetch_sub(1, Ordering::AcqRel);
+                }
+            }
+        }
+        self.push_to(&self.shards[current], task, block)
+    }
+
+    fn push_to(&self, shard: &Shard, mut task: Task, block: bool) -> Result<()> {
+        let shard = &shard.tasks;
+        loop {
+            if self.closed.load(Ordering::SeqCst) {
+                return Err(Error::from_dropped_task(
+                    SendToChannelError::Disconnected,
+                    task,
+                ));
+            }
+            match shard.push(task) {
+                Ok(()) => break,
+                Err(rejected) if !block => {
+                    return Err(Error::from_dropped_task(SendToChannelError::Full, rejected))
+                }
+                Err(rejected) => {
+                    task = rejected;
+                    let guard = self.lock.lock_expect();
+                    self.waiting_producers.fetch_add(1, Ordering::SeqCst);
+                    fence(Ordering::SeqCst);
+                    if shard.is_full() && !self.closed.load(Ordering::SeqCst) {
+                        drop(self.not_full.wait(guard));
+                    }
+                    self.waiting_producers.fetch_sub(1, Ordering::SeqCst);
+                }
+            }
+        }
+
+        fence(Ordering::SeqCst);
+        if self.waiting_workers.load(Ordering::SeqCst) != 0 {
+            let _guard = self.lock.lock_expect();
+            // Only the worker processing the shard can take the task
+            self.not_empty.notify_all();
+        }
+        Ok(())
+    }
+
+    // Takes tasks from the given shards, tasks from the same shard are kept in
+    // order.
+    fn pop_batch(
+        &self,
+        batch: &mut Vec<Task>,
+        origins: &mut Vec<(usize, usize)>,
+        shards: &[usize],
+        start_shard: usize,
+    ) {
+        for i in 0..shards.len() {
+            let index = shards[start_shard.wrapping_add(i) % shards.len()];
+            let len = batch.len();
+            while batch.len() < MAX_BATCH {
+                match self.shards[index].tasks.pop() {
+                    Some(task) => batch.push(task),
+                    None => break,
+                }
+            }
+            if batch.len() != len {
+                origins.push((index, batch.len() - len));
+            }
+        }
+    }
+
+    // Waits until there may be tasks in the given shards, returns `false` if the
+    // queue is closed and there are no remaining tasks.
+    #[must_use]
+    fn wait_tasks(&self, shards: &[usize]) -> bool {
+        let guard = self.lock.lock_expect();
+        self.waiting_workers.fetch_add(1, Ordering::SeqCst);
+        fence(Ordering::SeqCst);
+        // Loads `closed` first, the queue may be closed right after a task is pushed
+        // while waiting, the task must still be taken by the next pop.
+        let closed = self.closed.load(Ordering::SeqCst);
+        let is_empty = shards
+            .iter()
+            .all(|&shard| self.shards[shard].tasks.is_empty());
+        if is_empty && !closed {
+            drop(self.not_empty.wait(guard));
+        }
+        self.waiting_workers.fetch_sub(1, Ordering::SeqCst);
+        !(is_empty && closed)
+    }
+
+    fn notify_producers(&self) {
+        fence(Ordering::SeqCst);
+        if self.waiting_producers.load(Ordering::SeqCst) != 0 {
+            let _guard = self.lock.lock_expect();
+            self.not_full.notify_all();
+        }
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        let _guard = self.lock.lock_expect();
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+}
+
+// Reuses the allocation of a vector for another lifetime, the vector is
+// emptied.
+#[must_use]
+pub(crate) fn recycle_vec<'a>(mut vec: Vec<Record<'_>>) -> Vec<Record<'a>> {
+    vec.clear();
+    let mut vec = ManuallyDrop::new(vec);
+    // SAFETY: The element types differ only in lifetimes, so they have the same
+    // layout, and the vector is empty, so no element with the old lifetime can be
+    // accessed.
+    unsafe { Vec::from_raw_parts(vec.as_mut_ptr().cast::<Record<'a>>(), 0, vec.capacity()) }
+}
+
+// Gets the index of the shard assigned to the current thread.
+#[must_use]
+fn current_shard() -> usize {
+    static NEXT_SHARD: AtomicUsize = AtomicUsize::new(0);
+    thread_local! {
+        static SHARD: usize = NEXT_SHARD.fetch_add(1, Ordering::Relaxed);
+    }
+    // Thread-local storage may be unavailable at program exit
+    SHARD.try_with(|shard| *shard).unwrap_or(0)
+}
+
 #[must_use]
 pub(crate) fn default_thread_pool() -> Arc<ThreadPool> {
     static POOL_WEAK: Lazy<Mutex<Weak<ThreadPool>>> = Lazy::new(|| Mutex::new(Weak::new()));
@@ -226,3 +537,251 @@ pub(crate) fn default_thread_pool() -> Arc<ThreadPool> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{thread::sleep, time::Duration};
+
+    use super::*;
+    use crate::{
+        prelude::*,
+        sink::{AsyncPoolSink, GetSinkProp, Sink, SinkProp},
+        test_utils::*,
+        Record,
+    };
+
+    fn build_async_logger(sink: Arc<dyn Sink>, capacity: usize) -> Logger {
+        build_async_logger_with(
+            sink,
+            ThreadPool::builder().capacity(NonZeroUsize::new(capacity).unwrap()),
+        )
+    }
+
+    fn build_async_logger_with(sink: Arc<dyn Sink>, builder: &ThreadPoolBuilder) -> Logger {
+        let thread_pool = builder.build_arc().unwrap();
+        build_test_logger(|b| {
+            b.sink(
+                AsyncPoolSink::builder()
+                    .sink(sink)
+                    .thread_pool(thread_pool)
+                    .build_arc()
+                    .unwrap(),
+            )
+        })
+    }
+
+    #[test]
+    fn fan_in() {
+        // A small capacity to block producers frequently
+        let capacity = NonZeroUsize::new(64).unwrap();
+        fan_in_with(ThreadPool::builder().capacity(capacity));
+        fan_in_with(
+            ThreadPool::builder()
+                .capacity(capacity)
+                .shards(NonZeroUsize::new(4).unwrap())
+                .threads(NonZeroUsize::new(2).unwrap()),
+        );
+    }
+
+    fn fan_in_with(builder: &ThreadPoolBuilder) {
+        const THREADS: usize = 16;
+        const RECORDS: usize = 1000;
+
+        let test_sink = Arc::new(TestSink::new());
+        let logger = build_async_logger_with(test_sink.clone(), builder);
+        thread::scope(|scope| {
+            for thread in 0..THREADS {
+                let logger = &logger;
+                scope.spawn(move || {
+                    for i in 0..RECORDS {
+                        info!(logger: logger, "{thread} {i}");
+                    }
+                });
+            }
+        });
+        // Dropping the thread pool processes the remaining tasks
+        drop(logger);
+
+        let payloads = test_sink.payloads();
+        assert_eq!(payloads.len(), THREADS * RECORDS);
+        let mut next = [0; THREADS];
+        for payload in payloads {
+            let (thread, i) = payload.split_once(' ').unwrap();
+            let (thread, i) = (
+                thread.parse::<usize>().unwrap(),
+                i.parse::<usize>().unwrap(),
+            );
+            assert_eq!(next[thread], i);
+            next[thread] += 1;
+        }
+    }
+
+    #[test]
+    fn flush_barrier() {
+        const RECORDS: usize = 100;
+
+        struct FlushSink {
+            prop: SinkProp,
+            inner: TestSink,
+            // The number of records logged when each flush happens
+            flushed_at: Mutex<Vec<usize>>,
+        }
+
+        impl GetSinkProp for FlushSink {
+            fn prop(&self) -> &SinkProp {
+                &self.prop
+            }
+        }
+
+        impl Sink for FlushSink {
+            fn log(&self, record: &Record) -> Result<()> {
+                sleep(Duration::from_micros(100));
+                self.inner.log(record)
+            }
+
+            fn flush(&self) -> Result<()> {
+                self.flushed_at.lock_expect().push(self.inner.log_count());
+                Ok(())
+            }
+        }
+
+        let flush_sink = Arc::new(FlushSink {
+            prop: SinkProp::default(),
+            inner: TestSink::new(),
+            flushed_at: Mutex::new(vec![]),
+        });
+        let logger = build_async_logger_with(
+            flush_sink.clone(),
+            ThreadPool::builder()
+                .shards(NonZeroUsize::new(2).unwrap())
+                .threads(NonZeroUsize::new(2).unwrap()),
+        );
+        // The producers are assigned different shards, unless threads of other tests
+        // take shards in between
+        thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    for i in 0..RECORDS {
+                        info!(logger: logger, "{i}");
+                    }
+                })
+                .join()
+                .unwrap();
+            scope.spawn(|| logger.flush()).join().unwrap();
+        });
+        drop(logger);
+
+        assert_eq!(*flush_sink.flushed_at.lock_expect(), [RECORDS]);
+    }
+
+    #[test]
+    fn capacity_per_shard() {
+        let test_sink = Arc::new(TestSink::with_delay(Some(Duration::from_millis(200))));
+        let sink = AsyncPoolSink::builder()
+            .sink(test_sink.clone())
+            .overflow_policy(OverflowPolicy::DropIncoming)
+            .thread_pool(
+                ThreadPool::builder()
+                    .capacity(NonZeroUsize::new(10).unwrap())
+                    .shards(NonZeroUsize::new(4).unwrap())
+                    .build_arc()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let record = Record::new(Level::Info, "", None, None, &[]);
+        sink.log(&record).unwrap();
+        // Wait for the worker to take the first record
+        sleep(Duration::from_millis(50));
+
+        // A single thread can fill the whole capacity of its shard
+        let results = (0..20).map(|_| sink.log(&record)).collect::<Vec<_>>();
+        assert!(results[..10].iter().all(Result::is_ok));
+        assert!(results[10..].iter().all(|result| matches!(
+            result,
+            Err(Error::SendToChannel(SendToChannelError::Full, _))
+        )));
+
+        drop(sink);
+        assert_eq!(test_sink.log_count(), 11);
+    }
+
+    #[test]
+    fn recycle_records() {
+        let thread_pool = ThreadPool::builder().build_arc().unwrap();
+        let sink = AsyncPoolSink::builder()
+            .sink(Arc::new(TestSink::new()))
+            .thread_pool(thread_pool.clone())
+            .build()
+            .unwrap();
+        let recycled = || {
+            sleep(Duration::from_millis(50));
+            thread_pool.0.load().as_ref().unwrap().queue.shards[0]
+                .records
+                .len()
+        };
+
+        let record = Record::new(Level::Info, "small", None, None, &[]);
+        sink.log(&record).unwrap();
+        assert_eq!(recycled(), 1);
+        sink.log(&record).unwrap();
+        assert_eq!(recycled(), 1);
+
+        // The reused record grows too large to be kept
+        let payload = "x".repeat(MAX_RECYCLED_RECORD_SIZE + 1);
+        let record = Record::new(Level::Info, payload, None, None, &[]);
+        sink.log(&record).unwrap();
+        assert_eq!(recycled(), 0);
+    }
+
+    #[test]
+    fn batch() {
+        struct BatchSink {
+            prop: SinkProp,
+            batches: Mutex<Vec<usize>>,
+        }
+
+        impl GetSinkProp for BatchSink {
+            fn prop(&self) -> &SinkProp {
+                &self.prop
+            }
+        }
+
+        impl Sink for BatchSink {
+            fn log(&self, _: &Record) -> Result<()> {
+                unreachable!("records should be logged in batches")
+            }
+
+            fn log_batch(&self, records: &[Record]) -> Result<()> {
+                let mut batches = self.batches.lock_expect();
+                if batches.is_empty() {
+                    // Let the following records pile up
+                    sleep(Duration::from_millis(200));
+                }
+                batches.push(records.len());
+                Ok(())
+            }
+
+            fn flush(&self) -> Result<()> {
+                Ok(())
+            }
+        }
+
+        let batch_sink = Arc::new(BatchSink {
+            prop: SinkProp::default(),
+            batches: Mutex::new(vec![]),
+        });
+        let logger = build_async_logger(batch_sink.clone(), 8192);
+        info!(logger: logger, "first");
+        sleep(Duration::from_millis(50));
+        for _ in 0..100 {
+            info!(logger: logger, "piled up");
+        }
+        drop(logger);
+
+        let batches = batch_sink.batches.lock_expect();
+        assert_eq!(batches.iter().sum::<usize>(), 101);
+        assert_eq!(batches[..2], [1, 100]);
+    }
+}
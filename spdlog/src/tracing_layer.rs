@@ -0,0 +1,296 @@
+use std::fmt::{self, Write as _};
+
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+use crate::{default_logger, kv, sync::*, Level, Logger, Record, SourceLocation};
+
+/// A [`tracing_subscriber::Layer`] forwarding events from [tracing crate] to
+/// `spdlog-rs`.
+///
+/// It is the counterpart of [`LogCrateProxy`] for `tracing`. Each event is
+/// converted to a [`Record`] and logged by the global default logger or the
+/// logger set by [`TracingLayer::with_logger`]:
+///
+/// - The `message` field of the event becomes the payload.
+/// - The other fields of the event become key-values.
+/// - The fields of the spans the event is in are attached as key-values as
+///   well, fields of inner spans take precedence over fields with the same
+///   name of outer spans.
+/// - The names of the spans are attached as key-value `span`, joined by `:`
+///   from the outermost span, e.g. `handle_request:query`. This matches the
+///   spans of `spdlog-rs` itself, see module [`span`].
+///
+/// Spans themselves are not logged. The layer requires a subscriber that
+/// implements [`LookupSpan`], e.g. [`tracing_subscriber::Registry`].
+///
+/// This layer is only available if crate feature `tracing` is enabled.
+///
+/// ## Examples
+///
+/// ```
+/// use tracing_subscriber::layer::SubscriberExt as _;
+///
+/// let subscriber = tracing_subscriber::registry().with(spdlog::TracingLayer::new());
+/// tracing::subscriber::with_default(subscriber, || {
+///     tracing::info!(port = 8080, "listening");
+/// });
+/// ```
+///
+/// [tracing crate]: https://crates.io/crates/tracing
+/// [`LogCrateProxy`]: crate::LogCrateProxy
+/// [`span`]: crate::span
+#[derive(Default)]
+pub struct TracingLayer {
+    logger: Option<Arc<Logger>>,
+}
+
+impl TracingLayer {
+    /// Constructs a `TracingLayer` forwarding events to the global default
+    /// logger.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a logger as the receiver, instead of the global default logger.
+    #[must_use]
+    pub fn with_logger(mut self, logger: Arc<Logger>) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    #[must_use]
+    fn logger(&self) -> Arc<Logger> {
+        self.logger.clone().unwrap_or_else(default_logger)
+    }
+}
+
+impl<S> Layer<S> for TracingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut fields = FieldsVisitor::default();
+        attrs.record(&mut fields);
+        span.extensions_mut().insert(SpanFields(fields.pairs));
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut fields = FieldsVisitor::default();
+        values.record(&mut fields);
+
+        let mut extensions = span.extensions_mut();
+        match extensions.get_mut::<SpanFields>() {
+            Some(SpanFields(pairs)) => {
+                for (key, value) in fields.pairs {
+                    match pairs.iter_mut().find(|(k, _)| *k == key) {
+                        Some((_, v)) => *v = value,
+                        None => pairs.push((key, value)),
+                    }
+                }
+            }
+            None => extensions.insert(SpanFields(fields.pairs)),
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let level = Level::from(*metadata.level());
+        let logger = self.logger();
+        if !logger.should_log(level) {
+            return;
+        }
+
+        let mut fields = FieldsVisitor::default();
+        event.record(&mut fields);
+
+        let mut pairs = fields.pairs;
+        let mut span_path = String::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope {
+                if let Some(SpanFields(span_pairs)) = span.extensions().get::<SpanFields>() {
+                    for (key, value) in span_pairs {
+                        if !pairs.iter().any(|(k, _)| k == key) {
+                            pairs.push((key, value.clone()));
+                        }
+                    }
+                }
+                if span_path.is_empty() {
+                    span_path.push_str(span.name());
+                } else {
+                    span_path.insert(0, ':');
+                    span_path.insert_str(0, span.name());
+                }
+            }
+        }
+
+        let mut kvs = pairs
+            .iter()
+            .map(|(key, value)| (kv::Key::__from_static_str(key), value.by_ref()))
+            .collect::<Vec<_>>();
+        if !span_path.is_empty() {
+            kvs.push((
+                kv::Key::__from_static_str("span"),
+                kv::Value::from(&*span_path),
+            ));
+        }
+
+        let srcloc = match (metadata.module_path(), metadata.file(), metadata.line()) {
+            (None, None, None) => None,
+            (module_path, file, line) => Some(SourceLocation::__new(
+                module_path.unwrap_or(""),
+                file.unwrap_or(""),
+                line.unwrap_or(0),
+                0,
+            )),
+        };
+        // If the logger has a name configured, use that name. Otherwise, the name can
+        // also be given by the target of the event.
+        let logger_name = logger.name().or_else(|| Some(metadata.target()));
+        let record = Record::new(level, fields.message, srcloc, logger_name, &kvs);
+        logger.log(&record);
+    }
+}
+
+impl From<tracing::Level> for Level {
+    fn from(level: tracing::Level) -> Self {
+        match level {
+            tracing::Level::ERROR => Self::Error,
+            tracing::Level::WARN => Self::Warn,
+            tracing::Level::INFO => Self::Info,
+            tracing::Level::DEBUG => Self::Debug,
+            tracing::Level::TRACE => Self::Trace,
+        }
+    }
+}
+
+impl From<Level> for tracing::Level {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::Critical | Level::Error => Self::ERROR,
+            Level::Warn => Self::WARN,
+            Level::Info => Self::INFO,
+            Level::Debug => Self::DEBUG,
+            Level::Trace => Self::TRACE,
+        }
+    }
+}
+
+struct SpanFields(Vec<(&'static str, kv::ValueOwned)>);
+
+#[derive(Default)]
+struct FieldsVisitor {
+    message: String,
+    pairs: Vec<(&'static str, kv::ValueOwned)>,
+}
+
+impl FieldsVisitor {
+    fn push(&mut self, field: &Field, value: kv::Value) {
+        self.pairs.push((field.name(), value.to_owned()));
+    }
+}
+
+impl Visit for FieldsVisitor {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.push(field, kv::Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.push(field, kv::Value::from(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.push(field, kv::Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.push(field, kv::Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.push(field, kv::Value::from(value));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            // The message is usually `fmt::Arguments`, whose `Debug` implementation
+            // formats it without quotes
+            write!(self.message, "{value:?}").unwrap();
+        } else {
+            self.push(field, kv::Value::from_dyn_debug(value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::layer::SubscriberExt as _;
+
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn events_and_spans() {
+        let sink = Arc::new(TestSink::new());
+        let logger = Arc::new(build_test_logger(|b| {
+            b.sink(sink.clone()).level_filter(crate::LevelFilter::All)
+        }));
+        let subscriber =
+            tracing_subscriber::registry().with(TracingLayer::new().with_logger(logger));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(port = 8080, "listening on {}", "localhost");
+            let outer = tracing::info_span!("request", id = 1, user = tracing::field::Empty);
+            let _outer = outer.enter();
+            outer.record("user", "alice");
+            let _inner = tracing::debug_span!("query", id = 2, table = "users").entered();
+            tracing::warn!(rows = 3_u64, slow = true);
+        });
+
+        let records = sink.records();
+        let summary = records
+            .iter()
+            .map(|record| {
+                let kvs = record
+                    .key_values()
+                    .iter()
+                    .map(|(k, v)| format!("{}={v}", k.as_str()))
+                    .collect::<Vec<_>>();
+                (record.level(), record.payload().to_string(), kvs.join(" "))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                (
+                    Level::Info,
+                    "listening on localhost".to_string(),
+                    "port=8080".to_string()
+                ),
+                (
+                    Level::Warn,
+                    String::new(),
+                    "rows=3 slow=true id=2 table=users user=alice span=request:query".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            records[0].logger_name(),
+            Some("spdlog::tracing_layer::tests")
+        );
+    }
+}
@@ -1,8 +1,9 @@
 mod ref_str;
 
 use std::{
-    fs::{self, File, OpenOptions},
-    io::BufWriter,
+    fs::{self, DirBuilder, File, OpenOptions},
+    io::{self, BufWriter},
+    ops::Range,
     path::Path,
 };
 
@@ -10,33 +11,101 @@ pub(crate) use ref_str::*;
 
 use crate::{Error, Result};
 
-pub fn open_file<P: AsRef<Path>>(path: P, truncate: bool) -> Result<File> {
-    if let Some(parent) = path.as_ref().parent() {
+// Options for opening files of file sinks.
+#[derive(Clone, Debug)]
+pub(crate) struct FileOpenOptions {
+    #[cfg(unix)]
+    pub(crate) file_mode: Option<u32>,
+    #[cfg(unix)]
+    pub(crate) dir_mode: Option<u32>,
+    pub(crate) follow_symlinks: bool,
+    // Opens the file in append mode even if truncating, the file is shared with
+    // other processes.
+    pub(crate) multi_process: bool,
+}
+
+impl Default for FileOpenOptions {
+    fn default() -> Self {
+        Self {
+            #[cfg(unix)]
+            file_mode: None,
+            #[cfg(unix)]
+            dir_mode: None,
+            follow_symlinks: true,
+            multi_process: false,
+        }
+    }
+}
+
+pub fn open_file<P: AsRef<Path>>(
+    path: P,
+    truncate: bool,
+    options: &FileOpenOptions,
+) -> Result<File> {
+    let path = path.as_ref();
+
+    if let Some(parent) = path.parent() {
         if !parent.exists() {
-            fs::create_dir_all(parent).map_err(Error::CreateDirectory)?;
+            let mut dir_builder = DirBuilder::new();
+            dir_builder.recursive(true);
+            #[cfg(unix)]
+            if let Some(dir_mode) = options.dir_mode {
+                use std::os::unix::fs::DirBuilderExt as _;
+                dir_builder.mode(dir_mode);
+            }
+            dir_builder.create(parent).map_err(Error::CreateDirectory)?;
         }
     }
 
     let mut open_options = OpenOptions::new();
 
-    if truncate {
+    if truncate && !options.multi_process {
         open_options.write(true).truncate(true);
     } else {
         open_options.append(true);
     }
 
-    open_options
+    if !options.follow_symlinks {
+        // Checked in advance for a clear error, `O_NOFOLLOW` below closes the race on Unix
+        if fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+            return Err(Error::OpenFile(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the path is a symbolic link",
+            )));
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt as _;
+            open_options.custom_flags(libc::O_NOFOLLOW);
+        }
+    }
+
+    #[cfg(unix)]
+    if let Some(file_mode) = options.file_mode {
+        use std::os::unix::fs::OpenOptionsExt as _;
+        open_options.mode(file_mode);
+    }
+
+    let file = open_options
         .create(true)
         .open(path)
-        .map_err(Error::OpenFile)
+        .map_err(Error::OpenFile)?;
+
+    // `std` does not allow combining `append` and `truncate`
+    if truncate && options.multi_process {
+        file.set_len(0).map_err(Error::OpenFile)?;
+    }
+
+    Ok(file)
 }
 
 pub fn open_file_bufw<P: AsRef<Path>>(
     path: P,
     truncate: bool,
     capacity: Option<usize>,
+    options: &FileOpenOptions,
 ) -> Result<BufWriter<File>> {
-    let file = open_file(path, truncate)?;
+    let file = open_file(path, truncate, options)?;
     Ok(match capacity {
         Some(capacity) => BufWriter::with_capacity(capacity, file),
         None => BufWriter::new(file), // Use std internal default capacity
@@ -63,3 +132,10 @@ pub(crate) fn is_none_or<T>(opt: Option<T>, f: impl FnOnce(T) -> bool) -> bool {
         Some(x) => f(x),
     }
 }
+
+// Appends the string to the buffer, and returns its range in the buffer.
+pub(crate) fn push_str_range(buf: &mut String, s: &str) -> Range<usize> {
+    let start = buf.len();
+    buf.push_str(s);
+    start..buf.len()
+}
@@ -37,6 +37,13 @@ impl<'a> RefStr<'a> {
         unsafe { &*self.ptr }
     }
 
+    pub(crate) fn as_static(&self) -> Option<&'static str> {
+        match self.inner {
+            RefStrInner::Static(s) => Some(s),
+            RefStrInner::Borrowed(_) => None,
+        }
+    }
+
     pub(crate) fn to_cow_static(self) -> Cow<'static, str> {
         match self.inner {
             RefStrInner::Static(s) => Cow::Borrowed(s),
@@ -0,0 +1,110 @@
+// Logging through `AsyncPoolSink` should not allocate once the records of the
+// thread pool are reused.
+//
+// This test counts allocations with a global allocator, it does not use the
+// default test harness, so that no other thread allocates while counting.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use spdlog::{
+    prelude::*,
+    sink::{AsyncPoolSink, GetSinkProp, Sink, SinkProp},
+};
+
+static COUNTING: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if COUNTING.load(Ordering::SeqCst) {
+            ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if COUNTING.load(Ordering::SeqCst) {
+            ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        }
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+#[derive(Default)]
+struct CountingSink {
+    prop: SinkProp,
+    logged: AtomicUsize,
+}
+
+impl GetSinkProp for CountingSink {
+    fn prop(&self) -> &SinkProp {
+        &self.prop
+    }
+}
+
+impl Sink for CountingSink {
+    fn log(&self, _record: &spdlog::Record) -> error::Result<()> {
+        self.logged.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn flush(&self) -> error::Result<()> {
+        Ok(())
+    }
+}
+
+fn main() {
+    let sink = Arc::new(CountingSink::default());
+    let logger = Logger::builder()
+        .sink(
+            AsyncPoolSink::builder()
+                .sink(sink.clone())
+                .build_arc()
+                .unwrap(),
+        )
+        .name("no-alloc")
+        .flush_level_filter(LevelFilter::Off)
+        .build()
+        .unwrap();
+
+    let text = String::from("not static");
+    let mut expected = 0;
+    let mut log = |i: usize, wait: bool| {
+        // Records have the same size, so the buffers of reused records are large
+        // enough
+        info!(logger: logger, "record {i:04}", kv: { key = "value", text = text.as_str(), i });
+        expected += 1;
+        // Wait for the record to be processed
+        while wait && sink.logged.load(Ordering::SeqCst) != expected {
+            std::hint::spin_loop();
+        }
+    };
+
+    // Warm up, creating a few records to be reused
+    for i in 0..16 {
+        log(i, i == 15);
+    }
+
+    COUNTING.store(true, Ordering::SeqCst);
+    for i in 0..1000 {
+        log(i, true);
+    }
+    COUNTING.store(false, Ordering::SeqCst);
+
+    assert_eq!(ALLOCATIONS.load(Ordering::SeqCst), 0);
+}
@@ -0,0 +1,8 @@
+use spdlog::{log_deferred, Level};
+
+fn unsupported_format_specs() {
+    log_deferred!(Level::Info, "{:>5}", 1);
+    log_deferred!(Level::Info, "{:.2}", 1.5);
+}
+
+fn main() {}
@@ -0,0 +1,90 @@
+#[cfg(feature = "log")]
+mod log_crate_sink {
+    use std::sync::Mutex;
+
+    use spdlog::{debug, info, sink::LogCrateSink, warn, LevelFilter};
+
+    include!(concat!(
+        env!("OUT_DIR"),
+        "/test_utils/common_for_integration_test.rs"
+    ));
+    use test_utils::*;
+
+    #[derive(Default)]
+    struct Capture(Mutex<Vec<String>>);
+
+    impl log::Log for Capture {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            struct Collect(String);
+            impl<'kvs> log::kv::VisitSource<'kvs> for Collect {
+                fn visit_pair(
+                    &mut self,
+                    key: log::kv::Key<'kvs>,
+                    value: log::kv::Value<'kvs>,
+                ) -> Result<(), log::kv::Error> {
+                    let kind = if value.to_i64().is_some() {
+                        "i64"
+                    } else if value.to_bool().is_some() {
+                        "bool"
+                    } else {
+                        "other"
+                    };
+                    self.0 += &format!(" {key}={value}({kind})");
+                    Ok(())
+                }
+            }
+
+            let mut kvs = Collect(String::new());
+            record.key_values().visit(&mut kvs).unwrap();
+            self.0.lock().unwrap().push(format!(
+                "[{}] [{}] {}{}",
+                record.level(),
+                record.target(),
+                record.args(),
+                kvs.0
+            ));
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn forward_to_log_crate() {
+        let capture: &'static Capture = Box::leak(Box::default());
+        log::set_logger(capture).unwrap();
+        log::set_max_level(log::LevelFilter::Info);
+
+        let sink = LogCrateSink::builder().build_arc().unwrap();
+        let named = build_test_logger(|b| {
+            b.sink(sink.clone())
+                .name("network")
+                .level_filter(LevelFilter::All)
+        });
+        let unnamed = build_test_logger(|b| b.sink(sink.clone()));
+
+        info!(logger: named, "connected to {}", "example.com", kv: { port = 443, tls = true, peer = "a" });
+        warn!(logger: unnamed, "retrying");
+        debug!(logger: named, "filtered out by log::max_level");
+
+        assert_eq!(
+            *capture.0.lock().unwrap(),
+            [
+                "[INFO] [network] connected to example.com port=443(i64) tls=true(bool) peer=a(other)"
+                    .to_string(),
+                format!(
+                    "[WARN] [{}] retrying",
+                    // The target falls back to the module path
+                    if cfg!(feature = "source-location") {
+                        module_path!()
+                    } else {
+                        ""
+                    }
+                ),
+            ]
+        );
+    }
+}
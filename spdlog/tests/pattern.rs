@@ -9,9 +9,11 @@ use regex::Regex;
 use spdlog::formatter::runtime_pattern;
 use spdlog::{
     error,
-    formatter::{pattern, FormatterContext, Pattern, PatternFormatter},
+    error::InvalidArgumentError,
+    formatter::{pattern, EscapeMode, FormatterContext, Pattern, PatternFormatter, TimeZone},
     prelude::*,
     sink::{GetSinkProp, Sink, SinkProp},
+    terminal_style::{Color, Style},
     Error, StringBuf, __EOL,
 };
 
@@ -90,6 +92,166 @@ fn test_style_range() {
     );
 }
 
+#[test]
+fn test_explicit_style_ranges() {
+    #[track_caller]
+    fn check<P>(pat: P)
+    where
+        P: Pattern + 'static + Clone,
+    {
+        let sink = Arc::new(MockSink::new());
+        sink.set_formatter(Box::new(PatternFormatter::new(pat)));
+        let logger = Logger::builder()
+            .name("logger_name")
+            .sink(sink.clone())
+            .build()
+            .unwrap();
+        error!(logger: logger, "record_payload");
+
+        let (msg, style_range) = sink.get_last_msg().unwrap();
+        assert_eq!(msg, "> [error] logger_name: record_payload");
+        assert_eq!(style_range, Some(3..8));
+        assert_eq!(
+            sink.get_last_styled_ranges(),
+            vec![
+                (0..1, Style::builder().faint().build()),
+                (10..21, Style::builder().bold().color(Color::Cyan).build()),
+                (23..37, Style::builder().bg_color(Color::Red).build()),
+            ]
+        );
+    }
+
+    check(pattern!(
+        "{^dim}>{$} [{^{level}}] {^bold cyan}{logger}{$}: {^bg_red}{payload}{$}"
+    ));
+    #[cfg(feature = "runtime-pattern")]
+    check(
+        runtime_pattern!("{^dim}>{$} [{^{level}}] {^bold cyan}{logger}{$}: {^bg_red}{payload}{$}")
+            .unwrap(),
+    );
+}
+
+#[test]
+fn test_conditional() {
+    #[track_caller]
+    fn fmt(
+        pattern: impl Pattern + Clone + 'static,
+        name: Option<&'static str>,
+        kv: bool,
+    ) -> String {
+        let (logger, sink) = test_utils::echo_logger_from_pattern(pattern, name);
+        if kv {
+            info!(logger: logger, kv: { a=1 }, "payload");
+        } else {
+            info!(logger: logger, "payload");
+        }
+        sink.clone_string()
+    }
+
+    macro_rules! check {
+        ( $template:literal, $($args: expr),+ $(,)? ) => {
+            check_inner(pattern!($template), $($args),+);
+            #[cfg(feature = "runtime-pattern")]
+            check_inner(runtime_pattern!($template).unwrap(), $($args),+);
+        };
+    }
+
+    #[track_caller]
+    fn check_inner(pattern: impl Pattern + Clone + 'static, expected: [&str; 4]) {
+        assert_eq!(fmt(pattern.clone(), None, false), expected[0]);
+        assert_eq!(fmt(pattern.clone(), Some("name"), false), expected[1]);
+        assert_eq!(fmt(pattern.clone(), None, true), expected[2]);
+        assert_eq!(fmt(pattern, Some("name"), true), expected[3]);
+    }
+
+    check!(
+        "{?logger:[{logger}] }{payload}{?kv: {{ {kv} }}}",
+        [
+            "payload",
+            "[name] payload",
+            "payload { a=1 }",
+            "[name] payload { a=1 }",
+        ],
+    );
+    check!(
+        "{?logger:{^bold}{logger}{$}: }{^{level}}",
+        ["info", "name: info", "info", "name: info"],
+    );
+    #[cfg(feature = "source-location")]
+    check!("{?source:src }{payload}", ["src payload"; 4]);
+    #[cfg(not(feature = "source-location"))]
+    check!("{?source:src }{payload}", ["payload"; 4]);
+}
+
+#[cfg(feature = "runtime-pattern")]
+#[test]
+fn test_conditional_invalid() {
+    assert!(matches!(
+        runtime_pattern!("{?unknown:text}"),
+        Err(Error::BuildPattern(_))
+    ));
+    assert!(matches!(
+        runtime_pattern!("{?logger:{^{logger}}} {^{level}}"),
+        Err(Error::BuildPattern(_))
+    ));
+}
+
+#[test]
+fn test_time_zone() {
+    #[track_caller]
+    fn fmt(pattern: impl Pattern + Clone + 'static, time_zone: TimeZone) -> String {
+        let sink = Arc::new(test_utils::StringSink::with(|b| {
+            b.formatter(PatternFormatter::new(pattern).with_time_zone(time_zone))
+        }));
+        let logger = Logger::builder().sink(sink.clone()).build().unwrap();
+
+        info!(logger: logger, "test payload");
+
+        sink.clone_string()
+    }
+
+    assert_eq!(fmt(pattern!("{tz_offset}"), TimeZone::utc()), "+00:00");
+    assert_eq!(
+        fmt(
+            pattern!("{tz_offset} {time:%z}"),
+            TimeZone::fixed_offset(-(3 * 3600 + 30 * 60)).unwrap()
+        ),
+        "-03:30 -0330"
+    );
+    #[cfg(feature = "runtime-pattern")]
+    assert_eq!(
+        fmt(
+            runtime_pattern!("{tz_offset} {time:%:z}").unwrap(),
+            TimeZone::fixed_offset(8 * 3600).unwrap()
+        ),
+        "+08:00 +08:00"
+    );
+    // Each format is cached separately within a second
+    assert_eq!(
+        fmt(
+            pattern!("{time:%z} {time:%:z} {time:%z}"),
+            TimeZone::fixed_offset(-(3 * 3600 + 30 * 60)).unwrap()
+        ),
+        "-0330 -03:30 -0330"
+    );
+
+    // `{full}` follows the time zone of the pattern formatter
+    let output = fmt(
+        pattern!("[{date} {time}.{millisecond}] {full}"),
+        TimeZone::fixed_offset(-(11 * 3600)).unwrap(),
+    );
+    let (time, full) = output.split_at(output.find("] ").unwrap() + 2);
+    assert!(full.starts_with(time.trim_end()));
+}
+
+#[test]
+fn test_invalid_time_format() {
+    assert!(matches!(
+        spdlog::formatter::__pattern::FormattedTime::new("%Y-%!"),
+        Err(Error::InvalidArgument(InvalidArgumentError::TimeFormat(format))) if format == "%Y-%!"
+    ));
+}
+
 #[track_caller]
 fn test_pattern_inner<P, F>(pat: P, expect_formatted: F, expect_style_range: Option<Range<usize>>)
 where
@@ -116,6 +278,7 @@ where
 struct MockSink {
     prop: SinkProp,
     last_msg: Mutex<Option<(String, Option<Range<usize>>)>>,
+    last_styled_ranges: Mutex<Vec<(Range<usize>, Style)>>,
 }
 
 impl MockSink {
@@ -124,6 +287,7 @@ impl MockSink {
         Self {
             prop: SinkProp::default(),
             last_msg: Mutex::new(None),
+            last_styled_ranges: Mutex::new(Vec::new()),
         }
     }
 
@@ -131,6 +295,11 @@ impl MockSink {
     fn get_last_msg(&self) -> Option<(String, Option<Range<usize>>)> {
         self.last_msg.lock().unwrap().clone()
     }
+
+    #[must_use]
+    fn get_last_styled_ranges(&self) -> Vec<(Range<usize>, Style)> {
+        self.last_styled_ranges.lock().unwrap().clone()
+    }
 }
 
 impl GetSinkProp for MockSink {
@@ -148,6 +317,7 @@ impl Sink for MockSink {
             .format(record, &mut buf, &mut ctx)
             .unwrap();
         *self.last_msg.lock().unwrap() = Some((String::from(buf.as_str()), ctx.style_range()));
+        *self.last_styled_ranges.lock().unwrap() = ctx.styled_ranges().to_vec();
         Ok(())
     }
 
@@ -389,6 +559,19 @@ fn test_builtin_patterns() {
         Some(["00:00:00"]),
         vec![HOUR_RANGE, MINUTE_RANGE, SECOND_RANGE],
     );
+    check!(
+        "{time:%Y-%m-%dT%H:%M:%S%.3f}",
+        Some(["0000-00-00T00:00:00.000"]),
+        vec![
+            YEAR_RANGE,
+            MONTH_RANGE,
+            DAY_RANGE,
+            HOUR_RANGE,
+            MINUTE_RANGE,
+            SECOND_RANGE,
+            MILLISECOND_RANGE,
+        ],
+    );
     check!(
         "{tz_offset}",
         Some(["{begin_sign}00:00"]),
@@ -459,7 +642,127 @@ fn test_builtin_patterns() {
     check!("{kv}", Some(["a=true b=text"]), vec![]);
     check!("{pid}", None as Option<Vec<&str>>, vec![OS_ID_RANGE]);
     check!("{tid}", None as Option<Vec<&str>>, vec![OS_ID_RANGE]);
+    check!(
+        "{thread_name}",
+        Some([std::thread::current().name().unwrap_or("")]),
+        vec![]
+    );
+    check!(
+        "{elapsed}",
+        None as Option<Vec<&str>>,
+        vec![0..=u64::MAX, MICROSECOND_RANGE]
+    );
+    check!(
+        "{elapsed_delta}",
+        None as Option<Vec<&str>>,
+        vec![0..=u64::MAX, MICROSECOND_RANGE]
+    );
+    check!("{seq}", Some(["0"]), vec![0..=0]);
     check!("{eol}", Some(["{eol}"]), vec![]);
+
+    let process_name = std::env::current_exe().unwrap();
+    let process_name = process_name.file_stem().unwrap().to_str().unwrap();
+    assert_eq!(fmt(pattern!("{process_name}")), process_name);
+    assert!(!fmt(pattern!("{hostname}")).is_empty());
+    #[cfg(feature = "runtime-pattern")]
+    {
+        assert_eq!(
+            fmt(runtime_pattern!("{process_name}").unwrap()),
+            process_name
+        );
+        assert!(!fmt(runtime_pattern!("{hostname}").unwrap()).is_empty());
+    }
+}
+
+#[test]
+fn test_thread_name_and_seq() {
+    let sink = Arc::new(test_utils::StringSink::with(|b| {
+        b.formatter(PatternFormatter::new(pattern!(
+            "{seq} [{thread_name}] {elapsed_delta} {payload}{eol}"
+        )))
+    }));
+    let logger = Arc::new(
+        Logger::builder()
+            .sink(sink.clone())
+            .level_filter(LevelFilter::MoreSevereEqual(Level::Info))
+            .build()
+            .unwrap(),
+    );
+
+    info!(logger: logger, "first");
+    debug!(logger: logger, "filtered");
+    {
+        let logger = logger.clone();
+        std::thread::Builder::new()
+            .name("worker-a".into())
+            .spawn(move || info!(logger: logger, "second"))
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+    std::thread::Builder::new()
+        .spawn({
+            let logger = logger.clone();
+            move || warn!(logger: logger, "third")
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    let output = sink.clone_string();
+    let lines = output.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+
+    let regex = Regex::new(r"^(\d+) \[(.*)\] (\d+)\.(\d{6}) (\w+)$").unwrap();
+    let captures = lines
+        .iter()
+        .map(|line| regex.captures(line).unwrap())
+        .collect::<Vec<_>>();
+    let fields = |i: usize, group| captures[i].get(group).unwrap().as_str();
+
+    assert_eq!(fields(0, 1), "0");
+    assert_eq!(fields(1, 1), "1");
+    assert_eq!(fields(2, 1), "2");
+    assert_eq!(fields(0, 2), std::thread::current().name().unwrap_or(""));
+    assert_eq!(fields(1, 2), "worker-a");
+    assert_eq!(fields(2, 2), "");
+    assert_eq!(fields(0, 5), "first");
+    assert_eq!(fields(1, 5), "second");
+    assert_eq!(fields(2, 5), "third");
+
+    // A forked logger has its own sequence
+    let forked = logger.fork_with_name(Some("forked")).unwrap();
+    info!(logger: forked, "fourth");
+    let output = sink.clone_string();
+    assert!(output.lines().last().unwrap().starts_with("0 ["));
+}
+
+#[test]
+fn test_escape_mode() {
+    #[track_caller]
+    fn check(pattern: impl Pattern + Clone + 'static, expected: &str) {
+        let formatter = PatternFormatter::new(pattern).with_escape_mode(EscapeMode::Terminal);
+        let sink = Arc::new(test_utils::StringSink::with(|b| b.formatter(formatter)));
+        let logger = Logger::builder().sink(sink.clone()).build().unwrap();
+
+        let user = "eve\u{202e}";
+        info!(logger: logger, kv: { user }, "line1\n[error] line2 \x1b[31mred");
+
+        assert_eq!(sink.clone_string(), expected);
+    }
+
+    check(
+        pattern!("[{level}] {payload} ({kv}){eol}"),
+        &format!(
+            "[info] line1\\n[error] line2 \\u{{1b}}[31mred (user=eve\\u{{202e}}){}",
+            __EOL
+        ),
+    );
+    #[cfg(feature = "runtime-pattern")]
+    check(
+        runtime_pattern!("{payload}").unwrap(),
+        "line1\\n[error] line2 \\u{1b}[31mred",
+    );
 }
 
 #[cfg(feature = "runtime-pattern")]
@@ -510,6 +813,18 @@ fn runtime_pattern_invalid() {
         runtime_pattern!("{logger} {$r#custom_pat}", {$r#custom_pat} => custom_pat_creator),
         Err(Error::BuildPattern(_))
     ));
+    assert!(matches!(
+        runtime_pattern!("{time:%Y-%Q}"),
+        Err(Error::BuildPattern(_))
+    ));
+    assert!(matches!(
+        runtime_pattern!("{level:%Y}"),
+        Err(Error::BuildPattern(_))
+    ));
+    assert!(matches!(
+        runtime_pattern!("{logger} {$custom_pat:arg}", {$custom_pat} => custom_pat_creator),
+        Err(Error::BuildPattern(_))
+    ));
 }
 
 #[cfg(feature = "multi-thread")]